@@ -23,7 +23,7 @@ use winit::event::{DeviceEvent, DeviceId, Ime, MouseButton, MouseScrollDelta, Wi
 use winit::event_loop::{ActiveEventLoop, EventLoop};
 use winit::icon::{Icon, RgbaIcon};
 use winit::keyboard::{Key, ModifiersState};
-use winit::monitor::Fullscreen;
+use winit::monitor::{Fullscreen, MonitorEvent};
 #[cfg(macos_platform)]
 use winit::platform::macos::{OptionAsAlt, WindowAttributesMacOS, WindowExtMacOS};
 #[cfg(any(x11_platform, wayland_platform))]
@@ -510,6 +510,9 @@ impl ApplicationHandler for Application {
                 Ime::Commit(text) => {
                     info!("Committed: {}", text);
                 },
+                Ime::DeleteSurrounding { before_bytes, after_bytes } => {
+                    info!("Delete surrounding: {before_bytes} bytes before, {after_bytes} after");
+                },
                 Ime::Disabled => info!("IME disabled for Window={window_id:?}"),
             },
             WindowEvent::PinchGesture { delta, .. } => {
@@ -545,7 +548,11 @@ impl ApplicationHandler for Application {
             | WindowEvent::DragEntered { .. }
             | WindowEvent::DragMoved { .. }
             | WindowEvent::DragDropped { .. }
+            | WindowEvent::DragData { .. }
+            | WindowEvent::DragSource { .. }
             | WindowEvent::Destroyed
+            | WindowEvent::PopupDismissed
+            | WindowEvent::CapabilitiesChanged(_)
             | WindowEvent::Moved(_) => (),
         }
     }
@@ -559,6 +566,10 @@ impl ApplicationHandler for Application {
         info!("Device {device_id:?} event: {event:?}");
     }
 
+    fn monitor_event(&mut self, _event_loop: &dyn ActiveEventLoop, event: MonitorEvent) {
+        info!("Monitor event: {event:?}");
+    }
+
     fn can_create_surfaces(&mut self, event_loop: &dyn ActiveEventLoop) {
         info!("Ready to create surfaces");
         self.dump_monitors(event_loop);
@@ -1,7 +1,8 @@
 use std::error::Error;
 
 use winit::application::ApplicationHandler;
-use winit::event::WindowEvent;
+use winit::clipboard::ClipboardContents;
+use winit::event::{DragAction, ElementState, MouseButton, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, EventLoop};
 use winit::window::{Window, WindowAttributes, WindowId};
 
@@ -23,18 +24,20 @@ fn main() -> Result<(), Box<dyn Error>> {
 #[derive(Debug)]
 struct Application {
     window: Option<Box<dyn Window>>,
+    /// The text type offered by the current drag, if any.
+    text_mime_type: Option<String>,
 }
 
 impl Application {
     fn new() -> Self {
-        Self { window: None }
+        Self { window: None, text_mime_type: None }
     }
 }
 
 impl ApplicationHandler for Application {
     fn can_create_surfaces(&mut self, event_loop: &dyn ActiveEventLoop) {
-        let window_attributes =
-            WindowAttributes::default().with_title("Drag and drop files on me!");
+        let window_attributes = WindowAttributes::default()
+            .with_title("Drag and drop files or text on me, or from me!");
         self.window = Some(event_loop.create_window(window_attributes).unwrap());
     }
 
@@ -45,10 +48,42 @@ impl ApplicationHandler for Application {
         event: WindowEvent,
     ) {
         match event {
+            WindowEvent::DragEntered { ref paths, ref mime_types, .. } => {
+                println!("{event:?}");
+
+                // Accept dragged text too, files are accepted by default.
+                self.text_mime_type = mime_types
+                    .iter()
+                    .find(|mime_type| mime_type.starts_with("text/plain"))
+                    .cloned();
+                if paths.is_empty() && self.text_mime_type.is_some() {
+                    let _ = event_loop.set_drag_action(Some(DragAction::Copy));
+                }
+            },
+            WindowEvent::DragDropped { .. } => {
+                println!("{event:?}");
+                if let Some(mime_type) = self.text_mime_type.take() {
+                    let _ = event_loop.request_drag_data(&mime_type);
+                }
+            },
+            WindowEvent::DragData { result: Ok(data), .. } => {
+                println!("Dropped text: {:?}", String::from_utf8_lossy(&data.data));
+            },
+            WindowEvent::PointerButton { state: ElementState::Pressed, button, .. }
+                if button.mouse_button() == MouseButton::Left =>
+            {
+                // Drag some text out of the window.
+                let contents = ClipboardContents::new().with_text("Dragged out of winit");
+                let actions = [DragAction::Copy, DragAction::Move];
+                let window = self.window.as_ref().unwrap();
+                if let Err(err) = window.start_drag(contents, &actions, None) {
+                    println!("Failed to start the drag: {err}");
+                }
+            },
             WindowEvent::DragLeft { .. }
-            | WindowEvent::DragEntered { .. }
             | WindowEvent::DragMoved { .. }
-            | WindowEvent::DragDropped { .. } => {
+            | WindowEvent::DragData { .. }
+            | WindowEvent::DragSource { .. } => {
                 println!("{event:?}");
             },
             WindowEvent::RedrawRequested => {
@@ -192,6 +192,9 @@
 //!
 //! * `x11` (enabled by default): On Unix platforms, enables the X11 backend.
 //! * `wayland` (enabled by default): On Unix platforms, enables the Wayland backend.
+//! * `headless`: On Linux and BSD, enables the headless backend for testing, see
+//!   [`platform::headless`]. It can't be selected through the `EventLoopBuilder` on Windows,
+//!   macOS and the other platforms, where the `winit-headless` crate is used directly instead.
 //! * `rwh_06`: Implement `raw-window-handle v0.6` traits.
 //! * `serde`: Enables serialization/deserialization of certain types with [Serde](https://crates.io/crates/serde).
 //! * `mint`: Enables mint (math interoperability standard types) conversions.
@@ -292,7 +295,9 @@ pub use rwh_06 as raw_window_handle;
 #[cfg(any(doc, doctest, test))]
 pub mod changelog;
 pub mod event_loop;
-pub use winit_core::{application, cursor, error, event, icon, keyboard, monitor, window};
+pub use winit_core::{
+    application, clipboard, cursor, error, event, icon, keyboard, monitor, window,
+};
 #[macro_use]
 mod os_error;
 mod platform_impl;
@@ -0,0 +1,88 @@
+//! # Headless
+//!
+//! A backend which doesn't connect to any display server, meant for running integration tests of
+//! [`ApplicationHandler`] implementations in CI. It's enabled with the `headless` cargo feature
+//! and has to be requested explicitly with [`EventLoopBuilderExtHeadless::with_headless`].
+//!
+//! The [`HeadlessHandle`] plays the part of the windowing system: it injects window and device
+//! events, sets up fake monitors and exposes the state the application requested on its windows.
+//!
+//! ```no_run
+//! use winit::event_loop::EventLoop;
+//! use winit::platform::headless::{EventLoopBuilderExtHeadless, EventLoopExtHeadless};
+//!
+//! let event_loop = EventLoop::builder().with_headless().build().unwrap();
+//! let handle = event_loop.headless_handle().unwrap();
+//! ```
+//!
+//! Like any other backend, only one [`EventLoop`] can be created per process. Use the
+//! `winit-headless` crate directly to run several event loops in the same test binary.
+//!
+//! ## Platform-specific
+//!
+//! This module is only available on Linux and BSD, the other platforms can't select the headless
+//! backend through the [`EventLoopBuilder`]. Their tests have to drive the `winit-headless` crate
+//! directly, which works on every platform.
+//!
+//! [`ApplicationHandler`]: crate::application::ApplicationHandler
+pub use winit_headless::{HeadlessHandle, MonitorAttributes, ScriptedEvent, WindowState};
+
+use crate::event_loop::{ActiveEventLoop, EventLoop, EventLoopBuilder};
+
+/// Additional methods on [`ActiveEventLoop`] that are specific to the headless backend.
+pub trait ActiveEventLoopExtHeadless {
+    /// True if the [`ActiveEventLoop`] uses the headless backend.
+    fn is_headless(&self) -> bool;
+
+    /// Returns the handle to script the headless backend, `None` when using another backend.
+    fn headless_handle(&self) -> Option<HeadlessHandle>;
+}
+
+impl ActiveEventLoopExtHeadless for dyn ActiveEventLoop + '_ {
+    #[inline]
+    fn is_headless(&self) -> bool {
+        self.cast_ref::<winit_headless::ActiveEventLoop>().is_some()
+    }
+
+    #[inline]
+    fn headless_handle(&self) -> Option<HeadlessHandle> {
+        self.cast_ref::<winit_headless::ActiveEventLoop>().map(|event_loop| event_loop.handle())
+    }
+}
+
+/// Additional methods on [`EventLoop`] that are specific to the headless backend.
+pub trait EventLoopExtHeadless {
+    /// True if the [`EventLoop`] uses the headless backend.
+    fn is_headless(&self) -> bool;
+
+    /// Returns the handle to script the headless backend, `None` when using another backend.
+    fn headless_handle(&self) -> Option<HeadlessHandle>;
+}
+
+impl EventLoopExtHeadless for EventLoop {
+    #[inline]
+    fn is_headless(&self) -> bool {
+        self.event_loop.headless().is_some()
+    }
+
+    #[inline]
+    fn headless_handle(&self) -> Option<HeadlessHandle> {
+        self.event_loop.headless().map(|event_loop| event_loop.handle())
+    }
+}
+
+/// Additional methods on [`EventLoopBuilder`] that are specific to the headless backend.
+pub trait EventLoopBuilderExtHeadless {
+    /// Force using the headless backend.
+    ///
+    /// Unlike the other backends, the headless event loop may be created on any thread.
+    fn with_headless(&mut self) -> &mut Self;
+}
+
+impl EventLoopBuilderExtHeadless for EventLoopBuilder {
+    #[inline]
+    fn with_headless(&mut self) -> &mut Self {
+        self.platform_specific.forced_backend = Some(crate::platform_impl::Backend::Headless);
+        self
+    }
+}
@@ -4,6 +4,8 @@
 
 #[cfg(android_platform)]
 pub mod android;
+#[cfg(headless_platform)]
+pub mod headless;
 #[cfg(ios_platform)]
 pub mod ios;
 #[cfg(macos_platform)]
@@ -47,6 +47,13 @@ pub trait WindowExtStartupNotify {
     ///
     /// The token will be delivered inside
     fn request_activation_token(&self) -> Result<AsyncRequestSerial, RequestError>;
+
+    /// Activate the window with a token received from another process.
+    ///
+    /// This is how a single-instance application raises its window when it's launched again: the
+    /// new process reads the token from its environment and sends it to the running instance,
+    /// e.g. over D-Bus, which then activates its window with it.
+    fn activate_with_token(&self, token: ActivationToken) -> Result<(), RequestError>;
 }
 
 pub trait WindowAttributesExtStartupNotify {
@@ -86,6 +93,21 @@ impl WindowExtStartupNotify for dyn Window + '_ {
 
         Err(NotSupportedError::new("startup notify is not supported").into())
     }
+
+    fn activate_with_token(&self, token: ActivationToken) -> Result<(), RequestError> {
+        #[cfg(wayland_platform)]
+        if let Some(window) = self.cast_ref::<crate::platform_impl::wayland::Window>() {
+            return window.activate_with_token(token);
+        }
+
+        #[cfg(x11_platform)]
+        if let Some(window) = self.cast_ref::<crate::platform_impl::x11::window::Window>() {
+            return window.activate_with_token(token);
+        }
+
+        let _ = token;
+        Err(NotSupportedError::new("startup notify is not supported").into())
+    }
 }
 
 /// Remove the activation environment variables from the current process.
@@ -16,17 +16,75 @@
 use std::ffi::c_void;
 use std::ptr::NonNull;
 
+pub use winit_core::application::wayland::{
+    ApplicationHandlerExtWayland, DecorationMode, InputMethodEvent,
+};
 use winit_core::window::PlatformWindowAttributes;
 
+use crate::dpi::PhysicalInsets;
+use crate::error::{NotSupportedError, RequestError};
+use crate::event::ElementState;
 use crate::event_loop::{ActiveEventLoop, EventLoop, EventLoopBuilder};
+use crate::keyboard::{ModifiersState, PhysicalKey};
+use crate::monitor::MonitorHandle;
 use crate::platform_impl::wayland::Window;
 use crate::platform_impl::ApplicationName;
 use crate::window::{ActivationToken, Window as CoreWindow};
 
 /// Additional methods on [`ActiveEventLoop`] that are specific to Wayland.
+///
+/// The `input_method_*` and `virtual_keyboard_*` methods require the input method to be
+/// requested with [`EventLoopBuilderExtWayland::with_input_method`], and return
+/// [`RequestError::NotSupported`] when the compositor doesn't support it.
 pub trait ActiveEventLoopExtWayland {
     /// True if the [`ActiveEventLoop`] uses Wayland.
     fn is_wayland(&self) -> bool;
+
+    /// Insert the text at the cursor of the focused text input, replacing the selection.
+    ///
+    /// The request is applied with [`input_method_commit`].
+    ///
+    /// [`input_method_commit`]: Self::input_method_commit
+    fn input_method_commit_string(&self, text: &str) -> Result<(), RequestError>;
+
+    /// Show the text being composed at the cursor of the focused text input.
+    ///
+    /// The cursor range is in bytes, the cursor is hidden when it's `None`. The request is
+    /// applied with [`input_method_commit`].
+    ///
+    /// [`input_method_commit`]: Self::input_method_commit
+    fn input_method_set_preedit(
+        &self,
+        text: &str,
+        cursor_range: Option<(usize, usize)>,
+    ) -> Result<(), RequestError>;
+
+    /// Delete the text around the cursor of the focused text input, in bytes.
+    ///
+    /// The request is applied with [`input_method_commit`].
+    ///
+    /// [`input_method_commit`]: Self::input_method_commit
+    fn input_method_delete_surrounding(
+        &self,
+        before_bytes: usize,
+        after_bytes: usize,
+    ) -> Result<(), RequestError>;
+
+    /// Apply the pending requests of the input method to the focused text input.
+    fn input_method_commit(&self) -> Result<(), RequestError>;
+
+    /// Press or release the key on the virtual keyboard.
+    ///
+    /// The virtual keyboard uses the default keymap of the system, which can be overridden with
+    /// the `XKB_DEFAULT_*` environment variables.
+    fn virtual_keyboard_key(
+        &self,
+        key: PhysicalKey,
+        state: ElementState,
+    ) -> Result<(), RequestError>;
+
+    /// Set the modifiers held on the virtual keyboard.
+    fn virtual_keyboard_modifiers(&self, modifiers: ModifiersState) -> Result<(), RequestError>;
 }
 
 impl ActiveEventLoopExtWayland for dyn ActiveEventLoop + '_ {
@@ -34,6 +92,56 @@ impl ActiveEventLoopExtWayland for dyn ActiveEventLoop + '_ {
     fn is_wayland(&self) -> bool {
         self.cast_ref::<crate::platform_impl::wayland::ActiveEventLoop>().is_some()
     }
+
+    fn input_method_commit_string(&self, text: &str) -> Result<(), RequestError> {
+        wayland_event_loop(self)?.state.borrow().input_method()?.commit_string(text);
+        Ok(())
+    }
+
+    fn input_method_set_preedit(
+        &self,
+        text: &str,
+        cursor_range: Option<(usize, usize)>,
+    ) -> Result<(), RequestError> {
+        wayland_event_loop(self)?.state.borrow().input_method()?.set_preedit(text, cursor_range);
+        Ok(())
+    }
+
+    fn input_method_delete_surrounding(
+        &self,
+        before_bytes: usize,
+        after_bytes: usize,
+    ) -> Result<(), RequestError> {
+        let state = wayland_event_loop(self)?.state.borrow();
+        state.input_method()?.delete_surrounding(before_bytes, after_bytes);
+        Ok(())
+    }
+
+    fn input_method_commit(&self) -> Result<(), RequestError> {
+        wayland_event_loop(self)?.state.borrow().input_method()?.commit();
+        Ok(())
+    }
+
+    fn virtual_keyboard_key(
+        &self,
+        key: PhysicalKey,
+        state: ElementState,
+    ) -> Result<(), RequestError> {
+        wayland_event_loop(self)?.state.borrow().virtual_keyboard()?.key(key, state)
+    }
+
+    fn virtual_keyboard_modifiers(&self, modifiers: ModifiersState) -> Result<(), RequestError> {
+        wayland_event_loop(self)?.state.borrow().virtual_keyboard()?.modifiers(modifiers);
+        Ok(())
+    }
+}
+
+fn wayland_event_loop(
+    event_loop: &dyn ActiveEventLoop,
+) -> Result<&crate::platform_impl::wayland::ActiveEventLoop, RequestError> {
+    event_loop
+        .cast_ref::<crate::platform_impl::wayland::ActiveEventLoop>()
+        .ok_or_else(|| NotSupportedError::new("the event loop doesn't use Wayland").into())
 }
 
 /// Additional methods on [`EventLoop`] that are specific to Wayland.
@@ -59,6 +167,28 @@ pub trait EventLoopBuilderExtWayland {
     /// By default, the window is only allowed to be created on the main
     /// thread, to make platform compatibility easier.
     fn with_any_thread(&mut self, any_thread: bool) -> &mut Self;
+
+    /// Whether to synthesize the repeats of held keys.
+    ///
+    /// Wayland compositors only report the press and the release of keys, winit synthesizes
+    /// [`KeyEvent`]s with `repeat` set at the rate and delay advertised by the compositor.
+    /// Disabling this only delivers the press and the release of keys, which suits games.
+    ///
+    /// The repeat preferences can be read with [`ActiveEventLoop::input_preferences`].
+    ///
+    /// [`KeyEvent`]: crate::event::KeyEvent
+    /// [`ActiveEventLoop::input_preferences`]: crate::event_loop::ActiveEventLoop::input_preferences
+    fn with_key_repeat(&mut self, key_repeat: bool) -> &mut Self;
+
+    /// Act as the input method of the compositor, to build on-screen keyboards.
+    ///
+    /// This binds `zwp_input_method_v2` and `zwp_virtual_keyboard_v1` on every seat, the state of
+    /// the focused text input is reported with [`ApplicationHandlerExtWayland::input_method_event`]
+    /// and the text and keys are sent with the methods of [`ActiveEventLoopExtWayland`].
+    ///
+    /// Only one client can be the input method of a seat, [`InputMethodEvent::Unavailable`] is
+    /// reported when another one already is. By default, the input method isn't requested.
+    fn with_input_method(&mut self, input_method: bool) -> &mut Self;
 }
 
 impl EventLoopBuilderExtWayland for EventLoopBuilder {
@@ -73,6 +203,18 @@ impl EventLoopBuilderExtWayland for EventLoopBuilder {
         self.platform_specific.any_thread = any_thread;
         self
     }
+
+    #[inline]
+    fn with_key_repeat(&mut self, key_repeat: bool) -> &mut Self {
+        self.platform_specific.key_repeat = key_repeat;
+        self
+    }
+
+    #[inline]
+    fn with_input_method(&mut self, input_method: bool) -> &mut Self {
+        self.platform_specific.wayland_input_method = input_method;
+        self
+    }
 }
 
 /// Additional methods on [`Window`] that are specific to Wayland.
@@ -81,6 +223,18 @@ impl EventLoopBuilderExtWayland for EventLoopBuilder {
 pub trait WindowExtWayland {
     /// Returns `xdg_toplevel` of the window or [`None`] if the window is X11 window.
     fn xdg_toplevel(&self) -> Option<NonNull<c_void>>;
+
+    /// The decoration mode negotiated with the compositor.
+    ///
+    /// Returns [`None`] until the window is configured, for windows which aren't toplevels, and
+    /// if the window is X11 window. Changes are reported with
+    /// [`ApplicationHandlerExtWayland::decoration_mode_changed`].
+    fn decoration_mode(&self) -> Option<DecorationMode>;
+
+    /// The space taken by the frame drawn by winit around the surface.
+    ///
+    /// This is empty with server-side decorations, or when the decorations are disabled.
+    fn frame_insets(&self) -> PhysicalInsets<u32>;
 }
 
 impl WindowExtWayland for dyn CoreWindow + '_ {
@@ -88,6 +242,90 @@ impl WindowExtWayland for dyn CoreWindow + '_ {
     fn xdg_toplevel(&self) -> Option<NonNull<c_void>> {
         self.cast_ref::<Window>()?.xdg_toplevel()
     }
+
+    #[inline]
+    fn decoration_mode(&self) -> Option<DecorationMode> {
+        self.cast_ref::<Window>()?.decoration_mode()
+    }
+
+    #[inline]
+    fn frame_insets(&self) -> PhysicalInsets<u32> {
+        match self.cast_ref::<Window>() {
+            Some(window) => window.frame_insets(),
+            None => PhysicalInsets::new(0, 0, 0, 0),
+        }
+    }
+}
+
+/// The decoration mode requested to the compositor.
+///
+/// The compositor has the final say, unless client-side decorations are required, and the
+/// outcome is reported with [`ApplicationHandlerExtWayland::decoration_mode_changed`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecorationPreference {
+    /// Prefer server-side decorations, winit draws its frame when the compositor doesn't
+    /// support them.
+    ///
+    /// Client-side decorations are preferred instead when the window is created without
+    /// decorations, so that the compositor doesn't draw them.
+    #[default]
+    PreferServer,
+
+    /// Prefer client-side decorations, the compositor may still enforce server-side ones.
+    PreferClient,
+
+    /// Always use client-side decorations, by not negotiating them with the compositor at all.
+    ///
+    /// This is useful to draw a custom titlebar with [`Window::set_decorations`] set to `false`,
+    /// using [`Window::drag_window`], [`Window::drag_resize_window`] and
+    /// [`Window::show_window_menu`] to implement its interactions.
+    ///
+    /// [`Window::set_decorations`]: crate::window::Window::set_decorations
+    /// [`Window::drag_window`]: crate::window::Window::drag_window
+    /// [`Window::drag_resize_window`]: crate::window::Window::drag_resize_window
+    /// [`Window::show_window_menu`]: crate::window::Window::show_window_menu
+    RequireClient,
+}
+
+/// The layer of a layer shell surface, in the stacking order from the bottom.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    /// Below the windows, like a wallpaper.
+    Background,
+    /// Below the windows, like a desktop widget.
+    Bottom,
+    /// Above the windows, like a panel or a dock.
+    #[default]
+    Top,
+    /// Above everything, including fullscreen windows, like a notification or a lock screen.
+    Overlay,
+}
+
+bitflags::bitflags! {
+    /// The edges of the output a layer shell surface is anchored to.
+    ///
+    /// Anchoring to two opposite edges stretches the surface between them, anchoring to no edge
+    /// or to all of them centers it.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Anchor: u32 {
+        const TOP    = 1 << 0;
+        const BOTTOM = 1 << 1;
+        const LEFT   = 1 << 2;
+        const RIGHT  = 1 << 3;
+    }
+}
+
+/// How a layer shell surface receives the keyboard focus.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyboardInteractivity {
+    /// The surface never gets the keyboard focus.
+    #[default]
+    None,
+    /// The surface takes the keyboard focus as long as it's mapped, on the top and overlay
+    /// layers.
+    Exclusive,
+    /// The surface gets the keyboard focus like regular windows, for example when clicked.
+    OnDemand,
 }
 
 /// Window attributes methods specific to Wayland.
@@ -95,6 +333,13 @@ impl WindowExtWayland for dyn CoreWindow + '_ {
 pub struct WindowAttributesWayland {
     pub(crate) name: Option<ApplicationName>,
     pub(crate) activation_token: Option<ActivationToken>,
+    pub(crate) layer: Option<Layer>,
+    pub(crate) anchor: Anchor,
+    pub(crate) exclusive_zone: i32,
+    pub(crate) margin: (i32, i32, i32, i32),
+    pub(crate) keyboard_interactivity: KeyboardInteractivity,
+    pub(crate) output: Option<MonitorHandle>,
+    pub(crate) decoration_preference: DecorationPreference,
 }
 
 impl WindowAttributesWayland {
@@ -117,6 +362,80 @@ impl WindowAttributesWayland {
         self.activation_token = Some(token);
         self
     }
+
+    /// Build a layer shell surface on the given layer, instead of a regular window.
+    ///
+    /// Layer shell surfaces are used for desktop components, like panels, docks, wallpapers
+    /// and notifications, and are placed by the compositor relative to the edges of an output.
+    /// The `general` part of [`with_name`] is used as the namespace of the surface.
+    ///
+    /// The window requests which only apply to regular windows, like maximizing or dragging, are
+    /// ignored on layer shell surfaces. Creating the window fails when the compositor doesn't
+    /// support `zwlr_layer_shell_v1`.
+    ///
+    /// [`with_name`]: Self::with_name
+    #[inline]
+    pub fn with_layer(mut self, layer: Layer) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    /// Anchor the layer shell surface to the given edges of the output.
+    ///
+    /// The surface size is chosen by the compositor along the axes where it's anchored to both
+    /// edges, unless a size is requested.
+    ///
+    /// By default, the surface isn't anchored and is centered on the output.
+    #[inline]
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Reserve space along the anchored edge, so other surfaces aren't placed over it.
+    ///
+    /// A positive zone is the logical size to reserve, which is usually the size of a panel, zero
+    /// makes the surface move out of the way of the other exclusive zones, and `-1` places the
+    /// surface over them.
+    ///
+    /// The default is `0`.
+    #[inline]
+    pub fn with_exclusive_zone(mut self, exclusive_zone: i32) -> Self {
+        self.exclusive_zone = exclusive_zone;
+        self
+    }
+
+    /// The logical distance between the layer shell surface and the edges it's anchored to.
+    #[inline]
+    pub fn with_margin(mut self, top: i32, right: i32, bottom: i32, left: i32) -> Self {
+        self.margin = (top, right, bottom, left);
+        self
+    }
+
+    /// How the layer shell surface receives the keyboard focus.
+    #[inline]
+    pub fn with_keyboard_interactivity(mut self, interactivity: KeyboardInteractivity) -> Self {
+        self.keyboard_interactivity = interactivity;
+        self
+    }
+
+    /// The output the layer shell surface is shown on.
+    ///
+    /// By default, the compositor picks the output, usually the focused one.
+    #[inline]
+    pub fn with_output(mut self, monitor: MonitorHandle) -> Self {
+        self.output = Some(monitor);
+        self
+    }
+
+    /// The decoration mode to request to the compositor.
+    ///
+    /// The default is [`DecorationPreference::PreferServer`].
+    #[inline]
+    pub fn with_decoration_preference(mut self, preference: DecorationPreference) -> Self {
+        self.decoration_preference = preference;
+        self
+    }
 }
 
 impl PlatformWindowAttributes for WindowAttributesWayland {
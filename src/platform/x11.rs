@@ -1,6 +1,11 @@
 //! # X11
+use std::collections::BTreeMap;
+use std::time::Duration;
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[doc(inline)]
+pub use winit_core::application::x11::ApplicationHandlerExtX11;
 use winit_core::window::{ActivationToken, PlatformWindowAttributes, Window as CoreWindow};
 
 use crate::dpi::Size;
@@ -52,6 +57,24 @@ pub enum WindowType {
     Normal,
 }
 
+/// The protocol used to talk to the input method.
+///
+/// See [`EventLoopBuilderExtX11::with_ime_backend`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ImeBackend {
+    /// Use IBus or Fcitx5 over D-Bus when `XMODIFIERS`, `GTK_IM_MODULE` or `QT_IM_MODULE` name
+    /// one of them, and XIM otherwise.
+    #[default]
+    Auto,
+    /// The X Input Method protocol.
+    Xim,
+    /// IBus over D-Bus, directly or through its portal.
+    IBus,
+    /// Fcitx5 over D-Bus.
+    Fcitx,
+}
+
 /// The first argument in the provided hook will be the pointer to `XDisplay`
 /// and the second one the pointer to [`XErrorEvent`]. The returned `bool` is an
 /// indicator whether the error was handled by the callback.
@@ -83,10 +106,184 @@ pub fn register_xlib_error_hook(hook: XlibErrorHook) {
     crate::platform_impl::x11::XLIB_ERROR_HOOKS.lock().unwrap().push(hook);
 }
 
+/// The value of a single XSETTINGS entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum XSettingValue {
+    /// An integer setting.
+    Integer(i32),
+    /// A string setting.
+    String(String),
+    /// A color setting, with 16 bits per channel.
+    Color { red: u16, green: u16, blue: u16, alpha: u16 },
+}
+
+/// The hinting style of fonts, from the `Xft/HintStyle` setting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FontHintStyle {
+    /// No hinting.
+    None,
+    /// Slight hinting, only vertically.
+    Slight,
+    /// Medium hinting.
+    Medium,
+    /// Full hinting.
+    Full,
+}
+
+/// The order of the subpixels for font antialiasing, from the `Xft/RGBA` setting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SubpixelOrder {
+    /// Grayscale antialiasing.
+    None,
+    /// Horizontal red, green, blue.
+    Rgb,
+    /// Horizontal blue, green, red.
+    Bgr,
+    /// Vertical red, green, blue.
+    VerticalRgb,
+    /// Vertical blue, green, red.
+    VerticalBgr,
+}
+
+/// The settings of the screen, shared by the settings manager through the
+/// [XSETTINGS](https://specifications.freedesktop.org/xsettings-spec/latest/) protocol.
+///
+/// The whole table can be inspected by name, with typed accessors for the [common settings].
+///
+/// [common settings]: https://www.freedesktop.org/wiki/Specifications/XSettingsRegistry/
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct XSettings {
+    pub(crate) settings: BTreeMap<String, XSettingValue>,
+}
+
+impl XSettings {
+    /// The setting with the given name, like `Net/DoubleClickTime`.
+    pub fn get(&self, name: &str) -> Option<&XSettingValue> {
+        self.settings.get(name)
+    }
+
+    /// Iterate over all the settings, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &XSettingValue)> {
+        self.settings.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// The maximum time between the clicks of a double click, from `Net/DoubleClickTime`.
+    pub fn double_click_time(&self) -> Option<Duration> {
+        self.millis("Net/DoubleClickTime")
+    }
+
+    /// The maximum distance in pixels between the clicks of a double click, from
+    /// `Net/DoubleClickDistance`.
+    pub fn double_click_distance(&self) -> Option<u32> {
+        self.unsigned("Net/DoubleClickDistance")
+    }
+
+    /// The distance in pixels the pointer has to move before starting a drag, from
+    /// `Net/DndDragThreshold`.
+    pub fn drag_threshold(&self) -> Option<u32> {
+        self.unsigned("Net/DndDragThreshold")
+    }
+
+    /// Whether the text cursor should blink, from `Net/CursorBlink`.
+    pub fn cursor_blink(&self) -> Option<bool> {
+        self.boolean("Net/CursorBlink")
+    }
+
+    /// The length of a blink cycle of the text cursor, from `Net/CursorBlinkTime`.
+    pub fn cursor_blink_time(&self) -> Option<Duration> {
+        self.millis("Net/CursorBlinkTime")
+    }
+
+    /// The name of the cursor theme, from `Gtk/CursorThemeName`.
+    pub fn cursor_theme_name(&self) -> Option<&str> {
+        self.string("Gtk/CursorThemeName")
+    }
+
+    /// The size of the cursors, from `Gtk/CursorThemeSize`.
+    pub fn cursor_theme_size(&self) -> Option<u32> {
+        self.unsigned("Gtk/CursorThemeSize")
+    }
+
+    /// Whether fonts should be antialiased, from `Xft/Antialias`.
+    pub fn font_antialiasing(&self) -> Option<bool> {
+        self.boolean("Xft/Antialias")
+    }
+
+    /// Whether fonts should be hinted, from `Xft/Hinting`.
+    pub fn font_hinting(&self) -> Option<bool> {
+        self.boolean("Xft/Hinting")
+    }
+
+    /// The hinting style of fonts, from `Xft/HintStyle`.
+    pub fn font_hint_style(&self) -> Option<FontHintStyle> {
+        match self.string("Xft/HintStyle")? {
+            "hintnone" => Some(FontHintStyle::None),
+            "hintslight" => Some(FontHintStyle::Slight),
+            "hintmedium" => Some(FontHintStyle::Medium),
+            "hintfull" => Some(FontHintStyle::Full),
+            _ => None,
+        }
+    }
+
+    /// The subpixel order for font antialiasing, from `Xft/RGBA`.
+    pub fn font_rgba(&self) -> Option<SubpixelOrder> {
+        match self.string("Xft/RGBA")? {
+            "none" => Some(SubpixelOrder::None),
+            "rgb" => Some(SubpixelOrder::Rgb),
+            "bgr" => Some(SubpixelOrder::Bgr),
+            "vrgb" => Some(SubpixelOrder::VerticalRgb),
+            "vbgr" => Some(SubpixelOrder::VerticalBgr),
+            _ => None,
+        }
+    }
+
+    fn integer(&self, name: &str) -> Option<i32> {
+        match self.get(name)? {
+            XSettingValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            XSettingValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn unsigned(&self, name: &str) -> Option<u32> {
+        self.integer(name)?.try_into().ok()
+    }
+
+    fn millis(&self, name: &str) -> Option<Duration> {
+        self.unsigned(name).map(|millis| Duration::from_millis(millis.into()))
+    }
+
+    /// Booleans are stored as integers, with `-1` meaning the default is used.
+    fn boolean(&self, name: &str) -> Option<bool> {
+        match self.integer(name)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
 /// Additional methods on [`ActiveEventLoop`] that are specific to X11.
 pub trait ActiveEventLoopExtX11 {
     /// True if the [`ActiveEventLoop`] uses X11.
     fn is_x11(&self) -> bool;
+
+    /// The XSETTINGS of the screen.
+    ///
+    /// Returns `None` when the [`ActiveEventLoop`] doesn't use X11. The settings are empty when
+    /// no settings manager is running.
+    ///
+    /// Changes are reported with [`ApplicationHandlerExtX11::xsettings_changed`].
+    fn xsettings(&self) -> Option<XSettings>;
 }
 
 impl ActiveEventLoopExtX11 for dyn ActiveEventLoop + '_ {
@@ -94,6 +291,12 @@ impl ActiveEventLoopExtX11 for dyn ActiveEventLoop + '_ {
     fn is_x11(&self) -> bool {
         self.cast_ref::<crate::platform_impl::x11::ActiveEventLoop>().is_some()
     }
+
+    #[inline]
+    fn xsettings(&self) -> Option<XSettings> {
+        let event_loop = self.cast_ref::<crate::platform_impl::x11::ActiveEventLoop>()?;
+        Some(event_loop.xsettings())
+    }
 }
 
 /// Additional methods on [`EventLoop`] that are specific to X11.
@@ -105,7 +308,7 @@ pub trait EventLoopExtX11 {
 impl EventLoopExtX11 for EventLoop {
     #[inline]
     fn is_x11(&self) -> bool {
-        !self.event_loop.is_wayland()
+        self.event_loop.is_x11()
     }
 }
 
@@ -119,6 +322,27 @@ pub trait EventLoopBuilderExtX11 {
     /// By default, the window is only allowed to be created on the main
     /// thread, to make platform compatibility easier.
     fn with_any_thread(&mut self, any_thread: bool) -> &mut Self;
+
+    /// Whether to deliver the repeats of held keys.
+    ///
+    /// By default, holding a key delivers [`KeyEvent`]s with `repeat` set, at the rate configured
+    /// by the user. Disabling this only delivers the press and the release of keys, which suits
+    /// games. The repeats are identified with XKB detectable auto-repeat.
+    ///
+    /// The repeat preferences can be read with [`ActiveEventLoop::input_preferences`].
+    ///
+    /// [`KeyEvent`]: crate::event::KeyEvent
+    /// [`ActiveEventLoop::input_preferences`]: crate::event_loop::ActiveEventLoop::input_preferences
+    fn with_key_repeat(&mut self, key_repeat: bool) -> &mut Self;
+
+    /// The protocol used to talk to the input method.
+    ///
+    /// The D-Bus protocols of IBus and Fcitx5 don't depend on the locale, and position the
+    /// candidate window more reliably than XIM. When the chosen input method isn't running, XIM is
+    /// used instead.
+    ///
+    /// The default is [`ImeBackend::Auto`].
+    fn with_ime_backend(&mut self, backend: ImeBackend) -> &mut Self;
 }
 
 impl EventLoopBuilderExtX11 for EventLoopBuilder {
@@ -133,6 +357,18 @@ impl EventLoopBuilderExtX11 for EventLoopBuilder {
         self.platform_specific.any_thread = any_thread;
         self
     }
+
+    #[inline]
+    fn with_key_repeat(&mut self, key_repeat: bool) -> &mut Self {
+        self.platform_specific.key_repeat = key_repeat;
+        self
+    }
+
+    #[inline]
+    fn with_ime_backend(&mut self, backend: ImeBackend) -> &mut Self {
+        self.platform_specific.x11_ime_backend = backend;
+        self
+    }
 }
 
 /// Additional methods on [`Window`] that are specific to X11.
@@ -13,17 +13,19 @@ use tracing::{debug, trace, warn};
 use winit_core::application::ApplicationHandler;
 use winit_core::cursor::{Cursor, CustomCursor, CustomCursorSource};
 use winit_core::error::{EventLoopError, NotSupportedError, RequestError};
-use winit_core::event::{self, DeviceId, FingerId, Force, StartCause, SurfaceSizeWriter};
+use winit_core::event::{
+    self, DeviceId, DeviceInfo, FingerId, Force, StartCause, SurfaceSizeWriter,
+};
 use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::{
     ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents,
-    EventLoopProxy as CoreEventLoopProxy, EventLoopProxyProvider,
+    EventLoopProxy as CoreEventLoopProxy, EventLoopProxyProvider, InputPreferences,
     OwnedDisplayHandle as CoreOwnedDisplayHandle,
 };
 use winit_core::monitor::{Fullscreen, MonitorHandle as CoreMonitorHandle};
 use winit_core::window::{
-    self, CursorGrabMode, ImePurpose, ResizeDirection, Theme, Window as CoreWindow,
-    WindowAttributes, WindowButtons, WindowId, WindowLevel,
+    self, CursorGrabMode, ImeHint, ImePurpose, ResizeDirection, Theme, Window as CoreWindow,
+    WindowAttributes, WindowButtons, WindowCapabilities, WindowId, WindowLevel,
 };
 
 mod keycodes;
@@ -703,12 +705,42 @@ impl RootActiveEventLoop for ActiveEventLoop {
         None
     }
 
+    fn input_preferences(&self) -> InputPreferences {
+        InputPreferences::default()
+    }
+
+    fn clipboard(&self) -> Option<&dyn winit_core::clipboard::Clipboard> {
+        None
+    }
+
+    fn primary_selection(&self) -> Option<&dyn winit_core::clipboard::Clipboard> {
+        None
+    }
+
+    fn set_drag_action(
+        &self,
+        _: Option<winit_core::event::DragAction>,
+    ) -> Result<(), RequestError> {
+        Err(NotSupportedError::new("set_drag_action is not supported").into())
+    }
+
+    fn request_drag_data(
+        &self,
+        _: &str,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(NotSupportedError::new("request_drag_data is not supported").into())
+    }
+
     fn listen_device_events(&self, _allowed: DeviceEvents) {}
 
     fn set_control_flow(&self, control_flow: ControlFlow) {
         self.control_flow.set(control_flow)
     }
 
+    fn available_devices(&self) -> Box<dyn Iterator<Item = (DeviceId, DeviceInfo)>> {
+        Box::new(std::iter::empty())
+    }
+
     fn control_flow(&self) -> ControlFlow {
         self.control_flow.get()
     }
@@ -899,6 +931,10 @@ impl CoreWindow for Window {
         WindowButtons::all()
     }
 
+    fn window_capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities::empty()
+    }
+
     fn set_minimized(&self, _minimized: bool) {}
 
     fn is_minimized(&self) -> Option<bool> {
@@ -941,6 +977,10 @@ impl CoreWindow for Window {
 
     fn set_ime_purpose(&self, _purpose: ImePurpose) {}
 
+    fn set_ime_surrounding_text(&self, _text: &str, _cursor: usize, _anchor: usize) {}
+
+    fn set_ime_hints(&self, _hints: ImeHint) {}
+
     fn focus_window(&self) {}
 
     fn request_user_attention(&self, _request_type: Option<window::UserAttentionType>) {}
@@ -965,6 +1005,15 @@ impl CoreWindow for Window {
         Err(NotSupportedError::new("drag_resize_window").into())
     }
 
+    fn start_drag(
+        &self,
+        _: winit_core::clipboard::ClipboardContents,
+        _: &[winit_core::event::DragAction],
+        _: Option<winit_core::cursor::CustomCursorSource>,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(NotSupportedError::new("start_drag is not supported").into())
+    }
+
     #[inline]
     fn show_window_menu(&self, _position: Position) {}
 
@@ -17,11 +17,13 @@ use objc2_foundation::{NSNotificationCenter, NSObjectProtocol};
 use rwh_06::HasDisplayHandle;
 use winit_core::application::ApplicationHandler;
 use winit_core::cursor::{CustomCursor as CoreCustomCursor, CustomCursorSource};
-use winit_core::error::{EventLoopError, RequestError};
+use winit_core::error::{EventLoopError, NotSupportedError, RequestError};
+use winit_core::event::{DeviceId, DeviceInfo};
 use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::{
     ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents,
-    EventLoopProxy as CoreEventLoopProxy, OwnedDisplayHandle as CoreOwnedDisplayHandle,
+    EventLoopProxy as CoreEventLoopProxy, InputPreferences,
+    OwnedDisplayHandle as CoreOwnedDisplayHandle,
 };
 use winit_core::monitor::MonitorHandle as CoreMonitorHandle;
 use winit_core::window::Theme;
@@ -141,6 +143,36 @@ impl RootActiveEventLoop for ActiveEventLoop {
         }
     }
 
+    fn input_preferences(&self) -> InputPreferences {
+        InputPreferences::default()
+    }
+
+    fn available_devices(&self) -> Box<dyn Iterator<Item = (DeviceId, DeviceInfo)>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn clipboard(&self) -> Option<&dyn winit_core::clipboard::Clipboard> {
+        None
+    }
+
+    fn primary_selection(&self) -> Option<&dyn winit_core::clipboard::Clipboard> {
+        None
+    }
+
+    fn set_drag_action(
+        &self,
+        _: Option<winit_core::event::DragAction>,
+    ) -> Result<(), RequestError> {
+        Err(NotSupportedError::new("set_drag_action is not supported").into())
+    }
+
+    fn request_drag_data(
+        &self,
+        _: &str,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(NotSupportedError::new("request_drag_data is not supported").into())
+    }
+
     fn set_control_flow(&self, control_flow: ControlFlow) {
         self.app_state.set_control_flow(control_flow)
     }
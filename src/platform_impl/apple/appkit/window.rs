@@ -13,8 +13,8 @@ use winit_core::error::RequestError;
 use winit_core::icon::Icon;
 use winit_core::monitor::{Fullscreen, MonitorHandle as CoreMonitorHandle};
 use winit_core::window::{
-    ImePurpose, Theme, UserAttentionType, Window as CoreWindow, WindowAttributes, WindowButtons,
-    WindowId, WindowLevel,
+    ImeHint, ImePurpose, Theme, UserAttentionType, Window as CoreWindow, WindowAttributes,
+    WindowButtons, WindowCapabilities, WindowId, WindowLevel,
 };
 
 use super::event_loop::ActiveEventLoop;
@@ -193,6 +193,10 @@ impl CoreWindow for Window {
         self.maybe_wait_on_main(|delegate| delegate.enabled_buttons())
     }
 
+    fn window_capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities::MAXIMIZE | WindowCapabilities::FULLSCREEN | WindowCapabilities::MINIMIZE
+    }
+
     fn set_minimized(&self, minimized: bool) {
         self.maybe_wait_on_main(|delegate| delegate.set_minimized(minimized));
     }
@@ -245,6 +249,10 @@ impl CoreWindow for Window {
         self.maybe_wait_on_main(|delegate| delegate.set_ime_purpose(purpose));
     }
 
+    fn set_ime_surrounding_text(&self, _text: &str, _cursor: usize, _anchor: usize) {}
+
+    fn set_ime_hints(&self, _hints: ImeHint) {}
+
     fn focus_window(&self) {
         self.maybe_wait_on_main(|delegate| delegate.focus_window());
     }
@@ -303,6 +311,15 @@ impl CoreWindow for Window {
         Ok(self.maybe_wait_on_main(|delegate| delegate.drag_resize_window(direction))?)
     }
 
+    fn start_drag(
+        &self,
+        _: winit_core::clipboard::ClipboardContents,
+        _: &[winit_core::event::DragAction],
+        _: Option<winit_core::cursor::CustomCursorSource>,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(winit_core::error::NotSupportedError::new("start_drag is not supported").into())
+    }
+
     fn show_window_menu(&self, position: Position) {
         self.maybe_wait_on_main(|delegate| delegate.show_window_menu(position))
     }
@@ -366,7 +366,7 @@ define_class!(
             let position =
                 LogicalPosition::<f64>::from((dl.x, dl.y)).to_physical(self.scale_factor());
 
-            self.queue_event(WindowEvent::DragEntered { paths, position });
+            self.queue_event(WindowEvent::DragEntered { paths, mime_types: Vec::new(), position });
 
             true
         }
@@ -19,9 +19,11 @@ use rwh_06::HasDisplayHandle;
 use winit_core::application::ApplicationHandler;
 use winit_core::cursor::{CustomCursor, CustomCursorSource};
 use winit_core::error::{EventLoopError, NotSupportedError, RequestError};
+use winit_core::event::{DeviceId, DeviceInfo};
 use winit_core::event_loop::{
     ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents,
-    EventLoopProxy as CoreEventLoopProxy, OwnedDisplayHandle as CoreOwnedDisplayHandle,
+    EventLoopProxy as CoreEventLoopProxy, InputPreferences,
+    OwnedDisplayHandle as CoreOwnedDisplayHandle,
 };
 use winit_core::monitor::MonitorHandle as CoreMonitorHandle;
 use winit_core::window::{Theme, Window as CoreWindow};
@@ -75,10 +77,40 @@ impl RootActiveEventLoop for ActiveEventLoop {
         AppState::get_mut(self.mtm).set_control_flow(control_flow)
     }
 
+    fn available_devices(&self) -> Box<dyn Iterator<Item = (DeviceId, DeviceInfo)>> {
+        Box::new(std::iter::empty())
+    }
+
     fn system_theme(&self) -> Option<Theme> {
         None
     }
 
+    fn input_preferences(&self) -> InputPreferences {
+        InputPreferences::default()
+    }
+
+    fn clipboard(&self) -> Option<&dyn winit_core::clipboard::Clipboard> {
+        None
+    }
+
+    fn primary_selection(&self) -> Option<&dyn winit_core::clipboard::Clipboard> {
+        None
+    }
+
+    fn set_drag_action(
+        &self,
+        _: Option<winit_core::event::DragAction>,
+    ) -> Result<(), RequestError> {
+        Err(NotSupportedError::new("set_drag_action is not supported").into())
+    }
+
+    fn request_drag_data(
+        &self,
+        _: &str,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(NotSupportedError::new("request_drag_data is not supported").into())
+    }
+
     fn control_flow(&self) -> ControlFlow {
         AppState::get_mut(self.mtm).control_flow()
     }
@@ -23,8 +23,9 @@ use winit_core::event::WindowEvent;
 use winit_core::icon::Icon;
 use winit_core::monitor::{Fullscreen, MonitorHandle as CoreMonitorHandle};
 use winit_core::window::{
-    CursorGrabMode, ImePurpose, ResizeDirection, Theme, UserAttentionType, Window as CoreWindow,
-    WindowAttributes, WindowButtons, WindowId, WindowLevel,
+    CursorGrabMode, ImeHint, ImePurpose, ResizeDirection, Theme, UserAttentionType,
+    Window as CoreWindow, WindowAttributes, WindowButtons, WindowCapabilities, WindowId,
+    WindowLevel,
 };
 
 use super::app_state::EventWrapper;
@@ -669,6 +670,10 @@ impl CoreWindow for Window {
         self.maybe_wait_on_main(|delegate| delegate.enabled_buttons())
     }
 
+    fn window_capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities::empty()
+    }
+
     fn set_minimized(&self, minimized: bool) {
         self.maybe_wait_on_main(|delegate| delegate.set_minimized(minimized));
     }
@@ -721,6 +726,10 @@ impl CoreWindow for Window {
         self.maybe_wait_on_main(|delegate| delegate.set_ime_purpose(purpose));
     }
 
+    fn set_ime_surrounding_text(&self, _text: &str, _cursor: usize, _anchor: usize) {}
+
+    fn set_ime_hints(&self, _hints: ImeHint) {}
+
     fn focus_window(&self) {
         self.maybe_wait_on_main(|delegate| delegate.focus_window());
     }
@@ -779,6 +788,15 @@ impl CoreWindow for Window {
         Ok(self.maybe_wait_on_main(|delegate| delegate.drag_resize_window(direction))?)
     }
 
+    fn start_drag(
+        &self,
+        _: winit_core::clipboard::ClipboardContents,
+        _: &[winit_core::event::DragAction],
+        _: Option<winit_core::cursor::CustomCursorSource>,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(NotSupportedError::new("start_drag is not supported").into())
+    }
+
     fn show_window_menu(&self, position: Position) {
         self.maybe_wait_on_main(|delegate| delegate.show_window_menu(position))
     }
@@ -0,0 +1,958 @@
+//! Minimal D-Bus client, enough to talk to the desktop services over the session bus.
+//!
+//! Only the `unix` transport and the `EXTERNAL` authentication mechanism are supported, which is
+//! what every session bus on Linux/BSD offers.
+//!
+//! This isn't using an existing crate on purpose: `zbus` requires an async executor and brings a
+//! large dependency tree, while the `dbus` crate links to `libdbus`, which winit would have to
+//! load at runtime like the other system libraries. The portal and the input methods only need
+//! method calls and signals with the basic types, which is all that is implemented here.
+
+use std::collections::VecDeque;
+use std::ffi::OsStr;
+use std::io::{self, Read, Write};
+#[cfg(x11_platform)]
+use std::os::fd::AsRawFd;
+use std::os::fd::{AsFd, BorrowedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{env, fmt};
+
+const BUS_NAME: &str = "org.freedesktop.DBus";
+const BUS_PATH: &str = "/org/freedesktop/DBus";
+const BUS_INTERFACE: &str = "org.freedesktop.DBus";
+
+/// The time given to the bus to authenticate and greet the client.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The fixed part of the message header, before the header fields.
+const FIXED_HEADER_LEN: usize = 16;
+
+/// The header fields.
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SENDER: u8 = 7;
+const FIELD_SIGNATURE: u8 = 8;
+
+/// A value in the D-Bus type system.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Byte(u8),
+    Bool(bool),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    Double(f64),
+    UnixFd(u32),
+    String(String),
+    ObjectPath(String),
+    Signature(String),
+    Variant(Box<Value>),
+    /// An array, along with the signature of its elements.
+    Array(String, Vec<Value>),
+    Struct(Vec<Value>),
+    DictEntry(Box<Value>, Box<Value>),
+}
+
+impl Value {
+    /// The signature of the value.
+    pub fn signature(&self) -> String {
+        match self {
+            Value::Byte(_) => "y".into(),
+            Value::Bool(_) => "b".into(),
+            Value::I16(_) => "n".into(),
+            Value::U16(_) => "q".into(),
+            Value::I32(_) => "i".into(),
+            Value::U32(_) => "u".into(),
+            Value::I64(_) => "x".into(),
+            Value::U64(_) => "t".into(),
+            Value::Double(_) => "d".into(),
+            Value::UnixFd(_) => "h".into(),
+            Value::String(_) => "s".into(),
+            Value::ObjectPath(_) => "o".into(),
+            Value::Signature(_) => "g".into(),
+            Value::Variant(_) => "v".into(),
+            Value::Array(signature, _) => format!("a{signature}"),
+            Value::Struct(fields) => {
+                let fields: String = fields.iter().map(Value::signature).collect();
+                format!("({fields})")
+            },
+            Value::DictEntry(key, value) => format!("{{{}{}}}", key.signature(), value.signature()),
+        }
+    }
+
+    /// The string, when the value is one.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) | Value::ObjectPath(string) | Value::Signature(string) => {
+                Some(string)
+            },
+            _ => None,
+        }
+    }
+
+    /// The unsigned integer, when the value is one.
+    pub fn as_u32(&self) -> Option<u32> {
+        match *self {
+            Value::U32(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The integer, when the value is one of any size.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Byte(value) => Some(value.into()),
+            Value::I16(value) => Some(value.into()),
+            Value::U16(value) => Some(value.into()),
+            Value::I32(value) => Some(value.into()),
+            Value::U32(value) => Some(value.into()),
+            Value::I64(value) => Some(value),
+            Value::U64(value) => value.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// The boolean, when the value is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The key and value pairs, when the value is a dictionary.
+    pub fn dict_entries(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        let entries = match self {
+            Value::Array(_, entries) => entries.as_slice(),
+            _ => &[],
+        };
+        entries.iter().filter_map(|entry| match entry {
+            Value::DictEntry(key, value) => Some((&**key, &**value)),
+            _ => None,
+        })
+    }
+
+    /// The value inside of any number of variants.
+    pub fn peel(&self) -> &Value {
+        match self {
+            Value::Variant(value) => value.peel(),
+            value => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    MethodCall = 1,
+    MethodReturn = 2,
+    Error = 3,
+    Signal = 4,
+}
+
+/// A message sent over the bus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub kind: MessageKind,
+    pub serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    pub reply_serial: Option<u32>,
+    pub destination: Option<String>,
+    pub sender: Option<String>,
+    pub body: Vec<Value>,
+}
+
+impl Message {
+    /// Create a new method call.
+    pub fn method_call(
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        body: Vec<Value>,
+    ) -> Self {
+        Self {
+            kind: MessageKind::MethodCall,
+            serial: 0,
+            path: Some(path.into()),
+            interface: Some(interface.into()),
+            member: Some(member.into()),
+            error_name: None,
+            reply_serial: None,
+            destination: Some(destination.into()),
+            sender: None,
+            body,
+        }
+    }
+
+    /// Whether this is the given signal.
+    pub fn is_signal(&self, interface: &str, member: &str) -> bool {
+        self.kind == MessageKind::Signal
+            && self.interface.as_deref() == Some(interface)
+            && self.member.as_deref() == Some(member)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut body = Writer::default();
+        for value in &self.body {
+            body.value(value);
+        }
+
+        let mut fields = Vec::new();
+        let mut field = |code: u8, value: Option<Value>| {
+            if let Some(value) = value {
+                fields
+                    .push(Value::Struct(vec![Value::Byte(code), Value::Variant(Box::new(value))]));
+            }
+        };
+        field(FIELD_PATH, self.path.clone().map(Value::ObjectPath));
+        field(FIELD_INTERFACE, self.interface.clone().map(Value::String));
+        field(FIELD_MEMBER, self.member.clone().map(Value::String));
+        field(FIELD_ERROR_NAME, self.error_name.clone().map(Value::String));
+        field(FIELD_REPLY_SERIAL, self.reply_serial.map(Value::U32));
+        field(FIELD_DESTINATION, self.destination.clone().map(Value::String));
+        field(FIELD_SENDER, self.sender.clone().map(Value::String));
+        if !self.body.is_empty() {
+            let signature: String = self.body.iter().map(Value::signature).collect();
+            field(FIELD_SIGNATURE, Some(Value::Signature(signature)));
+        }
+
+        let mut header = Writer::default();
+        header.buf.extend_from_slice(&[b'l', self.kind as u8, 0, 1]);
+        header.u32(body.buf.len() as u32);
+        header.u32(self.serial);
+        header.value(&Value::Array("(yv)".into(), fields));
+        header.align(8);
+        header.buf.extend_from_slice(&body.buf);
+        header.buf
+    }
+
+    fn decode(header: &[u8], body: &[u8]) -> io::Result<Self> {
+        let big_endian = header[0] == b'B';
+        let kind = match header[1] {
+            1 => MessageKind::MethodCall,
+            2 => MessageKind::MethodReturn,
+            3 => MessageKind::Error,
+            4 => MessageKind::Signal,
+            kind => return Err(invalid_data(format!("unknown message type {kind}"))),
+        };
+
+        let mut reader = Reader { buf: header, pos: 8, big_endian };
+        let serial = reader.u32()?;
+        let mut message = Self {
+            kind,
+            serial,
+            path: None,
+            interface: None,
+            member: None,
+            error_name: None,
+            reply_serial: None,
+            destination: None,
+            sender: None,
+            body: Vec::new(),
+        };
+
+        let mut signature = String::new();
+        let Value::Array(_, fields) = reader.value("a(yv)")? else { unreachable!() };
+        for field in fields {
+            let Value::Struct(field) = field else { unreachable!() };
+            let (Value::Byte(code), value) = (&field[0], field[1].peel()) else { unreachable!() };
+            let string = value.as_str().map(str::to_owned);
+            match *code {
+                FIELD_PATH => message.path = string,
+                FIELD_INTERFACE => message.interface = string,
+                FIELD_MEMBER => message.member = string,
+                FIELD_ERROR_NAME => message.error_name = string,
+                FIELD_REPLY_SERIAL => message.reply_serial = value.as_u32(),
+                FIELD_DESTINATION => message.destination = string,
+                FIELD_SENDER => message.sender = string,
+                FIELD_SIGNATURE => signature = string.unwrap_or_default(),
+                _ => (),
+            }
+        }
+
+        let mut reader = Reader { buf: body, pos: 0, big_endian };
+        let mut signature = signature.as_str();
+        while !signature.is_empty() {
+            let (ty, rest) = split_type(signature)?;
+            message.body.push(reader.value(ty)?);
+            signature = rest;
+        }
+
+        Ok(message)
+    }
+}
+
+/// A connection to a message bus.
+pub struct Connection {
+    stream: UnixStream,
+    serial: u32,
+    /// The bytes read past the last complete message.
+    ///
+    /// A read interrupted by the timeout leaves the partial message here, to be completed by the
+    /// next read, instead of losing track of where the messages start.
+    buf: Vec<u8>,
+    /// The messages received while waiting for a reply.
+    queue: VecDeque<Message>,
+}
+
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connection").finish_non_exhaustive()
+    }
+}
+
+impl Connection {
+    /// Connect to the session bus.
+    pub fn session() -> io::Result<Self> {
+        match env::var("DBUS_SESSION_BUS_ADDRESS") {
+            Ok(addresses) => Self::open(&addresses),
+            Err(_) => {
+                let runtime_dir = env::var_os("XDG_RUNTIME_DIR")
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no session bus"))?;
+                Self::from_stream(UnixStream::connect(PathBuf::from(runtime_dir).join("bus"))?)
+            },
+        }
+    }
+
+    /// Connect to the first usable of the bus addresses, separated by `;`.
+    pub fn open(addresses: &str) -> io::Result<Self> {
+        let stream = addresses
+            .split(';')
+            .find_map(|address| connect(address).ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no usable bus address"))?;
+        Self::from_stream(stream)
+    }
+
+    /// Authenticate and greet the bus, then wait indefinitely for the messages.
+    fn from_stream(stream: UnixStream) -> io::Result<Self> {
+        let mut connection = Self { stream, serial: 0, buf: Vec::new(), queue: VecDeque::new() };
+        // A stuck bus must not block the caller forever.
+        connection.set_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        connection.authenticate()?;
+        connection.call(Message::method_call(
+            BUS_NAME,
+            BUS_PATH,
+            BUS_INTERFACE,
+            "Hello",
+            vec![],
+        ))?;
+        connection.set_timeout(None)?;
+        Ok(connection)
+    }
+
+    /// A handle to the socket of the connection, e.g. to shut it down from another thread.
+    pub fn try_clone_stream(&self) -> io::Result<UnixStream> {
+        self.stream.try_clone()
+    }
+
+    /// Limit the time spent waiting for the messages, `None` waits indefinitely.
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    /// Send the message, returning its serial.
+    pub fn send(&mut self, mut message: Message) -> io::Result<u32> {
+        self.serial += 1;
+        message.serial = self.serial;
+        self.stream.write_all(&message.encode())?;
+        Ok(self.serial)
+    }
+
+    /// Call a method and wait for its reply.
+    pub fn call(&mut self, message: Message) -> io::Result<Vec<Value>> {
+        let serial = self.send(message)?;
+        loop {
+            let message = self.read()?;
+            if message.reply_serial != Some(serial) {
+                self.queue.push_back(message);
+                continue;
+            }
+
+            return match message.kind {
+                MessageKind::Error => {
+                    let name = message.error_name.unwrap_or_default();
+                    let description = message.body.first().and_then(Value::as_str).unwrap_or("");
+                    Err(io::Error::other(format!("{name}: {description}")))
+                },
+                _ => Ok(message.body),
+            };
+        }
+    }
+
+    /// Subscribe to the messages matching the rule, like signals.
+    pub fn add_match(&mut self, rule: &str) -> io::Result<()> {
+        let body = vec![Value::String(rule.into())];
+        self.call(Message::method_call(BUS_NAME, BUS_PATH, BUS_INTERFACE, "AddMatch", body))
+            .map(drop)
+    }
+
+    /// Wait for the next message.
+    pub fn receive(&mut self) -> io::Result<Message> {
+        match self.queue.pop_front() {
+            Some(message) => Ok(message),
+            None => self.read(),
+        }
+    }
+
+    /// The next message, without waiting when none was received yet.
+    #[cfg(x11_platform)]
+    pub fn try_receive(&mut self) -> io::Result<Option<Message>> {
+        if let Some(message) = self.queue.pop_front() {
+            return Ok(Some(message));
+        }
+        if let Some(message) = self.take_buffered()? {
+            return Ok(Some(message));
+        }
+
+        let mut fd = libc::pollfd { fd: self.stream.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        match unsafe { libc::poll(&mut fd, 1, 0) } {
+            -1 => Err(io::Error::last_os_error()),
+            0 => Ok(None),
+            // The socket is readable, so this doesn't block, but may not complete the message.
+            _ => {
+                self.fill()?;
+                self.take_buffered()
+            },
+        }
+    }
+
+    /// Whether messages were received while waiting for a reply, and are yet to be handled.
+    #[cfg(x11_platform)]
+    pub fn has_queued(&self) -> bool {
+        !self.queue.is_empty() || message_len(&self.buf).is_some_and(|len| self.buf.len() >= len)
+    }
+
+    fn authenticate(&mut self) -> io::Result<()> {
+        let uid = unsafe { libc::getuid() }.to_string();
+        let uid: String = uid.bytes().map(|byte| format!("{byte:02x}")).collect();
+        self.stream.write_all(format!("\0AUTH EXTERNAL {uid}\r\n").as_bytes())?;
+
+        let end = loop {
+            if let Some(pos) = self.buf.windows(2).position(|bytes| bytes == b"\r\n") {
+                break pos + 2;
+            }
+            self.fill()?;
+        };
+        let line: Vec<u8> = self.buf.drain(..end).collect();
+        if !line.starts_with(b"OK ") {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                String::from_utf8_lossy(&line).trim_end().to_owned(),
+            ));
+        }
+
+        self.stream.write_all(b"BEGIN\r\n")
+    }
+
+    /// Wait for the next message from the socket.
+    fn read(&mut self) -> io::Result<Message> {
+        loop {
+            if let Some(message) = self.take_buffered()? {
+                return Ok(message);
+            }
+            self.fill()?;
+        }
+    }
+
+    /// Read what is available from the socket into the buffer, waiting for at least a byte.
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0; 4096];
+        let len = self.stream.read(&mut chunk)?;
+        if len == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.buf.extend_from_slice(&chunk[..len]);
+        Ok(())
+    }
+
+    /// Decode the first message of the buffer, if it was received entirely.
+    fn take_buffered(&mut self) -> io::Result<Option<Message>> {
+        let len = match message_len(&self.buf) {
+            Some(len) if self.buf.len() >= len => len,
+            _ => return Ok(None),
+        };
+
+        let bytes: Vec<u8> = self.buf.drain(..len).collect();
+        let reader = Reader { buf: &bytes, pos: 0, big_endian: bytes[0] == b'B' };
+        let fields_len = reader.u32_at(12)? as usize;
+        // The header fields are padded to 8 bytes.
+        let header_len = (FIXED_HEADER_LEN + fields_len).next_multiple_of(8);
+        Message::decode(&bytes[..header_len], &bytes[header_len..]).map(Some)
+    }
+}
+
+/// The length of the message starting the bytes, once its fixed header was received.
+fn message_len(bytes: &[u8]) -> Option<usize> {
+    let header = bytes.get(..FIXED_HEADER_LEN)?;
+    let reader = Reader { buf: header, pos: 0, big_endian: header[0] == b'B' };
+    let body_len = reader.u32_at(4).ok()? as usize;
+    let fields_len = reader.u32_at(12).ok()? as usize;
+    Some((FIXED_HEADER_LEN + fields_len).next_multiple_of(8) + body_len)
+}
+
+impl AsFd for Connection {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.stream.as_fd()
+    }
+}
+
+/// Connect to the server address, like `unix:path=/run/user/1000/bus`.
+fn connect(address: &str) -> io::Result<UnixStream> {
+    let unsupported = || io::Error::new(io::ErrorKind::Unsupported, address.to_owned());
+    let params = address.strip_prefix("unix:").ok_or_else(unsupported)?;
+    for param in params.split(',') {
+        let Some((key, value)) = param.split_once('=') else { continue };
+        match key {
+            "path" => return UnixStream::connect(OsStr::from_bytes(&unescape(value))),
+            #[cfg(target_os = "linux")]
+            "abstract" => {
+                use std::os::linux::net::SocketAddrExt;
+                use std::os::unix::net::SocketAddr;
+
+                let address = SocketAddr::from_abstract_name(unescape(value))?;
+                return UnixStream::connect_addr(&address);
+            },
+            _ => (),
+        }
+    }
+
+    Err(unsupported())
+}
+
+/// Undo the percent-encoding of the address values.
+fn unescape(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            },
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            },
+        }
+    }
+    bytes
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// The alignment of the type starting the signature.
+fn alignment(signature: &str) -> usize {
+    match signature.as_bytes().first() {
+        Some(b'n' | b'q') => 2,
+        Some(b'b' | b'i' | b'u' | b'h' | b's' | b'o' | b'a') => 4,
+        Some(b'x' | b't' | b'd' | b'(' | b'{') => 8,
+        _ => 1,
+    }
+}
+
+/// Split the first complete type off the signature.
+fn split_type(signature: &str) -> io::Result<(&str, &str)> {
+    let bytes = signature.as_bytes();
+    let len = match bytes.first() {
+        None => return Err(invalid_data("empty signature")),
+        Some(b'a') => 1 + split_type(&signature[1..])?.0.len(),
+        Some(&open @ (b'(' | b'{')) => {
+            let close = if open == b'(' { b')' } else { b'}' };
+            let mut depth = 0;
+            let end = bytes.iter().position(|&byte| {
+                if byte == open {
+                    depth += 1;
+                } else if byte == close {
+                    depth -= 1;
+                }
+                depth == 0
+            });
+            end.ok_or_else(|| invalid_data(format!("unbalanced signature {signature}")))? + 1
+        },
+        Some(_) => 1,
+    };
+    Ok(signature.split_at(len))
+}
+
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn align(&mut self, alignment: usize) {
+        let len = self.buf.len().next_multiple_of(alignment);
+        self.buf.resize(len, 0);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn signature(&mut self, value: &str) {
+        self.buf.push(value.len() as u8);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn value(&mut self, value: &Value) {
+        self.align(alignment(&value.signature()));
+        match value {
+            Value::Byte(value) => self.buf.push(*value),
+            Value::Bool(value) => self.u32(*value as u32),
+            Value::I16(value) => self.buf.extend_from_slice(&value.to_le_bytes()),
+            Value::U16(value) => self.buf.extend_from_slice(&value.to_le_bytes()),
+            Value::I32(value) => self.buf.extend_from_slice(&value.to_le_bytes()),
+            Value::U32(value) | Value::UnixFd(value) => self.u32(*value),
+            Value::I64(value) => self.buf.extend_from_slice(&value.to_le_bytes()),
+            Value::U64(value) => self.buf.extend_from_slice(&value.to_le_bytes()),
+            Value::Double(value) => self.buf.extend_from_slice(&value.to_le_bytes()),
+            Value::String(value) | Value::ObjectPath(value) => self.string(value),
+            Value::Signature(value) => self.signature(value),
+            Value::Variant(value) => {
+                self.signature(&value.signature());
+                self.value(value);
+            },
+            Value::Array(signature, values) => {
+                let len_pos = self.buf.len();
+                self.u32(0);
+                // The padding before the first element isn't part of the length.
+                self.align(alignment(signature));
+                let start = self.buf.len();
+                for value in values {
+                    self.value(value);
+                }
+                let len = (self.buf.len() - start) as u32;
+                self.buf[len_pos..len_pos + 4].copy_from_slice(&len.to_le_bytes());
+            },
+            Value::Struct(values) => {
+                for value in values {
+                    self.value(value);
+                }
+            },
+            Value::DictEntry(key, value) => {
+                self.value(key);
+                self.value(value);
+            },
+        }
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        self.pos = self.pos.next_multiple_of(N);
+        let bytes =
+            self.buf.get(self.pos..self.pos + N).ok_or_else(|| invalid_data("truncated"))?;
+        self.pos += N;
+        let mut array: [u8; N] = bytes.try_into().unwrap();
+        if self.big_endian {
+            array.reverse();
+        }
+        Ok(array)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn u32_at(&self, pos: usize) -> io::Result<u32> {
+        Reader { buf: self.buf, pos, big_endian: self.big_endian }.u32()
+    }
+
+    fn bytes(&mut self, len: usize) -> io::Result<&[u8]> {
+        let bytes =
+            self.buf.get(self.pos..self.pos + len).ok_or_else(|| invalid_data("truncated"))?;
+        // Skip the trailing nul byte.
+        self.pos += len + 1;
+        Ok(bytes)
+    }
+
+    fn string(&mut self, len: usize) -> io::Result<String> {
+        String::from_utf8(self.bytes(len)?.to_vec()).map_err(invalid_data)
+    }
+
+    /// Read a value of the single complete type.
+    fn value(&mut self, signature: &str) -> io::Result<Value> {
+        Ok(match signature.as_bytes()[0] {
+            b'y' => Value::Byte(self.take::<1>()?[0]),
+            b'b' => Value::Bool(self.u32()? != 0),
+            b'n' => Value::I16(i16::from_le_bytes(self.take()?)),
+            b'q' => Value::U16(u16::from_le_bytes(self.take()?)),
+            b'i' => Value::I32(i32::from_le_bytes(self.take()?)),
+            b'u' => Value::U32(self.u32()?),
+            b'x' => Value::I64(i64::from_le_bytes(self.take()?)),
+            b't' => Value::U64(u64::from_le_bytes(self.take()?)),
+            b'd' => Value::Double(f64::from_le_bytes(self.take()?)),
+            b'h' => Value::UnixFd(self.u32()?),
+            b's' => {
+                let len = self.u32()? as usize;
+                Value::String(self.string(len)?)
+            },
+            b'o' => {
+                let len = self.u32()? as usize;
+                Value::ObjectPath(self.string(len)?)
+            },
+            b'g' => {
+                let len = self.take::<1>()?[0] as usize;
+                Value::Signature(self.string(len)?)
+            },
+            b'v' => {
+                let len = self.take::<1>()?[0] as usize;
+                let signature = self.string(len)?;
+                let (ty, _) = split_type(&signature)?;
+                Value::Variant(Box::new(self.value(ty)?))
+            },
+            b'a' => {
+                let len = self.u32()? as usize;
+                let element = &signature[1..];
+                self.pos = self.pos.next_multiple_of(alignment(element));
+                let end = self.pos + len;
+                let mut values = Vec::new();
+                while self.pos < end {
+                    values.push(self.value(element)?);
+                }
+                Value::Array(element.into(), values)
+            },
+            b'(' => {
+                self.pos = self.pos.next_multiple_of(8);
+                let mut fields = &signature[1..signature.len() - 1];
+                let mut values = Vec::new();
+                while !fields.is_empty() {
+                    let (ty, rest) = split_type(fields)?;
+                    values.push(self.value(ty)?);
+                    fields = rest;
+                }
+                Value::Struct(values)
+            },
+            b'{' => {
+                self.pos = self.pos.next_multiple_of(8);
+                let (key, value) = split_type(&signature[1..signature.len() - 1])?;
+                Value::DictEntry(Box::new(self.value(key)?), Box::new(self.value(value)?))
+            },
+            ty => return Err(invalid_data(format!("unknown type {}", ty as char))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let entry = Value::DictEntry(
+            Box::new(Value::String("key".into())),
+            Box::new(Value::Variant(Box::new(Value::U32(1)))),
+        );
+        let mut message =
+            Message::method_call("org.example", "/org/example", "org.example", "Get", vec![
+                Value::String("namespace".into()),
+                Value::Array("{sv}".into(), vec![entry]),
+                Value::Struct(vec![Value::Byte(1), Value::Double(0.5)]),
+                Value::Array("s".into(), vec![]),
+            ]);
+        message.serial = 7;
+
+        let bytes = message.encode();
+        let header_len = (FIXED_HEADER_LEN + bytes[12] as usize).next_multiple_of(8);
+        let decoded = Message::decode(&bytes[..header_len], &bytes[header_len..]).unwrap();
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn roundtrip_basic_types() {
+        let body = vec![
+            Value::Byte(1),
+            Value::Bool(true),
+            Value::I16(-2),
+            Value::U16(3),
+            Value::I32(-4),
+            Value::U32(5),
+            Value::I64(-6),
+            Value::U64(7),
+            Value::Double(8.5),
+            Value::UnixFd(9),
+            Value::ObjectPath("/org/example".into()),
+            Value::Signature("a{sv}".into()),
+            Value::Variant(Box::new(Value::Variant(Box::new(Value::I16(10))))),
+        ];
+        let mut message = Message::method_call("org.example", "/", "org.example", "Set", body);
+        message.kind = MessageKind::Signal;
+        message.sender = Some(":1.2".into());
+        message.serial = 3;
+
+        let bytes = message.encode();
+        let header_len = (FIXED_HEADER_LEN + bytes[12] as usize).next_multiple_of(8);
+        let decoded = Message::decode(&bytes[..header_len], &bytes[header_len..]).unwrap();
+        assert_eq!(decoded, message);
+        assert!(decoded.is_signal("org.example", "Set"));
+        assert_eq!(decoded.body[12].peel(), &Value::I16(10));
+    }
+
+    #[test]
+    fn decode_big_endian() {
+        // A `MethodReturn` with the reply serial 1, and the body `u` 42.
+        let header = [
+            &[b'B', 2, 0, 1][..],
+            // The lengths of the body and the header fields, with the serial in between.
+            &[0, 0, 0, 4],
+            &[0, 0, 0, 9],
+            &[0, 0, 0, 15],
+            &[FIELD_REPLY_SERIAL, 1, b'u', 0, 0, 0, 0, 1],
+            &[FIELD_SIGNATURE, 1, b'g', 0, 1, b'u', 0, 0],
+        ]
+        .concat();
+        let message = Message::decode(&header, &[0, 0, 0, 42]).unwrap();
+        assert_eq!(message.kind, MessageKind::MethodReturn);
+        assert_eq!(message.serial, 9);
+        assert_eq!(message.reply_serial, Some(1));
+        assert_eq!(message.body, [Value::U32(42)]);
+    }
+
+    /// Answer the authentication and the `Hello` of a client, like the bus does.
+    fn fake_bus(stream: UnixStream) -> Connection {
+        let mut bus = Connection { stream, serial: 0, buf: Vec::new(), queue: VecDeque::new() };
+        let end = loop {
+            if let Some(pos) = bus.buf.windows(7).position(|bytes| bytes == b"BEGIN\r\n") {
+                break pos + 7;
+            }
+            bus.fill().unwrap();
+        };
+        assert!(bus.buf.starts_with(b"\0AUTH EXTERNAL "));
+        bus.buf.drain(..end);
+
+        let hello = bus.read().unwrap();
+        assert_eq!(hello.member.as_deref(), Some("Hello"));
+        bus.reply(&hello, vec![Value::String(":1.1".into())]);
+        bus
+    }
+
+    impl Connection {
+        fn reply(&mut self, call: &Message, body: Vec<Value>) {
+            let mut reply = Message::method_call("", "/", "", "", body);
+            (reply.kind, reply.reply_serial) = (MessageKind::MethodReturn, Some(call.serial));
+            (reply.path, reply.interface, reply.member, reply.destination) =
+                (None, None, None, None);
+            self.send(reply).unwrap();
+        }
+    }
+
+    #[test]
+    fn connection() {
+        let (client, bus) = UnixStream::pair().unwrap();
+        // The authentication is answered before the client starts reading.
+        (&bus).write_all(b"OK 0123456789abcdef\r\n").unwrap();
+        let bus = std::thread::spawn(move || {
+            let mut bus = fake_bus(bus);
+
+            // A signal is received before the reply of the call.
+            let call = bus.read().unwrap();
+            let mut signal =
+                Message::method_call("", "/org/example", "org.example", "Changed", vec![]);
+            (signal.kind, signal.destination) = (MessageKind::Signal, None);
+            bus.send(signal).unwrap();
+            bus.reply(&call, vec![Value::Bool(true)]);
+
+            let call = bus.read().unwrap();
+            let mut error =
+                Message::method_call("", "/", "", "", vec![Value::String("no such method".into())]);
+            error.kind = MessageKind::Error;
+            error.error_name = Some("org.freedesktop.DBus.Error.UnknownMethod".into());
+            error.reply_serial = Some(call.serial);
+            bus.send(error).unwrap();
+        });
+
+        let mut connection = Connection::from_stream(client).unwrap();
+        assert_eq!(connection.stream.read_timeout().unwrap(), None);
+        let call = Message::method_call("org.example", "/", "org.example", "Get", vec![]);
+        assert_eq!(connection.call(call.clone()).unwrap(), [Value::Bool(true)]);
+        assert!(connection.receive().unwrap().is_signal("org.example", "Changed"));
+
+        let err = connection.call(call).unwrap_err();
+        assert!(err.to_string().contains("UnknownMethod: no such method"));
+        bus.join().unwrap();
+    }
+
+    #[test]
+    fn stalled_bus() {
+        let (client, _bus) = UnixStream::pair().unwrap();
+        // The bus never answers the authentication.
+        let err = Connection::from_stream(client).unwrap_err();
+        assert!(matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut));
+    }
+
+    #[test]
+    #[cfg(x11_platform)]
+    fn partial_messages() {
+        let (client, mut bus) = UnixStream::pair().unwrap();
+        let mut connection =
+            Connection { stream: client, serial: 0, buf: Vec::new(), queue: VecDeque::new() };
+        connection.set_timeout(Some(Duration::from_millis(10))).unwrap();
+
+        let mut signal = Message::method_call("", "/org/example", "org.example", "Changed", vec![
+            Value::String("value".into()),
+        ]);
+        (signal.kind, signal.destination, signal.serial) = (MessageKind::Signal, None, 1);
+        let bytes = signal.encode();
+        let (head, tail) = bytes.split_at(20);
+
+        // The timeout interrupts the read in the middle of the message.
+        bus.write_all(head).unwrap();
+        assert!(connection.receive().is_err());
+        assert_eq!(connection.try_receive().unwrap(), None);
+        assert!(!connection.has_queued());
+
+        // The rest completes the same message, followed by the next one.
+        bus.write_all(tail).unwrap();
+        bus.write_all(&bytes).unwrap();
+        assert_eq!(connection.receive().unwrap(), signal);
+        assert!(connection.has_queued());
+        assert_eq!(connection.try_receive().unwrap(), Some(signal));
+        assert_eq!(connection.try_receive().unwrap(), None);
+    }
+
+    #[test]
+    fn split_types() {
+        assert_eq!(split_type("a{sv}s").unwrap(), ("a{sv}", "s"));
+        assert_eq!(split_type("(s(ii))u").unwrap(), ("(s(ii))", "u"));
+        assert!(split_type("(s").is_err());
+    }
+
+    #[test]
+    fn unescape_address() {
+        assert_eq!(unescape("/tmp/dbus%2dtest"), b"/tmp/dbus-test");
+        assert_eq!(unescape("100%"), b"100%");
+    }
+}
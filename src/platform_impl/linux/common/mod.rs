@@ -1 +1,3 @@
+pub mod dbus;
+pub mod portal;
 pub mod xkb;
@@ -0,0 +1,206 @@
+//! Desktop settings from the XDG desktop portal.
+
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::{io, thread};
+
+use tracing::{debug, warn};
+use winit_core::window::Theme;
+
+use super::dbus::{Connection, Message, Value};
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SETTINGS_INTERFACE: &str = "org.freedesktop.portal.Settings";
+
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const GNOME_INTERFACE_NAMESPACE: &str = "org.gnome.desktop.interface";
+const GNOME_MOUSE_NAMESPACE: &str = "org.gnome.desktop.peripherals.mouse";
+const KDE_NAMESPACE: &str = "org.kde.kdeglobals.KDE";
+
+/// How long to wait for the portal to answer, it's started on demand when not running yet.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The desktop settings winit is interested in.
+///
+/// Settings which aren't exposed by the portal are `None`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    /// The color scheme preferred by the user.
+    pub color_scheme: Option<Theme>,
+    /// The maximum time between the clicks of a double click.
+    pub double_click_interval: Option<Duration>,
+    /// The distance in pixels to move before a drag starts.
+    pub drag_threshold: Option<u32>,
+    /// Whether the text caret blinks.
+    pub caret_blink: Option<bool>,
+    /// The time between two blinks of the text caret.
+    pub caret_blink_interval: Option<Duration>,
+}
+
+impl Settings {
+    /// Update the setting, returns whether it's one winit is interested in.
+    fn set(&mut self, namespace: &str, key: &str, value: &Value) -> bool {
+        let value = value.peel();
+        match (namespace, key) {
+            (APPEARANCE_NAMESPACE, "color-scheme") => {
+                self.color_scheme = match value.as_i64() {
+                    Some(1) => Some(Theme::Dark),
+                    Some(2) => Some(Theme::Light),
+                    _ => None,
+                };
+            },
+            (GNOME_MOUSE_NAMESPACE, "double-click") | (KDE_NAMESPACE, "DoubleClickInterval") => {
+                self.double_click_interval = millis(value);
+            },
+            (GNOME_MOUSE_NAMESPACE, "drag-threshold") | (KDE_NAMESPACE, "StartDragDist") => {
+                self.drag_threshold = integer(value).and_then(|value| value.try_into().ok());
+            },
+            (GNOME_INTERFACE_NAMESPACE, "cursor-blink") => self.caret_blink = value.as_bool(),
+            (GNOME_INTERFACE_NAMESPACE, "cursor-blink-time") => {
+                self.caret_blink_interval = millis(value);
+            },
+            // KDE disables the blinking with a rate of zero.
+            (KDE_NAMESPACE, "CursorBlinkRate") => {
+                self.caret_blink_interval = millis(value).filter(|rate| !rate.is_zero());
+                self.caret_blink = millis(value).map(|rate| !rate.is_zero());
+            },
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+/// Watches the desktop settings from a separate thread, which stops when this is dropped.
+#[derive(Debug, Default)]
+pub struct SettingsWatcher {
+    shared: Arc<Mutex<WatcherState>>,
+}
+
+#[derive(Debug, Default)]
+struct WatcherState {
+    stopped: bool,
+    /// The socket of the connection, shut down to interrupt the thread waiting on it.
+    stream: Option<UnixStream>,
+}
+
+impl Drop for SettingsWatcher {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap_or_else(|err| err.into_inner());
+        shared.stopped = true;
+        if let Some(stream) = shared.stream.take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Read the desktop settings, and watch them for changes.
+///
+/// The portal is contacted from a separate thread, so a missing or slow portal doesn't delay the
+/// startup. The callback is invoked from that thread with the settings once they're read, and
+/// then whenever one of them changes. It's never invoked when the portal isn't available.
+pub fn watch_settings(callback: impl Fn(Settings) + Send + 'static) -> SettingsWatcher {
+    let watcher = SettingsWatcher::default();
+    let shared = watcher.shared.clone();
+    let spawned = thread::Builder::new().name("winit-portal".into()).spawn(move || {
+        let (mut connection, mut settings) = match connect() {
+            Ok(connection) => connection,
+            Err(err) => {
+                debug!("failed to read the settings from the desktop portal: {err}");
+                return;
+            },
+        };
+
+        {
+            let mut shared = shared.lock().unwrap_or_else(|err| err.into_inner());
+            if shared.stopped {
+                return;
+            }
+            match connection.try_clone_stream() {
+                Ok(stream) => shared.stream = Some(stream),
+                Err(err) => {
+                    warn!("failed to watch the desktop settings: {err}");
+                    return;
+                },
+            }
+        }
+
+        callback(settings);
+        loop {
+            let message = match connection.receive() {
+                Ok(message) => message,
+                Err(err) => {
+                    if !shared.lock().unwrap_or_else(|err| err.into_inner()).stopped {
+                        debug!("lost the connection to the desktop portal: {err}");
+                    }
+                    break;
+                },
+            };
+
+            if !message.is_signal(SETTINGS_INTERFACE, "SettingChanged") {
+                continue;
+            }
+
+            if let [namespace, key, value] = message.body.as_slice() {
+                let (Some(namespace), Some(key)) = (namespace.as_str(), key.as_str()) else {
+                    continue;
+                };
+                if settings.set(namespace, key, value) {
+                    callback(settings);
+                }
+            }
+        }
+    });
+    if let Err(err) = spawned {
+        warn!("failed to watch the desktop settings: {err}");
+    }
+
+    watcher
+}
+
+fn connect() -> io::Result<(Connection, Settings)> {
+    let mut connection = Connection::session()?;
+    connection.set_timeout(Some(STARTUP_TIMEOUT))?;
+
+    // Subscribe first, so no change is missed.
+    connection.add_match(&format!(
+        "type='signal',interface='{SETTINGS_INTERFACE}',member='SettingChanged'"
+    ))?;
+
+    let namespaces =
+        [APPEARANCE_NAMESPACE, GNOME_INTERFACE_NAMESPACE, GNOME_MOUSE_NAMESPACE, KDE_NAMESPACE];
+    let namespaces = namespaces.into_iter().map(|namespace| Value::String(namespace.into()));
+    let reply = connection.call(Message::method_call(
+        PORTAL_DESTINATION,
+        PORTAL_PATH,
+        SETTINGS_INTERFACE,
+        "ReadAll",
+        vec![Value::Array("s".into(), namespaces.collect())],
+    ))?;
+
+    let mut settings = Settings::default();
+    for (namespace, values) in reply.iter().flat_map(Value::dict_entries) {
+        let Some(namespace) = namespace.as_str() else { continue };
+        for (key, value) in values.dict_entries() {
+            if let Some(key) = key.as_str() {
+                settings.set(namespace, key, value);
+            }
+        }
+    }
+
+    connection.set_timeout(None)?;
+    Ok((connection, settings))
+}
+
+/// Integers are stored as strings by some desktops.
+fn integer(value: &Value) -> Option<i64> {
+    value.as_i64().or_else(|| value.as_str()?.trim().parse().ok())
+}
+
+fn millis(value: &Value) -> Option<Duration> {
+    let millis = integer(value)?.try_into().ok()?;
+    Some(Duration::from_millis(millis))
+}
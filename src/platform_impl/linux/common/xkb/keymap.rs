@@ -15,7 +15,7 @@ use xkbcommon_dl::{
     xkb_layout_index_t, xkb_mod_index_t,
 };
 #[cfg(wayland_platform)]
-use {memmap2::MmapOptions, std::os::unix::io::OwnedFd};
+use {memmap2::MmapOptions, std::ffi::CStr, std::os::unix::io::OwnedFd};
 
 #[cfg(x11_platform)]
 use crate::platform_impl::common::xkb::XKBXH;
@@ -964,6 +964,21 @@ impl XkbKeymap {
         Some(Self::new_inner(keymap, 0))
     }
 
+    /// Compile the keymap from the default rules, which can be overridden with the
+    /// `XKB_DEFAULT_*` environment variables.
+    #[cfg(wayland_platform)]
+    pub fn from_default_names(context: &XkbContext) -> Option<Self> {
+        let keymap = unsafe {
+            (XKBH.xkb_keymap_new_from_names)(
+                context.as_ptr(),
+                ptr::null(),
+                xkb_keymap_compile_flags::XKB_KEYMAP_COMPILE_NO_FLAGS,
+            )
+        };
+        let keymap = NonNull::new(keymap)?;
+        Some(Self::new_inner(keymap, 0))
+    }
+
     #[cfg(x11_platform)]
     pub fn from_x11_keymap(
         context: &XkbContext,
@@ -997,11 +1012,28 @@ impl XkbKeymap {
         Self { keymap, _mods_indices: mods_indices, _core_keyboard_id }
     }
 
-    #[cfg(x11_platform)]
     pub fn mods_indices(&self) -> ModsIndices {
         self._mods_indices
     }
 
+    /// Serialize the keymap in the text format.
+    #[cfg(wayland_platform)]
+    pub fn to_text(&self) -> Option<String> {
+        unsafe {
+            let text = (XKBH.xkb_keymap_get_as_string)(
+                self.keymap.as_ptr(),
+                xkb::xkb_keymap_format::XKB_KEYMAP_FORMAT_TEXT_V1,
+            );
+            if text.is_null() {
+                return None;
+            }
+
+            let string = CStr::from_ptr(text).to_string_lossy().into_owned();
+            libc::free(text as *mut _);
+            Some(string)
+        }
+    }
+
     pub fn first_keysym_by_level(
         &mut self,
         layout: xkb_layout_index_t,
@@ -24,10 +24,22 @@ mod state;
 use compose::{ComposeStatus, XkbComposeState, XkbComposeTable};
 #[cfg(x11_platform)]
 pub use keymap::raw_keycode_to_physicalkey;
+#[cfg(wayland_platform)]
+pub use keymap::ModsIndices;
 use keymap::XkbKeymap;
 pub use keymap::{physicalkey_to_scancode, scancode_to_physicalkey};
 pub use state::XkbState;
 
+/// Compile the default keymap of the system, returning it in the text format along with the
+/// indices of its modifiers.
+#[cfg(wayland_platform)]
+pub fn default_keymap() -> Option<(String, ModsIndices)> {
+    xkb::xkbcommon_option()?;
+    let context = XkbContext::new().ok()?;
+    let keymap = XkbKeymap::from_default_names(&context)?;
+    Some((keymap.to_text()?, keymap.mods_indices()))
+}
+
 // TODO: Wire this up without using a static `AtomicBool`.
 static RESET_DEAD_KEYS: AtomicBool = AtomicBool::new(false);
 
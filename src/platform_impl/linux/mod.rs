@@ -1,7 +1,7 @@
 #![cfg(free_unix)]
 
-#[cfg(all(not(x11_platform), not(wayland_platform)))]
-compile_error!("Please select a feature to build for unix: `x11`, `wayland`");
+#[cfg(all(not(x11_platform), not(wayland_platform), not(headless_platform)))]
+compile_error!("Please select a feature to build for unix: `x11`, `wayland`, `headless`");
 
 use std::env;
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
@@ -13,12 +13,15 @@ use winit_core::application::ApplicationHandler;
 use winit_core::error::{EventLoopError, NotSupportedError};
 use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::ActiveEventLoop;
+#[cfg(any(x11_platform, wayland_platform))]
 use winit_core::window::ActivationToken;
 
+#[cfg(any(x11_platform, wayland_platform))]
 pub(crate) use self::common::xkb::{physicalkey_to_scancode, scancode_to_physicalkey};
 #[cfg(x11_platform)]
-use crate::platform::x11::WindowType as XWindowType;
+use crate::platform::x11::{ImeBackend as XImeBackend, WindowType as XWindowType};
 
+#[cfg(any(x11_platform, wayland_platform))]
 pub(crate) mod common;
 #[cfg(wayland_platform)]
 pub(crate) mod wayland;
@@ -31,20 +34,43 @@ pub(crate) enum Backend {
     X,
     #[cfg(wayland_platform)]
     Wayland,
+    #[cfg(headless_platform)]
+    Headless,
 }
 
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct PlatformSpecificEventLoopAttributes {
     pub(crate) forced_backend: Option<Backend>,
     pub(crate) any_thread: bool,
+    pub(crate) key_repeat: bool,
+    #[cfg(x11_platform)]
+    pub(crate) x11_ime_backend: XImeBackend,
+    #[cfg(wayland_platform)]
+    pub(crate) wayland_input_method: bool,
+}
+
+impl Default for PlatformSpecificEventLoopAttributes {
+    fn default() -> Self {
+        Self {
+            forced_backend: None,
+            any_thread: false,
+            key_repeat: true,
+            #[cfg(x11_platform)]
+            x11_ime_backend: XImeBackend::default(),
+            #[cfg(wayland_platform)]
+            wayland_input_method: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg(any(x11_platform, wayland_platform))]
 pub struct ApplicationName {
     pub general: String,
     pub instance: String,
 }
 
+#[cfg(any(x11_platform, wayland_platform))]
 impl ApplicationName {
     pub fn new(general: String, instance: String) -> Self {
         Self { general, instance }
@@ -52,6 +78,7 @@ impl ApplicationName {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg(any(x11_platform, wayland_platform))]
 pub struct PlatformSpecificWindowAttributes {
     pub name: Option<ApplicationName>,
     pub activation_token: Option<ActivationToken>,
@@ -72,6 +99,7 @@ pub struct X11WindowAttributes {
     pub embed_window: Option<x11rb::protocol::xproto::Window>,
 }
 
+#[cfg(any(x11_platform, wayland_platform))]
 #[cfg_attr(not(x11_platform), allow(clippy::derivable_impls))]
 impl Default for PlatformSpecificWindowAttributes {
     fn default() -> Self {
@@ -107,6 +135,8 @@ macro_rules! x11_or_wayland {
             $enum::X($($c1)*) => $enum2::X($x),
             #[cfg(wayland_platform)]
             $enum::Wayland($($c1)*) => $enum2::Wayland($x),
+            #[cfg(headless_platform)]
+            $enum::Headless($($c1)*) => $enum2::Headless($x),
         }
     };
     (match $what:expr; $enum:ident ( $($c1:tt)* ) => $x:expr) => {
@@ -115,6 +145,8 @@ macro_rules! x11_or_wayland {
             $enum::X($($c1)*) => $x,
             #[cfg(wayland_platform)]
             $enum::Wayland($($c1)*) => $x,
+            #[cfg(headless_platform)]
+            $enum::Headless($($c1)*) => $x,
         }
     };
 }
@@ -126,13 +158,22 @@ pub enum EventLoop {
     Wayland(Box<wayland::EventLoop>),
     #[cfg(x11_platform)]
     X(x11::EventLoop),
+    #[cfg(headless_platform)]
+    Headless(winit_headless::EventLoop),
 }
 
 impl EventLoop {
     pub(crate) fn new(
         attributes: &PlatformSpecificEventLoopAttributes,
     ) -> Result<Self, EventLoopError> {
-        if !attributes.any_thread && !is_main_thread() {
+        // NOTE: The headless backend is meant for tests, which the test harness runs on their own
+        // threads.
+        #[cfg(headless_platform)]
+        let any_thread =
+            attributes.any_thread || attributes.forced_backend == Some(Backend::Headless);
+        #[cfg(not(headless_platform))]
+        let any_thread = attributes.any_thread;
+        if !any_thread && !is_main_thread() {
             panic!(
                 "Initializing the event loop outside of the main thread is a significant \
                  cross-platform compatibility hazard. If you absolutely need to create an \
@@ -180,29 +221,53 @@ impl EventLoop {
         // Create the display based on the backend.
         match backend {
             #[cfg(wayland_platform)]
-            Backend::Wayland => EventLoop::new_wayland_any_thread(),
+            Backend::Wayland => EventLoop::new_wayland_any_thread(attributes),
             #[cfg(x11_platform)]
-            Backend::X => EventLoop::new_x11_any_thread(),
+            Backend::X => EventLoop::new_x11_any_thread(attributes),
+            #[cfg(headless_platform)]
+            Backend::Headless => EventLoop::new_headless(),
         }
     }
 
     #[cfg(wayland_platform)]
-    fn new_wayland_any_thread() -> Result<EventLoop, EventLoopError> {
-        wayland::EventLoop::new().map(|evlp| EventLoop::Wayland(Box::new(evlp)))
+    fn new_wayland_any_thread(
+        attributes: &PlatformSpecificEventLoopAttributes,
+    ) -> Result<EventLoop, EventLoopError> {
+        wayland::EventLoop::new(attributes).map(|evlp| EventLoop::Wayland(Box::new(evlp)))
     }
 
     #[cfg(x11_platform)]
-    fn new_x11_any_thread() -> Result<EventLoop, EventLoopError> {
-        x11::EventLoop::new().map(EventLoop::X)
+    fn new_x11_any_thread(
+        attributes: &PlatformSpecificEventLoopAttributes,
+    ) -> Result<EventLoop, EventLoopError> {
+        x11::EventLoop::new(attributes).map(EventLoop::X)
+    }
+
+    #[cfg(headless_platform)]
+    fn new_headless() -> Result<EventLoop, EventLoopError> {
+        let attributes = winit_headless::PlatformSpecificEventLoopAttributes::default();
+        winit_headless::EventLoop::new(&attributes).map(EventLoop::Headless)
     }
 
     #[inline]
+    #[cfg(wayland_platform)]
     pub fn is_wayland(&self) -> bool {
-        match *self {
-            #[cfg(wayland_platform)]
-            EventLoop::Wayland(_) => true,
-            #[cfg(x11_platform)]
-            _ => false,
+        matches!(self, EventLoop::Wayland(_))
+    }
+
+    #[inline]
+    #[cfg(x11_platform)]
+    pub fn is_x11(&self) -> bool {
+        matches!(self, EventLoop::X(_))
+    }
+
+    #[inline]
+    #[cfg(headless_platform)]
+    pub fn headless(&self) -> Option<&winit_headless::EventLoop> {
+        match self {
+            EventLoop::Headless(evlp) => Some(evlp),
+            #[cfg(any(x11_platform, wayland_platform))]
+            _ => None,
         }
     }
 
@@ -245,6 +310,7 @@ impl AsRawFd for EventLoop {
 /// Returns the minimum `Option<Duration>`, taking into account that `None`
 /// equates to an infinite timeout, not a zero timeout (so can't just use
 /// `Option::min`)
+#[cfg(any(x11_platform, wayland_platform))]
 fn min_timeout(a: Option<Duration>, b: Option<Duration>) -> Option<Duration> {
     a.map_or(b, |a_timeout| b.map_or(Some(a_timeout), |b_timeout| Some(a_timeout.min(b_timeout))))
 }
@@ -17,19 +17,23 @@ use rustix::pipe::{self, PipeFlags};
 use sctk::reexports::calloop_wayland_source::WaylandSource;
 use sctk::reexports::client::{globals, Connection, QueueHandle};
 use tracing::warn;
+use winit_core::application::wayland::{DecorationMode, InputMethodEvent};
 use winit_core::application::ApplicationHandler;
+use winit_core::clipboard::{Clipboard as CoreClipboard, ClipboardEvent, ClipboardKind};
 use winit_core::cursor::{CustomCursor as CoreCustomCursor, CustomCursorSource};
 use winit_core::error::{EventLoopError, NotSupportedError, OsError, RequestError};
-use winit_core::event::{DeviceEvent, StartCause, SurfaceSizeWriter, WindowEvent};
+use winit_core::event::{
+    DeviceEvent, DeviceId, DeviceInfo, DragAction, StartCause, SurfaceSizeWriter, WindowEvent,
+};
 use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::{
-    ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents,
-    OwnedDisplayHandle as CoreOwnedDisplayHandle,
+    ActiveEventLoop as RootActiveEventLoop, AsyncRequestSerial, ControlFlow, DeviceEvents,
+    InputPreferences, OwnedDisplayHandle as CoreOwnedDisplayHandle,
 };
-use winit_core::monitor::MonitorHandle as CoreMonitorHandle;
+use winit_core::monitor::{MonitorEvent, MonitorHandle as CoreMonitorHandle};
 use winit_core::window::Theme;
 
-use crate::platform_impl::platform::min_timeout;
+use crate::platform_impl::platform::{min_timeout, PlatformSpecificEventLoopAttributes};
 use crate::platform_impl::wayland::types::cursor::WaylandCustomCursor;
 
 mod proxy;
@@ -40,6 +44,7 @@ use sink::EventSink;
 pub use winit_core::event_loop::EventLoopProxy as CoreEventLoopProxy;
 
 use super::output::MonitorHandle;
+use super::seat::Clipboard;
 use super::state::{WindowCompositorUpdate, WinitState};
 use super::window::state::FrameCallbackState;
 use super::{logical_to_physical_rounded, WindowId};
@@ -47,9 +52,15 @@ use super::{logical_to_physical_rounded, WindowId};
 type WaylandDispatcher = calloop::Dispatcher<'static, WaylandSource<WinitState>, WinitState>;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum Event {
     WindowEvent { window_id: WindowId, event: WindowEvent },
-    DeviceEvent { event: DeviceEvent },
+    DeviceEvent { device_id: Option<DeviceId>, event: DeviceEvent },
+    ClipboardEvent { event: ClipboardEvent },
+    MonitorEvent { event: MonitorEvent },
+    InputPreferencesChanged,
+    InputMethodEvent { event: InputMethodEvent },
+    DecorationModeChanged { window_id: WindowId, mode: DecorationMode },
 }
 
 /// The Wayland event loop.
@@ -80,7 +91,9 @@ pub struct EventLoop {
 }
 
 impl EventLoop {
-    pub fn new() -> Result<EventLoop, EventLoopError> {
+    pub fn new(
+        attributes: &PlatformSpecificEventLoopAttributes,
+    ) -> Result<EventLoop, EventLoopError> {
         let connection = Connection::connect_to_env().map_err(|err| os_error!(err))?;
 
         let (globals, mut event_queue) =
@@ -91,6 +104,10 @@ impl EventLoop {
             calloop::EventLoop::<WinitState>::try_new().map_err(|err| os_error!(err))?;
 
         let mut winit_state = WinitState::new(&globals, &queue_handle, event_loop.handle())?;
+        winit_state.key_repeat = attributes.key_repeat;
+        if attributes.wayland_input_method {
+            winit_state.bind_input_method(&globals, &queue_handle);
+        }
 
         // NOTE: do a roundtrip after binding the globals to prevent potential
         // races with the server.
@@ -137,8 +154,25 @@ impl EventLoop {
             })
             .map_err(|err| os_error!(err))?;
 
+        let clipboard = Clipboard::new(
+            &winit_state,
+            ClipboardKind::Clipboard,
+            queue_handle.clone(),
+            event_loop_awakener.clone(),
+        );
+        let primary_selection = winit_state.primary_selection_manager.is_some().then(|| {
+            Clipboard::new(
+                &winit_state,
+                ClipboardKind::Primary,
+                queue_handle.clone(),
+                event_loop_awakener.clone(),
+            )
+        });
+
         let handle = Arc::new(OwnedDisplayHandle::new(connection));
         let active_event_loop = ActiveEventLoop {
+            clipboard,
+            primary_selection,
             handle: handle.clone(),
             wayland_dispatcher: wayland_dispatcher.clone(),
             event_loop_awakener,
@@ -418,8 +452,25 @@ impl EventLoop {
                 Event::WindowEvent { window_id, event } => {
                     app.window_event(&self.active_event_loop, window_id, event)
                 },
-                Event::DeviceEvent { event } => {
-                    app.device_event(&self.active_event_loop, None, event)
+                Event::DeviceEvent { device_id, event } => {
+                    app.device_event(&self.active_event_loop, device_id, event)
+                },
+                Event::ClipboardEvent { event } => {
+                    app.clipboard_event(&self.active_event_loop, event)
+                },
+                Event::MonitorEvent { event } => app.monitor_event(&self.active_event_loop, event),
+                Event::InputPreferencesChanged => {
+                    app.input_preferences_changed(&self.active_event_loop)
+                },
+                Event::InputMethodEvent { event } => {
+                    if let Some(handler) = app.wayland_handler() {
+                        handler.input_method_event(&self.active_event_loop, event);
+                    }
+                },
+                Event::DecorationModeChanged { window_id, mode } => {
+                    if let Some(handler) = app.wayland_handler() {
+                        handler.decoration_mode_changed(&self.active_event_loop, window_id, mode);
+                    }
                 },
             }
         }
@@ -433,12 +484,32 @@ impl EventLoop {
                 Event::WindowEvent { window_id, event } => {
                     app.window_event(&self.active_event_loop, window_id, event)
                 },
-                Event::DeviceEvent { event } => {
-                    app.device_event(&self.active_event_loop, None, event)
+                Event::DeviceEvent { device_id, event } => {
+                    app.device_event(&self.active_event_loop, device_id, event)
+                },
+                Event::ClipboardEvent { event } => {
+                    app.clipboard_event(&self.active_event_loop, event)
+                },
+                Event::MonitorEvent { event } => app.monitor_event(&self.active_event_loop, event),
+                Event::InputPreferencesChanged => {
+                    app.input_preferences_changed(&self.active_event_loop)
+                },
+                Event::InputMethodEvent { event } => {
+                    if let Some(handler) = app.wayland_handler() {
+                        handler.input_method_event(&self.active_event_loop, event);
+                    }
+                },
+                Event::DecorationModeChanged { window_id, mode } => {
+                    if let Some(handler) = app.wayland_handler() {
+                        handler.decoration_mode_changed(&self.active_event_loop, window_id, mode);
+                    }
                 },
             }
         }
 
+        // The drops were delivered, so they can be finished.
+        self.with_state(|state| state.finish_drops());
+
         // Collect the window ids
         self.with_state(|state| {
             window_ids.extend(state.window_requests.get_mut().keys());
@@ -601,6 +672,12 @@ pub struct ActiveEventLoop {
 
     /// Handle for the underlying event loop.
     pub handle: Arc<OwnedDisplayHandle>,
+
+    /// The clipboard of the seats.
+    clipboard: Clipboard,
+
+    /// The primary selection of the seats, `None` when the compositor doesn't support it.
+    primary_selection: Option<Clipboard>,
 }
 
 impl RootActiveEventLoop for ActiveEventLoop {
@@ -627,6 +704,17 @@ impl RootActiveEventLoop for ActiveEventLoop {
     #[inline]
     fn listen_device_events(&self, _allowed: DeviceEvents) {}
 
+    fn available_devices(&self) -> Box<dyn Iterator<Item = (DeviceId, DeviceInfo)>> {
+        let devices: Vec<_> = self
+            .state
+            .borrow()
+            .devices
+            .iter()
+            .map(|(device_id, info)| (*device_id, info.clone()))
+            .collect();
+        Box::new(devices.into_iter())
+    }
+
     fn create_custom_cursor(
         &self,
         cursor: CustomCursorSource,
@@ -643,7 +731,30 @@ impl RootActiveEventLoop for ActiveEventLoop {
 
     #[inline]
     fn system_theme(&self) -> Option<Theme> {
-        None
+        self.state.borrow().portal_settings.color_scheme
+    }
+
+    fn input_preferences(&self) -> InputPreferences {
+        self.state.borrow().input_preferences
+    }
+
+    fn clipboard(&self) -> Option<&dyn CoreClipboard> {
+        Some(&self.clipboard)
+    }
+
+    fn primary_selection(&self) -> Option<&dyn CoreClipboard> {
+        self.primary_selection.as_ref().map(|clipboard| clipboard as &dyn CoreClipboard)
+    }
+
+    fn set_drag_action(&self, action: Option<DragAction>) -> Result<(), RequestError> {
+        self.state.borrow_mut().set_drag_action(action)
+    }
+
+    fn request_drag_data(&self, mime_type: &str) -> Result<AsyncRequestSerial, RequestError> {
+        let serial = self.state.borrow_mut().request_drag_data(mime_type)?;
+        // Data that isn't offered is reported right away.
+        self.event_loop_awakener.ping();
+        Ok(serial)
     }
 
     fn create_window(
@@ -2,7 +2,10 @@
 
 use std::vec::Drain;
 
-use winit_core::event::{DeviceEvent, WindowEvent};
+use winit_core::application::wayland::{DecorationMode, InputMethodEvent};
+use winit_core::clipboard::ClipboardEvent;
+use winit_core::event::{DeviceEvent, DeviceId, WindowEvent};
+use winit_core::monitor::MonitorEvent;
 use winit_core::window::WindowId;
 
 use super::Event;
@@ -27,8 +30,8 @@ impl EventSink {
 
     /// Add new device event to a queue.
     #[inline]
-    pub fn push_device_event(&mut self, event: DeviceEvent) {
-        self.window_events.push(Event::DeviceEvent { event });
+    pub fn push_device_event(&mut self, event: DeviceEvent, device_id: Option<DeviceId>) {
+        self.window_events.push(Event::DeviceEvent { device_id, event });
     }
 
     /// Add new window event to a queue.
@@ -37,6 +40,36 @@ impl EventSink {
         self.window_events.push(Event::WindowEvent { event, window_id });
     }
 
+    /// Add new clipboard event to a queue.
+    #[inline]
+    pub fn push_clipboard_event(&mut self, event: ClipboardEvent) {
+        self.window_events.push(Event::ClipboardEvent { event });
+    }
+
+    /// Add new monitor event to a queue.
+    #[inline]
+    pub fn push_monitor_event(&mut self, event: MonitorEvent) {
+        self.window_events.push(Event::MonitorEvent { event });
+    }
+
+    /// Notify that the input preferences have changed.
+    #[inline]
+    pub fn push_input_preferences_changed(&mut self) {
+        self.window_events.push(Event::InputPreferencesChanged);
+    }
+
+    /// Add new input method event to a queue.
+    #[inline]
+    pub fn push_input_method_event(&mut self, event: InputMethodEvent) {
+        self.window_events.push(Event::InputMethodEvent { event });
+    }
+
+    /// Notify that the decoration mode of the window has changed.
+    #[inline]
+    pub fn push_decoration_mode_changed(&mut self, window_id: WindowId, mode: DecorationMode) {
+        self.window_events.push(Event::DecorationModeChanged { window_id, mode });
+    }
+
     #[inline]
     pub fn append(&mut self, other: &mut Self) {
         self.window_events.append(&mut other.window_events);
@@ -3,6 +3,7 @@
 use dpi::{LogicalSize, PhysicalSize};
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::client::Proxy;
+use winit_core::event::DeviceId;
 use winit_core::window::WindowId;
 
 mod event_loop;
@@ -21,6 +22,12 @@ fn make_wid(surface: &WlSurface) -> WindowId {
     WindowId::from_raw(surface.id().as_ptr() as usize)
 }
 
+/// Get the DeviceId out of the object representing the device.
+#[inline]
+fn make_device_id(object: &impl Proxy) -> DeviceId {
+    DeviceId::from_raw(object.id().as_ptr() as i64)
+}
+
 /// The default routine does floor, but we need round on Wayland.
 fn logical_to_physical_rounded(size: LogicalSize<u32>, scale_factor: f64) -> PhysicalSize<u32> {
     let width = size.width as f64 * scale_factor;
@@ -0,0 +1,645 @@
+//! Drag and drop through the data device.
+
+use std::ffi::OsString;
+use std::io;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::io::AsFd;
+use std::path::PathBuf;
+
+use calloop::{PostAction, RegistrationToken};
+use dpi::{LogicalPosition, PhysicalPosition};
+use percent_encoding::percent_decode;
+use sctk::data_device_manager::data_device::{DataDeviceData, DataDeviceHandler};
+use sctk::data_device_manager::data_offer::{DataOfferHandler, DragOffer};
+use sctk::data_device_manager::data_source::DataSourceData;
+use sctk::data_device_manager::{ReadPipe, WritePipe};
+use sctk::reexports::client::backend::ObjectId;
+use sctk::reexports::client::protocol::wl_data_device::WlDataDevice;
+use sctk::reexports::client::protocol::wl_data_device_manager::{DndAction, WlDataDeviceManager};
+use sctk::reexports::client::protocol::wl_data_source::WlDataSource;
+use sctk::reexports::client::protocol::wl_surface::WlSurface;
+use sctk::reexports::client::{Connection, Proxy, QueueHandle};
+use tracing::warn;
+use winit_core::clipboard::{
+    ClipboardContents, ClipboardData, ClipboardError, ClipboardEvent, ClipboardKind,
+};
+use winit_core::error::{NotSupportedError, RequestError};
+use winit_core::event::{DragAction, DragSourceEvent, WindowEvent};
+use winit_core::event_loop::AsyncRequestSerial;
+
+use super::{offer_contents, read_to_end_nonblocking, requested_data, ClipboardState};
+use crate::platform_impl::wayland::state::WinitState;
+use crate::platform_impl::wayland::types::cursor::CustomCursor;
+use crate::platform_impl::wayland::{self, WindowId};
+
+/// The MIME type of the list of dragged files.
+const URI_LIST_MIME_TYPE: &str = "text/uri-list";
+
+/// A drag and drop over one of our windows.
+#[derive(Debug)]
+pub struct DragState {
+    offer: DragOffer,
+    window_id: WindowId,
+    position: PhysicalPosition<f64>,
+    mime_types: Vec<String>,
+    /// The dragged paths, `None` until the offer was read.
+    paths: Option<Vec<PathBuf>>,
+    /// The action chosen for the drop, `None` when rejected.
+    action: Option<DragAction>,
+    /// Whether the application chose the action, rather than getting the default one.
+    action_chosen: bool,
+    /// Whether the data was dropped, the offer is finished once the drop was delivered and all
+    /// the requested data was read.
+    dropped: bool,
+    /// The reads of the requested data that are still in progress.
+    pending_reads: Vec<(RegistrationToken, AsyncRequestSerial)>,
+}
+
+impl DragState {
+    fn has_paths(&self) -> bool {
+        self.mime_types.iter().any(|mime_type| mime_type == URI_LIST_MIME_TYPE)
+    }
+
+    /// Tell the source whether and how the drop would be accepted.
+    fn update_offer(&self) {
+        let (actions, preferred) = match self.action {
+            // Files are copied by default, or moved when the source only allows that.
+            Some(_) if !self.action_chosen => {
+                (DndAction::Copy | DndAction::Move, preferred_action(self.offer.source_actions))
+            },
+            Some(action) => {
+                let action = dnd_action(action).unwrap_or(DndAction::empty());
+                (action, action)
+            },
+            None => (DndAction::empty(), DndAction::empty()),
+        };
+
+        let mime_type = self.action.and_then(|_| match self.has_paths() {
+            true => Some(URI_LIST_MIME_TYPE.to_owned()),
+            false => self.mime_types.first().cloned(),
+        });
+        self.offer.accept_mime_type(self.offer.serial, mime_type);
+        self.offer.set_actions(actions, preferred);
+    }
+}
+
+impl WinitState {
+    /// The drag and drop state of the seat the data device belongs to.
+    fn drag_slot(&mut self, data_device: &WlDataDevice) -> Option<&mut Option<DragState>> {
+        let seat = data_device.data::<DataDeviceData>()?.seat();
+        self.seats.get_mut(&seat.id()).map(|seat_state| &mut seat_state.drag)
+    }
+
+    /// The drag and drop over one of our windows, from any seat.
+    fn current_drag(&mut self) -> Option<(&ObjectId, &mut DragState)> {
+        self.seats
+            .iter_mut()
+            .find_map(|(seat_id, seat_state)| Some((seat_id, seat_state.drag.as_mut()?)))
+    }
+
+    fn drag_position(&self, window_id: WindowId, x: f64, y: f64) -> Option<PhysicalPosition<f64>> {
+        let scale_factor = self.windows.borrow().get(&window_id)?.lock().unwrap().scale_factor();
+        Some(LogicalPosition::new(x, y).to_physical(scale_factor))
+    }
+
+    /// Accept the drag with the given action, or reject it.
+    pub fn set_drag_action(&mut self, action: Option<DragAction>) -> Result<(), RequestError> {
+        if action.is_some_and(|action| dnd_action(action).is_none()) {
+            return Err(NotSupportedError::new("links are not supported").into());
+        }
+
+        let (_, drag) =
+            self.current_drag().filter(|(_, drag)| !drag.dropped).ok_or(RequestError::Ignored)?;
+        drag.action = action;
+        drag.action_chosen = true;
+        drag.update_offer();
+        Ok(())
+    }
+
+    /// Read the dragged data in the given MIME type, delivered as `DragData`.
+    pub fn request_drag_data(
+        &mut self,
+        mime_type: &str,
+    ) -> Result<AsyncRequestSerial, RequestError> {
+        let serial = AsyncRequestSerial::get();
+        let (seat_id, drag) = self.current_drag().ok_or(RequestError::Ignored)?;
+        let (seat_id, window_id) = (seat_id.clone(), drag.window_id);
+
+        if !drag.mime_types.iter().any(|offered| offered == mime_type) {
+            let event = WindowEvent::DragData { serial, result: Err(ClipboardError::Unavailable) };
+            self.window_events_sink.lock().unwrap().push_window_event(event, window_id);
+            return Ok(serial);
+        }
+
+        let pipe = drag.offer.receive(mime_type.to_owned()).map_err(|err| os_error!(err))?;
+        let offer = drag.offer.clone();
+        let mime_type = mime_type.to_owned();
+        let token = self
+            .read_offer(pipe, move |state, result| {
+                let result = match result {
+                    Ok(data) => Ok(ClipboardData { mime_type, data }),
+                    Err(err) => {
+                        warn!("Failed to read the dragged data: {err}");
+                        Err(ClipboardError::TransferFailed)
+                    },
+                };
+
+                let drag =
+                    state.seats.get_mut(&seat_id).and_then(|seat_state| seat_state.drag.as_mut());
+                if let Some(drag) = drag.filter(|drag| drag.offer == offer) {
+                    drag.pending_reads.retain(|(_, pending)| *pending != serial);
+                }
+
+                let event = WindowEvent::DragData { serial, result };
+                state.events_sink.push_window_event(event, window_id);
+                state.dispatched_events = true;
+            })
+            .map_err(|err| os_error!(err))?;
+
+        if let Some((_, drag)) = self.current_drag() {
+            drag.pending_reads.push((token, serial));
+        }
+
+        Ok(serial)
+    }
+
+    /// Finish the drops that were delivered, once all the data requested for them was read.
+    pub fn finish_drops(&mut self) {
+        for seat_state in self.seats.values_mut() {
+            let finished = seat_state.drag.as_ref().is_some_and(|drag| {
+                drag.dropped && drag.paths.is_some() && drag.pending_reads.is_empty()
+            });
+            if finished {
+                let drag = seat_state.drag.take().unwrap();
+                drag.offer.finish();
+                drag.offer.destroy();
+            }
+        }
+    }
+
+    /// Give up on a dropped offer that is replaced by a new drag before it was finished.
+    ///
+    /// The data still being read for it is reported as failed.
+    fn abandon_drop(&mut self, drag: DragState) {
+        for (token, serial) in drag.pending_reads {
+            self.loop_handle.remove(token);
+            let event =
+                WindowEvent::DragData { serial, result: Err(ClipboardError::TransferFailed) };
+            self.events_sink.push_window_event(event, drag.window_id);
+            self.dispatched_events = true;
+        }
+
+        // Only an accepted drop can be finished.
+        if drag.dropped && drag.action.is_some() {
+            drag.offer.finish();
+        }
+        drag.offer.destroy();
+    }
+
+    /// Read the offer from the pipe in the background.
+    fn read_offer(
+        &self,
+        pipe: ReadPipe,
+        callback: impl FnOnce(&mut WinitState, io::Result<Vec<u8>>) + 'static,
+    ) -> io::Result<RegistrationToken> {
+        rustix::io::ioctl_fionbio(pipe.as_fd(), true)?;
+
+        let mut callback = Some(callback);
+        let mut data = Vec::new();
+        let token = self
+            .loop_handle
+            .insert_source(pipe, move |_, file, state| {
+                // SAFETY: the file is not closed.
+                let result = match read_to_end_nonblocking(unsafe { file.get_mut() }, &mut data) {
+                    Some(result) => result.map(|_| std::mem::take(&mut data)),
+                    None => return PostAction::Continue,
+                };
+
+                if let Some(callback) = callback.take() {
+                    callback(state, result);
+                }
+                PostAction::Remove
+            })
+            .map_err(|err| err.error)?;
+
+        Ok(token)
+    }
+
+    fn dragged_paths_read(
+        &mut self,
+        seat_id: &ObjectId,
+        offer: &DragOffer,
+        data: io::Result<Vec<u8>>,
+    ) {
+        // The drag could have left in the meantime.
+        let Some(slot) = self.seats.get_mut(seat_id).map(|seat_state| &mut seat_state.drag) else {
+            return;
+        };
+        if slot.as_ref().map_or(true, |drag| drag.offer != *offer) {
+            return;
+        }
+
+        let data = match data {
+            Ok(data) => data,
+            Err(err) => {
+                warn!("Failed to read the dragged paths: {err}");
+                if let Some(drag) = slot.take().filter(|drag| drag.dropped) {
+                    drag.offer.destroy();
+                }
+                return;
+            },
+        };
+
+        let drag = slot.as_mut().unwrap();
+        let paths = parse_uri_list(&data);
+        drag.paths = Some(paths.clone());
+
+        let event = WindowEvent::DragEntered {
+            paths: paths.clone(),
+            mime_types: drag.mime_types.clone(),
+            position: drag.position,
+        };
+        self.events_sink.push_window_event(event, drag.window_id);
+
+        if drag.dropped {
+            let event = WindowEvent::DragDropped { paths, position: drag.position };
+            self.events_sink.push_window_event(event, drag.window_id);
+        }
+
+        self.dispatched_events = true;
+    }
+}
+
+impl DataDeviceHandler for WinitState {
+    fn enter(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        data_device: &WlDataDevice,
+        x: f64,
+        y: f64,
+        surface: &WlSurface,
+    ) {
+        let Some(data) = data_device.data::<DataDeviceData>() else { return };
+        let Some(offer) = data.drag_offer() else { return };
+        let seat_id = data.seat().id();
+
+        // Only data dragged over the windows themselves is accepted.
+        let window_id = wayland::make_wid(surface);
+        let Some(position) = self.drag_position(window_id, x, y) else {
+            offer.accept_mime_type(offer.serial, None);
+            offer.set_actions(DndAction::empty(), DndAction::empty());
+            return;
+        };
+
+        let mime_types = offer.with_mime_types(<[String]>::to_vec);
+        let mut drag = DragState {
+            offer,
+            window_id,
+            position,
+            mime_types,
+            paths: None,
+            action: None,
+            action_chosen: false,
+            dropped: false,
+            pending_reads: Vec::new(),
+        };
+
+        // The paths are read first, they're needed for `DragEntered`.
+        let reading_paths = drag.has_paths() && {
+            let offer = drag.offer.clone();
+            let result = drag.offer.receive(URI_LIST_MIME_TYPE.to_owned()).and_then(|pipe| {
+                self.read_offer(pipe, move |state, result| {
+                    state.dragged_paths_read(&seat_id, &offer, result)
+                })
+            });
+            result.inspect_err(|err| warn!("Failed to read the dragged paths: {err}")).is_ok()
+        };
+
+        if reading_paths {
+            drag.action = Some(DragAction::Copy);
+        } else {
+            // Without the paths, the drag is still delivered with the other MIME types.
+            drag.paths = Some(Vec::new());
+            let event = WindowEvent::DragEntered {
+                paths: Vec::new(),
+                mime_types: drag.mime_types.clone(),
+                position,
+            };
+            self.events_sink.push_window_event(event, window_id);
+        }
+
+        drag.update_offer();
+        let Some(slot) = self.drag_slot(data_device) else { return };
+        // A new drag can start before the previous drop was finished.
+        if let Some(previous) = slot.replace(drag) {
+            self.abandon_drop(previous);
+        }
+    }
+
+    fn leave(&mut self, _: &Connection, _: &QueueHandle<Self>, data_device: &WlDataDevice) {
+        let Some(slot) = self.drag_slot(data_device) else { return };
+
+        // A dropped offer is kept until it's finished.
+        if slot.as_ref().map_or(true, |drag| drag.dropped) {
+            return;
+        }
+
+        let drag = slot.take().unwrap();
+        if drag.paths.is_some() {
+            let event = WindowEvent::DragLeft { position: Some(drag.position) };
+            self.events_sink.push_window_event(event, drag.window_id);
+        }
+    }
+
+    fn motion(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        data_device: &WlDataDevice,
+        x: f64,
+        y: f64,
+    ) {
+        let Some(Some(drag)) = self.drag_slot(data_device) else { return };
+        let window_id = drag.window_id;
+        let Some(position) = self.drag_position(window_id, x, y) else { return };
+
+        let Some(Some(drag)) = self.drag_slot(data_device) else { return };
+        drag.position = position;
+        if drag.paths.is_some() {
+            self.events_sink.push_window_event(WindowEvent::DragMoved { position }, window_id);
+        }
+    }
+
+    fn selection(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &WlDataDevice) {
+        self.events_sink
+            .push_clipboard_event(ClipboardEvent::Changed { kind: ClipboardKind::Clipboard });
+    }
+
+    fn drop_performed(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        data_device: &WlDataDevice,
+    ) {
+        let Some(Some(drag)) = self.drag_slot(data_device) else { return };
+        drag.dropped = true;
+
+        // Otherwise the drop is delivered once the paths were read.
+        if let Some(paths) = drag.paths.clone() {
+            let event = WindowEvent::DragDropped { paths, position: drag.position };
+            let window_id = drag.window_id;
+            self.events_sink.push_window_event(event, window_id);
+        }
+    }
+}
+
+impl DataOfferHandler for WinitState {
+    fn source_actions(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        offer: &mut DragOffer,
+        actions: DndAction,
+    ) {
+        let drag = self
+            .seats
+            .values_mut()
+            .find_map(|seat_state| seat_state.drag.as_mut().filter(|drag| drag.offer == *offer));
+        if let Some(drag) = drag.filter(|drag| !drag.dropped) {
+            drag.offer.source_actions = actions;
+            drag.update_offer();
+        }
+    }
+
+    fn selected_action(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &mut DragOffer,
+        _: DndAction,
+    ) {
+    }
+}
+
+/// A drag started from one of our windows.
+#[derive(Debug)]
+pub struct OutgoingDrag {
+    source: WlDataSource,
+    window_id: WindowId,
+    serial: AsyncRequestSerial,
+    contents: ClipboardContents,
+    /// Kept alive until the drag ends.
+    _icon: Option<DragIcon>,
+    /// Whether the target accepts one of the MIME types.
+    pub accepted: bool,
+    /// The action chosen by the target.
+    pub action: Option<DragAction>,
+}
+
+impl Drop for OutgoingDrag {
+    fn drop(&mut self) {
+        self.source.destroy();
+    }
+}
+
+/// The surface showing the image dragged along with the pointer.
+#[derive(Debug)]
+pub struct DragIcon {
+    surface: WlSurface,
+    image: CustomCursor,
+}
+
+impl DragIcon {
+    pub fn new(surface: WlSurface, image: CustomCursor) -> Self {
+        Self { surface, image }
+    }
+
+    /// Show the image, with its hotspot at the pointer.
+    fn commit(&self) {
+        let (x, y) = (-self.image.hotspot_x, -self.image.hotspot_y);
+        let buffer = self.image.buffer.wl_buffer();
+        if self.surface.version() >= 5 {
+            self.surface.attach(Some(buffer), 0, 0);
+            self.surface.offset(x, y);
+        } else {
+            self.surface.attach(Some(buffer), x, y);
+        }
+
+        if self.surface.version() >= 4 {
+            self.surface.damage_buffer(0, 0, self.image.w, self.image.h);
+        } else {
+            self.surface.damage(0, 0, self.image.w, self.image.h);
+        }
+        self.surface.commit();
+    }
+}
+
+impl Drop for DragIcon {
+    fn drop(&mut self) {
+        self.surface.destroy();
+    }
+}
+
+impl ClipboardState {
+    /// Start dragging the contents out of the surface, with the latest button press.
+    pub fn start_drag(
+        &mut self,
+        manager: &WlDataDeviceManager,
+        queue_handle: &QueueHandle<WinitState>,
+        origin: &WlSurface,
+        contents: ClipboardContents,
+        actions: &[DragAction],
+        icon: Option<DragIcon>,
+    ) -> Result<AsyncRequestSerial, RequestError> {
+        let actions = actions.iter().try_fold(DndAction::empty(), |actions, &action| {
+            dnd_action(action)
+                .map(|action| actions | action)
+                .ok_or_else(|| NotSupportedError::new("links are not supported"))
+        })?;
+
+        // The pointer can only drag one thing at a time, and only with a button held.
+        let Some((device, serial)) = self.latest_button.as_ref().filter(|_| self.drag.is_none())
+        else {
+            return Err(RequestError::Ignored);
+        };
+
+        let source = manager.create_data_source(queue_handle, DataSourceData::default());
+        offer_contents(&contents, |mime_type| source.offer(mime_type));
+        source.set_actions(actions);
+        device.start_drag(Some(&source), origin, icon.as_ref().map(|icon| &icon.surface), *serial);
+        if let Some(icon) = icon.as_ref() {
+            icon.commit();
+        }
+
+        let serial = AsyncRequestSerial::get();
+        self.drag = Some(OutgoingDrag {
+            source,
+            window_id: wayland::make_wid(origin),
+            serial,
+            contents,
+            _icon: icon,
+            accepted: false,
+            action: None,
+        });
+        Ok(serial)
+    }
+}
+
+impl WinitState {
+    /// Whether the source is the one of the drag started from our windows.
+    pub(super) fn is_outgoing_drag(&self, source: &WlDataSource) -> bool {
+        self.clipboard.lock().unwrap().drag.as_ref().is_some_and(|drag| drag.source == *source)
+    }
+
+    /// Update the drag started from our windows, delivering the returned event.
+    pub(super) fn update_outgoing_drag(
+        &mut self,
+        source: &WlDataSource,
+        update: impl FnOnce(&mut OutgoingDrag) -> Option<DragSourceEvent>,
+    ) {
+        let mut state = self.clipboard.lock().unwrap();
+        let Some(drag) = state.drag.as_mut().filter(|drag| drag.source == *source) else {
+            return;
+        };
+
+        if let Some(event) = update(drag) {
+            let event = WindowEvent::DragSource { serial: drag.serial, event };
+            self.events_sink.push_window_event(event, drag.window_id);
+        }
+    }
+
+    /// Serve the dragged contents to the target.
+    pub(super) fn send_drag_data(&mut self, mime: String, pipe: WritePipe) {
+        let data = self
+            .clipboard
+            .lock()
+            .unwrap()
+            .drag
+            .as_ref()
+            .and_then(|drag| requested_data(&drag.contents, &mime));
+        if let Some(data) = data {
+            self.write_data(data, pipe);
+        }
+    }
+
+    /// End the drag started from our windows with the event.
+    pub(super) fn end_outgoing_drag(&mut self, event: DragSourceEvent) {
+        let Some(drag) = self.clipboard.lock().unwrap().drag.take() else { return };
+        let event = WindowEvent::DragSource { serial: drag.serial, event };
+        self.events_sink.push_window_event(event, drag.window_id);
+    }
+}
+
+/// The protocol action, links aren't part of the protocol.
+fn dnd_action(action: DragAction) -> Option<DndAction> {
+    match action {
+        DragAction::Copy => Some(DndAction::Copy),
+        DragAction::Move => Some(DndAction::Move),
+        DragAction::Link => None,
+    }
+}
+
+/// The action chosen by the target of our drag.
+pub(super) fn drag_action(action: DndAction) -> Option<DragAction> {
+    if action.contains(DndAction::Copy) {
+        Some(DragAction::Copy)
+    } else if action.contains(DndAction::Move) {
+        Some(DragAction::Move)
+    } else {
+        None
+    }
+}
+
+/// Copy the files when the source allows it, move them otherwise.
+fn preferred_action(source_actions: DndAction) -> DndAction {
+    if source_actions.contains(DndAction::Copy) {
+        DndAction::Copy
+    } else if source_actions.contains(DndAction::Move) {
+        DndAction::Move
+    } else {
+        DndAction::empty()
+    }
+}
+
+/// Parse the local paths out of a `text/uri-list`.
+///
+/// Comments, and URIs other than local `file://` ones, are skipped.
+fn parse_uri_list(data: &[u8]) -> Vec<PathBuf> {
+    data.split(|&byte| byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.starts_with(b"#"))
+        .filter_map(|uri| {
+            let path = uri.strip_prefix(b"file://")?;
+            let path = path.strip_prefix(b"localhost").unwrap_or(path);
+            if !path.starts_with(b"/") {
+                return None;
+            }
+
+            let path: Vec<u8> = percent_decode(path).collect();
+            Some(PathBuf::from(OsString::from_vec(path)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uri_list() {
+        let data = b"# comment\r\nfile:///tmp/a%20b.txt\r\nfile://localhost/home/c\r\n\
+                     https://example.com/d\r\nfile://host/e\r\nfile:///f\n";
+        assert_eq!(parse_uri_list(data), [
+            PathBuf::from("/tmp/a b.txt"),
+            PathBuf::from("/home/c"),
+            PathBuf::from("/f"),
+        ]);
+    }
+
+    #[test]
+    fn uri_list_empty() {
+        assert!(parse_uri_list(b"").is_empty());
+        assert!(parse_uri_list(b"\r\n").is_empty());
+    }
+}
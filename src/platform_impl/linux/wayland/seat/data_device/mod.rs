@@ -0,0 +1,593 @@
+//! The clipboard handling through the data device, and the primary selection.
+
+use std::cell::Cell;
+use std::io::{self, ErrorKind, Read, Write};
+use std::os::unix::io::AsFd;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use calloop::ping::Ping;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{LoopHandle, PostAction};
+use sctk::data_device_manager::data_device::{DataDevice, DataDeviceData};
+use sctk::data_device_manager::data_offer::{self, SelectionOffer};
+use sctk::data_device_manager::data_source::{DataSourceData, DataSourceHandler};
+use sctk::data_device_manager::{ReadPipe, WritePipe};
+use sctk::globals::GlobalData;
+use sctk::primary_selection::device::{PrimarySelectionDevice, PrimarySelectionDeviceData};
+use sctk::primary_selection::offer::PrimarySelectionOffer;
+use sctk::reexports::client::protocol::wl_data_device::WlDataDevice;
+use sctk::reexports::client::protocol::wl_data_device_manager::{DndAction, WlDataDeviceManager};
+use sctk::reexports::client::protocol::wl_data_source::WlDataSource;
+use sctk::reexports::client::{Connection, Proxy, QueueHandle};
+use sctk::reexports::protocols::wp::primary_selection::zv1::client::zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1;
+use sctk::reexports::protocols::wp::primary_selection::zv1::client::zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1;
+use sctk::reexports::protocols::wp::primary_selection::zv1::client::zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1;
+use tracing::warn;
+use winit_core::clipboard::{
+    Clipboard as CoreClipboard, ClipboardContents, ClipboardData, ClipboardError, ClipboardEvent,
+    ClipboardKind, TEXT_MIME_TYPE,
+};
+use winit_core::error::{NotSupportedError, RequestError};
+use winit_core::event::DragSourceEvent;
+use winit_core::event_loop::AsyncRequestSerial;
+
+use crate::platform_impl::wayland::event_loop::sink::EventSink;
+use crate::platform_impl::wayland::state::WinitState;
+
+mod dnd;
+mod primary_selection;
+
+use dnd::{drag_action, OutgoingDrag};
+pub use dnd::{DragIcon, DragState};
+
+/// The MIME types other clients use for UTF-8 text, in the order of preference.
+const TEXT_MIME_TYPES: [&str; 3] = [TEXT_MIME_TYPE, "UTF8_STRING", "text/plain"];
+
+/// How long the owner of the selection has to send the data before the read is considered failed.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read from the nonblocking pipe until it would block.
+///
+/// Returns `None` while the other end of the pipe is still open.
+fn read_to_end_nonblocking(file: &mut impl Read, data: &mut Vec<u8>) -> Option<io::Result<()>> {
+    let mut buf = [0u8; 4096];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => return Some(Ok(())),
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => return None,
+            Err(err) => return Some(Err(err)),
+        }
+    }
+}
+
+/// Offer the contents in all their MIME types, text is also offered with the other text types.
+fn offer_contents(contents: &ClipboardContents, offer: impl Fn(String)) {
+    for mime_type in contents.mime_types() {
+        if mime_type == TEXT_MIME_TYPE {
+            TEXT_MIME_TYPES.iter().for_each(|mime_type| offer(mime_type.to_string()));
+        } else {
+            offer(mime_type.to_owned());
+        }
+    }
+}
+
+/// The data of the contents requested in one of the offered MIME types.
+fn requested_data(contents: &ClipboardContents, mime_type: &str) -> Option<Vec<u8>> {
+    let mime_type = if TEXT_MIME_TYPES.contains(&mime_type) { TEXT_MIME_TYPE } else { mime_type };
+    contents.get(mime_type).map(<[u8]>::to_vec)
+}
+
+/// The manager of the global backing a selection.
+#[derive(Debug, Clone)]
+enum SelectionManager {
+    Clipboard(WlDataDeviceManager),
+    Primary(ZwpPrimarySelectionDeviceManagerV1),
+}
+
+impl SelectionManager {
+    fn create_source(&self, queue_handle: &QueueHandle<WinitState>) -> SelectionSource {
+        match self {
+            Self::Clipboard(manager) => SelectionSource::Clipboard(
+                manager.create_data_source(queue_handle, DataSourceData::default()),
+            ),
+            Self::Primary(manager) => {
+                SelectionSource::Primary(manager.create_source(queue_handle, GlobalData))
+            },
+        }
+    }
+}
+
+/// The device of a seat for one of the selections.
+#[derive(Debug, Clone)]
+enum SelectionDevice {
+    Clipboard(WlDataDevice),
+    Primary(ZwpPrimarySelectionDeviceV1),
+}
+
+impl SelectionDevice {
+    fn set_selection(&self, source: Option<&SelectionSource>, serial: u32) {
+        match (self, source) {
+            (Self::Clipboard(device), Some(SelectionSource::Clipboard(source))) => {
+                device.set_selection(Some(source), serial)
+            },
+            (Self::Primary(device), Some(SelectionSource::Primary(source))) => {
+                device.set_selection(Some(source), serial)
+            },
+            (Self::Clipboard(device), None) => device.set_selection(None, serial),
+            (Self::Primary(device), None) => device.set_selection(None, serial),
+            _ => unreachable!("selection source of the wrong kind"),
+        }
+    }
+
+    /// The offer of the current selection, if any.
+    fn selection_offer(&self) -> Option<SelectionDeviceOffer> {
+        match self {
+            Self::Clipboard(device) => device
+                .data::<DataDeviceData>()
+                .and_then(DataDeviceData::selection_offer)
+                .map(SelectionDeviceOffer::Clipboard),
+            Self::Primary(device) => device
+                .data::<PrimarySelectionDeviceData>()
+                .and_then(PrimarySelectionDeviceData::selection_offer)
+                .map(SelectionDeviceOffer::Primary),
+        }
+    }
+}
+
+/// The selection offered by another client.
+#[derive(Debug)]
+enum SelectionDeviceOffer {
+    Clipboard(SelectionOffer),
+    Primary(PrimarySelectionOffer),
+}
+
+impl SelectionDeviceOffer {
+    fn with_mime_types<T, F: Fn(&[String]) -> T>(&self, callback: F) -> T {
+        match self {
+            Self::Clipboard(offer) => offer.with_mime_types(callback),
+            Self::Primary(offer) => offer.with_mime_types(callback),
+        }
+    }
+
+    fn receive(&self, mime_type: String) -> io::Result<ReadPipe> {
+        match self {
+            Self::Clipboard(offer) => data_offer::receive(offer.inner(), mime_type),
+            Self::Primary(offer) => offer.receive(mime_type),
+        }
+    }
+}
+
+/// The source we offer a selection with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SelectionSource {
+    Clipboard(WlDataSource),
+    Primary(ZwpPrimarySelectionSourceV1),
+}
+
+impl SelectionSource {
+    fn offer(&self, mime_type: String) {
+        match self {
+            Self::Clipboard(source) => source.offer(mime_type),
+            Self::Primary(source) => source.offer(mime_type),
+        }
+    }
+
+    fn destroy(&self) {
+        match self {
+            Self::Clipboard(source) => source.destroy(),
+            Self::Primary(source) => source.destroy(),
+        }
+    }
+}
+
+/// The state of one of the selections.
+#[derive(Debug, Default)]
+struct SelectionState {
+    /// The device of the seat that got the latest input, with the serial of that input.
+    latest_input: Option<(SelectionDevice, u32)>,
+
+    /// The source we set as the selection, with the contents it serves.
+    source: Option<(SelectionSource, ClipboardContents)>,
+}
+
+impl SelectionState {
+    /// Destroy our source, if any.
+    fn take_source(&mut self) -> Option<ClipboardContents> {
+        let (source, contents) = self.source.take()?;
+        source.destroy();
+        Some(contents)
+    }
+}
+
+/// The selections state shared between the event loop and the [`Clipboard`]s.
+#[derive(Debug, Default)]
+pub struct ClipboardState {
+    clipboard: SelectionState,
+    primary: SelectionState,
+    /// The data device of the seat whose pointer got the latest button press, with its serial.
+    latest_button: Option<(WlDataDevice, u32)>,
+    /// The drag started from one of our windows.
+    drag: Option<OutgoingDrag>,
+}
+
+impl ClipboardState {
+    /// Remember the input, the selections can only be set with the serial of a recent one.
+    pub fn input_received(
+        &mut self,
+        data_device: Option<&DataDevice>,
+        primary_device: Option<&PrimarySelectionDevice>,
+        serial: u32,
+    ) {
+        if let Some(data_device) = data_device {
+            let device = SelectionDevice::Clipboard(data_device.inner().clone());
+            self.clipboard.latest_input = Some((device, serial));
+        }
+
+        if let Some(primary_device) = primary_device {
+            let device = SelectionDevice::Primary(primary_device.inner().clone());
+            self.primary.latest_input = Some((device, serial));
+        }
+    }
+
+    /// Remember the button press, drags can only be started with the serial of one.
+    pub fn button_pressed(
+        &mut self,
+        data_device: Option<&DataDevice>,
+        primary_device: Option<&PrimarySelectionDevice>,
+        serial: u32,
+    ) {
+        self.input_received(data_device, primary_device, serial);
+        self.latest_button = data_device.map(|data_device| (data_device.inner().clone(), serial));
+    }
+
+    fn selection(&mut self, kind: ClipboardKind) -> &mut SelectionState {
+        match kind {
+            ClipboardKind::Clipboard => &mut self.clipboard,
+            ClipboardKind::Primary => &mut self.primary,
+        }
+    }
+}
+
+/// The clipboard or the primary selection of the seat that got the latest input.
+#[derive(Debug)]
+pub struct Clipboard {
+    kind: ClipboardKind,
+    state: Arc<Mutex<ClipboardState>>,
+    manager: Option<SelectionManager>,
+    queue_handle: QueueHandle<WinitState>,
+    loop_handle: LoopHandle<'static, WinitState>,
+    /// Sink for the results we have right away.
+    event_sink: Arc<Mutex<EventSink>>,
+    event_loop_awakener: Ping,
+}
+
+impl Clipboard {
+    pub fn new(
+        winit_state: &WinitState,
+        kind: ClipboardKind,
+        queue_handle: QueueHandle<WinitState>,
+        event_loop_awakener: Ping,
+    ) -> Self {
+        let manager = match kind {
+            ClipboardKind::Clipboard => winit_state
+                .data_device_manager
+                .as_ref()
+                .map(|manager| SelectionManager::Clipboard(manager.data_device_manager().clone())),
+            ClipboardKind::Primary => {
+                winit_state.primary_selection_manager.as_ref().map(|manager| {
+                    SelectionManager::Primary(manager.primary_selection_manager().clone())
+                })
+            },
+        };
+
+        Self {
+            kind,
+            state: winit_state.clipboard.clone(),
+            manager,
+            queue_handle,
+            loop_handle: winit_state.loop_handle.clone(),
+            event_sink: winit_state.window_events_sink.clone(),
+            event_loop_awakener,
+        }
+    }
+
+    fn manager(&self) -> Result<&SelectionManager, RequestError> {
+        self.manager.as_ref().ok_or_else(|| {
+            let message = match self.kind {
+                ClipboardKind::Clipboard => "the compositor doesn't support wl_data_device_manager",
+                ClipboardKind::Primary => {
+                    "the compositor doesn't support zwp_primary_selection_device_manager_v1"
+                },
+            };
+            NotSupportedError::new(message).into()
+        })
+    }
+
+    /// Deliver the event on the next iteration of the event loop.
+    fn send_event(&self, event: ClipboardEvent) {
+        self.event_sink.lock().unwrap().push_clipboard_event(event);
+        self.event_loop_awakener.ping();
+    }
+}
+
+impl CoreClipboard for Clipboard {
+    fn write(&self, contents: ClipboardContents) -> Result<(), RequestError> {
+        let manager = self.manager()?;
+        let mut state = self.state.lock().unwrap();
+        let state = state.selection(self.kind);
+        let Some((device, serial)) = state.latest_input.clone() else {
+            return Err(RequestError::Ignored);
+        };
+
+        let source = manager.create_source(&self.queue_handle);
+        offer_contents(&contents, |mime_type| source.offer(mime_type));
+        device.set_selection(Some(&source), serial);
+
+        state.take_source();
+        state.source = Some((source, contents));
+        Ok(())
+    }
+
+    fn read(&self, mime_type: &str) -> Result<AsyncRequestSerial, RequestError> {
+        self.manager()?;
+        let serial = AsyncRequestSerial::get();
+        let mut state = self.state.lock().unwrap();
+        let state = state.selection(self.kind);
+
+        // Don't go through the compositor when we're the owner.
+        if let Some((_, contents)) = state.source.as_ref() {
+            let result = contents
+                .get(mime_type)
+                .map(|data| ClipboardData { mime_type: mime_type.to_owned(), data: data.to_vec() })
+                .ok_or(ClipboardError::Unavailable);
+            self.send_event(ClipboardEvent::Read { serial, result });
+            return Ok(serial);
+        }
+
+        let offer = state.latest_input.as_ref().and_then(|(device, _)| device.selection_offer());
+        let offered = offer.as_ref().and_then(|offer| {
+            offer.with_mime_types(|offered| {
+                if mime_type == TEXT_MIME_TYPE {
+                    TEXT_MIME_TYPES.iter().find(|text| offered.iter().any(|o| o == *text)).copied()
+                } else {
+                    offered.iter().any(|o| o == mime_type).then_some(mime_type)
+                }
+                .map(str::to_owned)
+            })
+        });
+        let (Some(offer), Some(offered)) = (offer, offered) else {
+            self.send_event(ClipboardEvent::Read {
+                serial,
+                result: Err(ClipboardError::Unavailable),
+            });
+            return Ok(serial);
+        };
+
+        let pipe = offer.receive(offered).map_err(|err| os_error!(err))?;
+        rustix::io::ioctl_fionbio(pipe.as_fd(), true).map_err(|err| os_error!(err))?;
+
+        let mime_type = mime_type.to_owned();
+        let mut data = Vec::new();
+        // The read and its deadline cancel each other.
+        let timer_token = Rc::new(Cell::new(None));
+        let read_timer_token = timer_token.clone();
+        let pipe_token = self
+            .loop_handle
+            .insert_source(pipe, move |_, file, state| {
+                // SAFETY: the file is not closed.
+                let result = match read_to_end_nonblocking(unsafe { file.get_mut() }, &mut data) {
+                    Some(Ok(())) => Ok(std::mem::take(&mut data)),
+                    Some(Err(err)) => {
+                        warn!("Failed to read the clipboard: {err}");
+                        Err(ClipboardError::TransferFailed)
+                    },
+                    None => return PostAction::Continue,
+                };
+
+                if let Some(token) = read_timer_token.take() {
+                    state.loop_handle.remove(token);
+                }
+
+                let mime_type = mime_type.clone();
+                let result = result.map(|data| ClipboardData { mime_type, data });
+                state.events_sink.push_clipboard_event(ClipboardEvent::Read { serial, result });
+                state.dispatched_events = true;
+                PostAction::Remove
+            })
+            .map_err(|err| os_error!(err.error))?;
+
+        // Fail the read when the owner of the selection never finishes sending the data.
+        let timer = Timer::from_duration(TRANSFER_TIMEOUT);
+        let token = self
+            .loop_handle
+            .insert_source(timer, move |_, _, state| {
+                warn!("The owner of the selection didn't send the data in time");
+                state.loop_handle.remove(pipe_token);
+                let result = Err(ClipboardError::TransferFailed);
+                state.events_sink.push_clipboard_event(ClipboardEvent::Read { serial, result });
+                state.dispatched_events = true;
+                TimeoutAction::Drop
+            })
+            .map_err(|err| {
+                self.loop_handle.remove(pipe_token);
+                os_error!(err.error)
+            })?;
+        timer_token.set(Some(token));
+
+        Ok(serial)
+    }
+
+    fn request_mime_types(&self) -> Result<AsyncRequestSerial, RequestError> {
+        self.manager()?;
+        let serial = AsyncRequestSerial::get();
+        let mut state = self.state.lock().unwrap();
+        let state = state.selection(self.kind);
+
+        let mime_types = if let Some((_, contents)) = state.source.as_ref() {
+            contents.mime_types().map(str::to_owned).collect()
+        } else {
+            let offer =
+                state.latest_input.as_ref().and_then(|(device, _)| device.selection_offer());
+            offer.map(|offer| offer.with_mime_types(<[String]>::to_vec)).unwrap_or_default()
+        };
+
+        self.send_event(ClipboardEvent::MimeTypes { serial, mime_types });
+        Ok(serial)
+    }
+
+    fn clear(&self) -> Result<(), RequestError> {
+        self.manager()?;
+        let mut state = self.state.lock().unwrap();
+        let state = state.selection(self.kind);
+        if state.take_source().is_some() {
+            if let Some((device, serial)) = state.latest_input.as_ref() {
+                device.set_selection(None, *serial);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl WinitState {
+    /// Serve our selection to the client that asked for it through the pipe.
+    fn send_selection(
+        &mut self,
+        kind: ClipboardKind,
+        source: SelectionSource,
+        mime: String,
+        pipe: WritePipe,
+    ) {
+        let data = {
+            let mut state = self.clipboard.lock().unwrap();
+            let Some((_, contents)) =
+                state.selection(kind).source.as_ref().filter(|(ours, _)| *ours == source)
+            else {
+                return;
+            };
+
+            match requested_data(contents, &mime) {
+                Some(data) => data,
+                None => return,
+            }
+        };
+
+        self.write_data(data, pipe);
+    }
+
+    /// Write the data to the pipe of the client that requested it.
+    fn write_data(&self, data: Vec<u8>, pipe: WritePipe) {
+        if let Err(err) = rustix::io::ioctl_fionbio(pipe.as_fd(), true) {
+            warn!("Failed to write the requested data: {err}");
+            return;
+        }
+
+        // Write the data as the receiver reads it, the pipe is closed once we're done.
+        let mut written = 0;
+        let result = self.loop_handle.insert_source(pipe, move |_, file, _| loop {
+            // SAFETY: the file is not closed.
+            match unsafe { file.get_mut() }.write(&data[written..]) {
+                Ok(n) if written + n == data.len() => return PostAction::Remove,
+                Ok(n) => written += n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return PostAction::Continue,
+                Err(err) => {
+                    warn!("Failed to write the requested data: {err}");
+                    return PostAction::Remove;
+                },
+            }
+        });
+
+        if let Err(err) = result {
+            warn!("Failed to write the requested data: {}", err.error);
+        }
+    }
+
+    /// Another client took the selection, or the source was replaced.
+    fn selection_cancelled(&mut self, kind: ClipboardKind, source: SelectionSource) {
+        let mut state = self.clipboard.lock().unwrap();
+        let state = state.selection(kind);
+        if state.source.as_ref().is_some_and(|(ours, _)| *ours == source) {
+            state.take_source();
+        } else {
+            source.destroy();
+        }
+    }
+}
+
+impl DataSourceHandler for WinitState {
+    fn accept_mime(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        source: &WlDataSource,
+        mime: Option<String>,
+    ) {
+        self.update_outgoing_drag(source, |drag| {
+            let accepted = mime.is_some();
+            (drag.accepted != accepted).then(|| {
+                drag.accepted = accepted;
+                DragSourceEvent::Accepted(accepted)
+            })
+        });
+    }
+
+    fn send_request(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        source: &WlDataSource,
+        mime: String,
+        pipe: WritePipe,
+    ) {
+        if self.is_outgoing_drag(source) {
+            self.send_drag_data(mime, pipe);
+        } else {
+            let source = SelectionSource::Clipboard(source.clone());
+            self.send_selection(ClipboardKind::Clipboard, source, mime, pipe);
+        }
+    }
+
+    fn cancelled(&mut self, _: &Connection, _: &QueueHandle<Self>, source: &WlDataSource) {
+        if self.is_outgoing_drag(source) {
+            self.end_outgoing_drag(DragSourceEvent::Cancelled);
+        } else {
+            let source = SelectionSource::Clipboard(source.clone());
+            self.selection_cancelled(ClipboardKind::Clipboard, source);
+        }
+    }
+
+    fn dnd_dropped(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &WlDataSource) {}
+
+    fn dnd_finished(&mut self, _: &Connection, _: &QueueHandle<Self>, source: &WlDataSource) {
+        if !self.is_outgoing_drag(source) {
+            return;
+        }
+
+        let action = self.clipboard.lock().unwrap().drag.as_ref().and_then(|drag| drag.action);
+        let event = action.map_or(DragSourceEvent::Cancelled, DragSourceEvent::Finished);
+        self.end_outgoing_drag(event);
+    }
+
+    fn action(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        source: &WlDataSource,
+        action: DndAction,
+    ) {
+        self.update_outgoing_drag(source, |drag| {
+            let action = drag_action(action);
+            (drag.action != action).then(|| {
+                drag.action = action;
+                DragSourceEvent::Action(action)
+            })
+        });
+    }
+}
+
+sctk::delegate_data_device!(WinitState);
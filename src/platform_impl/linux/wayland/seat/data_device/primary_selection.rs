@@ -0,0 +1,50 @@
+//! The primary selection handling.
+
+use sctk::data_device_manager::WritePipe;
+use sctk::primary_selection::device::PrimarySelectionDeviceHandler;
+use sctk::primary_selection::selection::PrimarySelectionSourceHandler;
+use sctk::reexports::client::{Connection, QueueHandle};
+use sctk::reexports::protocols::wp::primary_selection::zv1::client::zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1;
+use sctk::reexports::protocols::wp::primary_selection::zv1::client::zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1;
+use winit_core::clipboard::{ClipboardEvent, ClipboardKind};
+
+use super::SelectionSource;
+use crate::platform_impl::wayland::state::WinitState;
+
+impl PrimarySelectionDeviceHandler for WinitState {
+    fn selection(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &ZwpPrimarySelectionDeviceV1,
+    ) {
+        self.events_sink
+            .push_clipboard_event(ClipboardEvent::Changed { kind: ClipboardKind::Primary });
+    }
+}
+
+impl PrimarySelectionSourceHandler for WinitState {
+    fn send_request(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        source: &ZwpPrimarySelectionSourceV1,
+        mime: String,
+        pipe: WritePipe,
+    ) {
+        let source = SelectionSource::Primary(source.clone());
+        self.send_selection(ClipboardKind::Primary, source, mime, pipe);
+    }
+
+    fn cancelled(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        source: &ZwpPrimarySelectionSourceV1,
+    ) {
+        let source = SelectionSource::Primary(source.clone());
+        self.selection_cancelled(ClipboardKind::Primary, source);
+    }
+}
+
+sctk::delegate_primary_selection!(WinitState);
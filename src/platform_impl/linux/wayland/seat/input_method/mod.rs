@@ -0,0 +1,412 @@
+//! Input method and virtual keyboard, to act as the input method of the compositor.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::fd::AsFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use std::{env, process};
+
+use sctk::globals::GlobalData;
+use sctk::reexports::client::globals::GlobalList;
+use sctk::reexports::client::protocol::wl_keyboard::KeymapFormat;
+use sctk::reexports::client::protocol::wl_seat::WlSeat;
+use sctk::reexports::client::{delegate_dispatch, Connection, Dispatch, Proxy, QueueHandle};
+use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
+    ContentHint, ContentPurpose,
+};
+use tracing::warn;
+use wayland_protocols_misc::zwp_input_method_v2::client::zwp_input_method_manager_v2::ZwpInputMethodManagerV2;
+use wayland_protocols_misc::zwp_input_method_v2::client::zwp_input_method_v2::{
+    Event as InputMethodV2Event, ZwpInputMethodV2,
+};
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1;
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1;
+use winit_core::application::wayland::InputMethodEvent;
+use winit_core::error::{NotSupportedError, RequestError};
+use winit_core::event::ElementState;
+use winit_core::keyboard::{ModifiersState, PhysicalKey};
+use winit_core::window::{ImeHint, ImePurpose};
+
+use crate::platform_impl::common::xkb::{self, ModsIndices};
+use crate::platform_impl::wayland::state::WinitState;
+
+/// The managers of the input method and virtual keyboard protocols.
+#[derive(Debug)]
+pub struct InputMethodState {
+    input_method_manager: Option<ZwpInputMethodManagerV2>,
+    virtual_keyboard_manager: Option<ZwpVirtualKeyboardManagerV1>,
+}
+
+impl InputMethodState {
+    /// Bind the managers, each of them is optional.
+    pub fn new(globals: &GlobalList, queue_handle: &QueueHandle<WinitState>) -> Self {
+        let input_method_manager = globals.bind(queue_handle, 1..=1, GlobalData).ok();
+        let virtual_keyboard_manager = globals.bind(queue_handle, 1..=1, GlobalData).ok();
+        Self { input_method_manager, virtual_keyboard_manager }
+    }
+
+    /// Get the input method for the given seat.
+    pub fn get_input_method(
+        &self,
+        seat: &WlSeat,
+        queue_handle: &QueueHandle<WinitState>,
+    ) -> Option<InputMethod> {
+        let manager = self.input_method_manager.as_ref()?;
+        let input_method = manager.get_input_method(seat, queue_handle, InputMethodData::default());
+        Some(InputMethod { input_method })
+    }
+
+    /// Get a virtual keyboard for the given seat, with the default keymap of the system.
+    pub fn get_virtual_keyboard(
+        &self,
+        seat: &WlSeat,
+        queue_handle: &QueueHandle<WinitState>,
+    ) -> Option<VirtualKeyboard> {
+        let manager = self.virtual_keyboard_manager.as_ref()?;
+        let (keymap, mods_indices) = match xkb::default_keymap() {
+            Some(keymap) => keymap,
+            None => {
+                warn!("Failed to compile the keymap of the virtual keyboard");
+                return None;
+            },
+        };
+        let file = match keymap_file(&keymap) {
+            Ok(file) => file,
+            Err(err) => {
+                warn!("Failed to share the keymap of the virtual keyboard: {err}");
+                return None;
+            },
+        };
+
+        let keyboard = manager.create_virtual_keyboard(seat, queue_handle, GlobalData);
+        // The size includes the terminating nul byte.
+        keyboard.keymap(KeymapFormat::XkbV1.into(), file.as_fd(), keymap.len() as u32 + 1);
+        Some(VirtualKeyboard { keyboard, mods_indices, start: Instant::now() })
+    }
+}
+
+/// Write the keymap to an unlinked file, to share it with the compositor.
+fn keymap_file(keymap: &str) -> io::Result<File> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(env::temp_dir);
+    let path = dir.join(format!(
+        "winit-keymap-{}-{}",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file =
+        OpenOptions::new().read(true).write(true).create_new(true).mode(0o600).open(&path)?;
+    let _ = fs::remove_file(&path);
+
+    file.write_all(keymap.as_bytes())?;
+    file.write_all(&[0])?;
+    Ok(file)
+}
+
+/// The input method bound on a seat.
+#[derive(Debug)]
+pub struct InputMethod {
+    input_method: ZwpInputMethodV2,
+}
+
+impl InputMethod {
+    fn data(&self) -> &InputMethodData {
+        self.input_method.data::<InputMethodData>().unwrap()
+    }
+
+    /// Whether a text input is focused.
+    pub fn is_active(&self) -> bool {
+        self.data().inner.lock().unwrap().active
+    }
+
+    /// Whether the compositor accepted us as the input method of the seat.
+    pub fn is_available(&self) -> bool {
+        !self.data().inner.lock().unwrap().unavailable
+    }
+
+    pub fn commit_string(&self, text: &str) {
+        self.input_method.commit_string(text.to_owned());
+    }
+
+    pub fn set_preedit(&self, text: &str, cursor_range: Option<(usize, usize)>) {
+        // The cursor is hidden with negative positions.
+        let (begin, end) =
+            cursor_range.map(|(begin, end)| (begin as i32, end as i32)).unwrap_or((-1, -1));
+        self.input_method.set_preedit_string(text.to_owned(), begin, end);
+    }
+
+    pub fn delete_surrounding(&self, before_bytes: usize, after_bytes: usize) {
+        self.input_method.delete_surrounding_text(before_bytes as u32, after_bytes as u32);
+    }
+
+    /// Apply the pending requests, matching the state last sent by the compositor.
+    pub fn commit(&self) {
+        let serial = self.data().inner.lock().unwrap().serial;
+        self.input_method.commit(serial);
+    }
+}
+
+impl Drop for InputMethod {
+    fn drop(&mut self) {
+        self.input_method.destroy();
+    }
+}
+
+/// The virtual keyboard bound on a seat.
+#[derive(Debug)]
+pub struct VirtualKeyboard {
+    keyboard: ZwpVirtualKeyboardV1,
+    mods_indices: ModsIndices,
+    /// The origin of the timestamps of the key events.
+    start: Instant,
+}
+
+impl VirtualKeyboard {
+    pub fn key(&self, key: PhysicalKey, state: ElementState) -> Result<(), RequestError> {
+        let scancode = xkb::physicalkey_to_scancode(key)
+            .ok_or_else(|| NotSupportedError::new("the key has no scancode"))?;
+        let time = self.start.elapsed().as_millis() as u32;
+        let state = match state {
+            ElementState::Pressed => 1,
+            ElementState::Released => 0,
+        };
+        self.keyboard.key(time, scancode, state);
+        Ok(())
+    }
+
+    pub fn modifiers(&self, modifiers: ModifiersState) {
+        let depressed = modifiers_mask(self.mods_indices, modifiers);
+        self.keyboard.modifiers(depressed, 0, 0, 0);
+    }
+}
+
+impl Drop for VirtualKeyboard {
+    fn drop(&mut self) {
+        self.keyboard.destroy();
+    }
+}
+
+/// Map the modifiers to the modifiers mask of the keymap.
+fn modifiers_mask(mods_indices: ModsIndices, modifiers: ModifiersState) -> u32 {
+    [
+        (mods_indices.shift, ModifiersState::SHIFT),
+        (mods_indices.ctrl, ModifiersState::CONTROL),
+        (mods_indices.alt, ModifiersState::ALT),
+        (mods_indices.logo, ModifiersState::META),
+    ]
+    .into_iter()
+    .filter_map(|(index, modifier)| index.filter(|_| modifiers.contains(modifier)))
+    .fold(0, |mask, index| mask | (1 << index))
+}
+
+/// Map the content purpose of the protocol to the IME purpose.
+fn ime_purpose(purpose: ContentPurpose) -> ImePurpose {
+    match purpose {
+        ContentPurpose::Alpha => ImePurpose::Alpha,
+        ContentPurpose::Digits => ImePurpose::Digits,
+        ContentPurpose::Number => ImePurpose::Number,
+        ContentPurpose::Phone => ImePurpose::Phone,
+        ContentPurpose::Url => ImePurpose::Url,
+        ContentPurpose::Email => ImePurpose::Email,
+        ContentPurpose::Name => ImePurpose::Name,
+        ContentPurpose::Password => ImePurpose::Password,
+        ContentPurpose::Pin => ImePurpose::Pin,
+        ContentPurpose::Date => ImePurpose::Date,
+        ContentPurpose::Time => ImePurpose::Time,
+        ContentPurpose::Datetime => ImePurpose::DateTime,
+        ContentPurpose::Terminal => ImePurpose::Terminal,
+        _ => ImePurpose::Normal,
+    }
+}
+
+impl WinitState {
+    /// The input method to send the requests to.
+    ///
+    /// This is the one with a focused text input, or the first one available.
+    pub fn input_method(&self) -> Result<&InputMethod, RequestError> {
+        let mut input_methods = self
+            .seats
+            .values()
+            .filter_map(|seat_state| seat_state.input_method.as_ref())
+            .filter(|input_method| input_method.is_available());
+        let first = input_methods.clone().next();
+        input_methods
+            .find(|input_method| input_method.is_active())
+            .or(first)
+            .ok_or_else(|| NotSupportedError::new("no input method available").into())
+    }
+
+    /// The virtual keyboard to send the key events to.
+    ///
+    /// This is the one of the seat with a focused text input, or the first one.
+    pub fn virtual_keyboard(&self) -> Result<&VirtualKeyboard, RequestError> {
+        let mut seats =
+            self.seats.values().filter(|seat_state| seat_state.virtual_keyboard.is_some());
+        let first = seats.clone().next();
+        seats
+            .find(|seat_state| {
+                seat_state
+                    .input_method
+                    .as_ref()
+                    .is_some_and(|input_method| input_method.is_active())
+            })
+            .or(first)
+            .and_then(|seat_state| seat_state.virtual_keyboard.as_ref())
+            .ok_or_else(|| NotSupportedError::new("no virtual keyboard available").into())
+    }
+}
+
+impl Dispatch<ZwpInputMethodManagerV2, GlobalData, WinitState> for InputMethodState {
+    fn event(
+        _state: &mut WinitState,
+        _proxy: &ZwpInputMethodManagerV2,
+        _event: <ZwpInputMethodManagerV2 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardManagerV1, GlobalData, WinitState> for InputMethodState {
+    fn event(
+        _state: &mut WinitState,
+        _proxy: &ZwpVirtualKeyboardManagerV1,
+        _event: <ZwpVirtualKeyboardManagerV1 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardV1, GlobalData, WinitState> for InputMethodState {
+    fn event(
+        _state: &mut WinitState,
+        _proxy: &ZwpVirtualKeyboardV1,
+        _event: <ZwpVirtualKeyboardV1 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpInputMethodV2, InputMethodData, WinitState> for InputMethodState {
+    fn event(
+        state: &mut WinitState,
+        _input_method: &ZwpInputMethodV2,
+        event: <ZwpInputMethodV2 as Proxy>::Event,
+        data: &InputMethodData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+        let mut inner = data.inner.lock().unwrap();
+        match event {
+            InputMethodV2Event::Activate => {
+                // The state is reset on activation.
+                inner.pending_active = Some(true);
+                inner.pending_surrounding_text = None;
+                inner.pending_content_type = None;
+            },
+            InputMethodV2Event::Deactivate => inner.pending_active = Some(false),
+            InputMethodV2Event::SurroundingText { text, cursor, anchor } => {
+                inner.pending_surrounding_text = Some(InputMethodEvent::SurroundingText {
+                    text,
+                    cursor: cursor as usize,
+                    anchor: anchor as usize,
+                });
+            },
+            InputMethodV2Event::ContentType { hint, purpose } => {
+                let hints = hint.into_result().unwrap_or(ContentHint::empty());
+                let purpose = purpose.into_result().unwrap_or(ContentPurpose::Normal);
+                inner.pending_content_type = Some(InputMethodEvent::ContentType {
+                    purpose: ime_purpose(purpose),
+                    hints: ImeHint::from_bits_truncate(hints.bits()),
+                });
+            },
+            InputMethodV2Event::Done => {
+                inner.serial = inner.serial.wrapping_add(1);
+
+                if let Some(active) = inner.pending_active.take() {
+                    inner.active = active;
+                    let event = if active {
+                        InputMethodEvent::Activated
+                    } else {
+                        InputMethodEvent::Deactivated
+                    };
+                    state.events_sink.push_input_method_event(event);
+                }
+
+                let events =
+                    [inner.pending_surrounding_text.take(), inner.pending_content_type.take()];
+                for event in events.into_iter().flatten() {
+                    state.events_sink.push_input_method_event(event);
+                }
+            },
+            InputMethodV2Event::Unavailable => {
+                inner.unavailable = true;
+                state.events_sink.push_input_method_event(InputMethodEvent::Unavailable);
+            },
+            _ => (),
+        }
+    }
+}
+
+/// The data associated with the input method.
+#[derive(Debug, Default)]
+pub struct InputMethodData {
+    inner: Mutex<InputMethodDataInner>,
+}
+
+#[derive(Debug, Default)]
+struct InputMethodDataInner {
+    /// Whether a text input is focused.
+    active: bool,
+
+    /// Whether another client is the input method of the seat.
+    unavailable: bool,
+
+    /// The number of `done` events received.
+    serial: u32,
+
+    /// The activation to apply on `done`.
+    pending_active: Option<bool>,
+
+    /// The surrounding text to report on `done`.
+    pending_surrounding_text: Option<InputMethodEvent>,
+
+    /// The content type to report on `done`.
+    pending_content_type: Option<InputMethodEvent>,
+}
+
+delegate_dispatch!(WinitState: [ZwpInputMethodManagerV2: GlobalData] => InputMethodState);
+delegate_dispatch!(WinitState: [ZwpInputMethodV2: InputMethodData] => InputMethodState);
+delegate_dispatch!(WinitState: [ZwpVirtualKeyboardManagerV1: GlobalData] => InputMethodState);
+delegate_dispatch!(WinitState: [ZwpVirtualKeyboardV1: GlobalData] => InputMethodState);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn virtual_keyboard_modifiers() {
+        let mods_indices =
+            ModsIndices { shift: Some(0), ctrl: Some(2), logo: Some(6), ..Default::default() };
+        assert_eq!(modifiers_mask(mods_indices, ModifiersState::empty()), 0);
+        assert_eq!(
+            modifiers_mask(mods_indices, ModifiersState::SHIFT | ModifiersState::CONTROL),
+            0b101
+        );
+        // Modifiers missing from the keymap are ignored.
+        assert_eq!(
+            modifiers_mask(mods_indices, ModifiersState::ALT | ModifiersState::META),
+            1 << 6
+        );
+    }
+}
@@ -59,7 +59,7 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     warn!("unknown keymap format 0x{:x}", value)
                 },
             },
-            WlKeyboardEvent::Enter { surface, .. } => {
+            WlKeyboardEvent::Enter { serial, surface, .. } => {
                 let window_id = wayland::make_wid(&surface);
 
                 // Mark the window as focused.
@@ -81,6 +81,13 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
 
                 *data.window_id.lock().unwrap() = Some(window_id);
 
+                state.clipboard.lock().unwrap().input_received(
+                    seat_state.data_device.as_ref(),
+                    seat_state.primary_selection_device.as_ref(),
+                    serial,
+                );
+                *state.latest_input.lock().unwrap() = Some((data.seat.clone(), serial));
+
                 // The keyboard focus is considered as general focus.
                 if was_unfocused {
                     state.events_sink.push_window_event(WindowEvent::Focused(true), window_id);
@@ -129,9 +136,18 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     state.events_sink.push_window_event(WindowEvent::Focused(false), window_id);
                 }
             },
-            WlKeyboardEvent::Key { key, state: WEnum::Value(WlKeyState::Pressed), .. } => {
+            WlKeyboardEvent::Key {
+                serial, key, state: WEnum::Value(WlKeyState::Pressed), ..
+            } => {
                 let key = key + 8;
 
+                state.clipboard.lock().unwrap().input_received(
+                    seat_state.data_device.as_ref(),
+                    seat_state.primary_selection_device.as_ref(),
+                    serial,
+                );
+                *state.latest_input.lock().unwrap() = Some((data.seat.clone(), serial));
+
                 key_input(
                     keyboard_state,
                     &mut state.events_sink,
@@ -141,6 +157,10 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     false,
                 );
 
+                if !state.key_repeat {
+                    return;
+                }
+
                 let delay = match keyboard_state.repeat_info {
                     RepeatInfo::Repeat { delay, .. } => delay,
                     RepeatInfo::Disable => return,
@@ -262,6 +282,7 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     let delay = Duration::from_millis(delay as u64);
                     RepeatInfo::Repeat { gap, delay }
                 };
+                state.update_input_preferences();
             },
             _ => unreachable!(),
         }
@@ -3,28 +3,45 @@
 use std::sync::Arc;
 
 use ahash::AHashMap;
+use sctk::data_device_manager::data_device::DataDevice;
+use sctk::data_device_manager::DataDeviceManagerState;
+use sctk::primary_selection::device::PrimarySelectionDevice;
+use sctk::primary_selection::PrimarySelectionManagerState;
 use sctk::reexports::client::backend::ObjectId;
+use sctk::reexports::client::globals::GlobalList;
 use sctk::reexports::client::protocol::wl_seat::WlSeat;
 use sctk::reexports::client::protocol::wl_touch::WlTouch;
 use sctk::reexports::client::{Connection, Proxy, QueueHandle};
 use sctk::reexports::protocols::wp::relative_pointer::zv1::client::zwp_relative_pointer_v1::ZwpRelativePointerV1;
+use sctk::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_seat_v2::ZwpTabletSeatV2;
 use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::ZwpTextInputV3;
 use sctk::seat::pointer::{ThemeSpec, ThemedPointer};
 use sctk::seat::{Capability as SeatCapability, SeatHandler, SeatState};
 use tracing::warn;
-use winit_core::event::WindowEvent;
+use winit_core::event::{DeviceId, DeviceInfo, DeviceKind, WindowEvent};
 use winit_core::keyboard::ModifiersState;
 
+use crate::platform_impl::wayland::make_device_id;
 use crate::platform_impl::wayland::state::WinitState;
 
+mod data_device;
+mod input_method;
 mod keyboard;
 mod pointer;
+mod tablet;
 mod text_input;
 mod touch;
 
+pub use data_device::{Clipboard, ClipboardState, DragIcon, DragState};
+pub use input_method::InputMethodState;
+use input_method::{InputMethod, VirtualKeyboard};
+pub use keyboard::RepeatInfo;
 use keyboard::{KeyboardData, KeyboardState};
+use pointer::pointer_gestures::PointerGestures;
+pub use pointer::pointer_gestures::PointerGesturesState;
 pub use pointer::relative_pointer::RelativePointerState;
 pub use pointer::{PointerConstraintsState, WinitPointerData, WinitPointerDataExt};
+pub use tablet::TabletManagerState;
 use text_input::TextInputData;
 pub use text_input::{TextInputState, ZwpTextInputV3Ext};
 use touch::TouchPoint;
@@ -49,6 +66,15 @@ pub struct WinitSeatState {
     /// The relative pointer bound on the seat.
     relative_pointer: Option<ZwpRelativePointerV1>,
 
+    /// The pointer gestures bound on the seat.
+    pointer_gestures: Option<PointerGestures>,
+
+    /// The tablet seat bound on the seat.
+    tablet_seat: Option<ZwpTabletSeatV2>,
+
+    /// The tablets announced on the tablet seat.
+    tablets: Vec<DeviceId>,
+
     /// The keyboard bound on the seat.
     keyboard_state: Option<KeyboardState>,
 
@@ -57,11 +83,42 @@ pub struct WinitSeatState {
 
     /// Whether we have pending modifiers.
     modifiers_pending: bool,
+
+    /// The data device bound on the seat.
+    data_device: Option<DataDevice>,
+
+    /// The primary selection device bound on the seat.
+    primary_selection_device: Option<PrimarySelectionDevice>,
+
+    /// The drag and drop of files in progress on the seat.
+    drag: Option<DragState>,
+
+    /// The input method bound on the seat.
+    input_method: Option<InputMethod>,
+
+    /// The virtual keyboard bound on the seat.
+    virtual_keyboard: Option<VirtualKeyboard>,
 }
 
 impl WinitSeatState {
-    pub fn new() -> Self {
-        Default::default()
+    pub fn new(
+        seat: &WlSeat,
+        queue_handle: &QueueHandle<WinitState>,
+        data_device_manager: Option<&DataDeviceManagerState>,
+        primary_selection_manager: Option<&PrimarySelectionManagerState>,
+        tablet_manager: Option<&TabletManagerState>,
+    ) -> Self {
+        let data_device =
+            data_device_manager.map(|manager| manager.get_data_device(queue_handle, seat));
+        let primary_selection_device = primary_selection_manager
+            .map(|manager| manager.get_selection_device(queue_handle, seat));
+        let tablet_seat = tablet_manager.map(|manager| manager.get_tablet_seat(seat, queue_handle));
+        Self { data_device, primary_selection_device, tablet_seat, ..Default::default() }
+    }
+
+    /// The key repeat of the keyboard, when the seat has one.
+    pub fn repeat_info(&self) -> Option<RepeatInfo> {
+        self.keyboard_state.as_ref().map(|keyboard_state| keyboard_state.repeat_info)
     }
 }
 
@@ -85,12 +142,18 @@ impl SeatHandler for WinitState {
             },
         };
 
+        let mut added = None;
         match capability {
             SeatCapability::Touch if seat_state.touch.is_none() => {
                 seat_state.touch = self.seat_state.get_touch(queue_handle, &seat).ok();
+                added = seat_state
+                    .touch
+                    .as_ref()
+                    .map(|touch| (make_device_id(touch), DeviceKind::Touch));
             },
             SeatCapability::Keyboard if seat_state.keyboard_state.is_none() => {
                 let keyboard = seat.get_keyboard(queue_handle, KeyboardData::new(seat.clone()));
+                added = Some((make_device_id(&keyboard), DeviceKind::Keyboard));
                 seat_state.keyboard_state =
                     Some(KeyboardState::new(keyboard, self.loop_handle.clone()));
             },
@@ -122,6 +185,13 @@ impl SeatHandler for WinitState {
                     )
                 });
 
+                seat_state.pointer_gestures = self
+                    .pointer_gestures
+                    .as_ref()
+                    .map(|manager| manager.get_gestures(themed_pointer.pointer(), queue_handle));
+
+                added = Some((make_device_id(themed_pointer.pointer()), DeviceKind::Pointer));
+
                 let themed_pointer = Arc::new(themed_pointer);
 
                 // Register cursor surface.
@@ -141,6 +211,10 @@ impl SeatHandler for WinitState {
                 TextInputData::default(),
             )));
         }
+
+        if let Some((device_id, kind)) = added {
+            self.add_seat_device(&seat, device_id, kind);
+        }
     }
 
     fn remove_capability(
@@ -162,9 +236,11 @@ impl SeatHandler for WinitState {
             text_input.destroy();
         }
 
+        let mut removed = None;
         match capability {
             SeatCapability::Touch => {
                 if let Some(touch) = seat_state.touch.take() {
+                    removed = Some(make_device_id(&touch));
                     if touch.version() >= 3 {
                         touch.release();
                     }
@@ -175,7 +251,10 @@ impl SeatHandler for WinitState {
                     relative_pointer.destroy();
                 }
 
+                seat_state.pointer_gestures = None;
+
                 if let Some(pointer) = seat_state.pointer.take() {
+                    removed = Some(make_device_id(pointer.pointer()));
                     let pointer_data = pointer.pointer().winit_data();
 
                     // Remove the cursor from the mapping.
@@ -192,20 +271,34 @@ impl SeatHandler for WinitState {
                 }
             },
             SeatCapability::Keyboard => {
-                seat_state.keyboard_state = None;
+                removed =
+                    seat_state.keyboard_state.take().map(|state| make_device_id(&state.keyboard));
                 self.on_keyboard_destroy(&seat.id());
+                self.update_input_preferences();
             },
             _ => (),
         }
+
+        if let Some(device_id) = removed {
+            self.remove_device(device_id);
+        }
     }
 
     fn new_seat(
         &mut self,
         _connection: &Connection,
-        _queue_handle: &QueueHandle<Self>,
+        queue_handle: &QueueHandle<Self>,
         seat: WlSeat,
     ) {
-        self.seats.insert(seat.id(), WinitSeatState::new());
+        let seat_state = WinitSeatState::new(
+            &seat,
+            queue_handle,
+            self.data_device_manager.as_ref(),
+            self.primary_selection_manager.as_ref(),
+            self.tablet_manager.as_ref(),
+        );
+        self.seats.insert(seat.id(), seat_state);
+        self.bind_seat_input_method(&seat, queue_handle);
     }
 
     fn remove_seat(
@@ -214,12 +307,80 @@ impl SeatHandler for WinitState {
         _queue_handle: &QueueHandle<Self>,
         seat: WlSeat,
     ) {
-        let _ = self.seats.remove(&seat.id());
+        if let Some(seat_state) = self.seats.remove(&seat.id()) {
+            let devices = seat_state
+                .keyboard_state
+                .as_ref()
+                .map(|state| make_device_id(&state.keyboard))
+                .into_iter()
+                .chain(seat_state.pointer.as_ref().map(|pointer| make_device_id(pointer.pointer())))
+                .chain(seat_state.touch.as_ref().map(make_device_id))
+                .chain(seat_state.tablets.iter().copied());
+            for device_id in devices {
+                self.remove_device(device_id);
+            }
+
+            if let Some(tablet_seat) = seat_state.tablet_seat {
+                tablet_seat.destroy();
+            }
+        }
         self.on_keyboard_destroy(&seat.id());
+
+        // The serial of a removed seat can't be used anymore, e.g. to request activation tokens.
+        let mut latest_input = self.latest_input.lock().unwrap();
+        if latest_input.as_ref().is_some_and(|(input_seat, _)| *input_seat == seat) {
+            *latest_input = None;
+        }
     }
 }
 
 impl WinitState {
+    /// Act as the input method of the compositor, on all the seats.
+    pub fn bind_input_method(&mut self, globals: &GlobalList, queue_handle: &QueueHandle<Self>) {
+        self.input_method_state = Some(InputMethodState::new(globals, queue_handle));
+        for seat in self.seat_state.seats().collect::<Vec<_>>() {
+            self.bind_seat_input_method(&seat, queue_handle);
+        }
+    }
+
+    /// Bind the input method and the virtual keyboard on the seat, when requested.
+    fn bind_seat_input_method(&mut self, seat: &WlSeat, queue_handle: &QueueHandle<Self>) {
+        let (Some(input_method_state), Some(seat_state)) =
+            (self.input_method_state.as_ref(), self.seats.get_mut(&seat.id()))
+        else {
+            return;
+        };
+
+        seat_state.input_method = input_method_state.get_input_method(seat, queue_handle);
+        seat_state.virtual_keyboard = input_method_state.get_virtual_keyboard(seat, queue_handle);
+    }
+
+    /// Report the capability of the seat as a device.
+    fn add_seat_device(&mut self, seat: &WlSeat, device_id: DeviceId, kind: DeviceKind) {
+        let kind_name = match kind {
+            DeviceKind::Keyboard => "keyboard",
+            DeviceKind::Pointer => "pointer",
+            _ => "touch",
+        };
+        let name = match self.seat_state.info(seat).and_then(|info| info.name) {
+            Some(seat_name) => format!("{seat_name} {kind_name}"),
+            None => kind_name.to_owned(),
+        };
+        let info = DeviceInfo { name, kind, vendor_id: None, product_id: None };
+        self.add_device(device_id, info);
+    }
+
+    /// Remember the tablet announced on the tablet seat, so it's removed along with the seat.
+    fn add_seat_tablet(&mut self, tablet_seat: &ZwpTabletSeatV2, device_id: DeviceId) {
+        let seat_state = self
+            .seats
+            .values_mut()
+            .find(|seat_state| seat_state.tablet_seat.as_ref() == Some(tablet_seat));
+        if let Some(seat_state) = seat_state {
+            seat_state.tablets.push(device_id);
+        }
+    }
+
     fn on_keyboard_destroy(&mut self, seat: &ObjectId) {
         for (window_id, window) in self.windows.get_mut() {
             let mut window = window.lock().unwrap();
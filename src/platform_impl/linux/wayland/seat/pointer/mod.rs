@@ -36,6 +36,7 @@ use winit_core::event::{
 use crate::platform_impl::wayland::state::WinitState;
 use crate::platform_impl::wayland::{self, WindowId};
 
+pub mod pointer_gestures;
 pub mod relative_pointer;
 
 impl PointerHandler for WinitState {
@@ -172,6 +173,15 @@ impl PointerHandler for WinitState {
                 | ref kind @ PointerEventKind::Release { button, serial, .. } => {
                     // Update the last button serial.
                     pointer.winit_data().inner.lock().unwrap().latest_button_serial = serial;
+                    if matches!(kind, PointerEventKind::Press { .. }) {
+                        self.clipboard.lock().unwrap().button_pressed(
+                            seat_state.data_device.as_ref(),
+                            seat_state.primary_selection_device.as_ref(),
+                            serial,
+                        );
+                        *self.latest_input.lock().unwrap() =
+                            Some((pointer.winit_data().seat().clone(), serial));
+                    }
 
                     let button = wayland_button_to_winit(button);
                     let state = if matches!(kind, PointerEventKind::Press { .. }) {
@@ -327,6 +337,11 @@ impl WinitPointerData {
         self.sctk_data.latest_button_serial().unwrap_or_default()
     }
 
+    /// Last button serial, unless no button was pressed yet.
+    pub fn button_serial(&self) -> Option<u32> {
+        self.sctk_data.latest_button_serial()
+    }
+
     /// Last enter serial.
     pub fn latest_enter_serial(&self) -> u32 {
         self.sctk_data.latest_enter_serial().unwrap_or_default()
@@ -0,0 +1,251 @@
+//! Pointer gestures.
+
+use std::ops::Deref;
+use std::sync::Mutex;
+
+use dpi::{LogicalPosition, PhysicalPosition};
+use sctk::globals::GlobalData;
+use sctk::reexports::client::globals::{BindError, GlobalList};
+use sctk::reexports::client::protocol::wl_pointer::WlPointer;
+use sctk::reexports::client::{delegate_dispatch, Connection, Dispatch, Proxy, QueueHandle};
+use sctk::reexports::protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_hold_v1::{
+    self, ZwpPointerGestureHoldV1,
+};
+use sctk::reexports::protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_pinch_v1::{
+    self, ZwpPointerGesturePinchV1,
+};
+use sctk::reexports::protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_swipe_v1::{
+    self, ZwpPointerGestureSwipeV1,
+};
+use sctk::reexports::protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gestures_v1::ZwpPointerGesturesV1;
+use winit_core::event::{TouchPhase, WindowEvent};
+use winit_core::window::WindowId;
+
+use crate::platform_impl::wayland;
+use crate::platform_impl::wayland::state::WinitState;
+
+/// Wrapper around the pointer gestures.
+#[derive(Debug)]
+pub struct PointerGesturesState {
+    manager: ZwpPointerGesturesV1,
+}
+
+impl PointerGesturesState {
+    /// Create new pointer gestures manager.
+    pub fn new(
+        globals: &GlobalList,
+        queue_handle: &QueueHandle<WinitState>,
+    ) -> Result<Self, BindError> {
+        let manager = globals.bind(queue_handle, 1..=3, GlobalData)?;
+        Ok(Self { manager })
+    }
+
+    /// Get the gestures for the given pointer.
+    pub fn get_gestures(
+        &self,
+        pointer: &WlPointer,
+        queue_handle: &QueueHandle<WinitState>,
+    ) -> PointerGestures {
+        let swipe = self.get_swipe_gesture(pointer, queue_handle, Default::default());
+        let pinch = self.get_pinch_gesture(pointer, queue_handle, Default::default());
+        let hold = (self.version() >= 3)
+            .then(|| self.get_hold_gesture(pointer, queue_handle, Default::default()));
+        PointerGestures { swipe, pinch, hold }
+    }
+}
+
+impl Deref for PointerGesturesState {
+    type Target = ZwpPointerGesturesV1;
+
+    fn deref(&self) -> &Self::Target {
+        &self.manager
+    }
+}
+
+/// The gestures bound on a pointer.
+#[derive(Debug)]
+pub struct PointerGestures {
+    swipe: ZwpPointerGestureSwipeV1,
+    pinch: ZwpPointerGesturePinchV1,
+    hold: Option<ZwpPointerGestureHoldV1>,
+}
+
+impl Drop for PointerGestures {
+    fn drop(&mut self) {
+        self.swipe.destroy();
+        self.pinch.destroy();
+        if let Some(hold) = self.hold.take() {
+            hold.destroy();
+        }
+    }
+}
+
+/// The data associated with the gesture.
+#[derive(Debug, Default)]
+pub struct GestureData {
+    inner: Mutex<GestureDataInner>,
+}
+
+#[derive(Debug, Default)]
+struct GestureDataInner {
+    /// The window the gesture in progress started on.
+    window_id: Option<WindowId>,
+
+    /// The last scale of the pinch in progress.
+    scale: f64,
+}
+
+fn end_phase(cancelled: i32) -> TouchPhase {
+    if cancelled == 0 {
+        TouchPhase::Ended
+    } else {
+        TouchPhase::Cancelled
+    }
+}
+
+impl Dispatch<ZwpPointerGesturesV1, GlobalData, WinitState> for PointerGesturesState {
+    fn event(
+        _state: &mut WinitState,
+        _proxy: &ZwpPointerGesturesV1,
+        _event: <ZwpPointerGesturesV1 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpPointerGestureSwipeV1, GestureData, WinitState> for PointerGesturesState {
+    fn event(
+        state: &mut WinitState,
+        _proxy: &ZwpPointerGestureSwipeV1,
+        event: <ZwpPointerGestureSwipeV1 as Proxy>::Event,
+        data: &GestureData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+        let mut inner = data.inner.lock().unwrap();
+        let (window_id, delta, phase) = match event {
+            zwp_pointer_gesture_swipe_v1::Event::Begin { surface, .. } => {
+                let window_id = wayland::make_wid(&surface);
+                inner.window_id = Some(window_id);
+                (window_id, PhysicalPosition::new(0., 0.), TouchPhase::Started)
+            },
+            zwp_pointer_gesture_swipe_v1::Event::Update { dx, dy, .. } => {
+                let window_id = match inner.window_id {
+                    Some(window_id) => window_id,
+                    None => return,
+                };
+                let scale_factor = match state.windows.get_mut().get(&window_id) {
+                    Some(window) => window.lock().unwrap().scale_factor(),
+                    None => return,
+                };
+                let delta = LogicalPosition::new(dx, dy).to_physical(scale_factor);
+                (window_id, delta, TouchPhase::Moved)
+            },
+            zwp_pointer_gesture_swipe_v1::Event::End { cancelled, .. } => {
+                let window_id = match inner.window_id.take() {
+                    Some(window_id) => window_id,
+                    None => return,
+                };
+                (window_id, PhysicalPosition::new(0., 0.), end_phase(cancelled))
+            },
+            _ => return,
+        };
+
+        state.events_sink.push_window_event(
+            WindowEvent::PanGesture { device_id: None, delta, phase },
+            window_id,
+        );
+    }
+}
+
+impl Dispatch<ZwpPointerGesturePinchV1, GestureData, WinitState> for PointerGesturesState {
+    fn event(
+        state: &mut WinitState,
+        _proxy: &ZwpPointerGesturePinchV1,
+        event: <ZwpPointerGesturePinchV1 as Proxy>::Event,
+        data: &GestureData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+        let mut inner = data.inner.lock().unwrap();
+        let (window_id, delta, rotation, phase) = match event {
+            zwp_pointer_gesture_pinch_v1::Event::Begin { surface, .. } => {
+                let window_id = wayland::make_wid(&surface);
+                inner.window_id = Some(window_id);
+                inner.scale = 1.;
+                (window_id, 0., 0., TouchPhase::Started)
+            },
+            zwp_pointer_gesture_pinch_v1::Event::Update { scale, rotation, .. } => {
+                let window_id = match inner.window_id {
+                    Some(window_id) => window_id,
+                    None => return,
+                };
+                // The scale is relative to the beginning of the gesture.
+                let delta = scale - inner.scale;
+                inner.scale = scale;
+                // The rotation is clockwise, while winit's rotation is counterclockwise.
+                (window_id, delta, -rotation as f32, TouchPhase::Moved)
+            },
+            zwp_pointer_gesture_pinch_v1::Event::End { cancelled, .. } => {
+                let window_id = match inner.window_id.take() {
+                    Some(window_id) => window_id,
+                    None => return,
+                };
+                (window_id, 0., 0., end_phase(cancelled))
+            },
+            _ => return,
+        };
+
+        state.events_sink.push_window_event(
+            WindowEvent::PinchGesture { device_id: None, delta, phase },
+            window_id,
+        );
+        state.events_sink.push_window_event(
+            WindowEvent::RotationGesture { device_id: None, delta: rotation, phase },
+            window_id,
+        );
+    }
+}
+
+impl Dispatch<ZwpPointerGestureHoldV1, GestureData, WinitState> for PointerGesturesState {
+    fn event(
+        state: &mut WinitState,
+        _proxy: &ZwpPointerGestureHoldV1,
+        event: <ZwpPointerGestureHoldV1 as Proxy>::Event,
+        data: &GestureData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+        // Fingers resting on the touchpad are reported as a pan without movement, which allows
+        // to stop kinetic scrolling.
+        let mut inner = data.inner.lock().unwrap();
+        let (window_id, phase) = match event {
+            zwp_pointer_gesture_hold_v1::Event::Begin { surface, .. } => {
+                let window_id = wayland::make_wid(&surface);
+                inner.window_id = Some(window_id);
+                (window_id, TouchPhase::Started)
+            },
+            zwp_pointer_gesture_hold_v1::Event::End { cancelled, .. } => {
+                let window_id = match inner.window_id.take() {
+                    Some(window_id) => window_id,
+                    None => return,
+                };
+                (window_id, end_phase(cancelled))
+            },
+            _ => return,
+        };
+
+        let delta = PhysicalPosition::new(0., 0.);
+        state.events_sink.push_window_event(
+            WindowEvent::PanGesture { device_id: None, delta, phase },
+            window_id,
+        );
+    }
+}
+
+delegate_dispatch!(WinitState: [ZwpPointerGesturesV1: GlobalData] => PointerGesturesState);
+delegate_dispatch!(WinitState: [ZwpPointerGestureSwipeV1: GestureData] => PointerGesturesState);
+delegate_dispatch!(WinitState: [ZwpPointerGesturePinchV1: GestureData] => PointerGesturesState);
+delegate_dispatch!(WinitState: [ZwpPointerGestureHoldV1: GestureData] => PointerGesturesState);
@@ -0,0 +1,450 @@
+//! Tablet tools handling.
+
+use std::ops::Deref;
+use std::sync::Mutex;
+
+use dpi::LogicalPosition;
+use sctk::globals::GlobalData;
+use sctk::reexports::client::globals::{BindError, GlobalList};
+use sctk::reexports::client::protocol::wl_seat::WlSeat;
+use sctk::reexports::client::protocol::wl_surface::WlSurface;
+use sctk::reexports::client::{
+    delegate_dispatch, event_created_child, Connection, Dispatch, Proxy, QueueHandle, WEnum,
+};
+use sctk::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_manager_v2::ZwpTabletManagerV2;
+use sctk::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_pad_group_v2::{
+    self, ZwpTabletPadGroupV2,
+};
+use sctk::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2;
+use sctk::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2;
+use sctk::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_pad_v2::{
+    self, ZwpTabletPadV2,
+};
+use sctk::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_seat_v2::{
+    self, ZwpTabletSeatV2,
+};
+use sctk::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2::{
+    self, ButtonState, Capability, Type, ZwpTabletToolV2,
+};
+use sctk::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_v2::{self, ZwpTabletV2};
+use winit_core::event::{
+    ButtonSource, DeviceId, DeviceInfo, DeviceKind, ElementState, Force, PointerKind,
+    PointerSource, TabletButton, TabletTool, TabletToolKind, TabletToolState, TabletToolTilt,
+    WindowEvent,
+};
+
+use crate::platform_impl::wayland;
+use crate::platform_impl::wayland::make_device_id;
+use crate::platform_impl::wayland::state::WinitState;
+
+/// The maximum value of the normalized axes, like pressure and distance.
+const AXIS_MAX: f64 = 65535.;
+
+/// Linux input event codes of the stylus buttons, and of the first button of a mouse.
+const BTN_STYLUS: u32 = 0x14b;
+const BTN_STYLUS2: u32 = 0x14c;
+const BTN_STYLUS3: u32 = 0x149;
+const BTN_MOUSE: u32 = 0x110;
+
+/// Wrapper around the tablet manager.
+#[derive(Debug)]
+pub struct TabletManagerState {
+    manager: ZwpTabletManagerV2,
+}
+
+impl TabletManagerState {
+    /// Create new tablet manager.
+    pub fn new(
+        globals: &GlobalList,
+        queue_handle: &QueueHandle<WinitState>,
+    ) -> Result<Self, BindError> {
+        let manager = globals.bind(queue_handle, 1..=1, GlobalData)?;
+        Ok(Self { manager })
+    }
+
+    /// Get the tablet seat for the given seat.
+    pub fn get_tablet_seat(
+        &self,
+        seat: &WlSeat,
+        queue_handle: &QueueHandle<WinitState>,
+    ) -> ZwpTabletSeatV2 {
+        self.manager.get_tablet_seat(seat, queue_handle, GlobalData)
+    }
+}
+
+impl Deref for TabletManagerState {
+    type Target = ZwpTabletManagerV2;
+
+    fn deref(&self) -> &Self::Target {
+        &self.manager
+    }
+}
+
+/// The data associated with the tablet.
+#[derive(Debug)]
+pub struct TabletData {
+    /// The tablet as reported to the user, complete once `done` is received.
+    info: Mutex<DeviceInfo>,
+}
+
+impl Default for TabletData {
+    fn default() -> Self {
+        let info = DeviceInfo {
+            name: String::new(),
+            kind: DeviceKind::Tablet,
+            vendor_id: None,
+            product_id: None,
+        };
+        Self { info: Mutex::new(info) }
+    }
+}
+
+/// The data associated with the tablet tool.
+#[derive(Debug, Default)]
+pub struct TabletToolData {
+    inner: Mutex<TabletToolDataInner>,
+}
+
+#[derive(Debug)]
+struct TabletToolDataInner {
+    /// The tool as reported to the user.
+    tool: TabletTool,
+
+    /// The current state of the axes.
+    state: TabletToolState,
+
+    /// The surface the tool is in proximity of.
+    surface: Option<WlSurface>,
+
+    /// The tablet the tool is used on, from the proximity.
+    device_id: Option<DeviceId>,
+
+    /// The current position of the tool on the surface.
+    position: LogicalPosition<f64>,
+
+    /// The changes accumulated until the next frame.
+    pending: PendingFrame,
+}
+
+impl Default for TabletToolDataInner {
+    fn default() -> Self {
+        Self {
+            tool: TabletTool { kind: TabletToolKind::Unknown, serial: None },
+            state: Default::default(),
+            surface: None,
+            device_id: None,
+            position: Default::default(),
+            pending: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PendingFrame {
+    proximity_in: bool,
+    proximity_out: bool,
+    moved: bool,
+    down: bool,
+    up: bool,
+    buttons: Vec<(TabletButton, ElementState)>,
+}
+
+impl Dispatch<ZwpTabletManagerV2, GlobalData, WinitState> for TabletManagerState {
+    fn event(
+        _state: &mut WinitState,
+        _proxy: &ZwpTabletManagerV2,
+        _event: <ZwpTabletManagerV2 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletSeatV2, GlobalData, WinitState> for TabletManagerState {
+    fn event(
+        state: &mut WinitState,
+        proxy: &ZwpTabletSeatV2,
+        event: <ZwpTabletSeatV2 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+        // Tablets, tools and pads are handled on their own objects.
+        if let zwp_tablet_seat_v2::Event::TabletAdded { id } = event {
+            state.add_seat_tablet(proxy, make_device_id(&id));
+        }
+    }
+
+    event_created_child!(WinitState, ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (ZwpTabletV2, Default::default()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (ZwpTabletToolV2, Default::default()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (ZwpTabletPadV2, GlobalData),
+    ]);
+}
+
+impl Dispatch<ZwpTabletV2, TabletData, WinitState> for TabletManagerState {
+    fn event(
+        state: &mut WinitState,
+        proxy: &ZwpTabletV2,
+        event: <ZwpTabletV2 as Proxy>::Event,
+        data: &TabletData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+        let mut info = data.info.lock().unwrap();
+        match event {
+            zwp_tablet_v2::Event::Name { name } => info.name = name,
+            zwp_tablet_v2::Event::Id { vid, pid } => {
+                info.vendor_id = u16::try_from(vid).ok();
+                info.product_id = u16::try_from(pid).ok();
+            },
+            zwp_tablet_v2::Event::Done => state.add_device(make_device_id(proxy), info.clone()),
+            zwp_tablet_v2::Event::Removed => {
+                state.remove_device(make_device_id(proxy));
+                proxy.destroy();
+            },
+            _ => (),
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletToolV2, TabletToolData, WinitState> for TabletManagerState {
+    fn event(
+        state: &mut WinitState,
+        proxy: &ZwpTabletToolV2,
+        event: <ZwpTabletToolV2 as Proxy>::Event,
+        data: &TabletToolData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+        let mut inner = data.inner.lock().unwrap();
+        match event {
+            zwp_tablet_tool_v2::Event::Type { tool_type } => {
+                inner.tool.kind = match tool_type {
+                    WEnum::Value(Type::Pen) => TabletToolKind::Pen,
+                    WEnum::Value(Type::Eraser) => TabletToolKind::Eraser,
+                    WEnum::Value(Type::Brush) => TabletToolKind::Brush,
+                    WEnum::Value(Type::Pencil) => TabletToolKind::Pencil,
+                    WEnum::Value(Type::Airbrush) => TabletToolKind::Airbrush,
+                    WEnum::Value(Type::Finger) => TabletToolKind::Finger,
+                    WEnum::Value(Type::Mouse) => TabletToolKind::Mouse,
+                    WEnum::Value(Type::Lens) => TabletToolKind::Lens,
+                    _ => TabletToolKind::Unknown,
+                };
+            },
+            zwp_tablet_tool_v2::Event::HardwareSerial {
+                hardware_serial_hi,
+                hardware_serial_lo,
+            } => {
+                inner.tool.serial =
+                    Some((hardware_serial_hi as u64) << 32 | hardware_serial_lo as u64);
+            },
+            // Mark the axes the tool has as present, so the user can tell them apart from the
+            // missing ones before the first change.
+            zwp_tablet_tool_v2::Event::Capability { capability } => match capability {
+                WEnum::Value(Capability::Pressure) => {
+                    inner.state.force = Some(Force::Normalized(0.));
+                },
+                WEnum::Value(Capability::Distance) => inner.state.distance = Some(0.),
+                WEnum::Value(Capability::Tilt) => inner.state.tilt = Some(Default::default()),
+                WEnum::Value(Capability::Rotation) => inner.state.twist = Some(0.),
+                _ => (),
+            },
+            zwp_tablet_tool_v2::Event::ProximityIn { tablet, surface, .. } => {
+                inner.surface = Some(surface);
+                inner.device_id = Some(make_device_id(&tablet));
+                inner.pending.proximity_in = true;
+            },
+            zwp_tablet_tool_v2::Event::ProximityOut => inner.pending.proximity_out = true,
+            zwp_tablet_tool_v2::Event::Down { .. } => inner.pending.down = true,
+            zwp_tablet_tool_v2::Event::Up => inner.pending.up = true,
+            zwp_tablet_tool_v2::Event::Motion { x, y } => {
+                inner.position = LogicalPosition::new(x, y);
+                inner.pending.moved = true;
+            },
+            zwp_tablet_tool_v2::Event::Pressure { pressure } => {
+                inner.state.force = Some(Force::Normalized(pressure as f64 / AXIS_MAX));
+                inner.pending.moved = true;
+            },
+            zwp_tablet_tool_v2::Event::Distance { distance } => {
+                inner.state.distance = Some(distance as f64 / AXIS_MAX);
+                inner.pending.moved = true;
+            },
+            zwp_tablet_tool_v2::Event::Tilt { tilt_x, tilt_y } => {
+                inner.state.tilt = Some(TabletToolTilt { x: tilt_x, y: tilt_y });
+                inner.pending.moved = true;
+            },
+            zwp_tablet_tool_v2::Event::Rotation { degrees } => {
+                inner.state.twist = Some(degrees);
+                inner.pending.moved = true;
+            },
+            zwp_tablet_tool_v2::Event::Button { button, state, .. } => {
+                let button = match button {
+                    BTN_STYLUS => TabletButton::Barrel(0),
+                    BTN_STYLUS2 => TabletButton::Barrel(1),
+                    BTN_STYLUS3 => TabletButton::Barrel(2),
+                    // Number the buttons of mouse and lens tools after the stylus ones.
+                    button => match button.checked_sub(BTN_MOUSE) {
+                        Some(offset) => TabletButton::Barrel(3 + offset as u16),
+                        None => return,
+                    },
+                };
+                let state = match state {
+                    WEnum::Value(ButtonState::Pressed) => ElementState::Pressed,
+                    _ => ElementState::Released,
+                };
+                inner.pending.buttons.push((button, state));
+            },
+            zwp_tablet_tool_v2::Event::Frame { .. } => {
+                let pending = std::mem::take(&mut inner.pending);
+                let surface = match inner.surface.as_ref() {
+                    Some(surface) => surface,
+                    None => return,
+                };
+
+                let window_id = wayland::make_wid(surface);
+                let scale_factor = match state.windows.get_mut().get(&window_id) {
+                    Some(window) => window.lock().unwrap().scale_factor(),
+                    None => return,
+                };
+
+                let position = inner.position.to_physical(scale_factor);
+                let tool = inner.tool;
+                let tool_state = inner.state;
+                let device_id = inner.device_id;
+
+                if pending.proximity_in {
+                    state.events_sink.push_window_event(
+                        WindowEvent::PointerEntered {
+                            device_id,
+                            primary: true,
+                            position,
+                            kind: PointerKind::Tablet(tool),
+                        },
+                        window_id,
+                    );
+                }
+
+                if pending.moved {
+                    state.events_sink.push_window_event(
+                        WindowEvent::PointerMoved {
+                            device_id,
+                            primary: true,
+                            position,
+                            source: PointerSource::Tablet { tool, state: tool_state },
+                        },
+                        window_id,
+                    );
+                }
+
+                let mut buttons = pending.buttons;
+                if pending.down {
+                    buttons.insert(0, (TabletButton::Tip, ElementState::Pressed));
+                }
+                if pending.up {
+                    buttons.push((TabletButton::Tip, ElementState::Released));
+                }
+
+                for (button, button_state) in buttons {
+                    state.events_sink.push_window_event(
+                        WindowEvent::PointerButton {
+                            device_id,
+                            primary: true,
+                            state: button_state,
+                            position,
+                            button: ButtonSource::Tablet { tool, button, state: tool_state },
+                        },
+                        window_id,
+                    );
+                }
+
+                if pending.proximity_out {
+                    inner.surface = None;
+                    inner.device_id = None;
+                    state.events_sink.push_window_event(
+                        WindowEvent::PointerLeft {
+                            device_id,
+                            primary: true,
+                            position: Some(position),
+                            kind: PointerKind::Tablet(tool),
+                        },
+                        window_id,
+                    );
+                }
+            },
+            zwp_tablet_tool_v2::Event::Removed => proxy.destroy(),
+            _ => (),
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletPadV2, GlobalData, WinitState> for TabletManagerState {
+    fn event(
+        _state: &mut WinitState,
+        proxy: &ZwpTabletPadV2,
+        event: <ZwpTabletPadV2 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+        // Pads aren't exposed, but still have to be tracked to handle their children.
+        if let zwp_tablet_pad_v2::Event::Removed = event {
+            proxy.destroy();
+        }
+    }
+
+    event_created_child!(WinitState, ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (ZwpTabletPadGroupV2, GlobalData),
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadGroupV2, GlobalData, WinitState> for TabletManagerState {
+    fn event(
+        _state: &mut WinitState,
+        _proxy: &ZwpTabletPadGroupV2,
+        _event: <ZwpTabletPadGroupV2 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+    }
+
+    event_created_child!(WinitState, ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (ZwpTabletPadRingV2, GlobalData),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (ZwpTabletPadStripV2, GlobalData),
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadRingV2, GlobalData, WinitState> for TabletManagerState {
+    fn event(
+        _state: &mut WinitState,
+        _proxy: &ZwpTabletPadRingV2,
+        _event: <ZwpTabletPadRingV2 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletPadStripV2, GlobalData, WinitState> for TabletManagerState {
+    fn event(
+        _state: &mut WinitState,
+        _proxy: &ZwpTabletPadStripV2,
+        _event: <ZwpTabletPadStripV2 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+    }
+}
+
+delegate_dispatch!(WinitState: [ZwpTabletManagerV2: GlobalData] => TabletManagerState);
+delegate_dispatch!(WinitState: [ZwpTabletSeatV2: GlobalData] => TabletManagerState);
+delegate_dispatch!(WinitState: [ZwpTabletV2: TabletData] => TabletManagerState);
+delegate_dispatch!(WinitState: [ZwpTabletToolV2: TabletToolData] => TabletManagerState);
+delegate_dispatch!(WinitState: [ZwpTabletPadV2: GlobalData] => TabletManagerState);
+delegate_dispatch!(WinitState: [ZwpTabletPadGroupV2: GlobalData] => TabletManagerState);
+delegate_dispatch!(WinitState: [ZwpTabletPadRingV2: GlobalData] => TabletManagerState);
+delegate_dispatch!(WinitState: [ZwpTabletPadStripV2: GlobalData] => TabletManagerState);
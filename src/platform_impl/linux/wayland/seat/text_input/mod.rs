@@ -9,7 +9,7 @@ use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::
     ContentHint, ContentPurpose, Event as TextInputEvent, ZwpTextInputV3,
 };
 use winit_core::event::{Ime, WindowEvent};
-use winit_core::window::ImePurpose;
+use winit_core::window::{ImeHint, ImePurpose};
 
 use crate::platform_impl::wayland;
 use crate::platform_impl::wayland::state::WinitState;
@@ -72,7 +72,8 @@ impl Dispatch<ZwpTextInputV3, TextInputData, WinitState> for TextInputState {
 
                 if window.ime_allowed() {
                     text_input.enable();
-                    text_input.set_content_type_by_purpose(window.ime_purpose());
+                    text_input
+                        .set_content_type_by_purpose(window.ime_purpose(), window.ime_hints());
                     text_input.commit();
                     state.events_sink.push_window_event(WindowEvent::Ime(Ime::Enabled), window_id);
                 }
@@ -114,6 +115,10 @@ impl Dispatch<ZwpTextInputV3, TextInputData, WinitState> for TextInputState {
                 text_input_data.pending_preedit = None;
                 text_input_data.pending_commit = text;
             },
+            TextInputEvent::DeleteSurroundingText { before_length, after_length } => {
+                text_input_data.pending_delete =
+                    Some((before_length as usize, after_length as usize));
+            },
             TextInputEvent::Done { .. } => {
                 let window_id = match text_input_data.surface.as_ref() {
                     Some(surface) => wayland::make_wid(surface),
@@ -122,6 +127,7 @@ impl Dispatch<ZwpTextInputV3, TextInputData, WinitState> for TextInputState {
 
                 // Clear preedit, unless all we'll be doing next is sending a new preedit.
                 if text_input_data.pending_commit.is_some()
+                    || text_input_data.pending_delete.is_some()
                     || text_input_data.pending_preedit.is_none()
                 {
                     state.events_sink.push_window_event(
@@ -130,6 +136,14 @@ impl Dispatch<ZwpTextInputV3, TextInputData, WinitState> for TextInputState {
                     );
                 }
 
+                // Delete the surrounding text before inserting the commit.
+                if let Some((before_bytes, after_bytes)) = text_input_data.pending_delete.take() {
+                    state.events_sink.push_window_event(
+                        WindowEvent::Ime(Ime::DeleteSurrounding { before_bytes, after_bytes }),
+                        window_id,
+                    );
+                }
+
                 // Send `Commit`.
                 if let Some(text) = text_input_data.pending_commit.take() {
                     state
@@ -148,27 +162,86 @@ impl Dispatch<ZwpTextInputV3, TextInputData, WinitState> for TextInputState {
                     );
                 }
             },
-            TextInputEvent::DeleteSurroundingText { .. } => {
-                // Not handled.
-            },
             _ => {},
         }
     }
 }
 
 pub trait ZwpTextInputV3Ext {
-    fn set_content_type_by_purpose(&self, purpose: ImePurpose);
+    fn set_content_type_by_purpose(&self, purpose: ImePurpose, hints: ImeHint);
+
+    fn set_surrounding_text_truncated(&self, text: &str, cursor: usize, anchor: usize);
 }
 
 impl ZwpTextInputV3Ext for ZwpTextInputV3 {
-    fn set_content_type_by_purpose(&self, purpose: ImePurpose) {
-        let (hint, purpose) = match purpose {
-            ImePurpose::Password => (ContentHint::SensitiveData, ContentPurpose::Password),
-            ImePurpose::Terminal => (ContentHint::None, ContentPurpose::Terminal),
-            _ => (ContentHint::None, ContentPurpose::Normal),
+    fn set_content_type_by_purpose(&self, purpose: ImePurpose, hints: ImeHint) {
+        // The hints mirror the ones of the protocol.
+        let mut hint = ContentHint::from_bits_truncate(hints.bits());
+        let purpose = match purpose {
+            ImePurpose::Password => {
+                hint |= ContentHint::SensitiveData;
+                ContentPurpose::Password
+            },
+            ImePurpose::Pin => {
+                hint |= ContentHint::SensitiveData;
+                ContentPurpose::Pin
+            },
+            ImePurpose::Terminal => ContentPurpose::Terminal,
+            ImePurpose::Alpha => ContentPurpose::Alpha,
+            ImePurpose::Digits => ContentPurpose::Digits,
+            ImePurpose::Number => ContentPurpose::Number,
+            ImePurpose::Phone => ContentPurpose::Phone,
+            ImePurpose::Url => ContentPurpose::Url,
+            ImePurpose::Email => ContentPurpose::Email,
+            ImePurpose::Name => ContentPurpose::Name,
+            ImePurpose::Date => ContentPurpose::Date,
+            ImePurpose::Time => ContentPurpose::Time,
+            ImePurpose::DateTime => ContentPurpose::Datetime,
+            _ => ContentPurpose::Normal,
         };
         self.set_content_type(hint, purpose);
     }
+
+    fn set_surrounding_text_truncated(&self, text: &str, cursor: usize, anchor: usize) {
+        if let Some((text, cursor, anchor)) = truncate_surrounding_text(text, cursor, anchor) {
+            self.set_surrounding_text(text.to_owned(), cursor as i32, anchor as i32);
+        }
+    }
+}
+
+/// The maximum length of the surrounding text, to fit in a Wayland message.
+const MAX_SURROUNDING_TEXT_LEN: usize = 4000;
+
+/// Cut the text around the cursor and the anchor to fit in a Wayland message.
+///
+/// Returns `None` when the cursor or the anchor aren't on a character boundary of the text.
+fn truncate_surrounding_text(
+    text: &str,
+    cursor: usize,
+    anchor: usize,
+) -> Option<(&str, usize, usize)> {
+    if !text.is_char_boundary(cursor) || !text.is_char_boundary(anchor) {
+        return None;
+    }
+    if text.len() <= MAX_SURROUNDING_TEXT_LEN {
+        return Some((text, cursor, anchor));
+    }
+
+    // Keep the selection when it fits, or the text around the cursor.
+    let (low, high) = (cursor.min(anchor), cursor.max(anchor));
+    let center = if high - low <= MAX_SURROUNDING_TEXT_LEN { (low + high) / 2 } else { cursor };
+    let mut start = center.saturating_sub(MAX_SURROUNDING_TEXT_LEN / 2);
+    start = start.min(text.len() - MAX_SURROUNDING_TEXT_LEN);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    let mut end = start + MAX_SURROUNDING_TEXT_LEN;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let (cursor, anchor) = (cursor.clamp(start, end), anchor.clamp(start, end));
+    Some((&text[start..end], cursor - start, anchor - start))
 }
 
 /// The Data associated with the text input.
@@ -187,6 +260,9 @@ pub struct TextInputDataInner {
 
     /// The preedit to submit on `done`.
     pending_preedit: Option<Preedit>,
+
+    /// The bytes to delete before and after the cursor on `done`.
+    pending_delete: Option<(usize, usize)>,
 }
 
 /// The state of the preedit.
@@ -198,3 +274,23 @@ struct Preedit {
 
 delegate_dispatch!(WinitState: [ZwpTextInputManagerV3: GlobalData] => TextInputState);
 delegate_dispatch!(WinitState: [ZwpTextInputV3: TextInputData] => TextInputState);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surrounding_text_truncation() {
+        assert_eq!(truncate_surrounding_text("héllo", 3, 1), Some(("héllo", 3, 1)));
+        assert_eq!(truncate_surrounding_text("héllo", 2, 2), None);
+
+        let text = "é".repeat(MAX_SURROUNDING_TEXT_LEN);
+        let (truncated, cursor, anchor) = truncate_surrounding_text(&text, 4000, 4010).unwrap();
+        assert!(truncated.len() <= MAX_SURROUNDING_TEXT_LEN);
+        assert_eq!(&truncated[cursor..anchor], "é".repeat(5));
+
+        // The cursor is kept when the selection doesn't fit.
+        let (truncated, cursor, anchor) = truncate_surrounding_text(&text, 0, 8000).unwrap();
+        assert_eq!((cursor, anchor), (0, truncated.len()));
+    }
+}
@@ -1,19 +1,25 @@
 use std::cell::RefCell;
+use std::mem;
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 
 use ahash::AHashMap;
 use sctk::compositor::{CompositorHandler, CompositorState};
+use sctk::data_device_manager::DataDeviceManagerState;
 use sctk::output::{OutputHandler, OutputState};
-use sctk::reexports::calloop::LoopHandle;
+use sctk::primary_selection::PrimarySelectionManagerState;
+use sctk::reexports::calloop::{channel, LoopHandle};
 use sctk::reexports::client::backend::ObjectId;
 use sctk::reexports::client::globals::GlobalList;
 use sctk::reexports::client::protocol::wl_output::WlOutput;
+use sctk::reexports::client::protocol::wl_seat::WlSeat;
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::client::{Connection, Proxy, QueueHandle};
 use sctk::registry::{ProvidesRegistryState, RegistryState};
 use sctk::seat::pointer::ThemedPointer;
 use sctk::seat::SeatState;
+use sctk::shell::wlr_layer::{LayerShell, LayerShellHandler, LayerSurface, LayerSurfaceConfigure};
+use sctk::shell::xdg::popup::{Popup, PopupConfigure, PopupHandler};
 use sctk::shell::xdg::window::{Window, WindowConfigure, WindowHandler};
 use sctk::shell::xdg::XdgShell;
 use sctk::shell::WaylandSurface;
@@ -21,17 +27,23 @@ use sctk::shm::slot::SlotPool;
 use sctk::shm::{Shm, ShmHandler};
 use sctk::subcompositor::SubcompositorState;
 use winit_core::error::OsError;
+use winit_core::event::{DeviceEvent, DeviceId, DeviceInfo, WindowEvent};
+use winit_core::event_loop::InputPreferences;
+use winit_core::monitor::{MonitorEvent, MonitorHandle as CoreMonitorHandle};
 
+use crate::platform_impl::common::portal;
 use crate::platform_impl::wayland::event_loop::sink::EventSink;
 use crate::platform_impl::wayland::output::MonitorHandle;
 use crate::platform_impl::wayland::seat::{
-    PointerConstraintsState, RelativePointerState, TextInputState, WinitPointerData,
+    ClipboardState, InputMethodState, PointerConstraintsState, PointerGesturesState,
+    RelativePointerState, RepeatInfo, TabletManagerState, TextInputState, WinitPointerData,
     WinitPointerDataExt, WinitSeatState,
 };
 use crate::platform_impl::wayland::types::kwin_blur::KWinBlurManager;
 use crate::platform_impl::wayland::types::wp_fractional_scaling::FractionalScalingManager;
 use crate::platform_impl::wayland::types::wp_viewporter::ViewporterState;
 use crate::platform_impl::wayland::types::xdg_activation::XdgActivationState;
+use crate::platform_impl::wayland::types::xdg_toplevel_icon::XdgToplevelIconManager;
 use crate::platform_impl::wayland::window::{WindowRequests, WindowState};
 use crate::platform_impl::wayland::WindowId;
 
@@ -62,6 +74,9 @@ pub struct WinitState {
     /// The XDG shell that is used for windows.
     pub xdg_shell: XdgShell,
 
+    /// The layer shell that is used for panels, docks and overlays.
+    pub layer_shell: Option<LayerShell>,
+
     /// The currently present windows.
     pub windows: RefCell<AHashMap<WindowId, Arc<Mutex<WindowState>>>>,
 
@@ -77,12 +92,39 @@ pub struct WinitState {
     /// Currently handled seats.
     pub seats: AHashMap<ObjectId, WinitSeatState>,
 
+    /// Currently connected input devices.
+    pub devices: AHashMap<DeviceId, DeviceInfo>,
+
+    /// The desktop settings read from the portal.
+    pub portal_settings: portal::Settings,
+
+    /// Watches the desktop settings until the event loop is dropped.
+    _settings_watcher: portal::SettingsWatcher,
+
+    /// The last input preferences reported to the application.
+    pub input_preferences: InputPreferences,
+
     /// Currently present cursor surfaces.
     pub pointer_surfaces: AHashMap<ObjectId, Arc<ThemedPointer<WinitPointerData>>>,
 
+    /// The data device manager for the clipboard.
+    pub data_device_manager: Option<DataDeviceManagerState>,
+
+    /// The primary selection device manager.
+    pub primary_selection_manager: Option<PrimarySelectionManagerState>,
+
+    /// The clipboard state shared with the `Clipboard`.
+    pub clipboard: Arc<Mutex<ClipboardState>>,
+
+    /// The seat and the serial of the latest user input, used to request activation tokens.
+    pub latest_input: Arc<Mutex<Option<(WlSeat, u32)>>>,
+
     /// The state of the text input on the client.
     pub text_input_state: Option<TextInputState>,
 
+    /// The input method and virtual keyboard managers, when acting as the input method.
+    pub input_method_state: Option<InputMethodState>,
+
     /// Observed monitors.
     pub monitors: Arc<Mutex<Vec<MonitorHandle>>>,
 
@@ -96,6 +138,12 @@ pub struct WinitState {
     /// Relative pointer.
     pub relative_pointer: Option<RelativePointerState>,
 
+    /// Pointer gestures to handle touchpad gestures.
+    pub pointer_gestures: Option<PointerGesturesState>,
+
+    /// Tablet manager to handle drawing tablet tools.
+    pub tablet_manager: Option<TabletManagerState>,
+
     /// Pointer constraints to handle pointer locking and confining.
     pub pointer_constraints: Option<Arc<PointerConstraintsState>>,
 
@@ -108,6 +156,9 @@ pub struct WinitState {
     /// KWin blur manager.
     pub kwin_blur_manager: Option<KWinBlurManager>,
 
+    /// Toplevel icon manager.
+    pub xdg_toplevel_icon_manager: Option<XdgToplevelIconManager>,
+
     /// Loop handle to re-register event sources, such as keyboard repeat.
     pub loop_handle: LoopHandle<'static, Self>,
 
@@ -117,6 +168,9 @@ pub struct WinitState {
 
     /// Whether the user initiated a wake up.
     pub proxy_wake_up: bool,
+
+    /// Whether to synthesize the repeats of held keys.
+    pub key_repeat: bool,
 }
 
 impl WinitState {
@@ -145,9 +199,22 @@ impl WinitState {
 
         let seat_state = SeatState::new(globals, queue_handle);
 
+        let data_device_manager = DataDeviceManagerState::bind(globals, queue_handle).ok();
+        let primary_selection_manager =
+            PrimarySelectionManagerState::bind(globals, queue_handle).ok();
+
+        let tablet_manager = TabletManagerState::new(globals, queue_handle).ok();
+
         let mut seats = AHashMap::default();
         for seat in seat_state.seats() {
-            seats.insert(seat.id(), WinitSeatState::new());
+            let seat_state = WinitSeatState::new(
+                &seat,
+                queue_handle,
+                data_device_manager.as_ref(),
+                primary_selection_manager.as_ref(),
+                tablet_manager.as_ref(),
+            );
+            seats.insert(seat.id(), seat_state);
         }
 
         let (viewporter_state, fractional_scaling_manager) =
@@ -157,10 +224,23 @@ impl WinitState {
                 (None, None)
             };
 
+        // Changes to the desktop settings are reported from the portal thread.
+        let (settings_sender, settings_channel) = channel::channel();
+        loop_handle
+            .insert_source(settings_channel, |event, _, winit_state: &mut WinitState| {
+                if let channel::Event::Msg(settings) = event {
+                    winit_state.portal_settings_changed(settings);
+                }
+            })
+            .map_err(|err| os_error!(err.error))?;
+        let settings_watcher = portal::watch_settings(move |settings| {
+            let _ = settings_sender.send(settings);
+        });
+
         let shm = Shm::bind(globals, queue_handle).map_err(|err| os_error!(err))?;
         let custom_cursor_pool = Arc::new(Mutex::new(SlotPool::new(2, &shm).unwrap()));
 
-        Ok(Self {
+        let mut state = Self {
             registry_state,
             compositor_state: Arc::new(compositor_state),
             subcompositor_state: subcompositor_state.map(Arc::new),
@@ -170,6 +250,7 @@ impl WinitState {
             custom_cursor_pool,
 
             xdg_shell: XdgShell::bind(globals, queue_handle).map_err(|err| os_error!(err))?,
+            layer_shell: LayerShell::bind(globals, queue_handle).ok(),
             xdg_activation: XdgActivationState::bind(globals, queue_handle).ok(),
 
             windows: Default::default(),
@@ -179,15 +260,27 @@ impl WinitState {
             viewporter_state,
             fractional_scaling_manager,
             kwin_blur_manager: KWinBlurManager::new(globals, queue_handle).ok(),
+            xdg_toplevel_icon_manager: XdgToplevelIconManager::new(globals, queue_handle).ok(),
 
             seats,
+            data_device_manager,
+            primary_selection_manager,
+            clipboard: Default::default(),
+            latest_input: Default::default(),
             text_input_state: TextInputState::new(globals, queue_handle).ok(),
+            input_method_state: None,
 
             relative_pointer: RelativePointerState::new(globals, queue_handle).ok(),
+            pointer_gestures: PointerGesturesState::new(globals, queue_handle).ok(),
+            tablet_manager,
             pointer_constraints: PointerConstraintsState::new(globals, queue_handle)
                 .map(Arc::new)
                 .ok(),
             pointer_surfaces: Default::default(),
+            devices: Default::default(),
+            portal_settings: Default::default(),
+            _settings_watcher: settings_watcher,
+            input_preferences: Default::default(),
 
             monitors: Arc::new(Mutex::new(monitors)),
             events_sink: EventSink::new(),
@@ -195,7 +288,71 @@ impl WinitState {
             // Make it true by default.
             dispatched_events: true,
             proxy_wake_up: false,
-        })
+            key_repeat: true,
+        };
+
+        // Start from what is known, the portal and the keyboards report the rest once available.
+        state.input_preferences = state.read_input_preferences();
+        Ok(state)
+    }
+
+    /// Track the newly connected device and notify the application.
+    pub fn add_device(&mut self, device_id: DeviceId, info: DeviceInfo) {
+        self.devices.insert(device_id, info.clone());
+        self.events_sink.push_device_event(DeviceEvent::Added(info), Some(device_id));
+    }
+
+    /// Forget the disconnected device and notify the application.
+    pub fn remove_device(&mut self, device_id: DeviceId) {
+        if self.devices.remove(&device_id).is_some() {
+            self.events_sink.push_device_event(DeviceEvent::Removed, Some(device_id));
+        }
+    }
+
+    /// Update the desktop settings, notifying the windows when the color scheme changed.
+    pub fn portal_settings_changed(&mut self, settings: portal::Settings) {
+        let previous = mem::replace(&mut self.portal_settings, settings);
+        if let Some(theme) =
+            settings.color_scheme.filter(|_| previous.color_scheme != settings.color_scheme)
+        {
+            // Windows with a theme override don't follow the system theme.
+            for (window_id, window) in self.windows.get_mut() {
+                if window.lock().unwrap().theme().is_none() {
+                    self.events_sink
+                        .push_window_event(WindowEvent::ThemeChanged(theme), *window_id);
+                }
+            }
+        }
+
+        self.update_input_preferences();
+    }
+
+    /// Re-evaluate the input preferences, notifying the application when they changed.
+    pub fn update_input_preferences(&mut self) {
+        let preferences = self.read_input_preferences();
+        if self.input_preferences != preferences {
+            self.input_preferences = preferences;
+            self.events_sink.push_input_preferences_changed();
+        }
+    }
+
+    /// The input preferences from the portal settings and the keyboards.
+    fn read_input_preferences(&self) -> InputPreferences {
+        let settings = &self.portal_settings;
+        let mut preferences = InputPreferences::default();
+        preferences.double_click_interval = settings.double_click_interval;
+        preferences.drag_threshold = settings.drag_threshold;
+        preferences.caret_blink = settings.caret_blink;
+        preferences.caret_blink_interval = settings.caret_blink_interval;
+
+        // Use the repeat of the first keyboard, seats rarely disagree.
+        let repeat_info = self.seats.values().find_map(WinitSeatState::repeat_info);
+        if let Some(RepeatInfo::Repeat { gap, delay }) = repeat_info {
+            preferences.key_repeat_delay = Some(delay);
+            preferences.key_repeat_interval = Some(gap);
+        }
+
+        preferences
     }
 
     pub fn scale_factor_changed(
@@ -275,7 +432,74 @@ impl WindowHandler for WinitState {
         _serial: u32,
     ) {
         let window_id = super::make_wid(window.wl_surface());
+        let mut capabilities = None;
+        let mut decoration_mode = None;
+        self.configure_window(window_id, |window, shm, subcompositor| {
+            let old_capabilities = window.window_capabilities();
+            let old_decoration_mode = window.decoration_mode();
+            let resized = window.configure(configure, shm, subcompositor);
+            capabilities =
+                Some(window.window_capabilities()).filter(|new| *new != old_capabilities);
+            decoration_mode =
+                window.decoration_mode().filter(|new| Some(*new) != old_decoration_mode);
+            resized
+        });
+
+        if let Some(capabilities) = capabilities {
+            let event = WindowEvent::CapabilitiesChanged(capabilities);
+            self.events_sink.push_window_event(event, window_id);
+        }
+
+        if let Some(mode) = decoration_mode {
+            self.events_sink.push_decoration_mode_changed(window_id, mode);
+        }
+    }
+}
 
+impl LayerShellHandler for WinitState {
+    fn closed(&mut self, _: &Connection, _: &QueueHandle<Self>, layer: &LayerSurface) {
+        let window_id = super::make_wid(layer.wl_surface());
+        Self::queue_close(&mut self.window_compositor_updates, window_id);
+    }
+
+    fn configure(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        layer: &LayerSurface,
+        configure: LayerSurfaceConfigure,
+        _serial: u32,
+    ) {
+        let window_id = super::make_wid(layer.wl_surface());
+        self.configure_window(window_id, |window, _, _| window.configure_layer(configure));
+    }
+}
+
+impl PopupHandler for WinitState {
+    fn configure(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        popup: &Popup,
+        configure: PopupConfigure,
+    ) {
+        let window_id = super::make_wid(popup.wl_surface());
+        self.configure_window(window_id, |window, _, _| window.configure_popup(configure));
+    }
+
+    fn done(&mut self, _: &Connection, _: &QueueHandle<Self>, popup: &Popup) {
+        let window_id = super::make_wid(popup.wl_surface());
+        self.events_sink.push_window_event(WindowEvent::PopupDismissed, window_id);
+    }
+}
+
+impl WinitState {
+    /// Apply the configure to the window, and ask the user to redraw it.
+    fn configure_window(
+        &mut self,
+        window_id: WindowId,
+        configure: impl FnOnce(&mut WindowState, &Shm, &Option<Arc<SubcompositorState>>) -> bool,
+    ) {
         let pos = if let Some(pos) =
             self.window_compositor_updates.iter().position(|update| update.window_id == window_id)
         {
@@ -286,14 +510,10 @@ impl WindowHandler for WinitState {
         };
 
         // Populate the configure to the window.
-        self.window_compositor_updates[pos].resized |= self
-            .windows
-            .get_mut()
-            .get_mut(&window_id)
-            .expect("got configure for dead window.")
-            .lock()
-            .unwrap()
-            .configure(configure, &self.shm, &self.subcompositor_state);
+        let window =
+            self.windows.get_mut().get(&window_id).expect("got configure for dead window.");
+        self.window_compositor_updates[pos].resized |=
+            configure(&mut window.lock().unwrap(), &self.shm, &self.subcompositor_state);
 
         // NOTE: configure demands wl_surface::commit, however winit doesn't commit on behalf of the
         // users, since it can break a lot of things, thus it'll ask users to redraw instead.
@@ -315,17 +535,29 @@ impl OutputHandler for WinitState {
     }
 
     fn new_output(&mut self, _: &Connection, _: &QueueHandle<Self>, output: WlOutput) {
-        self.monitors.lock().unwrap().push(MonitorHandle::new(output));
+        let mut monitors = self.monitors.lock().unwrap();
+        let output = MonitorHandle::new(output);
+        // The outputs present on startup are already known, but only get their information now.
+        if let Some(pos) = monitors.iter().position(|known| known == &output) {
+            monitors[pos] = output;
+        } else {
+            monitors.push(output.clone());
+            let event = MonitorEvent::Added(CoreMonitorHandle(Arc::new(output)));
+            self.events_sink.push_monitor_event(event);
+        }
     }
 
     fn update_output(&mut self, _: &Connection, _: &QueueHandle<Self>, updated: WlOutput) {
         let mut monitors = self.monitors.lock().unwrap();
         let updated = MonitorHandle::new(updated);
-        if let Some(pos) = monitors.iter().position(|output| output == &updated) {
-            monitors[pos] = updated
+        let event = if let Some(pos) = monitors.iter().position(|output| output == &updated) {
+            monitors[pos] = updated.clone();
+            MonitorEvent::Changed(CoreMonitorHandle(Arc::new(updated)))
         } else {
-            monitors.push(updated)
-        }
+            monitors.push(updated.clone());
+            MonitorEvent::Added(CoreMonitorHandle(Arc::new(updated)))
+        };
+        self.events_sink.push_monitor_event(event);
     }
 
     fn output_destroyed(&mut self, _: &Connection, _: &QueueHandle<Self>, removed: WlOutput) {
@@ -333,6 +565,8 @@ impl OutputHandler for WinitState {
         let removed = MonitorHandle::new(removed);
         if let Some(pos) = monitors.iter().position(|output| output == &removed) {
             monitors.remove(pos);
+            let event = MonitorEvent::Removed(CoreMonitorHandle(Arc::new(removed)));
+            self.events_sink.push_monitor_event(event);
         }
     }
 }
@@ -435,4 +669,6 @@ sctk::delegate_output!(WinitState);
 sctk::delegate_registry!(WinitState);
 sctk::delegate_shm!(WinitState);
 sctk::delegate_xdg_shell!(WinitState);
+sctk::delegate_layer!(WinitState);
 sctk::delegate_xdg_window!(WinitState);
+sctk::delegate_xdg_popup!(WinitState);
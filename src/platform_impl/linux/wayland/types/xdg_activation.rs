@@ -1,4 +1,4 @@
-//! Handling of xdg activation, which is used for user attention requests.
+//! Handling of xdg activation, which is used for focus and user attention requests.
 
 use std::sync::atomic::AtomicBool;
 use std::sync::Weak;
@@ -75,6 +75,7 @@ impl Dispatch<XdgActivationTokenV1, XdgActivationTokenData, WinitState> for XdgA
                     attention_requested.store(false, std::sync::atomic::Ordering::Relaxed);
                 }
             },
+            XdgActivationTokenData::Activate(surface) => global.activate(token, surface),
             XdgActivationTokenData::Obtain((window_id, serial)) => {
                 state.events_sink.push_window_event(
                     winit_core::event::WindowEvent::ActivationTokenDone {
@@ -94,6 +95,8 @@ impl Dispatch<XdgActivationTokenV1, XdgActivationTokenData, WinitState> for XdgA
 pub enum XdgActivationTokenData {
     /// Request user attention for the given surface.
     Attention((WlSurface, Weak<AtomicBool>)),
+    /// Activate the given surface.
+    Activate(WlSurface),
     /// Get a token to be passed outside of the winit.
     Obtain((WindowId, AsyncRequestSerial)),
 }
@@ -0,0 +1,286 @@
+//! Handling of the toplevel icons.
+
+use std::sync::Mutex;
+
+use sctk::reexports::client::globals::{BindError, GlobalList};
+use sctk::reexports::client::protocol::wl_shm::Format;
+use sctk::reexports::client::{delegate_dispatch, Connection, Dispatch, Proxy, QueueHandle};
+use sctk::reexports::protocols::xdg::shell::client::xdg_toplevel::XdgToplevel;
+use sctk::reexports::protocols::xdg::toplevel_icon::v1::client::xdg_toplevel_icon_manager_v1::{
+    Event as IconManagerEvent, XdgToplevelIconManagerV1,
+};
+use sctk::reexports::protocols::xdg::toplevel_icon::v1::client::xdg_toplevel_icon_v1::XdgToplevelIconV1;
+use sctk::shm::slot::{Buffer, SlotPool};
+use tracing::warn;
+use winit_core::icon::{Icon, RgbaIcon};
+
+use crate::platform_impl::wayland::state::WinitState;
+
+/// Toplevel icon manager.
+#[derive(Debug, Clone)]
+pub struct XdgToplevelIconManager {
+    manager: XdgToplevelIconManagerV1,
+}
+
+impl XdgToplevelIconManager {
+    pub fn new(
+        globals: &GlobalList,
+        queue_handle: &QueueHandle<WinitState>,
+    ) -> Result<Self, BindError> {
+        let manager = globals.bind(queue_handle, 1..=1, IconManagerData::default())?;
+        Ok(Self { manager })
+    }
+
+    /// Set the icon of the toplevel, or reset it to the default one.
+    ///
+    /// The themed name of the icon is set when present, and the images are uploaded for the
+    /// integer scale of the toplevel, see [`icon_buffers`]. The returned icon must be kept alive
+    /// for as long as it's used by the toplevel.
+    pub fn set_icon(
+        &self,
+        toplevel: &XdgToplevel,
+        icon: Option<&Icon>,
+        scale: u32,
+        pool: &mut SlotPool,
+        queue_handle: &QueueHandle<WinitState>,
+    ) -> Option<ToplevelIcon> {
+        let Some(icon) =
+            icon.filter(|icon| icon.theme_name().is_some() || !icon.rgba_images().is_empty())
+        else {
+            self.manager.set_icon(toplevel, None);
+            return None;
+        };
+
+        let xdg_icon = self.manager.create_icon(queue_handle, ());
+        if let Some(name) = icon.theme_name() {
+            xdg_icon.set_name(name.to_owned());
+        }
+
+        let sizes = icon_buffers(icon, &self.preferred_sizes(), scale);
+        let mut buffers = Vec::with_capacity(sizes.len());
+        for (size, buffer_scale, image) in sizes {
+            let pixels = size * buffer_scale;
+            let side = pixels as i32;
+            let (buffer, canvas) = match pool.create_buffer(side, side, 4 * side, Format::Argb8888)
+            {
+                Ok(buffer) => buffer,
+                Err(err) => {
+                    warn!("failed to allocate the {pixels}x{pixels} window icon: {err}");
+                    continue;
+                },
+            };
+            canvas.copy_from_slice(&square_argb(image, pixels));
+            xdg_icon.add_buffer(buffer.wl_buffer(), buffer_scale as i32);
+            buffers.push(buffer);
+        }
+
+        self.manager.set_icon(toplevel, Some(&xdg_icon));
+        Some(ToplevelIcon { icon: xdg_icon, source: icon.clone(), scale, _buffers: buffers })
+    }
+
+    /// The icon sizes preferred by the compositor, in surface-local coordinates.
+    fn preferred_sizes(&self) -> Vec<u32> {
+        let data = self.manager.data::<IconManagerData>().unwrap();
+        data.sizes.lock().unwrap().clone()
+    }
+}
+
+/// An icon set on a toplevel, with the buffers backing it.
+#[derive(Debug)]
+pub struct ToplevelIcon {
+    icon: XdgToplevelIconV1,
+    /// The icon the buffers were drawn from.
+    source: Icon,
+    /// The integer scale the buffers were drawn for.
+    scale: u32,
+    _buffers: Vec<Buffer>,
+}
+
+impl ToplevelIcon {
+    pub fn source(&self) -> &Icon {
+        &self.source
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+}
+
+impl Drop for ToplevelIcon {
+    fn drop(&mut self) {
+        // The buffers must outlive the icon.
+        self.icon.destroy();
+    }
+}
+
+/// The data of the icon manager, tracking the sizes preferred by the compositor.
+#[derive(Debug, Default)]
+pub struct IconManagerData {
+    pending_sizes: Mutex<Vec<u32>>,
+    sizes: Mutex<Vec<u32>>,
+}
+
+/// The buffers to upload for the icon, as their size in surface-local coordinates, their scale,
+/// and the image drawn on their `size * scale` pixels.
+///
+/// Each image is uploaded at its own size, and the preferred sizes that aren't covered are scaled
+/// from the closest image. When the scale is above 1, the same sizes are uploaded again at that
+/// scale, so the icon stays sharp on HiDPI outputs.
+fn icon_buffers<'a>(
+    icon: &'a Icon,
+    preferred_sizes: &[u32],
+    scale: u32,
+) -> Vec<(u32, u32, &'a RgbaIcon)> {
+    let images = icon.rgba_images();
+    let image_sizes = || images.iter().map(|image| image.width().max(image.height()));
+
+    let mut buffers: Vec<_> =
+        image_sizes().zip(images).map(|(size, image)| (size, 1, image)).collect();
+    for &size in preferred_sizes {
+        if let Some(image) = icon.rgba_image_for_size(size) {
+            buffers.push((size, 1, image));
+        }
+    }
+
+    if scale > 1 {
+        let sizes = image_sizes().map(|size| size / scale).chain(preferred_sizes.iter().copied());
+        for size in sizes.filter(|&size| size > 0) {
+            if let Some(image) = icon.rgba_image_for_size(size * scale) {
+                buffers.push((size, scale, image));
+            }
+        }
+    }
+
+    // Keep the images provided for a size over the scaled ones.
+    buffers.sort_by_key(|&(size, scale, _)| (scale, size));
+    buffers.dedup_by_key(|&mut (size, scale, _)| (size, scale));
+    buffers
+}
+
+/// Convert the icon to premultiplied ARGB, centered in a square of the given size.
+///
+/// The icon is scaled by averaging the pixels covered by each pixel of the square.
+fn square_argb(icon: &RgbaIcon, size: u32) -> Vec<u8> {
+    let (width, height) = (icon.width() as usize, icon.height() as usize);
+    let side = width.max(height);
+    let (left, top) = ((side - width) / 2, (side - height) / 2);
+    let size = size as usize;
+    let rgba = icon.buffer();
+
+    let mut argb = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        let (y_start, y_end) = (y * side / size, ((y + 1) * side / size).max(y * side / size + 1));
+        for x in 0..size {
+            let (x_start, x_end) =
+                (x * side / size, ((x + 1) * side / size).max(x * side / size + 1));
+
+            // Pixels of the padding are transparent, but still count towards the average.
+            let mut sum = [0u32; 4];
+            for src_y in y_start.max(top)..y_end.min(top + height) {
+                for src_x in x_start.max(left)..x_end.min(left + width) {
+                    let offset = ((src_y - top) * width + src_x - left) * 4;
+                    let pixel = &rgba[offset..offset + 4];
+                    let alpha = pixel[3] as u32;
+                    sum[0] += pixel[0] as u32 * alpha / 255;
+                    sum[1] += pixel[1] as u32 * alpha / 255;
+                    sum[2] += pixel[2] as u32 * alpha / 255;
+                    sum[3] += alpha;
+                }
+            }
+
+            let count = ((y_end - y_start) * (x_end - x_start)) as u32;
+            let [r, g, b, a] = sum.map(|channel| channel / count);
+            argb.extend_from_slice(&((a << 24) | (r << 16) | (g << 8) | b).to_le_bytes());
+        }
+    }
+
+    argb
+}
+
+impl Dispatch<XdgToplevelIconManagerV1, IconManagerData, WinitState> for XdgToplevelIconManager {
+    fn event(
+        _: &mut WinitState,
+        _: &XdgToplevelIconManagerV1,
+        event: <XdgToplevelIconManagerV1 as Proxy>::Event,
+        data: &IconManagerData,
+        _: &Connection,
+        _: &QueueHandle<WinitState>,
+    ) {
+        match event {
+            IconManagerEvent::IconSize { size } => {
+                if let Ok(size) = u32::try_from(size) {
+                    data.pending_sizes.lock().unwrap().push(size);
+                }
+            },
+            IconManagerEvent::Done => {
+                let sizes = std::mem::take(&mut *data.pending_sizes.lock().unwrap());
+                *data.sizes.lock().unwrap() = sizes;
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Dispatch<XdgToplevelIconV1, (), WinitState> for XdgToplevelIconManager {
+    fn event(
+        _: &mut WinitState,
+        _: &XdgToplevelIconV1,
+        _: <XdgToplevelIconV1 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<WinitState>,
+    ) {
+        unreachable!("no events defined for xdg_toplevel_icon_v1");
+    }
+}
+
+delegate_dispatch!(WinitState: [XdgToplevelIconManagerV1: IconManagerData] => XdgToplevelIconManager);
+delegate_dispatch!(WinitState: [XdgToplevelIconV1: ()] => XdgToplevelIconManager);
+
+#[cfg(test)]
+mod tests {
+    use winit_core::icon::{Icon, IconSet, RgbaIcon};
+
+    use super::{icon_buffers, square_argb};
+
+    fn pixels(argb: &[u8]) -> Vec<u32> {
+        argb.chunks_exact(4).map(|pixel| u32::from_le_bytes(pixel.try_into().unwrap())).collect()
+    }
+
+    #[test]
+    fn icon_is_padded_to_a_square() {
+        // A 2x1 icon of opaque red and half transparent white.
+        let icon = RgbaIcon::new(vec![255, 0, 0, 255, 255, 255, 255, 128], 2, 1).unwrap();
+        assert_eq!(pixels(&square_argb(&icon, 2)), [0xffff0000, 0x80808080, 0, 0]);
+    }
+
+    #[test]
+    fn icon_is_scaled() {
+        let icon = RgbaIcon::new([255, 255, 255, 255].repeat(4), 2, 2).unwrap();
+        assert_eq!(pixels(&square_argb(&icon, 1)), [0xffffffff]);
+        assert_eq!(pixels(&square_argb(&icon, 4)), [0xffffffff; 16]);
+    }
+
+    #[test]
+    fn icon_buffers_for_scale() {
+        let square = |size: u32| RgbaIcon::new(vec![0; (size * size * 4) as usize], size, size);
+        let icon: Icon = IconSet::new(vec![square(16).unwrap(), square(64).unwrap()]).into();
+        let buffers = |preferred: &[u32], scale| {
+            icon_buffers(&icon, preferred, scale)
+                .into_iter()
+                .map(|(size, scale, image)| (size, scale, image.width()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(buffers(&[], 1), [(16, 1, 16), (64, 1, 64)]);
+        assert_eq!(buffers(&[32], 1), [(16, 1, 16), (32, 1, 64), (64, 1, 64)]);
+        // The 2x buffers are drawn from the image covering their pixels.
+        assert_eq!(buffers(&[16], 2), [
+            (16, 1, 16),
+            (64, 1, 64),
+            (8, 2, 16),
+            (16, 2, 64),
+            (32, 2, 64)
+        ]);
+    }
+}
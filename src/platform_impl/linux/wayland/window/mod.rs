@@ -5,42 +5,61 @@ use std::ptr::NonNull;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
-use dpi::{LogicalSize, PhysicalInsets, PhysicalPosition, PhysicalSize, Position, Size};
+use dpi::{
+    LogicalInsets, LogicalPosition, LogicalSize, PhysicalInsets, PhysicalPosition, PhysicalSize,
+    Position, Size,
+};
 use sctk::compositor::{CompositorState, Region, SurfaceData};
+use sctk::reexports::client::protocol::wl_data_device_manager::WlDataDeviceManager;
 use sctk::reexports::client::protocol::wl_display::WlDisplay;
+use sctk::reexports::client::protocol::wl_seat::WlSeat;
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::client::{Proxy, QueueHandle};
 use sctk::reexports::protocols::xdg::activation::v1::client::xdg_activation_v1::XdgActivationV1;
-use sctk::shell::xdg::window::{Window as SctkWindow, WindowDecorations};
+use sctk::reexports::protocols::xdg::shell::client::xdg_positioner::{
+    Anchor as XdgAnchor, ConstraintAdjustment, Gravity,
+};
+use sctk::shell::wlr_layer::{
+    Anchor as SctkAnchor, KeyboardInteractivity as SctkKeyboardInteractivity, Layer as SctkLayer,
+    LayerShell, LayerSurface,
+};
+use sctk::shell::xdg::popup::Popup;
+use sctk::shell::xdg::window::WindowDecorations;
+use sctk::shell::xdg::{XdgPositioner, XdgSurface};
 use sctk::shell::WaylandSurface;
 use tracing::warn;
-use winit_core::cursor::Cursor;
+use winit_core::clipboard::ClipboardContents;
+use winit_core::cursor::{Cursor, CustomCursorSource};
 use winit_core::error::{NotSupportedError, RequestError};
-use winit_core::event::{Ime, WindowEvent};
+use winit_core::event::{DragAction, Ime, WindowEvent};
 use winit_core::event_loop::AsyncRequestSerial;
 use winit_core::monitor::{Fullscreen, MonitorHandle as CoreMonitorHandle};
 use winit_core::window::{
-    CursorGrabMode, ImePurpose, ResizeDirection, Theme, UserAttentionType, Window as CoreWindow,
-    WindowAttributes, WindowButtons, WindowId, WindowLevel,
+    ActivationToken, CursorGrabMode, ImeHint, ImePurpose, PopupAnchor, PopupAttributes,
+    ResizeDirection, Theme, UserAttentionType, Window as CoreWindow, WindowAttributes,
+    WindowButtons, WindowCapabilities, WindowId, WindowLevel,
 };
 
 use super::event_loop::sink::EventSink;
 use super::output::MonitorHandle;
+use super::seat::ClipboardState;
 use super::state::WinitState;
 use super::types::xdg_activation::XdgActivationTokenData;
 use super::ActiveEventLoop;
-use crate::platform::wayland::WindowAttributesWayland;
+use crate::platform::wayland::{
+    DecorationMode, DecorationPreference, KeyboardInteractivity, Layer, WindowAttributesWayland,
+};
 use crate::platform_impl::wayland::output;
 
 pub(crate) mod state;
 
-pub use state::WindowState;
+pub use state::{ShellSurface, WindowState};
 
 /// The Wayland window.
 #[derive(Debug)]
 pub struct Window {
-    /// Reference to the underlying SCTK window.
-    window: SctkWindow,
+    /// Reference to the underlying SCTK shell surface.
+    window: ShellSurface,
 
     /// Window id.
     window_id: WindowId,
@@ -61,6 +80,9 @@ pub struct Window {
     /// The state of the requested attention from the `xdg_activation`.
     attention_requested: Arc<AtomicBool>,
 
+    /// The latest user input, to request activation tokens on behalf of the user.
+    latest_input: Arc<Mutex<Option<(WlSeat, u32)>>>,
+
     /// Handle to the main queue to perform requests.
     queue_handle: QueueHandle<WinitState>,
 
@@ -75,6 +97,12 @@ pub struct Window {
 
     /// The event sink to deliver synthetic events.
     window_events_sink: Arc<Mutex<EventSink>>,
+
+    /// The data device manager to drag data out of the window.
+    data_device_manager: Option<WlDataDeviceManager>,
+
+    /// The clipboard state, which also tracks the drag out of the window.
+    clipboard: Arc<Mutex<ClipboardState>>,
 }
 
 impl Window {
@@ -86,6 +114,10 @@ impl Window {
         let mut state = event_loop_window_target.state.borrow_mut();
 
         let monitors = state.monitors.clone();
+        let data_device_manager =
+            state.data_device_manager.as_ref().map(|manager| manager.data_device_manager().clone());
+        let clipboard = state.clipboard.clone();
+        let latest_input = state.latest_input.clone();
 
         let surface = state.compositor_state.create_surface(&queue_handle);
         let compositor = state.compositor_state.clone();
@@ -95,16 +127,54 @@ impl Window {
 
         let size: Size = attributes.surface_size.unwrap_or(LogicalSize::new(800., 600.).into());
 
-        // We prefer server side decorations, however to not have decorations we ask for client
-        // side decorations instead.
-        let default_decorations = if attributes.decorations {
-            WindowDecorations::RequestServer
+        let platform_attributes = attributes
+            .platform
+            .take()
+            .and_then(|p| p.cast::<WindowAttributesWayland>().ok())
+            .map(|attrs| *attrs)
+            .unwrap_or_default();
+
+        let mut popup_positioner = None;
+        let window = if let Some(popup) = attributes.popup.as_ref() {
+            let (popup, positioner) =
+                create_popup(&state, &queue_handle, surface.clone(), popup, size)?;
+            popup_positioner = Some(positioner);
+            ShellSurface::Popup(popup)
+        } else if let Some(layer) = platform_attributes.layer {
+            let layer_shell = state
+                .layer_shell
+                .as_ref()
+                .ok_or_else(|| NotSupportedError::new("zwlr_layer_shell_v1 is not available"))?;
+            let layer_surface = create_layer_surface(
+                layer_shell,
+                &queue_handle,
+                surface.clone(),
+                layer,
+                &platform_attributes,
+                size.to_logical(1.),
+                attributes.surface_size.is_some(),
+            );
+
+            ShellSurface::Layer(layer_surface)
         } else {
-            WindowDecorations::RequestClient
-        };
+            // We prefer server side decorations, however to not have decorations we ask for client
+            // side decorations instead.
+            let default_decorations = match platform_attributes.decoration_preference {
+                DecorationPreference::PreferServer if attributes.decorations => {
+                    WindowDecorations::RequestServer
+                },
+                DecorationPreference::PreferServer | DecorationPreference::PreferClient => {
+                    WindowDecorations::RequestClient
+                },
+                DecorationPreference::RequireClient => WindowDecorations::ClientOnly,
+            };
 
-        let window =
-            state.xdg_shell.create_window(surface.clone(), default_decorations, &queue_handle);
+            ShellSurface::Toplevel(state.xdg_shell.create_window(
+                surface.clone(),
+                default_decorations,
+                &queue_handle,
+            ))
+        };
 
         let mut window_state = WindowState::new(
             event_loop_window_target.handle.clone(),
@@ -115,29 +185,31 @@ impl Window {
             attributes.preferred_theme,
         );
 
+        if let Some(positioner) = popup_positioner {
+            window_state.set_popup_positioner(positioner);
+        }
+
         // Set transparency hint.
         window_state.set_transparent(attributes.transparent);
 
         window_state.set_blur(attributes.blur);
 
         // Set the decorations hint.
+        window_state.set_decoration_preference(platform_attributes.decoration_preference);
         window_state.set_decorate(attributes.decorations);
 
-        let (app_name, activation_token) =
-            match attributes.platform.take().and_then(|p| p.cast::<WindowAttributesWayland>().ok())
-            {
-                Some(attrs) => (attrs.name, attrs.activation_token),
-                None => (None, None),
-            };
-
         // Set the app_id.
-        if let Some(name) = app_name.map(|name| name.general) {
-            window.set_app_id(name);
+        if let (Some(window), Some(name)) = (window.toplevel(), platform_attributes.name) {
+            window.set_app_id(name.general);
         }
 
         // Set the window title.
         window_state.set_title(attributes.title);
 
+        if attributes.window_icon.is_some() {
+            window_state.set_window_icon(attributes.window_icon.as_ref());
+        }
+
         // Set the min and max sizes. We must set the hints upon creating a window, so
         // we use the default `1.` scaling...
         let min_size = attributes.min_surface_size.map(|size| size.to_logical(1.));
@@ -148,20 +220,20 @@ impl Window {
         // Non-resizable implies that the min and max sizes are set to the same value.
         window_state.set_resizable(attributes.resizable);
 
-        // Set startup mode.
-        match attributes.fullscreen {
-            Some(Fullscreen::Exclusive(..)) => {
+        // Set startup mode, layer shell surfaces have none.
+        match (window.toplevel(), attributes.fullscreen) {
+            (_, Some(Fullscreen::Exclusive(..))) => {
                 warn!("`Fullscreen::Exclusive` is ignored on Wayland");
             },
             #[cfg_attr(not(x11_platform), allow(clippy::bind_instead_of_map))]
-            Some(Fullscreen::Borderless(monitor)) => {
+            (Some(window), Some(Fullscreen::Borderless(monitor))) => {
                 let output = monitor.as_ref().and_then(|monitor| {
                     monitor.cast_ref::<output::MonitorHandle>().map(|handle| &handle.proxy)
                 });
 
                 window.set_fullscreen(output)
             },
-            _ if attributes.maximized => window.set_maximized(),
+            (Some(window), _) if attributes.maximized => window.set_maximized(),
             _ => (),
         };
 
@@ -171,7 +243,9 @@ impl Window {
         }
 
         // Activate the window when the token is passed.
-        if let (Some(xdg_activation), Some(token)) = (xdg_activation.as_ref(), activation_token) {
+        if let (Some(xdg_activation), Some(token)) =
+            (xdg_activation.as_ref(), platform_attributes.activation_token)
+        {
             xdg_activation.activate(token.into_raw(), &surface);
         }
 
@@ -218,14 +292,26 @@ impl Window {
             queue_handle,
             xdg_activation,
             attention_requested: Arc::new(AtomicBool::new(false)),
+            latest_input,
             event_loop_awakener,
             window_requests,
             window_events_sink,
+            data_device_manager,
+            clipboard,
         })
     }
 
     pub(crate) fn xdg_toplevel(&self) -> Option<NonNull<c_void>> {
-        NonNull::new(self.window.xdg_toplevel().id().as_ptr().cast())
+        NonNull::new(self.window.toplevel()?.xdg_toplevel().id().as_ptr().cast())
+    }
+
+    pub(crate) fn decoration_mode(&self) -> Option<DecorationMode> {
+        self.window_state.lock().unwrap().decoration_mode()
+    }
+
+    pub(crate) fn frame_insets(&self) -> PhysicalInsets<u32> {
+        let window_state = self.window_state.lock().unwrap();
+        window_state.frame_insets().to_physical(window_state.scale_factor())
     }
 }
 
@@ -246,6 +332,17 @@ impl Window {
         Ok(serial)
     }
 
+    /// Activate the window with a token obtained by another client.
+    pub fn activate_with_token(&self, token: ActivationToken) -> Result<(), RequestError> {
+        let xdg_activation = match self.xdg_activation.as_ref() {
+            Some(xdg_activation) => xdg_activation,
+            None => return Err(NotSupportedError::new("xdg_activation_v1 is not available").into()),
+        };
+
+        xdg_activation.activate(token.into_raw(), self.surface());
+        Ok(())
+    }
+
     #[inline]
     pub fn surface(&self) -> &WlSurface {
         self.window.wl_surface()
@@ -315,7 +412,8 @@ impl CoreWindow for Window {
     }
 
     fn surface_position(&self) -> PhysicalPosition<i32> {
-        (0, 0).into()
+        let insets = self.frame_insets();
+        (insets.left as i32, insets.top as i32).into()
     }
 
     fn outer_position(&self) -> Result<PhysicalPosition<i32>, RequestError> {
@@ -405,13 +503,19 @@ impl CoreWindow for Window {
         self.window_state.lock().unwrap().resizable()
     }
 
-    fn set_enabled_buttons(&self, _buttons: WindowButtons) {
-        // TODO(kchibisov) v5 of the xdg_shell allows that.
+    fn set_enabled_buttons(&self, buttons: WindowButtons) {
+        if self.window_state.lock().unwrap().set_enabled_buttons(buttons) {
+            // NOTE: Requires commit to be applied.
+            self.request_redraw();
+        }
     }
 
     fn enabled_buttons(&self) -> WindowButtons {
-        // TODO(kchibisov) v5 of the xdg_shell allows that.
-        WindowButtons::all()
+        self.window_state.lock().unwrap().enabled_buttons()
+    }
+
+    fn window_capabilities(&self) -> WindowCapabilities {
+        self.window_state.lock().unwrap().window_capabilities()
     }
 
     fn set_minimized(&self, minimized: bool) {
@@ -421,7 +525,9 @@ impl CoreWindow for Window {
             return;
         }
 
-        self.window.set_minimized();
+        if let Some(window) = self.window.toplevel() {
+            window.set_minimized();
+        }
     }
 
     fn is_minimized(&self) -> Option<bool> {
@@ -430,10 +536,11 @@ impl CoreWindow for Window {
     }
 
     fn set_maximized(&self, maximized: bool) {
+        let Some(window) = self.window.toplevel() else { return };
         if maximized {
-            self.window.set_maximized()
+            window.set_maximized()
         } else {
-            self.window.unset_maximized()
+            window.unset_maximized()
         }
     }
 
@@ -448,6 +555,7 @@ impl CoreWindow for Window {
     }
 
     fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        let Some(window) = self.window.toplevel() else { return };
         match fullscreen {
             Some(Fullscreen::Exclusive(..)) => {
                 warn!("`Fullscreen::Exclusive` is ignored on Wayland");
@@ -458,9 +566,9 @@ impl CoreWindow for Window {
                     monitor.cast_ref::<output::MonitorHandle>().map(|handle| &handle.proxy)
                 });
 
-                self.window.set_fullscreen(output)
+                window.set_fullscreen(output)
             },
-            None => self.window.unset_fullscreen(),
+            None => window.unset_fullscreen(),
         }
     }
 
@@ -504,7 +612,11 @@ impl CoreWindow for Window {
 
     fn set_window_level(&self, _level: WindowLevel) {}
 
-    fn set_window_icon(&self, _window_icon: Option<winit_core::icon::Icon>) {}
+    fn set_window_icon(&self, window_icon: Option<winit_core::icon::Icon>) {
+        self.window_state.lock().unwrap().set_window_icon(window_icon.as_ref());
+        // The icon is applied on the next commit.
+        self.request_redraw();
+    }
 
     #[inline]
     fn set_ime_cursor_area(&self, position: Position, size: Size) {
@@ -533,7 +645,39 @@ impl CoreWindow for Window {
         self.window_state.lock().unwrap().set_ime_purpose(purpose);
     }
 
-    fn focus_window(&self) {}
+    #[inline]
+    fn set_ime_hints(&self, hints: ImeHint) {
+        self.window_state.lock().unwrap().set_ime_hints(hints);
+    }
+
+    #[inline]
+    fn set_ime_surrounding_text(&self, text: &str, cursor: usize, anchor: usize) {
+        let window_state = self.window_state.lock().unwrap();
+        if window_state.ime_allowed() {
+            window_state.set_ime_surrounding_text(text, cursor, anchor);
+        }
+    }
+
+    fn focus_window(&self) {
+        let xdg_activation = match self.xdg_activation.as_ref() {
+            Some(xdg_activation) => xdg_activation,
+            None => {
+                warn!("`focus_window` isn't supported");
+                return;
+            },
+        };
+
+        // The compositor only grants the token when it comes from a recent user input, without
+        // any, e.g. once its seat was removed, the token is requested without a serial.
+        let surface = self.surface().clone();
+        let data = XdgActivationTokenData::Activate(surface.clone());
+        let xdg_activation_token = xdg_activation.get_activation_token(&self.queue_handle, data);
+        if let Some((seat, serial)) = self.latest_input.lock().unwrap().as_ref() {
+            xdg_activation_token.set_serial(*serial, seat);
+        }
+        xdg_activation_token.set_surface(&surface);
+        xdg_activation_token.commit();
+    }
 
     fn has_focus(&self) -> bool {
         self.window_state.lock().unwrap().has_focus()
@@ -611,6 +755,32 @@ impl CoreWindow for Window {
         self.window_state.lock().unwrap().drag_resize_window(direction)
     }
 
+    fn start_drag(
+        &self,
+        contents: ClipboardContents,
+        actions: &[DragAction],
+        icon: Option<CustomCursorSource>,
+    ) -> Result<AsyncRequestSerial, RequestError> {
+        let manager = self.data_device_manager.as_ref().ok_or_else(|| {
+            NotSupportedError::new("the compositor doesn't support wl_data_device_manager")
+        })?;
+        let icon = match icon {
+            Some(CustomCursorSource::Image(image)) => Some(image),
+            Some(_) => return Err(NotSupportedError::new("unsupported drag icon kind").into()),
+            None => None,
+        };
+
+        let icon = icon.map(|image| self.window_state.lock().unwrap().drag_icon(image));
+        self.clipboard.lock().unwrap().start_drag(
+            manager,
+            &self.queue_handle,
+            self.window.wl_surface(),
+            contents,
+            actions,
+            icon,
+        )
+    }
+
     fn show_window_menu(&self, position: Position) {
         let scale_factor = self.scale_factor();
         let position = position.to_logical(scale_factor);
@@ -685,3 +855,183 @@ impl WindowRequests {
         self.redraw_requested.swap(false, Ordering::Relaxed)
     }
 }
+
+/// Create the popup for the window, attached to the surface of its parent.
+fn create_popup(
+    state: &WinitState,
+    queue_handle: &QueueHandle<WinitState>,
+    surface: WlSurface,
+    attributes: &PopupAttributes,
+    size: Size,
+) -> Result<(Popup, XdgPositioner), RequestError> {
+    let parent = state.windows.borrow().get(&attributes.parent).cloned().ok_or_else(|| {
+        NotSupportedError::new("the parent of the popup is not a window of the event loop")
+    })?;
+    let parent = parent.lock().unwrap();
+    let scale_factor = parent.scale_factor();
+
+    let positioner = XdgPositioner::new(&state.xdg_shell).map_err(|err| os_error!(err))?;
+    let size = size.to_logical::<i32>(scale_factor);
+    positioner.set_size(size.width.max(1), size.height.max(1));
+    let anchor_position = popup_anchor_position(
+        attributes.anchor_position.to_logical::<i32>(scale_factor),
+        parent.frame_insets(),
+    );
+    let anchor_size = attributes.anchor_size.to_logical::<i32>(scale_factor);
+    positioner.set_anchor_rect(
+        anchor_position.x,
+        anchor_position.y,
+        anchor_size.width,
+        anchor_size.height,
+    );
+    positioner.set_anchor(popup_anchor_to_xdg(attributes.anchor));
+    positioner.set_gravity(popup_gravity_to_xdg(attributes.gravity));
+    let offset = attributes.offset.to_logical::<i32>(scale_factor);
+    positioner.set_offset(offset.x, offset.y);
+    positioner.set_constraint_adjustment(ConstraintAdjustment::from_bits_truncate(
+        attributes.constraint_adjustment.bits(),
+    ));
+    // Follow the parent when it moves or resizes.
+    if positioner.version() >= 3 {
+        positioner.set_reactive();
+    }
+
+    let xdg_parent = match &parent.window {
+        ShellSurface::Toplevel(window) => Some(window.xdg_surface()),
+        ShellSurface::Popup(popup) => Some(popup.xdg_surface()),
+        ShellSurface::Layer(_) => None,
+    };
+    let popup =
+        Popup::from_surface(xdg_parent, &positioner, queue_handle, surface, &state.xdg_shell)
+            .map_err(|err| os_error!(err))?;
+    if let ShellSurface::Layer(layer) = &parent.window {
+        layer.get_popup(popup.xdg_popup());
+    }
+
+    if attributes.grab {
+        // Grab with the seat of the input which opened the popup.
+        if let Some((seat, _)) = state.latest_input.lock().unwrap().as_ref() {
+            parent.grab_popup(&popup, seat);
+        }
+    }
+
+    Ok((popup, positioner))
+}
+
+/// Move the anchor position from the parent surface into its window geometry.
+///
+/// The window geometry of parents with client-side decorations starts at the frame.
+fn popup_anchor_position(
+    position: LogicalPosition<i32>,
+    parent_insets: LogicalInsets<u32>,
+) -> LogicalPosition<i32> {
+    LogicalPosition::new(
+        position.x + parent_insets.left as i32,
+        position.y + parent_insets.top as i32,
+    )
+}
+
+/// Create the layer shell surface for the window.
+///
+/// Unless a size was requested, the compositor picks the size along the axes where the surface is
+/// stretched.
+fn create_layer_surface(
+    layer_shell: &LayerShell,
+    queue_handle: &QueueHandle<WinitState>,
+    surface: WlSurface,
+    layer: Layer,
+    attributes: &WindowAttributesWayland,
+    size: LogicalSize<u32>,
+    size_requested: bool,
+) -> LayerSurface {
+    let output = attributes.output.as_ref().and_then(|monitor| {
+        monitor.cast_ref::<output::MonitorHandle>().map(|handle| &handle.proxy)
+    });
+    let namespace = attributes.name.as_ref().map(|name| name.general.clone());
+    let layer_surface = layer_shell.create_layer_surface(
+        queue_handle,
+        surface,
+        layer_to_sctk(layer),
+        namespace,
+        output,
+    );
+
+    let anchor = SctkAnchor::from_bits_truncate(attributes.anchor.bits());
+    layer_surface.set_anchor(anchor);
+    layer_surface.set_exclusive_zone(attributes.exclusive_zone);
+    let (top, right, bottom, left) = attributes.margin;
+    layer_surface.set_margin(top, right, bottom, left);
+    layer_surface.set_keyboard_interactivity(keyboard_interactivity_to_sctk(
+        attributes.keyboard_interactivity,
+    ));
+
+    let stretched = |edges| !size_requested && anchor.contains(edges);
+    let width = if stretched(SctkAnchor::LEFT | SctkAnchor::RIGHT) { 0 } else { size.width };
+    let height = if stretched(SctkAnchor::TOP | SctkAnchor::BOTTOM) { 0 } else { size.height };
+    layer_surface.set_size(width, height);
+
+    layer_surface
+}
+
+fn popup_anchor_to_xdg(anchor: PopupAnchor) -> XdgAnchor {
+    match anchor {
+        PopupAnchor::Center => XdgAnchor::None,
+        PopupAnchor::Top => XdgAnchor::Top,
+        PopupAnchor::Bottom => XdgAnchor::Bottom,
+        PopupAnchor::Left => XdgAnchor::Left,
+        PopupAnchor::Right => XdgAnchor::Right,
+        PopupAnchor::TopLeft => XdgAnchor::TopLeft,
+        PopupAnchor::BottomLeft => XdgAnchor::BottomLeft,
+        PopupAnchor::TopRight => XdgAnchor::TopRight,
+        PopupAnchor::BottomRight => XdgAnchor::BottomRight,
+    }
+}
+
+fn popup_gravity_to_xdg(gravity: PopupAnchor) -> Gravity {
+    match gravity {
+        PopupAnchor::Center => Gravity::None,
+        PopupAnchor::Top => Gravity::Top,
+        PopupAnchor::Bottom => Gravity::Bottom,
+        PopupAnchor::Left => Gravity::Left,
+        PopupAnchor::Right => Gravity::Right,
+        PopupAnchor::TopLeft => Gravity::TopLeft,
+        PopupAnchor::BottomLeft => Gravity::BottomLeft,
+        PopupAnchor::TopRight => Gravity::TopRight,
+        PopupAnchor::BottomRight => Gravity::BottomRight,
+    }
+}
+
+fn layer_to_sctk(layer: Layer) -> SctkLayer {
+    match layer {
+        Layer::Background => SctkLayer::Background,
+        Layer::Bottom => SctkLayer::Bottom,
+        Layer::Top => SctkLayer::Top,
+        Layer::Overlay => SctkLayer::Overlay,
+    }
+}
+
+fn keyboard_interactivity_to_sctk(
+    interactivity: KeyboardInteractivity,
+) -> SctkKeyboardInteractivity {
+    match interactivity {
+        KeyboardInteractivity::None => SctkKeyboardInteractivity::None,
+        KeyboardInteractivity::Exclusive => SctkKeyboardInteractivity::Exclusive,
+        KeyboardInteractivity::OnDemand => SctkKeyboardInteractivity::OnDemand,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dpi::{LogicalInsets, LogicalPosition};
+
+    use super::popup_anchor_position;
+
+    #[test]
+    fn popup_anchor_is_offset_by_the_parent_frame() {
+        let position = LogicalPosition::new(10, 20);
+        assert_eq!(popup_anchor_position(position, LogicalInsets::new(0, 0, 0, 0)), position);
+        // A titlebar of 35 and borders of 5.
+        let insets = LogicalInsets::new(35, 5, 5, 5);
+        assert_eq!(popup_anchor_position(position, insets), LogicalPosition::new(15, 55));
+    }
+}
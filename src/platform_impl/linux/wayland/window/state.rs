@@ -5,7 +5,7 @@ use std::sync::{Arc, Mutex, Weak};
 use std::time::Duration;
 
 use ahash::HashSet;
-use dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Size};
+use dpi::{LogicalInsets, LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Size};
 use sctk::compositor::{CompositorState, Region, SurfaceData, SurfaceDataExt};
 use sctk::reexports::client::backend::ObjectId;
 use sctk::reexports::client::protocol::wl_seat::WlSeat;
@@ -13,35 +13,47 @@ use sctk::reexports::client::protocol::wl_shm::WlShm;
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::client::{Proxy, QueueHandle};
 use sctk::reexports::csd_frame::{
-    DecorationsFrame, FrameAction, FrameClick, ResizeEdge, WindowState as XdgWindowState,
+    DecorationsFrame, FrameAction, FrameClick, ResizeEdge, WindowManagerCapabilities,
+    WindowState as XdgWindowState,
 };
 use sctk::reexports::protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1::WpFractionalScaleV1;
 use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::ZwpTextInputV3;
 use sctk::reexports::protocols::wp::viewporter::client::wp_viewport::WpViewport;
 use sctk::reexports::protocols::xdg::shell::client::xdg_toplevel::ResizeEdge as XdgResizeEdge;
 use sctk::seat::pointer::{PointerDataExt, ThemedPointer};
+use sctk::shell::wlr_layer::{LayerSurface, LayerSurfaceConfigure};
+use sctk::shell::xdg::popup::{Popup, PopupConfigure};
 use sctk::shell::xdg::window::{DecorationMode, Window, WindowConfigure};
-use sctk::shell::xdg::XdgSurface;
+use sctk::shell::xdg::{XdgPositioner, XdgSurface};
 use sctk::shell::WaylandSurface;
 use sctk::shm::slot::SlotPool;
 use sctk::shm::Shm;
 use sctk::subcompositor::SubcompositorState;
 use tracing::{info, warn};
 use wayland_protocols_plasma::blur::client::org_kde_kwin_blur::OrgKdeKwinBlur;
-use winit_core::cursor::{CursorIcon, CustomCursor as CoreCustomCursor};
+use winit_core::application::wayland::DecorationMode as CoreDecorationMode;
+use winit_core::cursor::{CursorIcon, CursorImage, CustomCursor as CoreCustomCursor};
 use winit_core::error::{NotSupportedError, RequestError};
-use winit_core::window::{CursorGrabMode, ImePurpose, ResizeDirection, Theme, WindowId};
+use winit_core::icon::Icon;
+use winit_core::window::{
+    CursorGrabMode, ImeHint, ImePurpose, ResizeDirection, Theme, WindowButtons, WindowCapabilities,
+    WindowId,
+};
 
+use crate::platform::wayland::DecorationPreference;
 use crate::platform_impl::wayland::event_loop::OwnedDisplayHandle;
 use crate::platform_impl::wayland::logical_to_physical_rounded;
 use crate::platform_impl::wayland::seat::{
-    PointerConstraintsState, WinitPointerData, WinitPointerDataExt, ZwpTextInputV3Ext,
+    DragIcon, PointerConstraintsState, WinitPointerData, WinitPointerDataExt, ZwpTextInputV3Ext,
 };
 use crate::platform_impl::wayland::state::{WindowCompositorUpdate, WinitState};
 use crate::platform_impl::wayland::types::cursor::{
     CustomCursor, SelectedCursor, WaylandCustomCursor,
 };
 use crate::platform_impl::wayland::types::kwin_blur::KWinBlurManager;
+use crate::platform_impl::wayland::types::xdg_toplevel_icon::{
+    ToplevelIcon, XdgToplevelIconManager,
+};
 
 #[cfg(feature = "sctk-adwaita")]
 pub type WinitFrame = sctk_adwaita::AdwaitaFrame<WinitState>;
@@ -51,6 +63,38 @@ pub type WinitFrame = sctk::shell::xdg::fallback_frame::FallbackFrame<WinitState
 // Minimum window surface size.
 const MIN_WINDOW_SIZE: LogicalSize<u32> = LogicalSize::new(2, 1);
 
+/// The shell surface giving the role to the window surface.
+#[derive(Debug, Clone)]
+pub enum ShellSurface {
+    /// A regular window.
+    Toplevel(Window),
+    /// A layer shell surface, for panels, docks and overlays.
+    Layer(LayerSurface),
+    /// A popup, for menus, tooltips and dropdowns.
+    Popup(Popup),
+}
+
+impl ShellSurface {
+    /// The regular window, the requests specific to them are ignored for other surfaces.
+    #[inline]
+    pub fn toplevel(&self) -> Option<&Window> {
+        match self {
+            Self::Toplevel(window) => Some(window),
+            Self::Layer(_) | Self::Popup(_) => None,
+        }
+    }
+}
+
+impl WaylandSurface for ShellSurface {
+    fn wl_surface(&self) -> &WlSurface {
+        match self {
+            Self::Toplevel(window) => window.wl_surface(),
+            Self::Layer(layer) => layer.wl_surface(),
+            Self::Popup(popup) => popup.wl_surface(),
+        }
+    }
+}
+
 /// The state of the window which is being updated from the [`WinitState`].
 #[derive(Debug)]
 pub struct WindowState {
@@ -66,6 +110,15 @@ pub struct WindowState {
     /// The last received configure.
     pub last_configure: Option<WindowConfigure>,
 
+    /// The last received configure of the layer shell surface.
+    last_layer_configure: Option<LayerSurfaceConfigure>,
+
+    /// The last received configure of the popup.
+    last_popup_configure: Option<PopupConfigure>,
+
+    /// The positioner of the popup, to reposition it when resized.
+    popup_positioner: Option<XdgPositioner>,
+
     /// The pointers observed on the window.
     pub pointers: Vec<Weak<ThemedPointer<WinitPointerData>>>,
 
@@ -89,6 +142,9 @@ pub struct WindowState {
     /// Whether the frame is resizable.
     resizable: bool,
 
+    /// The buttons enabled on the frame.
+    enabled_buttons: WindowButtons,
+
     // NOTE: we can't use simple counter, since it's racy when seat getting destroyed and new
     // is created, since add/removed stuff could be delivered a bit out of order.
     /// Seats that has keyboard focus on that window.
@@ -112,6 +168,9 @@ pub struct WindowState {
     /// The current IME purpose.
     ime_purpose: ImePurpose,
 
+    /// The current IME hints.
+    ime_hints: ImeHint,
+
     /// The text inputs observed on the window.
     text_inputs: Vec<ZwpTextInputV3>,
 
@@ -124,6 +183,9 @@ pub struct WindowState {
     /// Whether we should decorate the frame.
     decorate: bool,
 
+    /// The decoration mode requested by the user.
+    decoration_preference: DecorationPreference,
+
     /// Min size.
     min_surface_size: LogicalSize<u32>,
     max_surface_size: Option<LogicalSize<u32>>,
@@ -145,13 +207,17 @@ pub struct WindowState {
     blur: Option<OrgKdeKwinBlur>,
     blur_manager: Option<KWinBlurManager>,
 
+    /// The icon of the window, kept alive while it's in use.
+    window_icon: Option<ToplevelIcon>,
+    toplevel_icon_manager: Option<XdgToplevelIconManager>,
+
     /// Whether the client side decorations have pending move operations.
     ///
     /// The value is the serial of the event triggered moved.
     has_pending_move: Option<u32>,
 
-    /// The underlying SCTK window.
-    pub window: Window,
+    /// The underlying SCTK shell surface.
+    pub window: ShellSurface,
 
     // NOTE: The spec says that destroying parent(`window` in our case), will unmap the
     // subsurfaces. Thus to achieve atomic unmap of the client, drop the decorations
@@ -168,7 +234,7 @@ impl WindowState {
         queue_handle: &QueueHandle<WinitState>,
         winit_state: &WinitState,
         initial_size: Size,
-        window: Window,
+        window: ShellSurface,
         theme: Option<Theme>,
     ) -> Self {
         let compositor = winit_state.compositor_state.clone();
@@ -185,6 +251,8 @@ impl WindowState {
         Self {
             blur: None,
             blur_manager: winit_state.kwin_blur_manager.clone(),
+            window_icon: None,
+            toplevel_icon_manager: winit_state.xdg_toplevel_icon_manager.clone(),
             compositor,
             handle,
             csd_fails: false,
@@ -192,6 +260,7 @@ impl WindowState {
             selected_cursor: Default::default(),
             cursor_visible: true,
             decorate: true,
+            decoration_preference: DecorationPreference::default(),
             fractional_scale,
             frame: None,
             frame_callback_state: FrameCallbackState::None,
@@ -199,13 +268,18 @@ impl WindowState {
             has_pending_move: None,
             ime_allowed: false,
             ime_purpose: ImePurpose::Normal,
+            ime_hints: ImeHint::empty(),
             last_configure: None,
+            last_layer_configure: None,
+            last_popup_configure: None,
+            popup_positioner: None,
             max_surface_size: None,
             min_surface_size: MIN_WINDOW_SIZE,
             pointer_constraints,
             pointers: Default::default(),
             queue_handle: queue_handle.clone(),
             resizable: true,
+            enabled_buttons: WindowButtons::all(),
             scale_factor: 1.,
             shm: winit_state.shm.wl_shm().clone(),
             custom_cursor_pool: winit_state.custom_cursor_pool.clone(),
@@ -273,13 +347,14 @@ impl WindowState {
             self.stateless_size = self.size;
         }
 
-        if let Some(subcompositor) = subcompositor.as_ref().filter(|_| {
+        let toplevel = self.window.toplevel().filter(|_| {
             configure.decoration_mode == DecorationMode::Client
                 && self.frame.is_none()
                 && !self.csd_fails
-        }) {
+        });
+        if let (Some(subcompositor), Some(window)) = (subcompositor.as_ref(), toplevel) {
             match WinitFrame::new(
-                &self.window,
+                window,
                 shm,
                 #[cfg(feature = "sctk-adwaita")]
                 self.compositor.clone(),
@@ -306,10 +381,12 @@ impl WindowState {
         }
 
         let stateless = Self::is_stateless(&configure);
+        let frame_capabilities = self.frame_capabilities(configure.capabilities);
 
         let (mut new_size, constrain) = if let Some(frame) = self.frame.as_mut() {
             // Configure the window states.
             frame.update_state(configure.state);
+            frame.update_wm_capabilities(frame_capabilities);
 
             match configure.new_size {
                 (Some(width), Some(height)) => {
@@ -364,6 +441,57 @@ impl WindowState {
         }
     }
 
+    /// Apply the configure of the layer shell surface.
+    ///
+    /// The compositor lets the client pick the size along the axes where it's zero.
+    pub fn configure_layer(&mut self, configure: LayerSurfaceConfigure) -> bool {
+        if let Some(initial_size) = self.initial_size.take() {
+            self.size = initial_size.to_logical(self.scale_factor());
+            self.stateless_size = self.size;
+        }
+
+        let (width, height) = configure.new_size;
+        let new_size = LogicalSize::new(
+            if width == 0 { self.size.width } else { width },
+            if height == 0 { self.size.height } else { height },
+        );
+
+        let initial_configure = self.last_layer_configure.is_none();
+        self.last_layer_configure = Some(configure);
+
+        if initial_configure || new_size != self.surface_size() {
+            self.resize(new_size);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Apply the configure of the popup.
+    ///
+    /// The compositor always picks the size of popups.
+    pub fn configure_popup(&mut self, configure: PopupConfigure) -> bool {
+        self.initial_size = None;
+
+        let new_size =
+            LogicalSize::new(configure.width.max(1) as u32, configure.height.max(1) as u32);
+
+        let initial_configure = self.last_popup_configure.is_none();
+        self.last_popup_configure = Some(configure);
+
+        if initial_configure || new_size != self.surface_size() {
+            self.resize(new_size);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Set the positioner the popup was created with.
+    pub fn set_popup_positioner(&mut self, positioner: XdgPositioner) {
+        self.popup_positioner = Some(positioner);
+    }
+
     /// Compute the bounds for the surface size of the surface.
     fn surface_size_bounds(
         &self,
@@ -392,7 +520,7 @@ impl WindowState {
 
     /// Start interacting drag resize.
     pub fn drag_resize_window(&self, direction: ResizeDirection) -> Result<(), RequestError> {
-        let xdg_toplevel = self.window.xdg_toplevel();
+        let xdg_toplevel = self.toplevel()?.xdg_toplevel();
 
         // TODO(kchibisov) handle touch serials.
         self.apply_on_pointer(|_, data| {
@@ -404,9 +532,24 @@ impl WindowState {
         Ok(())
     }
 
+    /// Grab the input for the popup, with the latest button press of the seat on the window.
+    ///
+    /// The grab is skipped when the seat didn't press any button on the window.
+    pub fn grab_popup(&self, popup: &Popup, seat: &WlSeat) {
+        let serial = self.pointers.iter().filter_map(Weak::upgrade).find_map(|pointer| {
+            let data = pointer.pointer().winit_data();
+            (data.seat() == seat).then(|| data.button_serial()).flatten()
+        });
+
+        match serial {
+            Some(serial) => popup.xdg_popup().grab(seat, serial),
+            None => warn!("Not grabbing the popup input without a button press on its parent"),
+        }
+    }
+
     /// Start the window drag.
     pub fn drag_window(&self) -> Result<(), RequestError> {
-        let xdg_toplevel = self.window.xdg_toplevel();
+        let xdg_toplevel = self.toplevel()?.xdg_toplevel();
         // TODO(kchibisov) handle touch serials.
         self.apply_on_pointer(|_, data| {
             let serial = data.latest_button_serial();
@@ -429,10 +572,11 @@ impl WindowState {
         window_id: WindowId,
         updates: &mut Vec<WindowCompositorUpdate>,
     ) -> Option<bool> {
+        let window = self.window.toplevel()?;
         match self.frame.as_mut()?.on_click(timestamp, click, pressed)? {
-            FrameAction::Minimize => self.window.set_minimized(),
-            FrameAction::Maximize => self.window.set_maximized(),
-            FrameAction::UnMaximize => self.window.unset_maximized(),
+            FrameAction::Minimize => window.set_minimized(),
+            FrameAction::Maximize => window.set_maximized(),
+            FrameAction::UnMaximize => window.unset_maximized(),
             FrameAction::Close => WinitState::queue_close(updates, window_id),
             FrameAction::Move => self.has_pending_move = Some(serial),
             FrameAction::Resize(edge) => {
@@ -448,9 +592,9 @@ impl WindowState {
                     ResizeEdge::BottomRight => XdgResizeEdge::BottomRight,
                     _ => return None,
                 };
-                self.window.resize(seat, serial, edge);
+                window.resize(seat, serial, edge);
             },
-            FrameAction::ShowMenu(x, y) => self.window.show_window_menu(seat, serial, (x, y)),
+            FrameAction::ShowMenu(x, y) => window.show_window_menu(seat, serial, (x, y)),
             _ => (),
         };
 
@@ -480,7 +624,9 @@ impl WindowState {
             // If we have a cursor change, that means that cursor is over the decorations,
             // so try to apply move.
             if let Some(serial) = cursor.is_some().then_some(serial).flatten() {
-                self.window.move_(seat, serial);
+                if let Some(window) = self.window.toplevel() {
+                    window.move_(seat, serial);
+                }
                 None
             } else {
                 cursor
@@ -522,6 +668,101 @@ impl WindowState {
         true
     }
 
+    /// Set the buttons shown on the frame, among the ones supported by the compositor.
+    pub fn set_enabled_buttons(&mut self, buttons: WindowButtons) -> bool {
+        if self.enabled_buttons == buttons {
+            return false;
+        }
+
+        self.enabled_buttons = buttons;
+        let capabilities = self
+            .last_configure
+            .as_ref()
+            .map(|configure| configure.capabilities)
+            .unwrap_or(WindowManagerCapabilities::all());
+        let capabilities = self.frame_capabilities(capabilities);
+        if let Some(frame) = self.frame.as_mut() {
+            frame.update_wm_capabilities(capabilities);
+        }
+
+        true
+    }
+
+    #[inline]
+    pub fn enabled_buttons(&self) -> WindowButtons {
+        self.enabled_buttons
+    }
+
+    /// The capabilities of the compositor, with the buttons disabled by the user removed.
+    fn frame_capabilities(
+        &self,
+        mut capabilities: WindowManagerCapabilities,
+    ) -> WindowManagerCapabilities {
+        if !self.enabled_buttons.contains(WindowButtons::MINIMIZE) {
+            capabilities.remove(WindowManagerCapabilities::MINIMIZE);
+        }
+        if !self.enabled_buttons.contains(WindowButtons::MAXIMIZE) {
+            capabilities.remove(WindowManagerCapabilities::MAXIMIZE);
+        }
+        capabilities
+    }
+
+    /// The decoration mode negotiated for the toplevel.
+    pub fn decoration_mode(&self) -> Option<CoreDecorationMode> {
+        self.window.toplevel()?;
+        self.last_configure.as_ref().map(|configure| match configure.decoration_mode {
+            DecorationMode::Client => CoreDecorationMode::Client,
+            DecorationMode::Server => CoreDecorationMode::Server,
+        })
+    }
+
+    /// The space taken by the frame around the surface.
+    pub fn frame_insets(&self) -> LogicalInsets<u32> {
+        let Some(frame) = self.frame.as_ref() else {
+            return LogicalInsets::new(0, 0, 0, 0);
+        };
+
+        // The frame is drawn at negative coordinates relative to the surface.
+        let (x, y) = frame.location();
+        let (left, top) = (x.unsigned_abs(), y.unsigned_abs());
+        let (width, height) = frame.add_borders(self.size.width, self.size.height);
+        let right = width.saturating_sub(self.size.width + left);
+        let bottom = height.saturating_sub(self.size.height + top);
+        LogicalInsets::new(top, left, bottom, right)
+    }
+
+    /// The window management actions supported by the compositor for the toplevel.
+    pub fn window_capabilities(&self) -> WindowCapabilities {
+        if self.window.toplevel().is_none() {
+            return WindowCapabilities::empty();
+        }
+
+        // The compositor supports all of them until it tells otherwise.
+        let Some(configure) = self.last_configure.as_ref() else {
+            return WindowCapabilities::all();
+        };
+
+        let mut capabilities = WindowCapabilities::empty();
+        let advertised = configure.capabilities;
+        capabilities.set(
+            WindowCapabilities::WINDOW_MENU,
+            advertised.contains(WindowManagerCapabilities::WINDOW_MENU),
+        );
+        capabilities.set(
+            WindowCapabilities::MAXIMIZE,
+            advertised.contains(WindowManagerCapabilities::MAXIMIZE),
+        );
+        capabilities.set(
+            WindowCapabilities::FULLSCREEN,
+            advertised.contains(WindowManagerCapabilities::FULLSCREEN),
+        );
+        capabilities.set(
+            WindowCapabilities::MINIMIZE,
+            advertised.contains(WindowManagerCapabilities::MINIMIZE),
+        );
+        capabilities
+    }
+
     /// Whether the window is focused by any seat.
     #[inline]
     pub fn has_focus(&self) -> bool {
@@ -544,10 +785,17 @@ impl WindowState {
     #[inline]
     pub fn is_configured(&self) -> bool {
         self.last_configure.is_some()
+            || self.last_layer_configure.is_some()
+            || self.last_popup_configure.is_some()
     }
 
     #[inline]
     pub fn is_decorated(&mut self) -> bool {
+        // Layer shell surfaces and popups are never decorated.
+        if self.window.toplevel().is_none() {
+            return false;
+        }
+
         let csd = self
             .last_configure
             .as_ref()
@@ -632,8 +880,28 @@ impl WindowState {
 
     /// Try to resize the window when the user can do so.
     pub fn request_surface_size(&mut self, surface_size: Size) -> PhysicalSize<u32> {
-        if self.last_configure.as_ref().map(Self::is_stateless).unwrap_or(true) {
-            self.resize(surface_size.to_logical(self.scale_factor()))
+        // The popup is repositioned with its new size, which needs `xdg_wm_base` v3, otherwise
+        // the compositor keeps placing it with the old size.
+        let resizable = match &self.window {
+            ShellSurface::Popup(popup) => popup.xdg_popup().version() >= 3,
+            ShellSurface::Toplevel(_) | ShellSurface::Layer(_) => true,
+        };
+
+        if resizable && self.last_configure.as_ref().map(Self::is_stateless).unwrap_or(true) {
+            let surface_size = surface_size.to_logical(self.scale_factor());
+            match &self.window {
+                ShellSurface::Layer(layer) => {
+                    layer.set_size(surface_size.width, surface_size.height)
+                },
+                ShellSurface::Popup(popup) => {
+                    if let Some(positioner) = self.popup_positioner.as_ref() {
+                        positioner.set_size(surface_size.width as i32, surface_size.height as i32);
+                        popup.reposition(positioner, 0);
+                    }
+                },
+                ShellSurface::Toplevel(_) => (),
+            }
+            self.resize(surface_size)
         }
 
         logical_to_physical_rounded(self.surface_size(), self.scale_factor())
@@ -667,12 +935,14 @@ impl WindowState {
         self.reload_transparency_hint();
 
         // Set the window geometry.
-        self.window.xdg_surface().set_window_geometry(
-            x,
-            y,
-            outer_size.width as i32,
-            outer_size.height as i32,
-        );
+        if let Some(window) = self.window.toplevel() {
+            window.xdg_surface().set_window_geometry(
+                x,
+                y,
+                outer_size.width as i32,
+                outer_size.height as i32,
+            );
+        }
 
         // Update the target viewport, this is used if and only if fractional scaling is in use.
         if let Some(viewport) = self.viewport.as_ref() {
@@ -724,6 +994,16 @@ impl WindowState {
         self.selected_cursor = SelectedCursor::Custom(cursor);
     }
 
+    /// Create the surface showing the image dragged out of the window.
+    pub(crate) fn drag_icon(&self, image: CursorImage) -> DragIcon {
+        let image = {
+            let mut pool = self.custom_cursor_pool.lock().unwrap();
+            CustomCursor::new(&mut pool, &WaylandCustomCursor(image))
+        };
+
+        DragIcon::new(self.compositor.create_surface(&self.queue_handle), image)
+    }
+
     fn apply_custom_cursor(&self, cursor: &CustomCursor) {
         self.apply_on_pointer(|pointer, data| {
             let surface = pointer.surface();
@@ -775,7 +1055,9 @@ impl WindowState {
             .unwrap_or(size);
 
         self.min_surface_size = size;
-        self.window.set_min_size(Some(size.into()));
+        if let Some(window) = self.window.toplevel() {
+            window.set_min_size(Some(size.into()));
+        }
     }
 
     /// Set maximum inner window size.
@@ -788,7 +1070,9 @@ impl WindowState {
         });
 
         self.max_surface_size = size;
-        self.window.set_max_size(size.map(Into::into));
+        if let Some(window) = self.window.toplevel() {
+            window.set_max_size(size.map(Into::into));
+        }
     }
 
     /// Set the CSD theme.
@@ -885,11 +1169,13 @@ impl WindowState {
     }
 
     pub fn show_window_menu(&self, position: LogicalPosition<u32>) {
+        let Some(window) = self.window.toplevel() else { return };
+
         // TODO(kchibisov) handle touch serials.
         self.apply_on_pointer(|_, data| {
             let serial = data.latest_button_serial();
             let seat = data.seat();
-            self.window.show_window_menu(seat, serial, position.into());
+            window.show_window_menu(seat, serial, position.into());
         });
     }
 
@@ -932,6 +1218,13 @@ impl WindowState {
         }
     }
 
+    /// Set the decoration mode requested by the user, this must be done before any other
+    /// decoration request.
+    #[inline]
+    pub fn set_decoration_preference(&mut self, preference: DecorationPreference) {
+        self.decoration_preference = preference;
+    }
+
     /// Whether show or hide client side decorations.
     #[inline]
     pub fn set_decorate(&mut self, decorate: bool) {
@@ -941,12 +1234,17 @@ impl WindowState {
 
         self.decorate = decorate;
 
+        let Some(window) = self.window.toplevel() else { return };
         match self.last_configure.as_ref().map(|configure| configure.decoration_mode) {
             Some(DecorationMode::Server) if !self.decorate => {
                 // To disable decorations we should request client and hide the frame.
-                self.window.request_decoration_mode(Some(DecorationMode::Client))
+                window.request_decoration_mode(Some(DecorationMode::Client))
+            },
+            _ if self.decorate
+                && self.decoration_preference == DecorationPreference::PreferServer =>
+            {
+                window.request_decoration_mode(Some(DecorationMode::Server))
             },
-            _ if self.decorate => self.window.request_decoration_mode(Some(DecorationMode::Server)),
             _ => (),
         }
 
@@ -978,7 +1276,7 @@ impl WindowState {
             applied = true;
             if allowed {
                 text_input.enable();
-                text_input.set_content_type_by_purpose(self.ime_purpose);
+                text_input.set_content_type_by_purpose(self.ime_purpose, self.ime_hints);
             } else {
                 text_input.disable();
             }
@@ -1006,7 +1304,30 @@ impl WindowState {
         self.ime_purpose = purpose;
 
         for text_input in &self.text_inputs {
-            text_input.set_content_type_by_purpose(purpose);
+            text_input.set_content_type_by_purpose(purpose, self.ime_hints);
+            text_input.commit();
+        }
+    }
+
+    /// Set the IME hints.
+    pub fn set_ime_hints(&mut self, hints: ImeHint) {
+        self.ime_hints = hints;
+
+        for text_input in &self.text_inputs {
+            text_input.set_content_type_by_purpose(self.ime_purpose, hints);
+            text_input.commit();
+        }
+    }
+
+    /// Get the IME hints.
+    pub fn ime_hints(&self) -> ImeHint {
+        self.ime_hints
+    }
+
+    /// Set the text around the IME cursor.
+    pub fn set_ime_surrounding_text(&self, text: &str, cursor: usize, anchor: usize) {
+        for text_input in &self.text_inputs {
+            text_input.set_surrounding_text_truncated(text, cursor, anchor);
             text_input.commit();
         }
     }
@@ -1029,6 +1350,12 @@ impl WindowState {
         if let Some(frame) = self.frame.as_mut() {
             frame.set_scaling_factor(scale_factor);
         }
+
+        // Draw the icon again for the new scale.
+        let icon = self.window_icon.as_ref().filter(|icon| icon.scale() != self.icon_scale());
+        if let Some(icon) = icon.map(|icon| icon.source().clone()) {
+            self.set_window_icon(Some(&icon));
+        }
     }
 
     /// Make window background blurred
@@ -1048,6 +1375,31 @@ impl WindowState {
         }
     }
 
+    /// Set the icon of the window, `None` resets it to the default one.
+    pub fn set_window_icon(&mut self, icon: Option<&Icon>) {
+        let Some(window) = self.window.toplevel() else { return };
+        let Some(icon_manager) = self.toplevel_icon_manager.as_ref() else {
+            info!("Toplevel icon manager unavailable, unable to change the window icon");
+            return;
+        };
+
+        let scale = self.icon_scale();
+        let mut pool = self.custom_cursor_pool.lock().unwrap();
+        // Replace the old icon only once the new one is set.
+        self.window_icon = icon_manager.set_icon(
+            window.xdg_toplevel(),
+            icon,
+            scale,
+            &mut pool,
+            &self.queue_handle,
+        );
+    }
+
+    /// The integer scale of the icon buffers, rounded up so the icon is scaled down.
+    fn icon_scale(&self) -> u32 {
+        self.scale_factor.ceil() as u32
+    }
+
     /// Set the window title to a new value.
     ///
     /// This will automatically truncate the title to something meaningful.
@@ -1067,7 +1419,9 @@ impl WindowState {
             frame.set_title(&title);
         }
 
-        self.window.set_title(&title);
+        if let Some(window) = self.window.toplevel() {
+            window.set_title(&title);
+        }
         self.title = title;
     }
 
@@ -1094,6 +1448,16 @@ impl WindowState {
         }
     }
 
+    /// The regular window, or an error for layer shell surfaces and popups.
+    fn toplevel(&self) -> Result<&Window, RequestError> {
+        self.window.toplevel().ok_or_else(|| {
+            NotSupportedError::new(
+                "the request is not supported on layer shell surfaces and popups",
+            )
+            .into()
+        })
+    }
+
     /// Get the cached title.
     #[inline]
     pub fn title(&self) -> &str {
@@ -13,7 +13,7 @@ macro_rules! atom_manager {
 
         /// Indices into the `Atoms` struct.
         #[derive(Copy, Clone, Debug)]
-        #[allow(non_camel_case_types)]
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
         pub enum AtomName {
             $($name,)*
         }
@@ -34,6 +34,12 @@ macro_rules! atom_manager {
 atom_manager! {
     // General Use Atoms
     CARD32,
+    CLIPBOARD,
+    INCR,
+    MULTIPLE,
+    SAVE_TARGETS,
+    TARGETS,
+    TIMESTAMP,
     UTF8_STRING,
     WM_CHANGE_STATE,
     WM_CLIENT_MACHINE,
@@ -43,6 +49,11 @@ atom_manager! {
     XIM_SERVERS,
 
     // Assorted ICCCM Atoms
+    _NET_WM_ACTION_FULLSCREEN,
+    _NET_WM_ACTION_MAXIMIZE_HORZ,
+    _NET_WM_ACTION_MAXIMIZE_VERT,
+    _NET_WM_ACTION_MINIMIZE,
+    _NET_WM_ALLOWED_ACTIONS,
     _NET_WM_ICON,
     _NET_WM_MOVERESIZE,
     _NET_WM_NAME,
@@ -87,14 +98,28 @@ atom_manager! {
     XdndDrop,
     XdndPosition,
     XdndStatus,
-    XdndActionPrivate,
+    XdndActionCopy,
+    XdndActionMove,
+    XdndActionLink,
     XdndSelection,
     XdndFinished,
     XdndTypeList,
+    XdndProxy,
     TextUriList: b"text/uri-list",
     None: b"None",
 
+    // XInput Valuator Labels
+    AbsPressure: b"Abs Pressure",
+    AbsDistance: b"Abs Distance",
+    AbsTiltX: b"Abs Tilt X",
+    AbsTiltY: b"Abs Tilt Y",
+    AbsWheel: b"Abs Wheel",
+
+    // XInput Device Properties
+    DeviceProductId: b"Device Product ID",
+
     // Miscellaneous Atoms
+    _GTK_SHOW_WINDOW_MENU,
     _GTK_THEME_VARIANT,
     _MOTIF_WM_HINTS,
     _NET_ACTIVE_WINDOW,
@@ -103,7 +128,8 @@ atom_manager! {
     _NET_SUPPORTED,
     _NET_SUPPORTING_WM_CHECK,
     _XEMBED,
-    _XSETTINGS_SETTINGS
+    _XSETTINGS_SETTINGS,
+    _WINIT_SELECTION
 }
 
 impl Index<AtomName> for Atoms {
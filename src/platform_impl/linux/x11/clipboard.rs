@@ -0,0 +1,707 @@
+//! X11 clipboard and primary selection handling, also used to read the dragged data.
+//!
+//! The selection is owned by, and converted into, a hidden window that's only used for that, so
+//! the clipboard doesn't depend on the windows of the application. See the ICCCM for the details
+//! of the protocol: <https://x.org/releases/X11R7.6/doc/xorg-docs/specs/ICCCM/icccm.html#use_of_selection_atoms>
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::ffi::c_int;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use winit_core::clipboard::{
+    Clipboard as CoreClipboard, ClipboardContents, ClipboardData, ClipboardError, ClipboardEvent,
+    ClipboardKind, TEXT_MIME_TYPE,
+};
+use winit_core::error::RequestError;
+use winit_core::event_loop::AsyncRequestSerial;
+use x11_dl::xfixes::XFixesSelectionNotifyEvent;
+use x11_dl::xlib::{
+    self, XPropertyEvent, XSelectionClearEvent, XSelectionEvent, XSelectionRequestEvent,
+};
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::xfixes::{self, SelectionEventMask};
+use x11rb::protocol::xproto::{self, ConnectionExt as _};
+
+use super::atoms::*;
+use super::{CookieResultExt, WakeSender, X11Error, XConnection};
+
+/// How long the owner of the selection has to answer before the request is considered failed.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The size of the chunks of the `INCR` transfers we serve.
+const INCR_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug)]
+enum RequestKind {
+    Data { mime_type: String, target: xproto::Atom },
+    MimeTypes,
+}
+
+#[derive(Debug)]
+struct Request {
+    serial: AsyncRequestSerial,
+    kind: RequestKind,
+    /// When the conversion was requested, or the last chunk of an `INCR` transfer received.
+    started: Instant,
+    /// The data received so far when the owner uses the `INCR` mechanism.
+    incr: Option<Vec<u8>>,
+}
+
+/// Our contents being sent to another client with the `INCR` mechanism.
+///
+/// A chunk is written each time the requestor deletes the property to read the previous one, and
+/// the transfer ends with a zero-length chunk.
+#[derive(Debug)]
+struct Transfer {
+    requestor: xproto::Window,
+    property: xproto::Atom,
+    target: xproto::Atom,
+    data: Vec<u8>,
+    /// The length of the data written so far.
+    sent: usize,
+    /// When the requestor last read a chunk.
+    updated: Instant,
+}
+
+#[derive(Debug)]
+pub(crate) struct Clipboard {
+    xconn: Arc<XConnection>,
+    /// `None` for the data dragged over the windows.
+    kind: Option<ClipboardKind>,
+    /// The selection atom, `CLIPBOARD`, `PRIMARY` or `XdndSelection`.
+    selection: xproto::Atom,
+    /// The hidden window owning the selection.
+    window: xproto::Window,
+    /// The contents we serve while we own the selection.
+    contents: RefCell<Option<ClipboardContents>>,
+    /// The pending reads, only the first one is converted at a time.
+    requests: RefCell<VecDeque<Request>>,
+    /// The `INCR` transfers of our contents to other clients.
+    transfers: RefCell<Vec<Transfer>>,
+    event_sender: WakeSender<ClipboardEvent>,
+}
+
+impl Clipboard {
+    pub(crate) fn new(
+        xconn: Arc<XConnection>,
+        kind: ClipboardKind,
+        event_sender: WakeSender<ClipboardEvent>,
+    ) -> Result<Self, X11Error> {
+        let selection = match kind {
+            ClipboardKind::Clipboard => xconn.atoms()[CLIPBOARD],
+            ClipboardKind::Primary => xproto::AtomEnum::PRIMARY.into(),
+        };
+        Self::with_selection(xconn, Some(kind), selection, event_sender)
+    }
+
+    /// The selection holding the data dragged over the windows, only used to read it.
+    pub(crate) fn drag(
+        xconn: Arc<XConnection>,
+        event_sender: WakeSender<ClipboardEvent>,
+    ) -> Result<Self, X11Error> {
+        let selection = xconn.atoms()[XdndSelection];
+        Self::with_selection(xconn, None, selection, event_sender)
+    }
+
+    fn with_selection(
+        xconn: Arc<XConnection>,
+        kind: Option<ClipboardKind>,
+        selection: xproto::Atom,
+        event_sender: WakeSender<ClipboardEvent>,
+    ) -> Result<Self, X11Error> {
+        let window = xconn.xcb_connection().generate_id()?;
+        let aux = xproto::CreateWindowAux::new().event_mask(xproto::EventMask::PROPERTY_CHANGE);
+        xconn.xcb_connection().create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            xconn.default_root().root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            xproto::WindowClass::INPUT_ONLY,
+            x11rb::COPY_FROM_PARENT,
+            &aux,
+        )?;
+
+        Ok(Self {
+            xconn,
+            kind,
+            selection,
+            window,
+            contents: Default::default(),
+            requests: Default::default(),
+            transfers: Default::default(),
+            event_sender,
+        })
+    }
+
+    /// Ask for XFixes notifications when the owner of the selection changes.
+    ///
+    /// Returns the type of the notification events, `None` when XFixes is not available.
+    pub(crate) fn select_owner_changes(&self) -> Option<c_int> {
+        let lib = self.xconn.xfixes.as_ref()?;
+        let display = self.xconn.display;
+
+        let (mut event_base, mut error_base) = (0, 0);
+        if unsafe { (lib.XFixesQueryExtension)(display, &mut event_base, &mut error_base) }
+            == xlib::False
+        {
+            return None;
+        }
+
+        // Selecting the input requires a version query, which is done by `libXfixes` itself.
+        let (mut major, mut minor) = (0, 0);
+        unsafe { (lib.XFixesQueryVersion)(display, &mut major, &mut minor) };
+        if major < 1 {
+            return None;
+        }
+
+        let mask = SelectionEventMask::SET_SELECTION_OWNER
+            | SelectionEventMask::SELECTION_WINDOW_DESTROY
+            | SelectionEventMask::SELECTION_CLIENT_CLOSE;
+        unsafe {
+            (lib.XFixesSelectSelectionInput)(
+                display,
+                self.window as xlib::Window,
+                self.selection as xlib::Atom,
+                u32::from(mask) as _,
+            )
+        };
+
+        Some(event_base + c_int::from(xfixes::SELECTION_NOTIFY_EVENT))
+    }
+
+    /// The owner of the selection changed.
+    ///
+    /// Returns `false` if the event isn't about this clipboard.
+    pub(crate) fn owner_changed(&self, xev: &XFixesSelectionNotifyEvent) -> bool {
+        let Some(kind) = self.kind else { return false };
+        if xev.window as xproto::Window != self.window
+            || xev.selection as xproto::Atom != self.selection
+        {
+            return false;
+        }
+
+        self.event_sender.send(ClipboardEvent::Changed { kind });
+        true
+    }
+
+    /// The property the selection is converted into.
+    fn property(&self) -> xproto::Atom {
+        self.xconn.atoms()[_WINIT_SELECTION]
+    }
+
+    /// The hidden window owning the selection.
+    pub(crate) fn window(&self) -> xproto::Window {
+        self.window
+    }
+
+    fn intern_atom(&self, name: &str) -> Result<xproto::Atom, X11Error> {
+        Ok(self.xconn.xcb_connection().intern_atom(false, name.as_bytes())?.reply()?.atom)
+    }
+
+    /// The target to request the data in the given MIME type with.
+    fn target_for(&self, mime_type: &str) -> Result<xproto::Atom, X11Error> {
+        if mime_type == TEXT_MIME_TYPE {
+            Ok(self.xconn.atoms()[UTF8_STRING])
+        } else {
+            self.intern_atom(mime_type)
+        }
+    }
+
+    /// The MIME type of the contents the target asks for.
+    fn mime_type_for(&self, target: xproto::Atom) -> Option<String> {
+        if target == self.xconn.atoms()[UTF8_STRING] {
+            return Some(TEXT_MIME_TYPE.to_owned());
+        }
+
+        let reply = self.xconn.xcb_connection().get_atom_name(target).ok()?.reply().ok()?;
+        String::from_utf8(reply.name).ok()
+    }
+
+    /// The targets we offer for the contents, see [`Self::mime_type_for`].
+    fn targets(&self, contents: &ClipboardContents) -> Vec<xproto::Atom> {
+        let mut targets = vec![self.xconn.atoms()[TARGETS]];
+        targets.extend(self.data_targets(contents));
+        targets
+    }
+
+    /// The targets of the data itself, without the special `TARGETS` one.
+    pub(crate) fn data_targets(&self, contents: &ClipboardContents) -> Vec<xproto::Atom> {
+        let atoms = self.xconn.atoms();
+        let mut targets = Vec::new();
+        for mime_type in contents.mime_types() {
+            if mime_type == TEXT_MIME_TYPE {
+                targets.push(atoms[UTF8_STRING]);
+            }
+            match self.intern_atom(mime_type) {
+                Ok(atom) => targets.push(atom),
+                Err(err) => tracing::warn!("Failed to intern clipboard target {mime_type}: {err}"),
+            }
+        }
+        targets
+    }
+
+    fn queue(&self, serial: AsyncRequestSerial, kind: RequestKind) {
+        let mut requests = self.requests.borrow_mut();
+        requests.push_back(Request { serial, kind, started: Instant::now(), incr: None });
+        if requests.len() == 1 {
+            self.convert(requests.front_mut().unwrap());
+        }
+    }
+
+    /// When the read being converted fails if the owner of the selection doesn't answer, or a
+    /// transfer to another client is dropped if it stops reading.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        let request = self.requests.borrow().front().map(|request| request.started);
+        let transfer = self.transfers.borrow().iter().map(|transfer| transfer.updated).min();
+        request.into_iter().chain(transfer).min().map(|started| started + TRANSFER_TIMEOUT)
+    }
+
+    /// Fail the read the owner of the selection didn't answer in time, so it doesn't block the
+    /// queue forever, and drop the transfers the requestors stopped reading.
+    pub(crate) fn expire_requests(&self) {
+        let expired = |started: Instant| started + TRANSFER_TIMEOUT <= Instant::now();
+        while self.requests.borrow().front().is_some_and(|request| expired(request.started)) {
+            tracing::warn!("The owner of the selection didn't answer in time");
+            self.finish(Err(ClipboardError::TransferFailed));
+        }
+
+        let mut transfers = self.transfers.borrow_mut();
+        while let Some(index) = transfers.iter().position(|transfer| expired(transfer.updated)) {
+            tracing::warn!("The requestor of the selection stopped reading it");
+            let transfer = transfers.swap_remove(index);
+            self.end_transfer(&transfers, transfer.requestor);
+        }
+    }
+
+    fn convert(&self, request: &mut Request) {
+        let target = match request.kind {
+            RequestKind::Data { target, .. } => target,
+            RequestKind::MimeTypes => self.xconn.atoms()[TARGETS],
+        };
+
+        request.started = Instant::now();
+        self.xconn
+            .xcb_connection()
+            .convert_selection(
+                self.window,
+                self.selection,
+                target,
+                self.property(),
+                self.xconn.timestamp(),
+            )
+            .expect_then_ignore_error("Failed to request the selection conversion");
+        if let Err(err) = self.xconn.flush_requests() {
+            tracing::warn!("Failed to flush the selection conversion: {err}");
+        }
+    }
+
+    /// Complete the first request and convert the next one.
+    fn finish(&self, result: Result<Vec<u8>, ClipboardError>) {
+        let mut requests = self.requests.borrow_mut();
+        let Some(request) = requests.pop_front() else { return };
+        self.complete(request, result);
+        if let Some(request) = requests.front_mut() {
+            self.convert(request);
+        }
+    }
+
+    fn complete(&self, request: Request, result: Result<Vec<u8>, ClipboardError>) {
+        let serial = request.serial;
+        let event = match request.kind {
+            RequestKind::Data { mime_type, .. } => ClipboardEvent::Read {
+                serial,
+                result: result.map(|data| ClipboardData { mime_type, data }),
+            },
+            RequestKind::MimeTypes => {
+                let atoms = self.xconn.atoms();
+                let data = result.unwrap_or_default();
+                let targets = data.chunks_exact(4).map(|atom| {
+                    xproto::Atom::from_ne_bytes(atom.try_into().expect("chunk of 4 bytes"))
+                });
+                let mut mime_types = Vec::new();
+                for target in targets {
+                    if [atoms[TARGETS], atoms[MULTIPLE], atoms[TIMESTAMP], atoms[SAVE_TARGETS]]
+                        .contains(&target)
+                    {
+                        continue;
+                    }
+                    if let Some(mime_type) = self.mime_type_for(target) {
+                        if !mime_types.contains(&mime_type) {
+                            mime_types.push(mime_type);
+                        }
+                    }
+                }
+                ClipboardEvent::MimeTypes { serial, mime_types }
+            },
+        };
+
+        self.event_sender.send(event);
+    }
+
+    /// Read and delete the property the selection was converted into.
+    fn take_property(&self) -> Result<(xproto::Atom, Vec<u8>), X11Error> {
+        let reply = self
+            .xconn
+            .xcb_connection()
+            .get_property(
+                true,
+                self.window,
+                self.property(),
+                xproto::AtomEnum::ANY,
+                0,
+                u32::MAX / 4,
+            )?
+            .reply()?;
+        Ok((reply.type_, reply.value))
+    }
+
+    /// Handle the answer of the selection owner to our conversion request.
+    ///
+    /// Returns `false` if the event isn't about this clipboard.
+    pub(crate) fn selection_notify(&self, xev: &XSelectionEvent) -> bool {
+        if xev.requestor as xproto::Window != self.window
+            || xev.selection as xproto::Atom != self.selection
+        {
+            return false;
+        }
+
+        if xev.property == 0 {
+            self.finish(Err(ClipboardError::Unavailable));
+            return true;
+        }
+
+        match self.take_property() {
+            Ok((type_, _)) if type_ == self.xconn.atoms()[INCR] => {
+                // Deleting the property told the owner to start sending the chunks.
+                if let Some(request) = self.requests.borrow_mut().front_mut() {
+                    request.incr = Some(Vec::new());
+                }
+            },
+            Ok((_, data)) => self.finish(Ok(data)),
+            Err(err) => {
+                tracing::warn!("Failed to read the clipboard: {err}");
+                self.finish(Err(ClipboardError::TransferFailed));
+            },
+        }
+
+        true
+    }
+
+    /// Collect the chunks of an `INCR` transfer, or send the ones of a transfer we serve.
+    ///
+    /// Returns `false` if the event isn't about this clipboard.
+    pub(crate) fn property_notify(&self, xev: &XPropertyEvent) -> bool {
+        if xev.window as xproto::Window != self.window {
+            return self.continue_transfer(xev);
+        }
+
+        if xev.atom as xproto::Atom != self.property()
+            || xev.state != xlib::PropertyNewValue
+            || !self.requests.borrow().front().is_some_and(|request| request.incr.is_some())
+        {
+            return true;
+        }
+
+        match self.take_property() {
+            // The transfer ends with a zero-length chunk.
+            Ok((_, data)) if data.is_empty() => {
+                let data = self.requests.borrow_mut().front_mut().and_then(|r| r.incr.take());
+                self.finish(Ok(data.unwrap_or_default()));
+            },
+            Ok((_, data)) => {
+                if let Some(request) = self.requests.borrow_mut().front_mut() {
+                    // The owner is still sending, only time out when it stops.
+                    request.started = Instant::now();
+                    if let Some(incr) = request.incr.as_mut() {
+                        incr.extend_from_slice(&data);
+                    }
+                }
+            },
+            Err(err) => {
+                tracing::warn!("Failed to read the clipboard: {err}");
+                self.finish(Err(ClipboardError::TransferFailed));
+            },
+        }
+
+        true
+    }
+
+    /// Serve our contents to another client.
+    ///
+    /// Returns `false` if the event isn't about this clipboard.
+    pub(crate) fn selection_request(&self, xev: &XSelectionRequestEvent) -> bool {
+        if xev.owner as xproto::Window != self.window
+            || xev.selection as xproto::Atom != self.selection
+        {
+            return false;
+        }
+
+        let requestor = xev.requestor as xproto::Window;
+        let target = xev.target as xproto::Atom;
+        // Obsolete clients don't set the property, the target should be used instead.
+        let property = match xev.property as xproto::Atom {
+            0 => target,
+            property => property,
+        };
+
+        let property = match self.write_target(requestor, target, property) {
+            Ok(true) => property,
+            Ok(false) => x11rb::NONE,
+            Err(err) => {
+                tracing::warn!("Failed to serve the clipboard: {err}");
+                x11rb::NONE
+            },
+        };
+
+        let event = xproto::SelectionNotifyEvent {
+            response_type: xproto::SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: xev.time as xproto::Timestamp,
+            requestor,
+            selection: self.selection,
+            target,
+            property,
+        };
+        self.xconn
+            .xcb_connection()
+            .send_event(false, requestor, xproto::EventMask::NO_EVENT, event)
+            .expect_then_ignore_error("Failed to send the selection notification");
+        if let Err(err) = self.xconn.flush_requests() {
+            tracing::warn!("Failed to flush the selection notification: {err}");
+        }
+
+        true
+    }
+
+    /// Write the contents in the requested target, returns `false` if it can't be provided.
+    fn write_target(
+        &self,
+        requestor: xproto::Window,
+        target: xproto::Atom,
+        property: xproto::Atom,
+    ) -> Result<bool, X11Error> {
+        let contents = self.contents.borrow();
+        let Some(contents) = contents.as_ref() else { return Ok(false) };
+
+        if target == self.xconn.atoms()[TARGETS] {
+            self.xconn
+                .change_property(
+                    requestor,
+                    property,
+                    xproto::AtomEnum::ATOM.into(),
+                    xproto::PropMode::REPLACE,
+                    &self.targets(contents),
+                )?
+                .ignore_error();
+            return Ok(true);
+        }
+
+        let Some(data) = self.mime_type_for(target).and_then(|mime| contents.get(&mime)) else {
+            return Ok(false);
+        };
+
+        // The data that doesn't fit in a request is sent in chunks.
+        let max_request = self.xconn.xcb_connection().maximum_request_bytes();
+        if data.len() + 24 > max_request {
+            self.start_transfer(requestor, property, target, data.to_vec())?;
+            return Ok(true);
+        }
+
+        self.xconn
+            .change_property(requestor, property, target, xproto::PropMode::REPLACE, data)?
+            .ignore_error();
+        Ok(true)
+    }
+
+    /// Announce an `INCR` transfer, the chunks are sent as the requestor reads them.
+    fn start_transfer(
+        &self,
+        requestor: xproto::Window,
+        property: xproto::Atom,
+        target: xproto::Atom,
+        data: Vec<u8>,
+    ) -> Result<(), X11Error> {
+        // The requestor deletes the property to ask for the next chunk.
+        if !self.is_own_window(requestor) {
+            let aux = xproto::ChangeWindowAttributesAux::new()
+                .event_mask(xproto::EventMask::PROPERTY_CHANGE);
+            self.xconn.xcb_connection().change_window_attributes(requestor, &aux)?.ignore_error();
+        }
+
+        // The size is only a lower bound, it must fit in the property.
+        let size = u32::try_from(data.len()).unwrap_or(u32::MAX);
+        self.xconn
+            .change_property(
+                requestor,
+                property,
+                self.xconn.atoms()[INCR],
+                xproto::PropMode::REPLACE,
+                &[size],
+            )?
+            .ignore_error();
+
+        let mut transfers = self.transfers.borrow_mut();
+        // A new request for the same property replaces the transfer.
+        transfers.retain(|t| (t.requestor, t.property) != (requestor, property));
+        let updated = Instant::now();
+        transfers.push(Transfer { requestor, property, target, data, sent: 0, updated });
+        Ok(())
+    }
+
+    /// Send the next chunk once the requestor read the previous one.
+    ///
+    /// Returns `false` if the event isn't about a transfer of this clipboard.
+    fn continue_transfer(&self, xev: &XPropertyEvent) -> bool {
+        let (requestor, property) = (xev.window as xproto::Window, xev.atom as xproto::Atom);
+        let mut transfers = self.transfers.borrow_mut();
+        let Some(index) =
+            transfers.iter().position(|t| (t.requestor, t.property) == (requestor, property))
+        else {
+            return false;
+        };
+
+        if xev.state != xlib::PropertyDelete {
+            return true;
+        }
+
+        let transfer = &mut transfers[index];
+        let end = (transfer.sent + INCR_CHUNK_SIZE).min(transfer.data.len());
+        let chunk = &transfer.data[transfer.sent..end];
+        let result = self
+            .xconn
+            .change_property(requestor, property, transfer.target, xproto::PropMode::REPLACE, chunk)
+            .map(|cookie| cookie.ignore_error())
+            .and_then(|_| Ok(self.xconn.flush_requests()?));
+        if let Err(err) = &result {
+            tracing::warn!("Failed to serve the clipboard: {err}");
+        }
+
+        // The zero-length chunk ends the transfer.
+        if chunk.is_empty() || result.is_err() {
+            transfers.swap_remove(index);
+            self.end_transfer(&transfers, requestor);
+        } else {
+            transfer.sent = end;
+            transfer.updated = Instant::now();
+        }
+
+        true
+    }
+
+    /// Stop listening to the properties of the requestor, unless other transfers are going on.
+    fn end_transfer(&self, transfers: &[Transfer], requestor: xproto::Window) {
+        if self.is_own_window(requestor)
+            || transfers.iter().any(|transfer| transfer.requestor == requestor)
+        {
+            return;
+        }
+
+        let aux = xproto::ChangeWindowAttributesAux::new().event_mask(xproto::EventMask::NO_EVENT);
+        self.xconn
+            .xcb_connection()
+            .change_window_attributes(requestor, &aux)
+            .expect_then_ignore_error("Failed to stop listening to the requestor");
+    }
+
+    /// Whether the window was created by us, its event mask is then already set and kept.
+    fn is_own_window(&self, window: xproto::Window) -> bool {
+        let setup = self.xconn.xcb_connection().setup();
+        window & !setup.resource_id_mask == setup.resource_id_base
+    }
+
+    /// Another client took the ownership of the selection.
+    ///
+    /// Returns `false` if the event isn't about this clipboard.
+    pub(crate) fn selection_clear(&self, xev: &XSelectionClearEvent) -> bool {
+        if xev.window as xproto::Window != self.window
+            || xev.selection as xproto::Atom != self.selection
+        {
+            return false;
+        }
+
+        self.contents.borrow_mut().take();
+        true
+    }
+
+    fn set_owner(&self, owner: xproto::Window) -> Result<(), X11Error> {
+        self.xconn
+            .xcb_connection()
+            .set_selection_owner(owner, self.selection, self.xconn.timestamp())?
+            .ignore_error();
+        self.xconn.flush_requests()?;
+        Ok(())
+    }
+
+    fn is_owner(&self) -> Result<bool, X11Error> {
+        let reply = self.xconn.xcb_connection().get_selection_owner(self.selection)?.reply()?;
+        Ok(reply.owner == self.window)
+    }
+}
+
+impl CoreClipboard for Clipboard {
+    fn write(&self, contents: ClipboardContents) -> Result<(), RequestError> {
+        self.set_owner(self.window).map_err(|err| os_error!(err))?;
+        if !self.is_owner().map_err(|err| os_error!(err))? {
+            return Err(RequestError::Ignored);
+        }
+
+        *self.contents.borrow_mut() = Some(contents);
+        Ok(())
+    }
+
+    fn read(&self, mime_type: &str) -> Result<AsyncRequestSerial, RequestError> {
+        let serial = AsyncRequestSerial::get();
+
+        // Don't go through the server when we're the owner.
+        if let Some(contents) = self.contents.borrow().as_ref() {
+            let result = contents
+                .get(mime_type)
+                .map(|data| ClipboardData { mime_type: mime_type.to_owned(), data: data.to_vec() })
+                .ok_or(ClipboardError::Unavailable);
+            self.event_sender.send(ClipboardEvent::Read { serial, result });
+            return Ok(serial);
+        }
+
+        let target = self.target_for(mime_type).map_err(|err| os_error!(err))?;
+        self.queue(serial, RequestKind::Data { mime_type: mime_type.to_owned(), target });
+        Ok(serial)
+    }
+
+    fn request_mime_types(&self) -> Result<AsyncRequestSerial, RequestError> {
+        let serial = AsyncRequestSerial::get();
+
+        if let Some(contents) = self.contents.borrow().as_ref() {
+            let mime_types = contents.mime_types().map(str::to_owned).collect();
+            self.event_sender.send(ClipboardEvent::MimeTypes { serial, mime_types });
+            return Ok(serial);
+        }
+
+        self.queue(serial, RequestKind::MimeTypes);
+        Ok(serial)
+    }
+
+    fn clear(&self) -> Result<(), RequestError> {
+        if self.contents.borrow_mut().take().is_some()
+            && self.is_owner().map_err(|err| os_error!(err))?
+        {
+            self.set_owner(x11rb::NONE).map_err(|err| os_error!(err))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Clipboard {
+    fn drop(&mut self) {
+        self.xconn
+            .xcb_connection()
+            .destroy_window(self.window)
+            .expect_then_ignore_error("Failed to destroy the clipboard window");
+    }
+}
@@ -1,22 +1,31 @@
-use std::io;
 use std::os::raw::*;
 use std::path::{Path, PathBuf};
 use std::str::Utf8Error;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{io, iter};
 
 use dpi::PhysicalPosition;
 use percent_encoding::percent_decode;
+use winit_core::clipboard::{Clipboard as _, ClipboardContents, ClipboardEvent};
+use winit_core::cursor::CursorIcon;
+use winit_core::error::RequestError;
+use winit_core::event::{DragAction, DragSourceEvent, WindowEvent};
+use winit_core::event_loop::AsyncRequestSerial;
+use winit_core::window::WindowId;
 use x11rb::protocol::xproto::{self, ConnectionExt};
 
 use super::atoms::AtomName::None as DndNone;
 use super::atoms::*;
-use super::{util, CookieResultExt, X11Error, XConnection};
+use super::clipboard::Clipboard;
+use super::util::CustomCursor;
+use super::{util, CookieResultExt, WakeSender, X11Error, XConnection};
 
-#[derive(Debug, Clone, Copy)]
-pub enum DndState {
-    Accepted,
-    Rejected,
-}
+/// The latest version of the protocol we implement as a source.
+const XDND_VERSION: u32 = 5;
+
+/// How long the target has to answer our messages before the drag is cancelled.
+const TARGET_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub enum DndDataParseError {
@@ -39,61 +48,194 @@ impl From<io::Error> for DndDataParseError {
     }
 }
 
+/// A drag started with `Window::start_drag`, sent to the event loop to be started there.
+#[derive(Debug)]
+pub struct DragRequest {
+    pub window_id: WindowId,
+    pub serial: AsyncRequestSerial,
+    pub contents: ClipboardContents,
+    pub actions: Vec<DragAction>,
+    pub icon: Option<CustomCursor>,
+}
+
+/// An `XdndAware` window our data can be dropped on.
+#[derive(Debug, Clone, Copy)]
+struct DropTarget {
+    /// The window under the pointer.
+    window: xproto::Window,
+    /// The window receiving the messages for it, which differs when it has an `XdndProxy`.
+    proxy: xproto::Window,
+    /// The version of the protocol used with it.
+    version: u32,
+}
+
+/// The drag started from one of our windows, driven by the pointer grab.
+#[derive(Debug)]
+pub struct DragSource {
+    window_id: WindowId,
+    serial: AsyncRequestSerial,
+    actions: Vec<DragAction>,
+    /// The targets of the dragged data.
+    type_list: Vec<xproto::Atom>,
+    /// The cursor showing the icon, kept alive until the drag ends.
+    _icon: Option<CustomCursor>,
+    /// The `XdndAware` window under the pointer.
+    target: Option<DropTarget>,
+    /// The position to send once the target answered the previous one.
+    pending_position: Option<(i16, i16, xproto::Timestamp)>,
+    /// Whether we wait for the `XdndStatus` of the last position.
+    awaiting_status: bool,
+    /// When we started waiting for `XdndStatus` or `XdndFinished`, to give up if it never comes.
+    awaiting_since: Option<Instant>,
+    accepted: bool,
+    action: Option<DragAction>,
+    /// Whether the data was dropped, and we wait for `XdndFinished`.
+    dropped: bool,
+}
+
+impl DragSource {
+    /// Whether the message comes from the target.
+    fn is_target(&self, window: xproto::Window) -> bool {
+        self.target.is_some_and(|target| window == target.window || window == target.proxy)
+    }
+
+    fn event(&self, event: DragSourceEvent) -> (WindowId, WindowEvent) {
+        (self.window_id, WindowEvent::DragSource { serial: self.serial, event })
+    }
+
+    /// Update whether and how the target accepts the drop.
+    fn set_status(
+        &mut self,
+        accepted: bool,
+        action: Option<DragAction>,
+        events: &mut Vec<(WindowId, WindowEvent)>,
+    ) {
+        if self.accepted != accepted {
+            self.accepted = accepted;
+            events.push(self.event(DragSourceEvent::Accepted(accepted)));
+        }
+
+        if self.action != action {
+            self.action = action;
+            events.push(self.event(DragSourceEvent::Action(action)));
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Dnd {
     xconn: Arc<XConnection>,
     // Populated by XdndEnter event handler
     pub version: Option<c_long>,
     pub type_list: Option<Vec<xproto::Atom>>,
+    // Populated by XdndEnter event handler
+    pub mime_types: Vec<String>,
+    // Populated by XdndEnter event handler, and by the application
+    pub action: Option<DragAction>,
     // Populated by XdndPosition event handler
     pub source_window: Option<xproto::Window>,
     // Populated by XdndPosition event handler
+    pub window: Option<xproto::Window>,
+    // Populated by XdndPosition event handler
     pub position: PhysicalPosition<f64>,
     // Populated by SelectionNotify event handler (triggered by XdndPosition event handler)
     pub result: Option<Result<Vec<PathBuf>, DndDataParseError>>,
     // Populated by SelectionNotify event handler (triggered by XdndPosition event handler)
     pub dragging: bool,
+    // Populated by XdndDrop event handler
+    pub dropped: bool,
+    /// Reads the data requested by the application.
+    pub data: Clipboard,
+    /// The pending data requests, with the windows to deliver them to.
+    pub requests: Vec<(AsyncRequestSerial, WindowId)>,
+    /// The drag started from one of our windows, served through `data`.
+    pub source: Option<DragSource>,
 }
 
 impl Dnd {
-    pub fn new(xconn: Arc<XConnection>) -> Result<Self, X11Error> {
+    pub fn new(
+        xconn: Arc<XConnection>,
+        data_sender: WakeSender<ClipboardEvent>,
+    ) -> Result<Self, X11Error> {
         Ok(Dnd {
+            data: Clipboard::drag(xconn.clone(), data_sender)?,
             xconn,
             version: None,
             type_list: None,
+            mime_types: Vec::new(),
+            action: None,
             source_window: None,
+            window: None,
             position: PhysicalPosition::default(),
             result: None,
             dragging: false,
+            dropped: false,
+            requests: Vec::new(),
+            source: None,
         })
     }
 
     pub fn reset(&mut self) {
         self.version = None;
         self.type_list = None;
+        self.mime_types.clear();
+        self.action = None;
         self.source_window = None;
+        self.window = None;
         self.result = None;
         self.dragging = false;
+        self.dropped = false;
+    }
+
+    /// Whether files are dragged.
+    pub fn has_paths(&self) -> bool {
+        let atoms = self.xconn.atoms();
+        self.type_list.as_ref().is_some_and(|type_list| type_list.contains(&atoms[TextUriList]))
+    }
+
+    /// The MIME types named by the atoms of the type list.
+    pub fn mime_types_for(&self, type_list: &[xproto::Atom]) -> Vec<String> {
+        type_list
+            .iter()
+            .filter(|&&atom| atom != x11rb::NONE)
+            .filter_map(|&atom| self.xconn.xcb_connection().get_atom_name(atom).ok()?.reply().ok())
+            .filter_map(|reply| String::from_utf8(reply.name).ok())
+            .collect()
+    }
+
+    fn action_atom(&self, action: Option<DragAction>) -> xproto::Atom {
+        let atoms = self.xconn.atoms();
+        match action {
+            Some(DragAction::Copy) => atoms[XdndActionCopy],
+            Some(DragAction::Move) => atoms[XdndActionMove],
+            Some(DragAction::Link) => atoms[XdndActionLink],
+            None => atoms[DndNone],
+        }
+    }
+
+    /// The action named by the atom.
+    fn action_for(&self, atom: xproto::Atom) -> Option<DragAction> {
+        let atoms = self.xconn.atoms();
+        [DragAction::Copy, DragAction::Move, DragAction::Link]
+            .into_iter()
+            .find(|&action| atom != atoms[DndNone] && self.action_atom(Some(action)) == atom)
     }
 
     pub unsafe fn send_status(
         &self,
         this_window: xproto::Window,
         target_window: xproto::Window,
-        state: DndState,
+        action: Option<DragAction>,
     ) -> Result<(), X11Error> {
         let atoms = self.xconn.atoms();
-        let (accepted, action) = match state {
-            DndState::Accepted => (1, atoms[XdndActionPrivate]),
-            DndState::Rejected => (0, atoms[DndNone]),
-        };
+        let accepted = action.is_some() as u32;
         self.xconn
             .send_client_msg(target_window, target_window, atoms[XdndStatus] as _, None, [
                 this_window,
                 accepted,
                 0,
                 0,
-                action as _,
+                self.action_atom(action),
             ])?
             .ignore_error();
 
@@ -104,18 +246,15 @@ impl Dnd {
         &self,
         this_window: xproto::Window,
         target_window: xproto::Window,
-        state: DndState,
+        action: Option<DragAction>,
     ) -> Result<(), X11Error> {
         let atoms = self.xconn.atoms();
-        let (accepted, action) = match state {
-            DndState::Accepted => (1, atoms[XdndActionPrivate]),
-            DndState::Rejected => (0, atoms[DndNone]),
-        };
+        let accepted = action.is_some() as u32;
         self.xconn
             .send_client_msg(target_window, target_window, atoms[XdndFinished] as _, None, [
                 this_window,
                 accepted,
-                action as _,
+                self.action_atom(action),
                 0,
                 0,
             ])?
@@ -124,6 +263,351 @@ impl Dnd {
         Ok(())
     }
 
+    /// Tell the source the drop is done, once the data requested for it was read.
+    pub fn finish_drop(&mut self) {
+        if !self.dropped || !self.requests.is_empty() {
+            return;
+        }
+
+        if let (Some(window), Some(source_window)) = (self.window, self.source_window) {
+            unsafe {
+                self.send_finished(window, source_window, self.action)
+                    .expect("Failed to send `XdndFinished` message.");
+            }
+        }
+        self.reset();
+    }
+
+    /// Start dragging the contents, the pointer is grabbed until the button is released.
+    pub fn start_drag(&mut self, request: DragRequest) -> Result<(), RequestError> {
+        if self.source.is_some() {
+            return Err(RequestError::Ignored);
+        }
+
+        let type_list = self.data.data_targets(&request.contents);
+        self.data.write(request.contents)?;
+        let atoms = self.xconn.atoms();
+        self.xconn
+            .change_property(
+                self.data.window(),
+                atoms[XdndTypeList],
+                xproto::AtomEnum::ATOM.into(),
+                xproto::PropMode::REPLACE,
+                &type_list,
+            )
+            .map_err(|err| os_error!(err))?
+            .ignore_error();
+
+        let cursor = match request.icon.as_ref() {
+            Some(icon) => icon.xcursor(),
+            None => self
+                .xconn
+                .cached_cursor(Some(CursorIcon::Grabbing))
+                .map_err(|err| os_error!(err))?,
+        };
+        let mask = xproto::EventMask::BUTTON_RELEASE | xproto::EventMask::POINTER_MOTION;
+        let status = self
+            .xconn
+            .xcb_connection()
+            .grab_pointer(
+                false,
+                self.xconn.default_root().root,
+                mask,
+                xproto::GrabMode::ASYNC,
+                xproto::GrabMode::ASYNC,
+                x11rb::NONE,
+                cursor,
+                x11rb::CURRENT_TIME,
+            )
+            .map_err(|err| os_error!(err))?
+            .reply()
+            .map_err(|err| os_error!(err))?
+            .status;
+        if status != xproto::GrabStatus::SUCCESS {
+            self.data.clear()?;
+            return Err(RequestError::Ignored);
+        }
+
+        // The keyboard is only grabbed to cancel the drag with Escape, we can do without it.
+        let status = self
+            .xconn
+            .xcb_connection()
+            .grab_keyboard(
+                false,
+                self.xconn.default_root().root,
+                x11rb::CURRENT_TIME,
+                xproto::GrabMode::ASYNC,
+                xproto::GrabMode::ASYNC,
+            )
+            .map_err(X11Error::from)
+            .and_then(|cookie| Ok(cookie.reply()?.status));
+        if !matches!(status, Ok(xproto::GrabStatus::SUCCESS)) {
+            tracing::debug!("Failed to grab the keyboard for the drag: {status:?}");
+        }
+
+        self.source = Some(DragSource {
+            window_id: request.window_id,
+            serial: request.serial,
+            actions: request.actions,
+            type_list,
+            _icon: request.icon,
+            target: None,
+            pending_position: None,
+            awaiting_status: false,
+            awaiting_since: None,
+            accepted: false,
+            action: None,
+            dropped: false,
+        });
+        Ok(())
+    }
+
+    /// The window the messages about the window are sent to, following its `XdndProxy`.
+    fn proxy_for(&self, window: xproto::Window) -> xproto::Window {
+        let atoms = self.xconn.atoms();
+        let proxy = |window| {
+            let proxy = self.xconn.get_property::<xproto::Window>(
+                window,
+                atoms[XdndProxy],
+                xproto::AtomEnum::WINDOW.into(),
+            );
+            proxy.ok().and_then(|proxy| proxy.first().copied())
+        };
+
+        // The proxy points to itself, otherwise the property was left by a client that exited.
+        match proxy(window) {
+            Some(target) if proxy(target) == Some(target) => target,
+            _ => window,
+        }
+    }
+
+    /// The deepest `XdndAware` window at the root position.
+    fn find_target(&self, x: i16, y: i16) -> Result<Option<DropTarget>, X11Error> {
+        let atoms = self.xconn.atoms();
+        let root = self.xconn.default_root().root;
+        let mut window = root;
+        loop {
+            let reply =
+                self.xconn.xcb_connection().translate_coordinates(root, window, x, y)?.reply()?;
+            if reply.child == x11rb::NONE {
+                return Ok(None);
+            }
+
+            window = reply.child;
+            // The proxy is the one advertising the protocol.
+            let proxy = self.proxy_for(window);
+            let version = self.xconn.get_property::<xproto::Atom>(
+                proxy,
+                atoms[XdndAware],
+                xproto::AtomEnum::ATOM.into(),
+            );
+            if let Some(&version) = version.as_deref().ok().and_then(<[_]>::first) {
+                let version = version.min(XDND_VERSION);
+                return Ok(Some(DropTarget { window, proxy, version }));
+            }
+        }
+    }
+
+    fn send_to_target(
+        &self,
+        target: DropTarget,
+        message: AtomName,
+        data: [u32; 4],
+    ) -> Result<(), X11Error> {
+        let atoms = self.xconn.atoms();
+        let [a, b, c, d] = data;
+        self.xconn
+            .send_client_msg(target.window, target.proxy, atoms[message], None, [
+                self.data.window(),
+                a,
+                b,
+                c,
+                d,
+            ])?
+            .ignore_error();
+        Ok(())
+    }
+
+    fn send_position(&mut self, x: i16, y: i16, time: xproto::Timestamp) -> Result<(), X11Error> {
+        let Some(source) = self.source.as_ref() else { return Ok(()) };
+        let Some(target) = source.target else { return Ok(()) };
+        let action = self.action_atom(source.actions.first().copied());
+        let position = ((x as u16 as u32) << 16) | y as u16 as u32;
+        self.send_to_target(target, XdndPosition, [0, position, time, action])?;
+        let source = self.source.as_mut().unwrap();
+        source.awaiting_status = true;
+        source.awaiting_since = Some(Instant::now());
+        Ok(())
+    }
+
+    /// The pointer moved during our drag.
+    pub fn source_motion(
+        &mut self,
+        x: i16,
+        y: i16,
+        time: xproto::Timestamp,
+    ) -> Result<Vec<(WindowId, WindowEvent)>, X11Error> {
+        let mut events = Vec::new();
+        if self.source.as_ref().map_or(true, |source| source.dropped) {
+            return Ok(events);
+        }
+
+        let target = self.find_target(x, y)?;
+        let source = self.source.as_mut().unwrap();
+        let previous = source.target;
+        if previous.map(|target| target.window) != target.map(|target| target.window) {
+            source.target = target;
+            source.pending_position = None;
+            source.awaiting_status = false;
+            source.awaiting_since = None;
+            source.set_status(false, None, &mut events);
+
+            if let Some(previous) = previous {
+                self.send_to_target(previous, XdndLeave, [0; 4])?;
+            }
+
+            if let Some(target) = target {
+                let type_list = &self.source.as_ref().unwrap().type_list;
+                let more_types = (type_list.len() > 3) as u32;
+                let mut types = type_list.iter().copied().chain(iter::repeat(x11rb::NONE));
+                let types = [(); 3].map(|_| types.next().unwrap());
+                let flags = (target.version << 24) | more_types;
+                self.send_to_target(target, XdndEnter, [flags, types[0], types[1], types[2]])?;
+            }
+        }
+
+        let source = self.source.as_mut().unwrap();
+        if source.awaiting_status {
+            source.pending_position = Some((x, y, time));
+        } else {
+            self.send_position(x, y, time)?;
+        }
+
+        self.xconn.flush_requests()?;
+        Ok(events)
+    }
+
+    /// The target answered our position.
+    pub fn source_status(
+        &mut self,
+        data: &[c_long],
+    ) -> Result<Vec<(WindowId, WindowEvent)>, X11Error> {
+        let mut events = Vec::new();
+        let window = data[0] as xproto::Window;
+        let accepted = data[1] & 1 == 1;
+        let action = accepted.then(|| self.action_for(data[4] as xproto::Atom)).flatten();
+        let Some(source) = self.source.as_mut() else { return Ok(events) };
+        if source.dropped || !source.is_target(window) {
+            return Ok(events);
+        }
+
+        source.awaiting_status = false;
+        source.awaiting_since = None;
+        source.set_status(accepted, action, &mut events);
+        if let Some((x, y, time)) = source.pending_position.take() {
+            self.send_position(x, y, time)?;
+            self.xconn.flush_requests()?;
+        }
+
+        Ok(events)
+    }
+
+    /// The pointer button was released, dropping the data on the target if it accepts it.
+    pub fn source_release(
+        &mut self,
+        time: xproto::Timestamp,
+    ) -> Result<Vec<(WindowId, WindowEvent)>, X11Error> {
+        let Some(source) = self.source.as_mut().filter(|source| !source.dropped) else {
+            return Ok(Vec::new());
+        };
+
+        self.xconn.xcb_connection().ungrab_pointer(time)?.ignore_error();
+        self.xconn.xcb_connection().ungrab_keyboard(time)?.ignore_error();
+        let target = source.target;
+        match target {
+            Some(target) if source.accepted => {
+                source.dropped = true;
+                source.awaiting_since = Some(Instant::now());
+                self.send_to_target(target, XdndDrop, [0, time, 0, 0])?;
+                self.xconn.flush_requests()?;
+                Ok(Vec::new())
+            },
+            _ => {
+                if let Some(target) = target {
+                    self.send_to_target(target, XdndLeave, [0; 4])?;
+                }
+                self.xconn.flush_requests()?;
+                Ok(self.end_drag(DragSourceEvent::Cancelled).into_iter().collect())
+            },
+        }
+    }
+
+    /// Whether the pointer and keyboard are grabbed by our drag.
+    pub fn is_dragging(&self) -> bool {
+        self.source.as_ref().is_some_and(|source| !source.dropped)
+    }
+
+    /// Cancel our drag, e.g. when Escape is pressed.
+    pub fn cancel_drag(
+        &mut self,
+        time: xproto::Timestamp,
+    ) -> Result<Vec<(WindowId, WindowEvent)>, X11Error> {
+        let Some(source) = self.source.as_ref() else { return Ok(Vec::new()) };
+
+        // Once dropped, the target already has the data and only the answer is missing.
+        if !source.dropped {
+            let target = source.target;
+            self.xconn.xcb_connection().ungrab_pointer(time)?.ignore_error();
+            self.xconn.xcb_connection().ungrab_keyboard(time)?.ignore_error();
+            if let Some(target) = target {
+                self.send_to_target(target, XdndLeave, [0; 4])?;
+            }
+            self.xconn.flush_requests()?;
+        }
+
+        Ok(self.end_drag(DragSourceEvent::Cancelled).into_iter().collect())
+    }
+
+    /// When the drag is cancelled if the target doesn't answer.
+    pub fn source_deadline(&self) -> Option<Instant> {
+        self.source.as_ref()?.awaiting_since.map(|since| since + TARGET_TIMEOUT)
+    }
+
+    /// Cancel our drag when the target didn't answer in time.
+    pub fn expire_source(&mut self) -> Result<Vec<(WindowId, WindowEvent)>, X11Error> {
+        if !self.source_deadline().is_some_and(|deadline| deadline <= Instant::now()) {
+            return Ok(Vec::new());
+        }
+
+        tracing::warn!("The target of the drag didn't answer in time");
+        self.cancel_drag(x11rb::CURRENT_TIME)
+    }
+
+    /// The target is done with the dropped data.
+    pub fn source_finished(&mut self, data: &[c_long]) -> Option<(WindowId, WindowEvent)> {
+        let window = data[0] as xproto::Window;
+        let source = self.source.as_ref()?;
+        if !source.dropped || !source.is_target(window) {
+            return None;
+        }
+
+        // The result of the drop is only reported since version 5.
+        let action = if source.target?.version >= 5 {
+            (data[1] & 1 == 1).then(|| self.action_for(data[2] as xproto::Atom)).flatten()
+        } else {
+            source.action
+        };
+        self.end_drag(action.map_or(DragSourceEvent::Cancelled, DragSourceEvent::Finished))
+    }
+
+    fn end_drag(&mut self, event: DragSourceEvent) -> Option<(WindowId, WindowEvent)> {
+        let source = self.source.take()?;
+        if let Err(err) = self.data.clear() {
+            tracing::warn!("Failed to release the dragged data: {err}");
+        }
+        Some(source.event(event))
+    }
+
     pub unsafe fn get_type_list(
         &self,
         source_window: xproto::Window,
@@ -1,32 +1,36 @@
 use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, VecDeque};
 use std::os::raw::{c_char, c_int, c_long, c_ulong};
-use std::slice;
 use std::sync::{Arc, Mutex};
+use std::{mem, slice};
 
 use dpi::{PhysicalPosition, PhysicalSize};
 use winit_core::application::ApplicationHandler;
 use winit_core::event::{
-    ButtonSource, DeviceEvent, DeviceId, ElementState, FingerId, Ime, MouseButton,
-    MouseScrollDelta, PointerKind, PointerSource, RawKeyEvent, SurfaceSizeWriter, TouchPhase,
-    WindowEvent,
+    ButtonSource, DeviceEvent, DeviceId, DragAction, ElementState, FingerId, Ime, MouseButton,
+    MouseScrollDelta, PointerKind, PointerSource, RawKeyEvent, SurfaceSizeWriter, TabletTool,
+    TabletToolState, TouchPhase, WindowEvent,
 };
 use winit_core::keyboard::ModifiersState;
+use winit_core::monitor::{MonitorEvent, MonitorHandle as CoreMonitorHandle};
+use x11_dl::xfixes::XFixesSelectionNotifyEvent;
 use x11_dl::xinput2::{
     self, XIDeviceEvent, XIEnterEvent, XIFocusInEvent, XIFocusOutEvent, XIHierarchyEvent,
     XILeaveEvent, XIModifierState, XIRawEvent,
 };
 use x11_dl::xlib::{
-    self, Display as XDisplay, Window as XWindow, XAnyEvent, XClientMessageEvent, XConfigureEvent,
-    XDestroyWindowEvent, XEvent, XExposeEvent, XKeyEvent, XMapEvent, XPropertyEvent,
-    XReparentEvent, XSelectionEvent, XVisibilityEvent, XkbAnyEvent, XkbStateRec,
+    self, Display as XDisplay, Window as XWindow, XAnyEvent, XButtonEvent, XClientMessageEvent,
+    XConfigureEvent, XDestroyWindowEvent, XEvent, XExposeEvent, XKeyEvent, XMapEvent, XMotionEvent,
+    XPropertyEvent, XReparentEvent, XSelectionClearEvent, XSelectionEvent, XSelectionRequestEvent,
+    XVisibilityEvent, XkbAnyEvent, XkbStateRec,
 };
+use x11_dl::xrandr::{RRNotify_CrtcChange, RRNotify_OutputChange, XRRNotifyEvent};
 use x11rb::protocol::sync::{ConnectionExt, Int64};
 use x11rb::protocol::xinput;
 use x11rb::protocol::xkb::ID as XkbId;
 use x11rb::protocol::xproto::{self, ConnectionExt as _, ModMask};
 use x11rb::x11_utils::{ExtensionInformation, Serialize};
-use xkbcommon_dl::xkb_mod_mask_t;
+use xkbcommon_dl::{keysyms, xkb_mod_mask_t};
 
 use crate::platform_impl::common::xkb::{self, XkbState};
 use crate::platform_impl::platform::common::xkb::Context;
@@ -35,8 +39,8 @@ use crate::platform_impl::platform::x11::ActiveEventLoop;
 use crate::platform_impl::x11::atoms::*;
 use crate::platform_impl::x11::util::cookie::GenericEventCookie;
 use crate::platform_impl::x11::{
-    mkdid, mkwid, util, CookieResultExt, Device, DeviceInfo, Dnd, DndState, ImeReceiver,
-    ScrollOrientation, UnownedWindow, WindowId,
+    ffi, mkdid, mkwid, util, CookieResultExt, Device, DeviceInfo, ImeReceiver, ScrollOrientation,
+    TabletDevice, UnownedWindow, WindowId, X11Error,
 };
 
 /// The maximum amount of X modifiers to replay.
@@ -47,10 +51,11 @@ const KEYCODE_OFFSET: u8 = 8;
 
 #[derive(Debug)]
 pub struct EventProcessor {
-    pub dnd: Dnd,
     pub ime_receiver: ImeReceiver,
     pub ime_event_receiver: ImeEventReceiver,
     pub randr_event_offset: u8,
+    /// The type of the XFixes selection notifications, `None` without XFixes.
+    pub xfixes_event_offset: Option<c_int>,
     pub devices: RefCell<HashMap<DeviceId, Device>>,
     pub xi2ext: ExtensionInformation,
     pub xkbext: ExtensionInformation,
@@ -63,7 +68,11 @@ pub struct EventProcessor {
     //
     // Used to detect key repeats.
     pub held_key_press: Option<u32>,
+    // Whether to deliver the repeats of held keys.
+    pub key_repeat: bool,
     pub first_touch: Option<u32>,
+    // The scale of the pinch gesture in progress, relative to its beginning.
+    pub pinch_scale: f64,
     // Currently focused window belonging to this process
     pub active_window: Option<xproto::Window>,
     /// Latest modifiers we've sent for the user to trigger change in event.
@@ -79,9 +88,24 @@ pub struct EventProcessor {
 impl EventProcessor {
     pub(crate) fn process_event(&mut self, xev: &mut XEvent, app: &mut dyn ApplicationHandler) {
         self.process_xevent(xev, app);
+        self.process_ime(app);
+    }
 
+    /// Handle the IME requests of the windows, and deliver the IME events.
+    pub(crate) fn process_ime(&mut self, app: &mut dyn ApplicationHandler) {
         // Handle IME requests.
         while let Ok(request) = self.ime_receiver.try_recv() {
+            if let Some(ime) = self.target.dbus_ime.as_mut() {
+                let ime = ime.get_mut();
+                match request {
+                    ImeRequest::Area(window_id, x, y, w, h) => ime.set_area(window_id, x, y, w, h),
+                    ImeRequest::Allow(window_id, allowed) => {
+                        ime.set_ime_allowed(window_id, allowed)
+                    },
+                }
+                continue;
+            }
+
             let ime = match self.target.ime.as_mut() {
                 Some(ime) => ime,
                 None => continue,
@@ -98,6 +122,11 @@ impl EventProcessor {
             }
         }
 
+        // Read the replies and signals of the D-Bus input method.
+        if let Some(ime) = self.target.dbus_ime.as_mut() {
+            ime.get_mut().dispatch();
+        }
+
         // Drain IME events.
         while let Ok((window, event)) = self.ime_event_receiver.try_recv() {
             let window_id = mkwid(window as xproto::Window);
@@ -110,6 +139,12 @@ impl EventProcessor {
                 ImeEvent::Update(text, position) if self.is_composing => {
                     WindowEvent::Ime(Ime::Preedit(text, Some((position, position))))
                 },
+                ImeEvent::Commit(text) => {
+                    let event = WindowEvent::Ime(Ime::Preedit(String::new(), None));
+                    app.window_event(&self.target, window_id, event);
+                    self.is_composing = false;
+                    WindowEvent::Ime(Ime::Commit(text))
+                },
                 ImeEvent::End => {
                     self.is_composing = false;
                     // Issue empty preedit on `Done`.
@@ -124,6 +159,18 @@ impl EventProcessor {
 
             app.window_event(&self.target, window_id, event);
         }
+
+        // Deliver the key events the D-Bus input method didn't consume, after its text.
+        while let Some(mut xev) =
+            self.target.dbus_ime.as_ref().and_then(|ime| ime.borrow_mut().next_key())
+        {
+            let state = if xev.type_ == xlib::KeyPress {
+                ElementState::Pressed
+            } else {
+                ElementState::Released
+            };
+            self.xinput_key_input(&mut xev, state, app);
+        }
     }
 
     /// XFilterEvent tells us when an event has been discarded by the input method.
@@ -140,14 +187,47 @@ impl EventProcessor {
         }
     }
 
+    /// Send the key event to the D-Bus input method, returns whether it's held until the input
+    /// method answers.
+    fn filter_dbus_key(&mut self, xev: &XKeyEvent) -> bool {
+        let (Some(ime), Some(window)) = (self.target.dbus_ime.as_ref(), self.active_window) else {
+            return false;
+        };
+        let mut ime = ime.borrow_mut();
+        if !ime.is_ime_allowed(window as XWindow) {
+            return false;
+        }
+
+        let keycode = xev.keycode;
+        let Some(keysym) = self.xkb_context.state_mut().map(|state| state.get_one_sym_raw(keycode))
+        else {
+            return false;
+        };
+        ime.process_key(window as XWindow, xev, keysym)
+    }
+
     fn process_xevent(&mut self, xev: &mut XEvent, app: &mut dyn ApplicationHandler) {
         let event_type = xev.get_type();
 
+        // The keyboard is grabbed by our drag, only to cancel it with Escape.
+        if matches!(event_type, xlib::KeyPress | xlib::KeyRelease)
+            && self.target.dnd.borrow().is_dragging()
+        {
+            if event_type == xlib::KeyPress {
+                self.drag_source_key(xev.as_ref(), app);
+            }
+            return;
+        }
+
         // If we have IME disabled, don't try to `filter_event`, since only IME can consume them
         // and forward back. This is not desired for e.g. games since some IMEs may delay the input
         // and game can toggle IME back when e.g. typing into some field where latency won't really
         // matter.
-        let filtered = if event_type == xlib::KeyPress || event_type == xlib::KeyRelease {
+        let filtered = if let (xlib::KeyPress | xlib::KeyRelease, Some(_)) =
+            (event_type, self.target.dbus_ime.as_ref())
+        {
+            self.filter_dbus_key(xev.as_ref())
+        } else if event_type == xlib::KeyPress || event_type == xlib::KeyRelease {
             let ime = self.target.ime.as_ref();
             let window = self.active_window.map(|window| window as XWindow);
             let forward_to_ime = ime
@@ -180,6 +260,8 @@ impl EventProcessor {
         match event_type {
             xlib::ClientMessage => self.client_message(xev.as_ref(), app),
             xlib::SelectionNotify => self.selection_notify(xev.as_ref(), app),
+            xlib::SelectionRequest => self.selection_request(xev.as_ref()),
+            xlib::SelectionClear => self.selection_clear(xev.as_ref()),
             xlib::ConfigureNotify => self.configure_notify(xev.as_ref(), app),
             xlib::ReparentNotify => self.reparent_notify(xev.as_ref()),
             xlib::MapNotify => self.map_notify(xev.as_ref(), app),
@@ -187,6 +269,9 @@ impl EventProcessor {
             xlib::PropertyNotify => self.property_notify(xev.as_ref(), app),
             xlib::VisibilityNotify => self.visibility_notify(xev.as_ref(), app),
             xlib::Expose => self.expose(xev.as_ref()),
+            // Only delivered by the pointer grab of our drag.
+            xlib::MotionNotify => self.drag_source_motion(xev.as_ref(), app),
+            xlib::ButtonRelease => self.drag_source_release(xev.as_ref(), app),
             // Note that in compose/pre-edit sequences, we'll always receive KeyRelease events.
             ty @ xlib::KeyPress | ty @ xlib::KeyRelease => {
                 let state = if ty == xlib::KeyPress {
@@ -244,6 +329,20 @@ impl EventProcessor {
                         let xev: &XIDeviceEvent = unsafe { xev.as_event() };
                         self.xinput2_touch(xev, evtype, app);
                     },
+                    ffi::XI_GesturePinchBegin
+                    | ffi::XI_GesturePinchUpdate
+                    | ffi::XI_GesturePinchEnd => {
+                        let xev: &ffi::XIGesturePinchEvent = unsafe { xev.as_event() };
+                        self.update_mods_from_xinput2_event(&xev.mods, &xev.group, false, app);
+                        self.xinput2_gesture_pinch(xev, app);
+                    },
+                    ffi::XI_GestureSwipeBegin
+                    | ffi::XI_GestureSwipeUpdate
+                    | ffi::XI_GestureSwipeEnd => {
+                        let xev: &ffi::XIGestureSwipeEvent = unsafe { xev.as_event() };
+                        self.update_mods_from_xinput2_event(&xev.mods, &xev.group, false, app);
+                        self.xinput2_gesture_swipe(xev, app);
+                    },
                     xinput2::XI_RawButtonPress | xinput2::XI_RawButtonRelease => {
                         let state = match evtype {
                             xinput2::XI_RawButtonPress => ElementState::Pressed,
@@ -271,7 +370,7 @@ impl EventProcessor {
 
                     xinput2::XI_HierarchyChanged => {
                         let xev: &XIHierarchyEvent = unsafe { xev.as_event() };
-                        self.xinput2_hierarchy_changed(xev);
+                        self.xinput2_hierarchy_changed(xev, app);
                     },
                     _ => {},
                 }
@@ -284,6 +383,20 @@ impl EventProcessor {
                 if event_type == self.randr_event_offset as c_int {
                     self.process_dpi_change(app);
                 }
+                // Only the changes of the CRTCs and outputs affect the monitors, the changes of
+                // their properties, like the backlight, don't.
+                if event_type == self.randr_event_offset as c_int + 1 {
+                    let xev: &XRRNotifyEvent =
+                        unsafe { &*(xev as *const _ as *const XRRNotifyEvent) };
+                    if xev.subtype == RRNotify_CrtcChange || xev.subtype == RRNotify_OutputChange {
+                        self.process_dpi_change(app);
+                    }
+                }
+                if Some(event_type) == self.xfixes_event_offset {
+                    let xev: &XFixesSelectionNotifyEvent =
+                        unsafe { &*(xev as *const _ as *const XFixesSelectionNotifyEvent) };
+                    self.selection_owner_changed(xev);
+                }
             },
         }
     }
@@ -322,7 +435,10 @@ impl EventProcessor {
         let mut devices = self.devices.borrow_mut();
         if let Some(info) = DeviceInfo::get(&self.target.xconn, device as _) {
             for info in info.iter() {
-                devices.insert(mkdid(info.deviceid as xinput::DeviceId), Device::new(info));
+                devices.insert(
+                    mkdid(info.deviceid as xinput::DeviceId),
+                    Device::new(info, &self.target.xconn),
+                );
             }
         }
     }
@@ -364,6 +480,21 @@ impl EventProcessor {
             return;
         }
 
+        // The answers of the targets of our drag.
+        if xev.message_type == atoms[XdndStatus] as c_ulong {
+            let result = self.target.dnd.borrow_mut().source_status(xev.data.as_longs());
+            self.drag_source_events(result, app);
+            return;
+        }
+
+        if xev.message_type == atoms[XdndFinished] as c_ulong {
+            let event = self.target.dnd.borrow_mut().source_finished(xev.data.as_longs());
+            if let Some((window_id, event)) = event {
+                app.window_event(&self.target, window_id, event);
+            }
+            return;
+        }
+
         if xev.data.get_long(0) as xproto::Atom == self.target.net_wm_ping {
             let client_msg = xproto::ClientMessageEvent {
                 response_type: xproto::CLIENT_MESSAGE_EVENT,
@@ -419,10 +550,11 @@ impl EventProcessor {
         }
 
         if xev.message_type == atoms[XdndEnter] as c_ulong {
+            let mut dnd = self.target.dnd.borrow_mut();
             let source_window = xev.data.get_long(0) as xproto::Window;
             let flags = xev.data.get_long(1);
             let version = flags >> 24;
-            self.dnd.version = Some(version);
+            dnd.version = Some(version);
             let has_more_types = flags - (flags & (c_long::MAX - 1)) == 1;
             if !has_more_types {
                 let type_list = vec![
@@ -430,21 +562,21 @@ impl EventProcessor {
                     xev.data.get_long(3) as xproto::Atom,
                     xev.data.get_long(4) as xproto::Atom,
                 ];
-                self.dnd.type_list = Some(type_list);
-            } else if let Ok(more_types) = unsafe { self.dnd.get_type_list(source_window) } {
-                self.dnd.type_list = Some(more_types);
+                dnd.type_list = Some(type_list);
+            } else if let Ok(more_types) = unsafe { dnd.get_type_list(source_window) } {
+                dnd.type_list = Some(more_types);
             }
+
+            dnd.mime_types = dnd.mime_types_for(dnd.type_list.as_deref().unwrap_or_default());
+            // Files are accepted by default, other data once the application asks for it.
+            dnd.action = dnd.has_paths().then_some(DragAction::Copy);
             return;
         }
 
         if xev.message_type == atoms[XdndPosition] as c_ulong {
-            // This event occurs every time the mouse moves while a file's being dragged
-            // over our window. We emit HoveredFile in response; while the macOS backend
-            // does that upon a drag entering, XDND doesn't have access to the actual drop
-            // data until this event. For parity with other platforms, we only emit
-            // `HoveredFile` the first time, though if winit's API is later extended to
-            // supply position updates with `HoveredFile` or another event, implementing
-            // that here would be trivial.
+            // This event occurs every time the mouse moves while something's being dragged
+            // over our window. XDND doesn't have access to the actual drop data until this
+            // event, so `DragEntered` is emitted the first time and `DragMoved` afterwards.
 
             let source_window = xev.data.get_long(0) as xproto::Window;
 
@@ -460,31 +592,18 @@ impl EventProcessor {
                 .xconn
                 .translate_coords(self.target.root, window, x, y)
                 .expect("Failed to translate window coordinates");
-            self.dnd.position = PhysicalPosition::new(coords.dst_x as f64, coords.dst_y as f64);
+
+            let mut dnd = self.target.dnd.borrow_mut();
+            dnd.position = PhysicalPosition::new(coords.dst_x as f64, coords.dst_y as f64);
 
             // By our own state flow, `version` should never be `None` at this point.
-            let version = self.dnd.version.unwrap_or(5);
+            let version = dnd.version.unwrap_or(5);
 
             // Action is specified in versions 2 and up, though we don't need it anyway.
             // let action = xev.data.get_long(4);
 
-            let accepted = if let Some(ref type_list) = self.dnd.type_list {
-                type_list.contains(&atoms[TextUriList])
-            } else {
-                false
-            };
-
-            if !accepted {
-                unsafe {
-                    self.dnd
-                        .send_status(window, source_window, DndState::Rejected)
-                        .expect("Failed to send `XdndStatus` message.");
-                }
-                self.dnd.reset();
-                return;
-            }
-
-            self.dnd.source_window = Some(source_window);
+            dnd.source_window = Some(source_window);
+            dnd.window = Some(window);
             let time = if version == 0 {
                 // In version 0, time isn't specified
                 x11rb::CURRENT_TIME
@@ -495,52 +614,79 @@ impl EventProcessor {
             // Log this timestamp.
             self.target.xconn.set_timestamp(time);
 
-            // This results in the `SelectionNotify` event below
             unsafe {
-                self.dnd.convert_selection(window, time);
+                dnd.send_status(window, source_window, dnd.action)
+                    .expect("Failed to send `XdndStatus` message.");
             }
 
-            unsafe {
-                self.dnd
-                    .send_status(window, source_window, DndState::Accepted)
-                    .expect("Failed to send `XdndStatus` message.");
+            if dnd.has_paths() {
+                // This results in the `SelectionNotify` event below
+                unsafe {
+                    dnd.convert_selection(window, time);
+                }
+                return;
             }
+
+            // Without paths there's nothing to wait for.
+            let position = dnd.position;
+            let event = if mem::replace(&mut dnd.dragging, true) {
+                WindowEvent::DragMoved { position }
+            } else {
+                WindowEvent::DragEntered {
+                    paths: Vec::new(),
+                    mime_types: dnd.mime_types.clone(),
+                    position,
+                }
+            };
+            drop(dnd);
+            app.window_event(&self.target, window_id, event);
             return;
         }
 
         if xev.message_type == atoms[XdndDrop] as c_ulong {
-            let (source_window, state) = if let Some(source_window) = self.dnd.source_window {
-                if let Some(Ok(ref path_list)) = self.dnd.result {
-                    let event = WindowEvent::DragDropped {
-                        paths: path_list.iter().map(Into::into).collect(),
-                        position: self.dnd.position,
-                    };
-                    app.window_event(&self.target, window_id, event);
-                }
-                (source_window, DndState::Accepted)
-            } else {
-                // `source_window` won't be part of our DND state if we already rejected the drop in
-                // our `XdndPosition` handler.
+            let mut dnd = self.target.dnd.borrow_mut();
+            if dnd.source_window.is_none() || !dnd.dragging || dnd.action.is_none() {
+                // `source_window` won't be part of our DND state if the drag never reached our
+                // `XdndPosition` handler.
                 let source_window = xev.data.get_long(0) as xproto::Window;
-                (source_window, DndState::Rejected)
-            };
+                unsafe {
+                    dnd.send_finished(window, source_window, None)
+                        .expect("Failed to send `XdndFinished` message.");
+                }
+                dnd.reset();
+                return;
+            }
 
-            unsafe {
-                self.dnd
-                    .send_finished(window, source_window, state)
-                    .expect("Failed to send `XdndFinished` message.");
+            // The data is converted with the time of the drop.
+            if dnd.version.unwrap_or(5) >= 1 {
+                self.target.xconn.set_timestamp(xev.data.get_long(2) as xproto::Timestamp);
             }
 
-            self.dnd.reset();
+            let paths = match dnd.result {
+                Some(Ok(ref path_list)) => path_list.clone(),
+                _ => Vec::new(),
+            };
+            let event = WindowEvent::DragDropped { paths, position: dnd.position };
+            dnd.dropped = true;
+            drop(dnd);
+            app.window_event(&self.target, window_id, event);
+
+            // The data requested while handling the drop is still needed.
+            self.target.dnd.borrow_mut().finish_drop();
             return;
         }
 
         if xev.message_type == atoms[XdndLeave] as c_ulong {
-            if self.dnd.dragging {
-                let event = WindowEvent::DragLeft { position: Some(self.dnd.position) };
+            let mut dnd = self.target.dnd.borrow_mut();
+            let dragging = dnd.dragging;
+            let position = dnd.position;
+            dnd.reset();
+            drop(dnd);
+
+            if dragging {
+                let event = WindowEvent::DragLeft { position: Some(position) };
                 app.window_event(&self.target, window_id, event);
             }
-            self.dnd.reset();
         }
     }
 
@@ -553,31 +699,103 @@ impl EventProcessor {
         // Set the timestamp.
         self.target.xconn.set_timestamp(xev.time as xproto::Timestamp);
 
+        if self.target.clipboard.selection_notify(xev)
+            || self.target.primary_selection.selection_notify(xev)
+            || self.target.dnd.borrow().data.selection_notify(xev)
+        {
+            return;
+        }
+
         if xev.property != atoms[XdndSelection] as c_ulong {
             return;
         }
 
         // This is where we receive data from drag and drop
-        self.dnd.result = None;
-        if let Ok(mut data) = unsafe { self.dnd.read_data(window) } {
-            let parse_result = self.dnd.parse_data(&mut data);
-
-            if let Ok(ref path_list) = parse_result {
-                let event = if self.dnd.dragging {
-                    WindowEvent::DragMoved { position: self.dnd.position }
+        let mut dnd = self.target.dnd.borrow_mut();
+        dnd.result = None;
+        if let Ok(mut data) = unsafe { dnd.read_data(window) } {
+            let parse_result = dnd.parse_data(&mut data);
+
+            let event = parse_result.as_ref().ok().map(|path_list| {
+                if dnd.dragging {
+                    WindowEvent::DragMoved { position: dnd.position }
                 } else {
-                    let paths = path_list.iter().map(Into::into).collect();
-                    self.dnd.dragging = true;
-                    WindowEvent::DragEntered { paths, position: self.dnd.position }
-                };
+                    WindowEvent::DragEntered {
+                        paths: path_list.clone(),
+                        mime_types: dnd.mime_types.clone(),
+                        position: dnd.position,
+                    }
+                }
+            });
+            dnd.dragging |= event.is_some();
+            dnd.result = Some(parse_result);
+            drop(dnd);
 
+            if let Some(event) = event {
                 app.window_event(&self.target, window_id, event);
             }
+        }
+    }
+
+    fn selection_request(&self, xev: &XSelectionRequestEvent) {
+        let _ = self.target.clipboard.selection_request(xev)
+            || self.target.primary_selection.selection_request(xev)
+            || self.target.dnd.borrow().data.selection_request(xev);
+    }
+
+    fn selection_clear(&self, xev: &XSelectionClearEvent) {
+        self.target.xconn.set_timestamp(xev.time as xproto::Timestamp);
+        let _ = self.target.clipboard.selection_clear(xev)
+            || self.target.primary_selection.selection_clear(xev)
+            || self.target.dnd.borrow().data.selection_clear(xev);
+    }
 
-            self.dnd.result = Some(parse_result);
+    fn drag_source_motion(&self, xev: &XMotionEvent, app: &mut dyn ApplicationHandler) {
+        let time = xev.time as xproto::Timestamp;
+        self.target.xconn.set_timestamp(time);
+        let result =
+            self.target.dnd.borrow_mut().source_motion(xev.x_root as i16, xev.y_root as i16, time);
+        self.drag_source_events(result, app);
+    }
+
+    fn drag_source_release(&self, xev: &XButtonEvent, app: &mut dyn ApplicationHandler) {
+        let time = xev.time as xproto::Timestamp;
+        self.target.xconn.set_timestamp(time);
+        let result = self.target.dnd.borrow_mut().source_release(time);
+        self.drag_source_events(result, app);
+    }
+
+    fn drag_source_key(&mut self, xev: &XKeyEvent, app: &mut dyn ApplicationHandler) {
+        let time = xev.time as xproto::Timestamp;
+        self.target.xconn.set_timestamp(time);
+        let keysym = self.xkb_context.state_mut().map(|state| state.get_one_sym_raw(xev.keycode));
+        if keysym == Some(keysyms::Escape) {
+            let result = self.target.dnd.borrow_mut().cancel_drag(time);
+            self.drag_source_events(result, app);
+        }
+    }
+
+    fn drag_source_events(
+        &self,
+        result: Result<Vec<(WindowId, WindowEvent)>, X11Error>,
+        app: &mut dyn ApplicationHandler,
+    ) {
+        match result {
+            Ok(events) => {
+                for (window_id, event) in events {
+                    app.window_event(&self.target, window_id, event);
+                }
+            },
+            Err(err) => tracing::warn!("Failed to update the drag: {err}"),
         }
     }
 
+    fn selection_owner_changed(&self, xev: &XFixesSelectionNotifyEvent) {
+        self.target.xconn.set_timestamp(xev.timestamp as xproto::Timestamp);
+        let _ = self.target.clipboard.owner_changed(xev)
+            || self.target.primary_selection.owner_changed(xev);
+    }
+
     fn configure_notify(&self, xev: &XConfigureEvent, app: &mut dyn ApplicationHandler) {
         let xwindow = xev.window as xproto::Window;
         let window_id = mkwid(xwindow);
@@ -791,6 +1009,9 @@ impl EventProcessor {
                 .remove_context(window as XWindow)
                 .expect("Failed to destroy input context");
         }
+        if let Some(ime) = self.target.dbus_ime.as_ref() {
+            ime.borrow_mut().remove_context(window as XWindow);
+        }
 
         app.window_event(&self.target, window_id, WindowEvent::Destroyed);
     }
@@ -799,11 +1020,80 @@ impl EventProcessor {
         let atoms = self.target.x_connection().atoms();
         let atom = xev.atom as xproto::Atom;
 
+        if self.target.clipboard.property_notify(xev)
+            || self.target.primary_selection.property_notify(xev)
+            || self.target.dnd.borrow().data.property_notify(xev)
+        {
+            return;
+        }
+
         if atom == xproto::Atom::from(xproto::AtomEnum::RESOURCE_MANAGER)
             || atom == atoms[_XSETTINGS_SETTINGS]
         {
             self.process_dpi_change(app);
         }
+
+        if atom == atoms[_XSETTINGS_SETTINGS] {
+            self.update_system_theme(app);
+            self.update_xsettings(app);
+        }
+
+        if atom == atoms[_NET_WM_ALLOWED_ACTIONS] {
+            let xwindow = xev.window as xproto::Window;
+            let capabilities =
+                self.with_window(xwindow, |window| window.update_window_capabilities());
+            if let Some(capabilities) = capabilities.flatten() {
+                let window_id = mkwid(xwindow);
+                let event = WindowEvent::CapabilitiesChanged(capabilities);
+                app.window_event(&self.target, window_id, event);
+            }
+        }
+    }
+
+    /// Re-read the XSETTINGS, notifying the application when they changed.
+    fn update_xsettings(&self, app: &mut dyn ApplicationHandler) {
+        let xsettings = self.target.read_xsettings();
+        if *self.target.xsettings.borrow() == xsettings {
+            return;
+        }
+
+        self.target.xsettings.replace(xsettings);
+        if let Some(handler) = app.x11_handler() {
+            handler.xsettings_changed(&self.target);
+        }
+        self.update_input_preferences(app);
+    }
+
+    /// Re-evaluate the input preferences, notifying the application when they changed.
+    fn update_input_preferences(&self, app: &mut dyn ApplicationHandler) {
+        let preferences = self.target.read_input_preferences();
+        if self.target.input_preferences.replace(preferences) != preferences {
+            app.input_preferences_changed(&self.target);
+        }
+    }
+
+    /// Re-evaluate the system theme, notifying the windows when it changed.
+    pub fn update_system_theme(&self, app: &mut dyn ApplicationHandler) {
+        let theme = self.target.current_system_theme();
+        if self.target.system_theme.replace(theme) == theme {
+            return;
+        }
+
+        let Some(theme) = theme else { return };
+        // Windows with a theme override don't follow the system theme.
+        let window_ids: Vec<_> = self
+            .target
+            .windows
+            .borrow()
+            .iter()
+            .filter_map(|(window_id, window)| {
+                let window = window.upgrade()?;
+                window.theme().is_none().then_some(*window_id)
+            })
+            .collect();
+        for window_id in window_ids {
+            app.window_event(&self.target, window_id, WindowEvent::ThemeChanged(theme));
+        }
     }
 
     fn visibility_notify(&self, xev: &XVisibilityEvent, app: &mut dyn ApplicationHandler) {
@@ -877,6 +1167,11 @@ impl EventProcessor {
             false
         };
 
+        // The repeats are detectable, see `XkbSetDetectableAutoRepeat`, so they can be dropped.
+        if repeat && !self.key_repeat {
+            return;
+        }
+
         // NOTE: When the modifier was captured by the XFilterEvents the modifiers for the modifier
         // itself are out of sync due to XkbState being delivered before XKeyEvent, since it's
         // being replayed by the XIM, thus we should replay ourselves.
@@ -979,6 +1274,22 @@ impl EventProcessor {
 
         let position = PhysicalPosition::new(event.event_x, event.event_y);
 
+        // Scrolling with a tool is still reported as a mouse wheel.
+        let tablet_tool =
+            self.tablet_tool(event.sourceid).filter(|_| !(4..=7).contains(&event.detail));
+        if let Some((tool, tool_state)) = tablet_tool {
+            let button = TabletDevice::button(event.detail as u32);
+            let event = WindowEvent::PointerButton {
+                device_id,
+                primary: true,
+                state,
+                position,
+                button: ButtonSource::Tablet { tool, button, state: tool_state },
+            };
+            app.window_event(&self.target, window_id, event);
+            return;
+        }
+
         let event = match event.detail as u32 {
             xlib::Button1 => WindowEvent::PointerButton {
                 device_id,
@@ -1054,44 +1365,40 @@ impl EventProcessor {
         let window_id = mkwid(window);
         let new_cursor_pos = (event.event_x, event.event_y);
 
-        let cursor_moved = self.with_window(window, |window| {
+        let cursor_moved = match self.with_window(window, |window| {
             let mut shared_state_lock = window.shared_state_lock();
             util::maybe_change(&mut shared_state_lock.cursor_pos, new_cursor_pos)
-        });
-
-        if cursor_moved == Some(true) {
-            let position = PhysicalPosition::new(event.event_x, event.event_y);
-
-            let event = WindowEvent::PointerMoved {
-                device_id,
-                primary: true,
-                position,
-                source: PointerSource::Mouse,
-            };
-            app.window_event(&self.target, window_id, event);
-        } else if cursor_moved.is_none() {
-            return;
-        }
+        }) {
+            Some(cursor_moved) => cursor_moved,
+            None => return,
+        };
 
         // More gymnastics, for self.devices
         let mask = unsafe {
             slice::from_raw_parts(event.valuators.mask, event.valuators.mask_len as usize)
         };
         let mut devices = self.devices.borrow_mut();
-        let physical_device = match devices.get_mut(&mkdid(event.sourceid as xinput::DeviceId)) {
-            Some(device) => device,
-            None => return,
-        };
+        let mut physical_device = devices.get_mut(&mkdid(event.sourceid as xinput::DeviceId));
 
         let mut events = Vec::new();
+        let mut tablet_moved = false;
         let mut value = event.valuators.values;
         for i in 0..event.valuators.mask_len * 8 {
+            let physical_device = match physical_device.as_deref_mut() {
+                Some(device) => device,
+                None => break,
+            };
+
             if !xinput2::XIMaskIsSet(mask, i) {
                 continue;
             }
 
             let x = unsafe { *value };
 
+            if let Some(tablet) = physical_device.tablet.as_mut() {
+                tablet_moved |= tablet.update(i as _, x);
+            }
+
             if let Some(&mut (_, ref mut info)) =
                 physical_device.scroll_axes.iter_mut().find(|&&mut (axis, _)| axis == i as _)
             {
@@ -1112,11 +1419,31 @@ impl EventProcessor {
             value = unsafe { value.offset(1) };
         }
 
+        let source = match physical_device.and_then(|device| device.tablet.as_ref()) {
+            Some(tablet) => PointerSource::Tablet { tool: tablet.tool, state: tablet.state },
+            None => PointerSource::Mouse,
+        };
+        drop(devices);
+
+        if cursor_moved || tablet_moved {
+            let position = PhysicalPosition::new(event.event_x, event.event_y);
+
+            let event = WindowEvent::PointerMoved { device_id, primary: true, position, source };
+            app.window_event(&self.target, window_id, event);
+        }
+
         for event in events {
             app.window_event(&self.target, window_id, event);
         }
     }
 
+    /// The tool and its state, when the device is a drawing tablet.
+    fn tablet_tool(&self, device: c_int) -> Option<(TabletTool, TabletToolState)> {
+        let devices = self.devices.borrow();
+        let tablet = devices.get(&mkdid(device as xinput::DeviceId))?.tablet.as_ref()?;
+        Some((tablet.tool, tablet.state))
+    }
+
     fn xinput2_mouse_enter(&self, event: &XIEnterEvent, app: &mut dyn ApplicationHandler) {
         // Set the timestamp.
         self.target.xconn.set_timestamp(event.time as xproto::Timestamp);
@@ -1145,13 +1472,12 @@ impl EventProcessor {
         if self.window_exists(window) {
             let device_id = Some(device_id);
             let position = PhysicalPosition::new(event.event_x, event.event_y);
-
-            let event = WindowEvent::PointerEntered {
-                device_id,
-                primary: true,
-                position,
-                kind: PointerKind::Mouse,
+            let kind = match self.tablet_tool(event.sourceid) {
+                Some((tool, _)) => PointerKind::Tablet(tool),
+                None => PointerKind::Mouse,
             };
+
+            let event = WindowEvent::PointerEntered { device_id, primary: true, position, kind };
             app.window_event(&self.target, window_id, event);
         }
     }
@@ -1166,11 +1492,15 @@ impl EventProcessor {
         // been destroyed, which the user presumably doesn't want to deal with.
         if self.window_exists(window) {
             let window_id = mkwid(window);
+            let kind = match self.tablet_tool(event.sourceid) {
+                Some((tool, _)) => PointerKind::Tablet(tool),
+                None => PointerKind::Mouse,
+            };
             let event = WindowEvent::PointerLeft {
                 device_id: Some(mkdid(event.deviceid as xinput::DeviceId)),
                 primary: true,
                 position: Some(PhysicalPosition::new(event.event_x, event.event_y)),
-                kind: PointerKind::Mouse,
+                kind,
             };
             app.window_event(&self.target, window_id, event);
         }
@@ -1185,6 +1515,9 @@ impl EventProcessor {
         if let Some(ime) = self.target.ime.as_ref() {
             ime.borrow_mut().focus(xev.event).expect("Failed to focus input context");
         }
+        if let Some(ime) = self.target.dbus_ime.as_ref() {
+            ime.borrow_mut().focus(xev.event);
+        }
 
         if self.active_window == Some(window) {
             return;
@@ -1244,6 +1577,9 @@ impl EventProcessor {
         if let Some(ime) = self.target.ime.as_ref() {
             ime.borrow_mut().unfocus(xev.event).expect("Failed to unfocus input context");
         }
+        if let Some(ime) = self.target.dbus_ime.as_ref() {
+            ime.borrow_mut().unfocus(xev.event);
+        }
 
         if self.active_window.take() == Some(window) {
             let window_id = mkwid(window);
@@ -1275,6 +1611,29 @@ impl EventProcessor {
             }
 
             app.window_event(&self.target, window_id, WindowEvent::Focused(false));
+
+            self.dismiss_popups(window, app);
+        }
+    }
+
+    /// Dismiss the popups of the window, and the popups of those popups.
+    fn dismiss_popups(&self, parent: xproto::Window, app: &mut dyn ApplicationHandler) {
+        let popups: Vec<_> = self
+            .target
+            .windows
+            .borrow()
+            .iter()
+            .filter(|(_, window)| {
+                window
+                    .upgrade()
+                    .is_some_and(|window| window.shared_state_lock().popup_parent == Some(parent))
+            })
+            .map(|(window_id, _)| *window_id)
+            .collect();
+
+        for window_id in popups {
+            app.window_event(&self.target, window_id, WindowEvent::PopupDismissed);
+            self.dismiss_popups(window_id.into_raw() as xproto::Window, app);
         }
     }
 
@@ -1354,6 +1713,76 @@ impl EventProcessor {
         }
     }
 
+    fn xinput2_gesture_pinch(
+        &mut self,
+        xev: &ffi::XIGesturePinchEvent,
+        app: &mut dyn ApplicationHandler,
+    ) {
+        // Set the timestamp.
+        self.target.xconn.set_timestamp(xev.time as xproto::Timestamp);
+
+        let window = xev.event as xproto::Window;
+        if !self.window_exists(window) {
+            return;
+        }
+
+        let window_id = mkwid(window);
+        let device_id = Some(mkdid(xev.deviceid as xinput::DeviceId));
+
+        let (phase, delta, rotation) = match xev.evtype {
+            ffi::XI_GesturePinchBegin => {
+                self.pinch_scale = 1.;
+                (TouchPhase::Started, 0., 0.)
+            },
+            ffi::XI_GesturePinchUpdate => {
+                let delta = xev.scale - self.pinch_scale;
+                self.pinch_scale = xev.scale;
+                // The angle is clockwise, while winit's rotation is counterclockwise.
+                (TouchPhase::Moved, delta, -xev.delta_angle as f32)
+            },
+            _ if xev.flags & ffi::XIGesturePinchEventCancelled != 0 => {
+                (TouchPhase::Cancelled, 0., 0.)
+            },
+            _ => (TouchPhase::Ended, 0., 0.),
+        };
+
+        let event = WindowEvent::PinchGesture { device_id, delta, phase };
+        app.window_event(&self.target, window_id, event);
+        let event = WindowEvent::RotationGesture { device_id, delta: rotation, phase };
+        app.window_event(&self.target, window_id, event);
+    }
+
+    fn xinput2_gesture_swipe(
+        &mut self,
+        xev: &ffi::XIGestureSwipeEvent,
+        app: &mut dyn ApplicationHandler,
+    ) {
+        // Set the timestamp.
+        self.target.xconn.set_timestamp(xev.time as xproto::Timestamp);
+
+        let window = xev.event as xproto::Window;
+        if !self.window_exists(window) {
+            return;
+        }
+
+        let window_id = mkwid(window);
+        let device_id = Some(mkdid(xev.deviceid as xinput::DeviceId));
+
+        let (phase, delta) = match xev.evtype {
+            ffi::XI_GestureSwipeBegin => (TouchPhase::Started, PhysicalPosition::new(0., 0.)),
+            ffi::XI_GestureSwipeUpdate => {
+                (TouchPhase::Moved, PhysicalPosition::new(xev.delta_x as f32, xev.delta_y as f32))
+            },
+            _ if xev.flags & ffi::XIGestureSwipeEventCancelled != 0 => {
+                (TouchPhase::Cancelled, PhysicalPosition::new(0., 0.))
+            },
+            _ => (TouchPhase::Ended, PhysicalPosition::new(0., 0.)),
+        };
+
+        let event = WindowEvent::PanGesture { device_id, delta, phase };
+        app.window_event(&self.target, window_id, event);
+    }
+
     fn xinput2_raw_button_input(
         &self,
         xev: &XIRawEvent,
@@ -1431,16 +1860,28 @@ impl EventProcessor {
         app.device_event(&self.target, device_id, event);
     }
 
-    fn xinput2_hierarchy_changed(&mut self, xev: &XIHierarchyEvent) {
+    fn xinput2_hierarchy_changed(
+        &mut self,
+        xev: &XIHierarchyEvent,
+        app: &mut dyn ApplicationHandler,
+    ) {
         // Set the timestamp.
         self.target.xconn.set_timestamp(xev.time as xproto::Timestamp);
         let infos = unsafe { slice::from_raw_parts(xev.info, xev.num_info as usize) };
         for info in infos {
+            let device_id = mkdid(info.deviceid as xinput::DeviceId);
             if 0 != info.flags & (xinput2::XISlaveAdded | xinput2::XIMasterAdded) {
                 self.init_device(info.deviceid as xinput::DeviceId);
+                let added =
+                    self.devices.borrow().get(&device_id).and_then(|device| device.info.clone());
+                if let Some(info) = added {
+                    app.device_event(&self.target, Some(device_id), DeviceEvent::Added(info));
+                }
             } else if 0 != info.flags & (xinput2::XISlaveRemoved | xinput2::XIMasterRemoved) {
-                let mut devices = self.devices.borrow_mut();
-                devices.remove(&mkdid(info.deviceid as xinput::DeviceId));
+                let removed = self.devices.borrow_mut().remove(&device_id);
+                if removed.is_some_and(|device| device.info.is_some()) {
+                    app.device_event(&self.target, Some(device_id), DeviceEvent::Removed);
+                }
             }
         }
     }
@@ -1491,6 +1932,7 @@ impl EventProcessor {
                     self.send_modifiers(window_id, mods, true, app);
                 }
             },
+            xlib::XkbControlsNotify => self.update_input_preferences(app),
             xlib::XkbStateNotify => {
                 let xev = unsafe { &*(xev as *const _ as *const xlib::XkbStateNotifyEvent) };
 
@@ -1709,7 +2151,6 @@ impl EventProcessor {
     fn process_dpi_change(&self, app: &mut dyn ApplicationHandler) {
         self.target.xconn.reload_database().expect("failed to reload Xft database");
 
-        // In the future, it would be quite easy to emit monitor hotplug events.
         let prev_list = {
             let prev_list = self.target.xconn.invalidate_cached_monitor_list();
             match prev_list {
@@ -1719,14 +2160,31 @@ impl EventProcessor {
         };
 
         let new_list = self.target.xconn.available_monitors().expect("Failed to get monitor list");
+
+        for prev_monitor in &prev_list {
+            if !new_list.iter().any(|new_monitor| new_monitor.name == prev_monitor.name) {
+                let monitor = CoreMonitorHandle(Arc::new(prev_monitor.clone()));
+                app.monitor_event(&self.target, MonitorEvent::Removed(monitor));
+            }
+        }
+
         for new_monitor in new_list {
             // Previous list may be empty, in case of disconnecting and
             // reconnecting the only one monitor. We still need to emit events in
             // this case.
-            let maybe_prev_scale_factor = prev_list
-                .iter()
-                .find(|prev_monitor| prev_monitor.name == new_monitor.name)
-                .map(|prev_monitor| prev_monitor.scale_factor);
+            let prev_monitor =
+                prev_list.iter().find(|prev_monitor| prev_monitor.name == new_monitor.name);
+            let monitor = CoreMonitorHandle(Arc::new(new_monitor.clone()));
+            match prev_monitor {
+                None => app.monitor_event(&self.target, MonitorEvent::Added(monitor)),
+                Some(prev_monitor) if !prev_monitor.same_configuration(&new_monitor) => {
+                    app.monitor_event(&self.target, MonitorEvent::Changed(monitor))
+                },
+                Some(_) => (),
+            }
+
+            let maybe_prev_scale_factor =
+                prev_monitor.map(|prev_monitor| prev_monitor.scale_factor);
             if Some(new_monitor.scale_factor) != maybe_prev_scale_factor {
                 for window in self.target.windows.borrow().iter().filter_map(|(_, w)| w.upgrade()) {
                     window.refresh_dpi_for_monitor(
@@ -1,4 +1,87 @@
+use std::os::raw::{c_int, c_ulong};
+
 pub use x11_dl::error::OpenError;
+pub use x11_dl::xfixes::Xlib as XFixes;
 pub use x11_dl::xinput2::*;
 pub use x11_dl::xlib::*;
 pub use x11_dl::xlib_xcb::*;
+
+// XInput 2.4 gesture events, which aren't provided by `x11_dl`.
+
+#[allow(non_upper_case_globals)]
+pub const XI_GesturePinchBegin: c_int = 27;
+#[allow(non_upper_case_globals)]
+pub const XI_GesturePinchUpdate: c_int = 28;
+#[allow(non_upper_case_globals)]
+pub const XI_GesturePinchEnd: c_int = 29;
+#[allow(non_upper_case_globals)]
+pub const XI_GestureSwipeBegin: c_int = 30;
+#[allow(non_upper_case_globals)]
+pub const XI_GestureSwipeUpdate: c_int = 31;
+#[allow(non_upper_case_globals)]
+pub const XI_GestureSwipeEnd: c_int = 32;
+
+#[allow(non_upper_case_globals)]
+pub const XIGesturePinchEventCancelled: c_int = 1 << 0;
+#[allow(non_upper_case_globals)]
+pub const XIGestureSwipeEventCancelled: c_int = 1 << 0;
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct XIGesturePinchEvent {
+    pub _type: c_int,
+    pub serial: c_ulong,
+    pub send_event: Bool,
+    pub display: *mut Display,
+    pub extension: c_int,
+    pub evtype: c_int,
+    pub time: Time,
+    pub deviceid: c_int,
+    pub sourceid: c_int,
+    pub detail: c_int,
+    pub root: Window,
+    pub event: Window,
+    pub child: Window,
+    pub root_x: f64,
+    pub root_y: f64,
+    pub event_x: f64,
+    pub event_y: f64,
+    pub delta_x: f64,
+    pub delta_y: f64,
+    pub delta_unaccel_x: f64,
+    pub delta_unaccel_y: f64,
+    pub scale: f64,
+    pub delta_angle: f64,
+    pub flags: c_int,
+    pub mods: XIModifierState,
+    pub group: XIGroupState,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct XIGestureSwipeEvent {
+    pub _type: c_int,
+    pub serial: c_ulong,
+    pub send_event: Bool,
+    pub display: *mut Display,
+    pub extension: c_int,
+    pub evtype: c_int,
+    pub time: Time,
+    pub deviceid: c_int,
+    pub sourceid: c_int,
+    pub detail: c_int,
+    pub root: Window,
+    pub event: Window,
+    pub child: Window,
+    pub root_x: f64,
+    pub root_y: f64,
+    pub event_x: f64,
+    pub event_y: f64,
+    pub delta_x: f64,
+    pub delta_y: f64,
+    pub delta_unaccel_x: f64,
+    pub delta_unaccel_y: f64,
+    pub flags: c_int,
+    pub mods: XIModifierState,
+    pub group: XIGroupState,
+}
@@ -0,0 +1,813 @@
+//! Input method frontend talking to IBus or Fcitx5 over D-Bus.
+//!
+//! Unlike XIM, the key events are sent to the input method explicitly, and the composed text is
+//! delivered through signals of the input context.
+
+use std::collections::{HashMap, VecDeque};
+use std::os::fd::{AsFd, BorrowedFd};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{env, fs, io, mem};
+
+use tracing::debug;
+
+use super::{ffi, ImeEvent, ImeEventSender, XConnection};
+use crate::platform::x11::ImeBackend;
+use crate::platform_impl::common::dbus::{Connection, Message, MessageKind, Value};
+
+const IBUS_DESTINATION: &str = "org.freedesktop.IBus";
+const IBUS_PORTAL_DESTINATION: &str = "org.freedesktop.portal.IBus";
+const IBUS_PATH: &str = "/org/freedesktop/IBus";
+const IBUS_INTERFACE: &str = "org.freedesktop.IBus";
+const IBUS_PORTAL_INTERFACE: &str = "org.freedesktop.IBus.Portal";
+const IBUS_INPUT_CONTEXT_INTERFACE: &str = "org.freedesktop.IBus.InputContext";
+const IBUS_SERVICE_INTERFACE: &str = "org.freedesktop.IBus.Service";
+
+/// `IBUS_CAP_PREEDIT_TEXT | IBUS_CAP_FOCUS`.
+const IBUS_CAPABILITIES: u32 = 1 | 1 << 3;
+/// The modifier set on the released keys.
+const IBUS_RELEASE_MASK: u32 = 1 << 30;
+
+const FCITX_DESTINATIONS: [&str; 2] = ["org.fcitx.Fcitx5", "org.freedesktop.portal.Fcitx"];
+const FCITX_PATH: &str = "/org/freedesktop/portal/inputmethod";
+const FCITX_INTERFACE: &str = "org.fcitx.Fcitx.InputMethod1";
+const FCITX_INPUT_CONTEXT_INTERFACE: &str = "org.fcitx.Fcitx.InputContext1";
+
+/// `CapabilityFlag::Preedit | CapabilityFlag::FormattedPreedit`.
+const FCITX_CAPABILITIES: u64 = 1 << 1 | 1 << 4;
+
+/// How long to wait for the input method, the key events are held until it replies.
+const CALL_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    IBus { portal: bool },
+    Fcitx,
+}
+
+impl Protocol {
+    fn input_context_interface(self) -> &'static str {
+        match self {
+            Protocol::IBus { .. } => IBUS_INPUT_CONTEXT_INTERFACE,
+            Protocol::Fcitx => FCITX_INPUT_CONTEXT_INTERFACE,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct InputContext {
+    /// The object path, `None` until the input method created the context.
+    path: Option<String>,
+    /// The area requested while the context was being created, relative to the screen.
+    area: Option<[i32; 4]>,
+    /// Whether the preedit is shown.
+    preedit: bool,
+}
+
+/// A key event held until the input method tells whether it consumed it.
+#[derive(Debug)]
+struct PendingKey {
+    event: ffi::XKeyEvent,
+    /// The serial of the `ProcessKeyEvent` call, `None` once answered or when not sent.
+    serial: Option<u32>,
+    consumed: bool,
+    sent: Instant,
+}
+
+impl PendingKey {
+    fn is_ready(&self, now: Instant) -> bool {
+        self.serial.is_none() || now >= self.sent + CALL_TIMEOUT
+    }
+}
+
+/// The key events to deliver, in order.
+#[derive(Debug, Default)]
+struct KeyQueue {
+    keys: VecDeque<PendingKey>,
+}
+
+impl KeyQueue {
+    fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Queue the key event, held until the reply of the call with the serial, if any.
+    fn push(&mut self, event: ffi::XKeyEvent, serial: Option<u32>, now: Instant) {
+        self.keys.push_back(PendingKey { event, serial, consumed: false, sent: now });
+    }
+
+    /// Release the key event held for the reply, returns whether one was waiting for it.
+    fn resolve(&mut self, serial: u32, consumed: bool) -> bool {
+        let Some(key) = self.keys.iter_mut().find(|key| key.serial == Some(serial)) else {
+            return false;
+        };
+        key.serial = None;
+        key.consumed = consumed;
+        true
+    }
+
+    /// The next key event the input method didn't consume, once it answered or timed out.
+    fn next(&mut self, now: Instant) -> Option<ffi::XKeyEvent> {
+        while self.keys.front()?.is_ready(now) {
+            let key = self.keys.pop_front().unwrap();
+            if key.serial.is_some() {
+                debug!("the input method didn't answer in time, delivering the key event");
+            }
+            if !key.consumed {
+                return Some(key.event);
+            }
+        }
+        None
+    }
+
+    /// When the key event waiting for the input method's answer is delivered anyway.
+    fn deadline(&self) -> Option<Instant> {
+        let key = self.keys.front()?;
+        key.serial.map(|_| key.sent + CALL_TIMEOUT)
+    }
+
+    /// Whether the next key event can be delivered.
+    fn has_ready(&self, now: Instant) -> bool {
+        self.keys.front().is_some_and(|key| key.is_ready(now))
+    }
+}
+
+/// The input contexts of the windows allowing IME, on a D-Bus input method.
+///
+/// The calls don't wait for their replies, which are matched by serial when dispatching the
+/// messages of the input method.
+#[derive(Debug)]
+pub(crate) struct DbusIme {
+    xconn: Arc<XConnection>,
+    connection: Connection,
+    protocol: Protocol,
+    destination: String,
+    contexts: HashMap<ffi::Window, InputContext>,
+    /// The windows of the `CreateInputContext` calls, by serial.
+    creating: HashMap<u32, ffi::Window>,
+    keys: KeyQueue,
+    focused: Option<ffi::Window>,
+    event_sender: ImeEventSender,
+}
+
+impl DbusIme {
+    /// Connect to the input method picked by the backend.
+    ///
+    /// Returns `None` when XIM should be used instead.
+    pub fn new(
+        backend: ImeBackend,
+        xconn: Arc<XConnection>,
+        event_sender: ImeEventSender,
+    ) -> Option<Self> {
+        let backend = match backend {
+            ImeBackend::Auto => detect_backend()?,
+            ImeBackend::Xim => return None,
+            backend => backend,
+        };
+
+        let connected = match backend {
+            ImeBackend::Fcitx => connect_fcitx(),
+            _ => connect_ibus(),
+        };
+        let (mut connection, protocol, destination) = match connected {
+            Ok(connected) => connected,
+            Err(err) => {
+                debug!("failed to connect to the {backend:?} input method, using XIM: {err}");
+                return None;
+            },
+        };
+
+        let rule = format!("type='signal',interface='{}'", protocol.input_context_interface());
+        if let Err(err) = with_timeout(&mut connection, |connection| connection.add_match(&rule)) {
+            debug!("failed to subscribe to the {backend:?} input method, using XIM: {err}");
+            return None;
+        }
+
+        Some(Self {
+            xconn,
+            connection,
+            protocol,
+            destination,
+            contexts: HashMap::new(),
+            creating: HashMap::new(),
+            keys: KeyQueue::default(),
+            focused: None,
+            event_sender,
+        })
+    }
+
+    pub fn is_ime_allowed(&self, window: ffi::Window) -> bool {
+        self.contexts.contains_key(&window)
+    }
+
+    pub fn set_ime_allowed(&mut self, window: ffi::Window, allowed: bool) {
+        if allowed == self.is_ime_allowed(window) {
+            return;
+        }
+
+        if !allowed {
+            if self.remove_context(window) {
+                let _ = self.event_sender.send((window, ImeEvent::Disabled));
+            }
+            return;
+        }
+
+        match self.create_input_context() {
+            Ok(serial) => {
+                self.creating.insert(serial, window);
+                self.contexts.insert(window, InputContext {
+                    path: None,
+                    area: None,
+                    preedit: false,
+                });
+            },
+            Err(err) => debug!("failed to create the input context: {err}"),
+        }
+    }
+
+    /// Destroy the input context of the window, returns whether it was created already.
+    pub fn remove_context(&mut self, window: ffi::Window) -> bool {
+        let Some(InputContext { path: Some(path), .. }) = self.contexts.remove(&window) else {
+            return false;
+        };
+        self.destroy_input_context(&path);
+        true
+    }
+
+    pub fn focus(&mut self, window: ffi::Window) {
+        self.focused = Some(window);
+        self.call_context(window, "FocusIn", vec![]);
+    }
+
+    pub fn unfocus(&mut self, window: ffi::Window) {
+        if self.focused == Some(window) {
+            self.focused = None;
+        }
+        self.call_context(window, "FocusOut", vec![]);
+    }
+
+    /// Place the candidate window next to the area, relative to the window.
+    pub fn set_area(&mut self, window: ffi::Window, x: i16, y: i16, w: u16, h: u16) {
+        if !self.is_ime_allowed(window) {
+            return;
+        }
+
+        // The input methods expect the area relative to the screen.
+        let root = self.xconn.default_root().root;
+        let (x, y) = match self.xconn.translate_coords(window as _, root, x, y) {
+            Ok(reply) => (reply.dst_x, reply.dst_y),
+            Err(err) => {
+                debug!("failed to translate the IME area: {err}");
+                return;
+            },
+        };
+
+        let area = [x.into(), y.into(), w.into(), h.into()];
+        match self.contexts.get_mut(&window) {
+            Some(InputContext { path: None, area: pending, .. }) => *pending = Some(area),
+            _ => self.send_area(window, area),
+        }
+    }
+
+    /// Send the key event to the input method, returns whether it's held until the input method
+    /// answers, see [`Self::next_key`].
+    ///
+    /// The `keysym` is the one of the event, looked up with the current keyboard state.
+    pub fn process_key(
+        &mut self,
+        window: ffi::Window,
+        event: &ffi::XKeyEvent,
+        keysym: u32,
+    ) -> bool {
+        let (keycode, state) = (event.keycode, event.state);
+        let released = event.type_ == ffi::KeyRelease;
+        let body = match self.protocol {
+            Protocol::IBus { .. } => {
+                let state = if released { state | IBUS_RELEASE_MASK } else { state };
+                // IBus uses the evdev keycodes.
+                vec![Value::U32(keysym), Value::U32(keycode.saturating_sub(8)), Value::U32(state)]
+            },
+            Protocol::Fcitx => vec![
+                Value::U32(keysym),
+                Value::U32(keycode),
+                Value::U32(state),
+                Value::Bool(released),
+                Value::U32(event.time as u32),
+            ],
+        };
+
+        let serial = self.call_context(window, "ProcessKeyEvent", body);
+        // Keep the order of the events behind the ones waiting for an answer.
+        if serial.is_none() && self.keys.is_empty() {
+            return false;
+        }
+
+        self.keys.push(*event, serial, Instant::now());
+        true
+    }
+
+    /// The next key event the input method didn't consume, once it answered or timed out.
+    pub fn next_key(&mut self) -> Option<ffi::XKeyEvent> {
+        self.keys.next(Instant::now())
+    }
+
+    /// When the key event waiting for the input method's answer is delivered anyway.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.keys.deadline()
+    }
+
+    /// Whether messages were read ahead, or key events are ready to be delivered.
+    pub fn has_pending(&self) -> bool {
+        self.connection.has_queued() || self.keys.has_ready(Instant::now())
+    }
+
+    /// Handle the messages received from the input method.
+    pub fn dispatch(&mut self) {
+        loop {
+            match self.connection.try_receive() {
+                Ok(Some(message)) => self.handle_message(message),
+                Ok(None) => break,
+                Err(err) => {
+                    debug!("failed to read from the input method: {err}");
+                    break;
+                },
+            }
+        }
+    }
+
+    fn handle_message(&mut self, message: Message) {
+        match message.kind {
+            MessageKind::MethodReturn | MessageKind::Error => self.handle_reply(message),
+            MessageKind::Signal => self.handle_signal(message),
+            MessageKind::MethodCall => (),
+        }
+    }
+
+    fn handle_reply(&mut self, message: Message) {
+        let Some(serial) = message.reply_serial else { return };
+        let error = (message.kind == MessageKind::Error).then(|| {
+            let name = message.error_name.as_deref().unwrap_or_default();
+            let description = message.body.first().and_then(Value::as_str).unwrap_or("");
+            format!("{name}: {description}")
+        });
+
+        // The reply of `ProcessKeyEvent` tells whether the key event was consumed.
+        let consumed = message.body.first().and_then(Value::as_bool).unwrap_or(false);
+
+        if let Some(window) = self.creating.remove(&serial) {
+            let path = match (error, message.body.first().and_then(Value::as_str)) {
+                (None, Some(path)) => path.to_owned(),
+                (error, _) => {
+                    let error = error.as_deref().unwrap_or("no input context");
+                    debug!("failed to create the input context: {error}");
+                    // Stop tracking the window, so it can be allowed again.
+                    if self.contexts.get(&window).is_some_and(|context| context.path.is_none()) {
+                        self.contexts.remove(&window);
+                    }
+                    return;
+                },
+            };
+            self.input_context_created(window, path);
+        } else if self.keys.resolve(serial, consumed) {
+            if let Some(error) = error {
+                debug!("the input method failed to process the key event: {error}");
+            }
+        } else if let Some(error) = error {
+            debug!("input context call failed: {error}");
+        }
+    }
+
+    fn input_context_created(&mut self, window: ffi::Window, path: String) {
+        let context = match self.contexts.get_mut(&window) {
+            Some(context) if context.path.is_none() => context,
+            // IME was disallowed in the meantime, or allowed again and created twice.
+            _ => return self.destroy_input_context(&path),
+        };
+        context.path = Some(path);
+        let area = context.area.take();
+
+        let capabilities = match self.protocol {
+            Protocol::IBus { .. } => ("SetCapabilities", Value::U32(IBUS_CAPABILITIES)),
+            Protocol::Fcitx => ("SetCapability", Value::U64(FCITX_CAPABILITIES)),
+        };
+        self.call_context(window, capabilities.0, vec![capabilities.1]);
+        let _ = self.event_sender.send((window, ImeEvent::Enabled));
+
+        if self.focused == Some(window) {
+            self.call_context(window, "FocusIn", vec![]);
+        }
+        if let Some(area) = area {
+            self.send_area(window, area);
+        }
+    }
+
+    fn handle_signal(&mut self, message: Message) {
+        let Some((&window, context)) = self
+            .contexts
+            .iter_mut()
+            .find(|(_, context)| message.path.is_some() && message.path == context.path)
+        else {
+            return;
+        };
+
+        let interface = self.protocol.input_context_interface();
+        if message.interface.as_deref() != Some(interface) {
+            return;
+        }
+        let Some(member) = message.member.as_deref() else { return };
+
+        let preedit = match (self.protocol, member, message.body.as_slice()) {
+            (Protocol::IBus { .. }, "CommitText", [text, ..]) => {
+                let Some(text) = ibus_text(text) else { return };
+                context.preedit = false;
+                let _ = self.event_sender.send((window, ImeEvent::Commit(text.to_owned())));
+                return;
+            },
+            (Protocol::Fcitx, "CommitString", [text, ..]) => {
+                let Some(text) = text.as_str() else { return };
+                context.preedit = false;
+                let _ = self.event_sender.send((window, ImeEvent::Commit(text.to_owned())));
+                return;
+            },
+            (Protocol::IBus { .. }, "ForwardKeyEvent", [keysym, keycode, state, ..]) => {
+                let (Some(keysym), Some(keycode), Some(state)) =
+                    (keysym.as_u32(), keycode.as_u32(), state.as_u32())
+                else {
+                    return;
+                };
+                let released = state & IBUS_RELEASE_MASK != 0;
+                // The keycode is the evdev one, when known.
+                let keycode = if keycode != 0 { keycode + 8 } else { 0 };
+                return self.forward_key(
+                    window,
+                    keysym,
+                    keycode,
+                    state & !IBUS_RELEASE_MASK,
+                    released,
+                );
+            },
+            (Protocol::Fcitx, "ForwardKey", [keysym, state, released, ..]) => {
+                let (Some(keysym), Some(state), Some(released)) =
+                    (keysym.as_u32(), state.as_u32(), released.as_bool())
+                else {
+                    return;
+                };
+                return self.forward_key(window, keysym, 0, state, released);
+            },
+            (Protocol::IBus { .. }, "UpdatePreeditText", [text, cursor, visible, ..]) => {
+                let text = ibus_text(text).unwrap_or_default();
+                let visible = visible.as_bool().unwrap_or(true);
+                // The cursor is in characters.
+                let cursor = cursor.as_i64().unwrap_or_default() as usize;
+                let cursor = text.char_indices().nth(cursor).map_or(text.len(), |(i, _)| i);
+                visible.then(|| (text.to_owned(), cursor))
+            },
+            (Protocol::IBus { .. }, "HidePreeditText", _) => None,
+            (Protocol::Fcitx, "UpdateFormattedPreedit", [segments, cursor, ..]) => {
+                let Value::Array(_, segments) = segments else { return };
+                let text: String = segments
+                    .iter()
+                    .filter_map(|segment| match segment {
+                        Value::Struct(fields) => fields.first()?.as_str(),
+                        _ => None,
+                    })
+                    .collect();
+                // The cursor is in bytes, and negative when hidden.
+                let cursor = cursor
+                    .as_i64()
+                    .and_then(|cursor| usize::try_from(cursor).ok())
+                    .filter(|&cursor| text.is_char_boundary(cursor))
+                    .unwrap_or(text.len());
+                Some((text, cursor))
+            },
+            _ => return,
+        };
+
+        match preedit.filter(|(text, _)| !text.is_empty()) {
+            Some((text, cursor)) => {
+                if !context.preedit {
+                    context.preedit = true;
+                    let _ = self.event_sender.send((window, ImeEvent::Start));
+                }
+                let _ = self.event_sender.send((window, ImeEvent::Update(text, cursor)));
+            },
+            None if context.preedit => {
+                context.preedit = false;
+                let _ = self.event_sender.send((window, ImeEvent::End));
+            },
+            None => (),
+        }
+    }
+
+    /// Deliver a key event the input method sent back, after the held ones.
+    fn forward_key(
+        &mut self,
+        window: ffi::Window,
+        keysym: u32,
+        keycode: u32,
+        state: u32,
+        released: bool,
+    ) {
+        let keycode = match keycode {
+            0 => unsafe {
+                (self.xconn.xlib.XKeysymToKeycode)(self.xconn.display, keysym.into()).into()
+            },
+            keycode => keycode,
+        };
+        if keycode == 0 {
+            debug!("no keycode for the keysym {keysym:#x} forwarded by the input method");
+            return;
+        }
+
+        // SAFETY: the event is plain data, all zeroes is valid.
+        let mut event: ffi::XKeyEvent = unsafe { mem::zeroed() };
+        event.type_ = if released { ffi::KeyRelease } else { ffi::KeyPress };
+        event.display = self.xconn.display;
+        event.window = window;
+        event.root = self.xconn.default_root().root as ffi::Window;
+        event.time = self.xconn.timestamp().into();
+        event.state = state;
+        event.keycode = keycode;
+        event.same_screen = ffi::True;
+        self.keys.push(event, None, Instant::now());
+    }
+
+    fn create_input_context(&mut self) -> io::Result<u32> {
+        let program = env::args_os()
+            .next()
+            .map(PathBuf::from)
+            .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "winit".into());
+
+        let (path, interface, body) = match self.protocol {
+            Protocol::IBus { portal } => {
+                let interface = if portal { IBUS_PORTAL_INTERFACE } else { IBUS_INTERFACE };
+                (IBUS_PATH, interface, vec![Value::String(program)])
+            },
+            Protocol::Fcitx => {
+                let program =
+                    Value::Struct(vec![Value::String("program".into()), Value::String(program)]);
+                (FCITX_PATH, FCITX_INTERFACE, vec![Value::Array("(ss)".into(), vec![program])])
+            },
+        };
+
+        let message =
+            Message::method_call(&self.destination, path, interface, "CreateInputContext", body);
+        self.connection.send(message)
+    }
+
+    fn destroy_input_context(&mut self, path: &str) {
+        let (interface, method) = match self.protocol {
+            Protocol::IBus { .. } => (IBUS_SERVICE_INTERFACE, "Destroy"),
+            Protocol::Fcitx => (FCITX_INPUT_CONTEXT_INTERFACE, "DestroyIC"),
+        };
+        let message = Message::method_call(&self.destination, path, interface, method, vec![]);
+        if let Err(err) = self.connection.send(message) {
+            debug!("failed to destroy the input context: {err}");
+        }
+    }
+
+    fn send_area(&mut self, window: ffi::Window, area: [i32; 4]) {
+        let method = match self.protocol {
+            Protocol::IBus { .. } => "SetCursorLocation",
+            Protocol::Fcitx => "SetCursorRect",
+        };
+        self.call_context(window, method, area.into_iter().map(Value::I32).collect());
+    }
+
+    /// Call a method on the input context of the window, if it was created, returns the serial.
+    fn call_context(&mut self, window: ffi::Window, method: &str, body: Vec<Value>) -> Option<u32> {
+        let path = self.contexts.get(&window)?.path.as_deref()?;
+        let interface = self.protocol.input_context_interface();
+        let message = Message::method_call(&self.destination, path, interface, method, body);
+        self.connection
+            .send(message)
+            .inspect_err(|err| debug!("input context call {method} failed: {err}"))
+            .ok()
+    }
+}
+
+impl AsFd for DbusIme {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.connection.as_fd()
+    }
+}
+
+impl Drop for DbusIme {
+    fn drop(&mut self) {
+        let windows: Vec<_> = self.contexts.keys().copied().collect();
+        for window in windows {
+            self.remove_context(window);
+        }
+    }
+}
+
+/// Pick the input method from the environment configuring the toolkits.
+fn detect_backend() -> Option<ImeBackend> {
+    detect_backend_with(|name| env::var(name).ok())
+}
+
+/// Pick the input method from the variables, `XMODIFIERS` first, then the toolkits' ones.
+fn detect_backend_with(var: impl Fn(&str) -> Option<String>) -> Option<ImeBackend> {
+    let xmodifiers = var("XMODIFIERS");
+    let xmodifiers = xmodifiers.as_deref().and_then(|value| value.split_once("@im="));
+    let modules =
+        [xmodifiers.map(|(_, name)| name.to_owned()), var("GTK_IM_MODULE"), var("QT_IM_MODULE")];
+
+    modules.into_iter().flatten().find_map(|module| {
+        let module = module.to_ascii_lowercase();
+        if module.starts_with("ibus") {
+            Some(ImeBackend::IBus)
+        } else if module.starts_with("fcitx") {
+            Some(ImeBackend::Fcitx)
+        } else {
+            None
+        }
+    })
+}
+
+fn connect_ibus() -> io::Result<(Connection, Protocol, String)> {
+    if let Some(address) = ibus_address() {
+        match Connection::open(&address) {
+            Ok(connection) => {
+                return Ok((connection, Protocol::IBus { portal: false }, IBUS_DESTINATION.into()))
+            },
+            Err(err) => debug!("failed to connect to the IBus daemon, trying its portal: {err}"),
+        }
+    }
+
+    // Sandboxed applications can only reach IBus through its portal on the session bus.
+    let mut connection = Connection::session()?;
+    if !has_owner(&mut connection, IBUS_PORTAL_DESTINATION)? {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "IBus isn't running"));
+    }
+    Ok((connection, Protocol::IBus { portal: true }, IBUS_PORTAL_DESTINATION.into()))
+}
+
+/// The address of the IBus daemon's own bus.
+fn ibus_address() -> Option<String> {
+    if let Ok(address) = env::var("IBUS_ADDRESS") {
+        return Some(address);
+    }
+
+    // The daemon writes its address to a file named after the machine and the display.
+    let display = env::var("DISPLAY").ok()?;
+    let (host, number) = display.rsplit_once(':')?;
+    let host = if host.is_empty() { "unix" } else { host };
+    let number = number.split('.').next()?;
+
+    let machine_id = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .into_iter()
+        .find_map(|path| fs::read_to_string(path).ok())?;
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(".config")))?;
+    let path = config_dir.join("ibus/bus").join(format!("{}-{host}-{number}", machine_id.trim()));
+
+    fs::read_to_string(path).ok()?.lines().find_map(|line| {
+        let address = line.strip_prefix("IBUS_ADDRESS=")?;
+        Some(address.to_owned())
+    })
+}
+
+fn connect_fcitx() -> io::Result<(Connection, Protocol, String)> {
+    let mut connection = Connection::session()?;
+
+    // Fcitx5 is reachable under its own name, or the name of its portal.
+    let mut last_err = None;
+    for destination in FCITX_DESTINATIONS {
+        match has_owner(&mut connection, destination) {
+            Ok(true) => return Ok((connection, Protocol::Fcitx, destination.into())),
+            Ok(false) => (),
+            Err(err) => last_err = Some(err),
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Fcitx5 isn't running")))
+}
+
+/// Whether a client owns the name on the bus.
+fn has_owner(connection: &mut Connection, name: &str) -> io::Result<bool> {
+    let message = Message::method_call(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        "org.freedesktop.DBus",
+        "NameHasOwner",
+        vec![Value::String(name.into())],
+    );
+    let reply = with_timeout(connection, |connection| connection.call(message))?;
+    Ok(reply.first().and_then(Value::as_bool) == Some(true))
+}
+
+/// Wait for the bus only for a while, as the calls are made when starting the event loop.
+///
+/// The reads wait indefinitely again afterwards.
+fn with_timeout<T>(
+    connection: &mut Connection,
+    f: impl FnOnce(&mut Connection) -> io::Result<T>,
+) -> io::Result<T> {
+    connection.set_timeout(Some(CALL_TIMEOUT))?;
+    let result = f(connection);
+    connection.set_timeout(None)?;
+    result
+}
+
+/// The string of a serialized `IBusText`.
+fn ibus_text(text: &Value) -> Option<&str> {
+    // `(sa{sv}sv)`: the type name, the attachments, the text and the attributes.
+    match text.peel() {
+        Value::Struct(fields) => fields.get(2)?.as_str(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ibus_text() {
+        let text = Value::Variant(Box::new(Value::Struct(vec![
+            Value::String("IBusText".into()),
+            Value::Array("{sv}".into(), vec![]),
+            Value::String("日本".into()),
+            Value::Variant(Box::new(Value::Struct(vec![]))),
+        ])));
+        assert_eq!(ibus_text(&text), Some("日本"));
+        assert_eq!(ibus_text(&Value::String("text".into())), None);
+    }
+
+    #[test]
+    fn backend_from_env() {
+        let detect = |vars: &[(&str, &str)]| {
+            let vars: HashMap<_, _> = vars.iter().copied().collect();
+            detect_backend_with(|name| vars.get(name).map(|value| value.to_string()))
+        };
+
+        assert_eq!(detect(&[]), None);
+        assert_eq!(detect(&[("GTK_IM_MODULE", "IBus")]), Some(ImeBackend::IBus));
+        assert_eq!(detect(&[("QT_IM_MODULE", "fcitx5")]), Some(ImeBackend::Fcitx));
+        // `XMODIFIERS` is preferred over the toolkits' variables.
+        assert_eq!(
+            detect(&[("XMODIFIERS", "@im=fcitx"), ("GTK_IM_MODULE", "ibus")]),
+            Some(ImeBackend::Fcitx)
+        );
+        assert_eq!(
+            detect(&[("GTK_IM_MODULE", "ibus"), ("QT_IM_MODULE", "fcitx")]),
+            Some(ImeBackend::IBus)
+        );
+        // Unknown input methods are skipped.
+        assert_eq!(
+            detect(&[("XMODIFIERS", "@im=xim"), ("QT_IM_MODULE", "ibus")]),
+            Some(ImeBackend::IBus)
+        );
+    }
+
+    fn key(keycode: u32) -> ffi::XKeyEvent {
+        // SAFETY: the event is plain data, all zeroes is valid.
+        let mut event: ffi::XKeyEvent = unsafe { mem::zeroed() };
+        event.keycode = keycode;
+        event
+    }
+
+    #[test]
+    fn keys_resolved_by_replies() {
+        let now = Instant::now();
+        let mut keys = KeyQueue::default();
+        keys.push(key(10), Some(1), now);
+        keys.push(key(11), Some(2), now);
+        // A forwarded key event is delivered after the held ones.
+        keys.push(key(12), None, now);
+        assert_eq!(keys.deadline(), Some(now + CALL_TIMEOUT));
+
+        // The key events are held in order, even when a later one is answered first.
+        assert!(keys.resolve(2, false));
+        assert!(!keys.has_ready(now));
+        assert!(keys.next(now).is_none());
+
+        // The consumed key event is dropped.
+        assert!(keys.resolve(1, true));
+        assert!(!keys.resolve(1, true));
+        assert!(keys.has_ready(now));
+        assert_eq!(keys.next(now).map(|event| event.keycode), Some(11));
+        assert_eq!(keys.next(now).map(|event| event.keycode), Some(12));
+        assert!(keys.next(now).is_none());
+        assert!(keys.is_empty());
+        assert_eq!(keys.deadline(), None);
+    }
+
+    #[test]
+    fn keys_delivered_on_timeout() {
+        let now = Instant::now();
+        let mut keys = KeyQueue::default();
+        keys.push(key(10), Some(1), now);
+        assert!(keys.next(now + CALL_TIMEOUT / 2).is_none());
+
+        let later = now + CALL_TIMEOUT;
+        assert!(keys.has_ready(later));
+        assert_eq!(keys.next(later).map(|event| event.keycode), Some(10));
+        // The late reply is ignored.
+        assert!(!keys.resolve(1, true));
+    }
+}
@@ -2,6 +2,7 @@
 
 mod callbacks;
 mod context;
+mod dbus;
 mod inner;
 mod input_method;
 
@@ -15,6 +16,7 @@ use serde::{Deserialize, Serialize};
 use self::callbacks::*;
 use self::context::ImeContext;
 pub use self::context::ImeContextCreationError;
+pub(crate) use self::dbus::DbusIme;
 use self::inner::{close_im, ImeInner};
 use self::input_method::PotentialInputMethods;
 use super::{ffi, util, XConnection, XError};
@@ -25,6 +27,8 @@ pub enum ImeEvent {
     Enabled,
     Start,
     Update(String, usize),
+    /// Text committed by an input method which isn't XIM.
+    Commit(String),
     End,
     Disabled,
 }
@@ -34,7 +38,7 @@ pub type ImeSender = Sender<ImeRequest>;
 pub type ImeEventReceiver = Receiver<(ffi::Window, ImeEvent)>;
 pub type ImeEventSender = Sender<(ffi::Window, ImeEvent)>;
 
-/// Request to control XIM handler from the window.
+/// Request to control the IME handler from the window.
 pub enum ImeRequest {
     /// Set IME preedit area for given `window_id`.
     Area(ffi::Window, i16, i16, u16, u16),
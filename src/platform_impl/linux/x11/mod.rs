@@ -16,13 +16,18 @@ use calloop::{EventLoop as Loop, Readiness};
 use libc::{setlocale, LC_CTYPE};
 use tracing::warn;
 use winit_core::application::ApplicationHandler;
+use winit_core::clipboard::{Clipboard as CoreClipboard, ClipboardEvent, ClipboardKind};
 use winit_core::cursor::{CustomCursor as CoreCustomCursor, CustomCursorSource};
 use winit_core::error::{EventLoopError, RequestError};
-use winit_core::event::{DeviceId, StartCause, WindowEvent};
+use winit_core::event::{
+    DeviceId, DeviceInfo as CoreDeviceInfo, DeviceKind, DragAction, DragSourceEvent, Force,
+    StartCause, TabletButton, TabletTool, TabletToolKind, TabletToolState, TabletToolTilt,
+    WindowEvent,
+};
 use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::{
-    ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents,
-    EventLoopProxy as CoreEventLoopProxy, EventLoopProxyProvider,
+    ActiveEventLoop as RootActiveEventLoop, AsyncRequestSerial, ControlFlow, DeviceEvents,
+    EventLoopProxy as CoreEventLoopProxy, EventLoopProxyProvider, InputPreferences,
     OwnedDisplayHandle as CoreOwnedDisplayHandle,
 };
 use winit_core::monitor::MonitorHandle as CoreMonitorHandle;
@@ -30,17 +35,20 @@ use winit_core::window::{Theme, Window as CoreWindow, WindowAttributes, WindowId
 use x11rb::connection::RequestConnection;
 use x11rb::errors::{ConnectError, ConnectionError, IdsExhausted, ReplyError};
 use x11rb::protocol::xinput::{self, ConnectionExt as _};
-use x11rb::protocol::{xkb, xproto};
+use x11rb::protocol::xkb::{self, ConnectionExt as _};
+use x11rb::protocol::xproto;
 use x11rb::x11_utils::X11Error as LogicalError;
 use x11rb::xcb_ffi::ReplyOrIdError;
 
-use crate::platform::x11::XlibErrorHook;
+use crate::platform::x11::{XSettings, XlibErrorHook};
+use crate::platform_impl::common::portal;
 use crate::platform_impl::common::xkb::Context;
-use crate::platform_impl::platform::min_timeout;
+use crate::platform_impl::platform::{min_timeout, PlatformSpecificEventLoopAttributes};
 use crate::platform_impl::x11::window::Window;
 
 mod activation;
 mod atoms;
+mod clipboard;
 mod dnd;
 mod event_processor;
 pub mod ffi;
@@ -52,9 +60,10 @@ mod xdisplay;
 mod xsettings;
 
 use atoms::*;
-use dnd::{Dnd, DndState};
+use clipboard::Clipboard;
+use dnd::{Dnd, DragRequest};
 use event_processor::{EventProcessor, MAX_MOD_REPLAY_LEN};
-use ime::{Ime, ImeCreationError, ImeReceiver, ImeRequest, ImeSender};
+use ime::{DbusIme, Ime, ImeCreationError, ImeReceiver, ImeRequest, ImeSender};
 pub(crate) use monitor::{MonitorHandle, VideoModeHandle};
 pub use util::CustomCursor;
 use window::UnownedWindow;
@@ -194,11 +203,27 @@ pub struct ActiveEventLoop {
     exit: Cell<Option<i32>>,
     root: xproto::Window,
     ime: Option<RefCell<Ime>>,
+    /// The IBus or Fcitx5 input method, used instead of XIM.
+    dbus_ime: Option<RefCell<DbusIme>>,
     windows: RefCell<HashMap<WindowId, Weak<UnownedWindow>>>,
     redraw_sender: WakeSender<WindowId>,
     activation_sender: WakeSender<ActivationToken>,
+    drag_sender: WakeSender<DragRequest>,
+    clipboard: Clipboard,
+    primary_selection: Clipboard,
+    dnd: RefCell<Dnd>,
     event_loop_proxy: CoreEventLoopProxy,
     device_events: Cell<DeviceEvents>,
+    /// Whether the server supports XInput 2.4 gesture events.
+    xinput2_gestures: bool,
+    /// The color scheme preferred through the desktop portal.
+    portal_theme: Cell<Option<Theme>>,
+    /// The last system theme reported to the windows.
+    system_theme: Cell<Option<Theme>>,
+    /// The last XSETTINGS read from the settings owner.
+    xsettings: RefCell<XSettings>,
+    /// The last input preferences reported to the application.
+    input_preferences: Cell<InputPreferences>,
 }
 
 #[derive(Debug)]
@@ -208,6 +233,12 @@ pub struct EventLoop {
     event_processor: EventProcessor,
     redraw_receiver: PeekableReceiver<WindowId>,
     activation_receiver: PeekableReceiver<ActivationToken>,
+    clipboard_receiver: PeekableReceiver<ClipboardEvent>,
+    dnd_receiver: PeekableReceiver<ClipboardEvent>,
+    drag_receiver: PeekableReceiver<DragRequest>,
+    theme_receiver: PeekableReceiver<Option<Theme>>,
+    /// Watches the desktop settings until the event loop is dropped.
+    _settings_watcher: portal::SettingsWatcher,
 
     /// The current state of the event loop.
     state: EventLoopState,
@@ -222,10 +253,15 @@ struct EventLoopState {
 
     /// User requested a wake up.
     proxy_wake_up: bool,
+
+    /// The D-Bus input method sent messages.
+    ime_readable: bool,
 }
 
 impl EventLoop {
-    pub(crate) fn new() -> Result<EventLoop, EventLoopError> {
+    pub(crate) fn new(
+        attributes: &PlatformSpecificEventLoopAttributes,
+    ) -> Result<EventLoop, EventLoopError> {
         let xconn = match X11_BACKEND.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
             Ok(xconn) => xconn.clone(),
             Err(err) => return Err(os_error!(err.clone()).into()),
@@ -238,9 +274,6 @@ impl EventLoop {
         let net_wm_ping = atoms[_NET_WM_PING];
         let net_wm_sync_request = atoms[_NET_WM_SYNC_REQUEST];
 
-        let dnd = Dnd::new(Arc::clone(&xconn))
-            .expect("Failed to call XInternAtoms when initializing drag and drop");
-
         let (ime_sender, ime_receiver) = mpsc::channel();
         let (ime_event_sender, ime_event_receiver) = mpsc::channel();
         // Input methods will open successfully without setting the locale, but it won't be
@@ -267,17 +300,27 @@ impl EventLoop {
             }
         }
 
-        let ime = Ime::new(Arc::clone(&xconn), ime_event_sender);
-        if let Err(ImeCreationError::OpenFailure(state)) = ime.as_ref() {
-            warn!("Failed to open input method: {state:#?}");
-        } else if let Err(err) = ime.as_ref() {
-            warn!("Failed to set input method destruction callback: {err:?}");
-        }
-
-        let ime = ime.ok().map(RefCell::new);
+        // Prefer talking to the input method over D-Bus, XIM is the fallback.
+        let dbus_ime =
+            DbusIme::new(attributes.x11_ime_backend, Arc::clone(&xconn), ime_event_sender.clone());
+        let ime = if dbus_ime.is_none() {
+            let ime = Ime::new(Arc::clone(&xconn), ime_event_sender);
+            if let Err(ImeCreationError::OpenFailure(state)) = ime.as_ref() {
+                warn!("Failed to open input method: {state:#?}");
+            } else if let Err(err) = ime.as_ref() {
+                warn!("Failed to set input method destruction callback: {err:?}");
+            }
+            ime.ok().map(RefCell::new)
+        } else {
+            None
+        };
 
         let randr_event_offset =
             xconn.select_xrandr_input(root).expect("Failed to query XRandR extension");
+        // Cache the monitors to tell what changed on the RandR notifications.
+        if let Err(err) = xconn.available_monitors() {
+            warn!("Failed to query the monitors: {err}");
+        }
 
         let xi2ext = xconn
             .xcb_connection()
@@ -290,13 +333,15 @@ impl EventLoop {
             .expect("Failed to query XKB extension")
             .expect("X server missing XKB extension");
 
-        // Check for XInput2 support.
-        xconn
+        // Check for XInput2 support, gestures need XInput 2.4.
+        let xinput2_version = xconn
             .xcb_connection()
-            .xinput_xi_query_version(2, 3)
+            .xinput_xi_query_version(2, 4)
             .expect("Failed to send XInput2 query version request")
             .reply()
             .expect("Error while checking for XInput2 query version reply");
+        let xinput2_gestures =
+            (xinput2_version.major_version, xinput2_version.minor_version) >= (2, 4);
 
         xconn.update_cached_wm_info(root);
 
@@ -319,6 +364,23 @@ impl EventLoop {
             })
             .expect("Failed to register the X11 event dispatcher");
 
+        // Wake up for the messages of the D-Bus input method, they're read when draining events.
+        if let Some(dbus_ime) = dbus_ime.as_ref() {
+            let source = X11Source::new(
+                // SAFETY: the input method is owned by the event loop and outlives the source.
+                unsafe { BorrowedFd::borrow_raw(dbus_ime.as_fd().as_raw_fd()) },
+                calloop::Interest::READ,
+                calloop::Mode::Level,
+            );
+            handle
+                .insert_source(source, |_, _, state| {
+                    state.ime_readable = true;
+                    Ok(calloop::PostAction::Continue)
+                })
+                .expect("Failed to register the input method source");
+        }
+        let dbus_ime = dbus_ime.map(RefCell::new);
+
         let (waker, waker_source) =
             calloop::ping::make_ping().expect("Failed to create event loop waker");
         event_loop
@@ -334,6 +396,32 @@ impl EventLoop {
         // Create a channel for sending activation tokens.
         let (activation_token_sender, activation_token_channel) = mpsc::channel();
 
+        // Create a channel for delivering the results of clipboard requests.
+        let (clipboard_sender, clipboard_channel) = mpsc::channel();
+        let clipboard_sender = WakeSender { sender: clipboard_sender, waker: waker.clone() };
+        let clipboard =
+            Clipboard::new(xconn.clone(), ClipboardKind::Clipboard, clipboard_sender.clone())
+                .map_err(|err| os_error!(err))?;
+        let primary_selection =
+            Clipboard::new(xconn.clone(), ClipboardKind::Primary, clipboard_sender)
+                .map_err(|err| os_error!(err))?;
+        let xfixes_event_offset = clipboard.select_owner_changes();
+        primary_selection.select_owner_changes();
+
+        // Create a channel for delivering the dragged data.
+        let (dnd_sender, dnd_channel) = mpsc::channel();
+        let dnd_sender = WakeSender { sender: dnd_sender, waker: waker.clone() };
+        let dnd = Dnd::new(xconn.clone(), dnd_sender).map_err(|err| os_error!(err))?;
+
+        // Create a channel for starting the drags from the windows.
+        let (drag_sender, drag_channel) = mpsc::channel();
+
+        // Create a channel for the changes of the preferred color scheme.
+        let (theme_sender, theme_channel) = mpsc::channel();
+        let theme_sender = WakeSender { sender: theme_sender, waker: waker.clone() };
+        let settings_watcher =
+            portal::watch_settings(move |settings| theme_sender.send(settings.color_scheme));
+
         // Create a channel for sending user events.
         let (user_waker, user_waker_source) =
             calloop::ping::make_ping().expect("Failed to create user event loop waker.");
@@ -354,6 +442,7 @@ impl EventLoop {
 
         let window_target = ActiveEventLoop {
             ime,
+            dbus_ime,
             root,
             control_flow: Cell::new(ControlFlow::default()),
             exit: Cell::new(None),
@@ -371,18 +460,30 @@ impl EventLoop {
                 sender: activation_token_sender, // not used again so no clone
                 waker: waker.clone(),
             },
+            drag_sender: WakeSender { sender: drag_sender, waker: waker.clone() },
+            clipboard,
+            primary_selection,
+            dnd: RefCell::new(dnd),
             event_loop_proxy: event_loop_proxy.into(),
             device_events: Default::default(),
+            xinput2_gestures,
+            portal_theme: Cell::new(None),
+            system_theme: Cell::new(None),
+            xsettings: Default::default(),
+            input_preferences: Default::default(),
         };
+        window_target.system_theme.set(window_target.current_system_theme());
+        window_target.xsettings.replace(window_target.read_xsettings());
+        window_target.input_preferences.set(window_target.read_input_preferences());
 
         // Set initial device event filter.
         window_target.update_listen_device_events(true);
 
         let event_processor = EventProcessor {
             target: window_target,
-            dnd,
             devices: Default::default(),
             randr_event_offset,
+            xfixes_event_offset,
             ime_receiver,
             ime_event_receiver,
             xi2ext,
@@ -392,7 +493,9 @@ impl EventLoop {
             xkb_context,
             num_touch: 0,
             held_key_press: None,
+            key_repeat: attributes.key_repeat,
             first_touch: None,
+            pinch_scale: 1.,
             active_window: None,
             modifiers: Default::default(),
             is_composing: false,
@@ -417,7 +520,8 @@ impl EventLoop {
                 0x100, // Use the "core keyboard device"
                 xkb::EventType::NEW_KEYBOARD_NOTIFY
                     | xkb::EventType::MAP_NOTIFY
-                    | xkb::EventType::STATE_NOTIFY,
+                    | xkb::EventType::STATE_NOTIFY
+                    | xkb::EventType::CONTROLS_NOTIFY,
             )
             .unwrap();
 
@@ -429,7 +533,16 @@ impl EventLoop {
             event_processor,
             redraw_receiver: PeekableReceiver::from_recv(redraw_channel),
             activation_receiver: PeekableReceiver::from_recv(activation_token_channel),
-            state: EventLoopState { x11_readiness: Readiness::EMPTY, proxy_wake_up: false },
+            clipboard_receiver: PeekableReceiver::from_recv(clipboard_channel),
+            dnd_receiver: PeekableReceiver::from_recv(dnd_channel),
+            drag_receiver: PeekableReceiver::from_recv(drag_channel),
+            theme_receiver: PeekableReceiver::from_recv(theme_channel),
+            _settings_watcher: settings_watcher,
+            state: EventLoopState {
+                x11_readiness: Readiness::EMPTY,
+                proxy_wake_up: false,
+                ime_readable: false,
+            },
         };
 
         Ok(event_loop)
@@ -504,7 +617,18 @@ impl EventLoop {
     fn has_pending(&mut self) -> bool {
         self.event_processor.poll()
             || self.state.proxy_wake_up
+            || self.state.ime_readable
             || self.redraw_receiver.has_incoming()
+            || self.clipboard_receiver.has_incoming()
+            || self.dnd_receiver.has_incoming()
+            || self.drag_receiver.has_incoming()
+            || self.theme_receiver.has_incoming()
+            || self
+                .event_processor
+                .target
+                .dbus_ime
+                .as_ref()
+                .is_some_and(|ime| ime.borrow().has_pending())
     }
 
     fn poll_events_with_timeout<A: ApplicationHandler>(
@@ -528,10 +652,28 @@ impl EventLoop {
                 },
             };
 
-            min_timeout(control_flow_timeout, timeout)
+            // Wake up to fail the transfers the selection owners don't answer.
+            let transfer_timeout = self
+                .event_processor
+                .target
+                .transfer_deadline()
+                .map(|deadline| deadline.saturating_duration_since(start));
+
+            // Wake up to deliver the key events the input method doesn't answer for.
+            let ime_timeout = self
+                .event_processor
+                .target
+                .dbus_ime
+                .as_ref()
+                .and_then(|ime| ime.borrow().deadline())
+                .map(|deadline| deadline.saturating_duration_since(start));
+
+            let timeout = min_timeout(min_timeout(control_flow_timeout, timeout), transfer_timeout);
+            min_timeout(timeout, ime_timeout)
         };
 
         self.state.x11_readiness = Readiness::EMPTY;
+        self.state.ime_readable = false;
         if let Err(error) =
             self.event_loop.dispatch(timeout, &mut self.state).map_err(std::io::Error::from)
         {
@@ -541,6 +683,8 @@ impl EventLoop {
             return;
         }
 
+        self.event_processor.target.expire_transfers();
+
         // NB: `StartCause::Init` is handled as a special case and doesn't need
         // to be considered here
         let cause = match self.control_flow() {
@@ -608,6 +752,58 @@ impl EventLoop {
             }
         }
 
+        // Follow the color scheme preferred through the desktop portal.
+        while let Ok(theme) = self.theme_receiver.try_recv() {
+            self.event_processor.target.portal_theme.set(theme);
+            self.event_processor.update_system_theme(app);
+        }
+
+        // Deliver the results of clipboard requests.
+        while let Ok(event) = self.clipboard_receiver.try_recv() {
+            app.clipboard_event(&self.event_processor.target, event);
+        }
+
+        // Deliver the dragged data, and finish the drop once it was all read.
+        while let Ok(event) = self.dnd_receiver.try_recv() {
+            let ClipboardEvent::Read { serial, result } = event else { continue };
+            let target = &self.event_processor.target;
+            let window_id = {
+                let mut dnd = target.dnd.borrow_mut();
+                let Some(index) = dnd.requests.iter().position(|&(s, _)| s == serial) else {
+                    continue;
+                };
+                dnd.requests.swap_remove(index).1
+            };
+            app.window_event(target, window_id, WindowEvent::DragData { serial, result });
+            target.dnd.borrow_mut().finish_drop();
+        }
+
+        // Start the drags requested by the windows.
+        while let Ok(request) = self.drag_receiver.try_recv() {
+            let target = &self.event_processor.target;
+            let (window_id, serial) = (request.window_id, request.serial);
+            let result = target.dnd.borrow_mut().start_drag(request);
+            if let Err(err) = result {
+                if !matches!(err, RequestError::Ignored) {
+                    warn!("Failed to start the drag: {err}");
+                }
+                let event = DragSourceEvent::Cancelled;
+                app.window_event(target, window_id, WindowEvent::DragSource { serial, event });
+            }
+        }
+
+        // Cancel our drag when its target stopped answering.
+        let target = &self.event_processor.target;
+        let result = target.dnd.borrow_mut().expire_source();
+        match result {
+            Ok(events) => {
+                for (window_id, event) in events {
+                    app.window_event(target, window_id, event);
+                }
+            },
+            Err(err) => warn!("Failed to cancel the drag: {err}"),
+        }
+
         // Empty the user event buffer
         if mem::take(&mut self.state.proxy_wake_up) {
             app.proxy_wake_up(&self.event_processor.target);
@@ -641,6 +837,9 @@ impl EventLoop {
             let mut xev = unsafe { xev.assume_init() };
             self.event_processor.process_event(&mut xev, app);
         }
+
+        // Handle the IME requests and signals which came without X events.
+        self.event_processor.process_ime(app);
     }
 
     fn control_flow(&self) -> ControlFlow {
@@ -698,6 +897,85 @@ impl ActiveEventLoop {
             .expect_then_ignore_error("Failed to update device event filter");
     }
 
+    /// The theme preferred through the desktop portal, or guessed from XSETTINGS.
+    fn current_system_theme(&self) -> Option<Theme> {
+        self.portal_theme.get().or_else(|| {
+            let xsettings_screen = self.xconn.xsettings_screen()?;
+            match self.xconn.xsettings_theme(xsettings_screen) {
+                Ok(theme) => theme,
+                Err(err) => {
+                    warn!("failed to fetch XSettings: {err}");
+                    None
+                },
+            }
+        })
+    }
+
+    /// The XSETTINGS of the screen, empty when there's no settings manager.
+    fn read_xsettings(&self) -> XSettings {
+        let Some(xsettings_screen) = self.xconn.xsettings_screen() else {
+            return XSettings::default();
+        };
+        self.xconn.xsettings(xsettings_screen).unwrap_or_else(|err| {
+            warn!("failed to fetch XSettings: {err}");
+            XSettings::default()
+        })
+    }
+
+    /// The input preferences from the XSETTINGS and the XKB controls.
+    fn read_input_preferences(&self) -> InputPreferences {
+        let xsettings = self.xsettings.borrow();
+        let mut preferences = InputPreferences::default();
+        preferences.double_click_interval = xsettings.double_click_time();
+        preferences.double_click_distance = xsettings.double_click_distance();
+        preferences.drag_threshold = xsettings.drag_threshold();
+        preferences.caret_blink = xsettings.cursor_blink();
+        preferences.caret_blink_interval = xsettings.cursor_blink_time();
+
+        match self.xkb_controls() {
+            Ok(controls) if controls.enabled_controls.contains(xkb::BoolCtrl::REPEAT_KEYS) => {
+                preferences.key_repeat_delay =
+                    Some(Duration::from_millis(controls.repeat_delay.into()));
+                preferences.key_repeat_interval =
+                    Some(Duration::from_millis(controls.repeat_interval.into()));
+            },
+            Ok(_) => (),
+            Err(err) => warn!("failed to query the XKB controls: {err}"),
+        }
+
+        preferences
+    }
+
+    fn xkb_controls(&self) -> Result<xkb::GetControlsReply, X11Error> {
+        let device_spec = u16::from(xkb::ID::USE_CORE_KBD);
+        Ok(self.xconn.xcb_connection().xkb_get_controls(device_spec)?.reply()?)
+    }
+
+    /// When the next pending selection transfer, or the target of our drag, times out.
+    fn transfer_deadline(&self) -> Option<Instant> {
+        let dnd = self.dnd.borrow();
+        [
+            self.clipboard.deadline(),
+            self.primary_selection.deadline(),
+            dnd.data.deadline(),
+            dnd.source_deadline(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Fail the selection transfers the owners didn't answer in time.
+    fn expire_transfers(&self) {
+        self.clipboard.expire_requests();
+        self.primary_selection.expire_requests();
+        self.dnd.borrow().data.expire_requests();
+    }
+
+    pub(crate) fn xsettings(&self) -> XSettings {
+        self.xsettings.borrow().clone()
+    }
+
     pub(crate) fn clear_exit(&self) {
         self.exit.set(None)
     }
@@ -727,7 +1005,7 @@ impl RootActiveEventLoop for ActiveEventLoop {
         &self,
         custom_cursor: CustomCursorSource,
     ) -> Result<CoreCustomCursor, RequestError> {
-        Ok(CoreCustomCursor(Arc::new(CustomCursor::new(self, custom_cursor)?)))
+        Ok(CoreCustomCursor(Arc::new(CustomCursor::new(&self.xconn, custom_cursor)?)))
     }
 
     fn available_monitors(&self) -> Box<dyn Iterator<Item = CoreMonitorHandle>> {
@@ -745,13 +1023,67 @@ impl RootActiveEventLoop for ActiveEventLoop {
     }
 
     fn system_theme(&self) -> Option<Theme> {
-        None
+        self.system_theme.get()
+    }
+
+    fn input_preferences(&self) -> InputPreferences {
+        self.input_preferences.get()
+    }
+
+    fn clipboard(&self) -> Option<&dyn CoreClipboard> {
+        Some(&self.clipboard)
+    }
+
+    fn primary_selection(&self) -> Option<&dyn CoreClipboard> {
+        Some(&self.primary_selection)
+    }
+
+    fn set_drag_action(&self, action: Option<DragAction>) -> Result<(), RequestError> {
+        let mut dnd = self.dnd.borrow_mut();
+        let (Some(window), Some(source_window)) = (dnd.window, dnd.source_window) else {
+            return Err(RequestError::Ignored);
+        };
+        if dnd.dropped {
+            return Err(RequestError::Ignored);
+        }
+
+        dnd.action = action;
+        unsafe { dnd.send_status(window, source_window, action) }.map_err(|err| os_error!(err))?;
+        self.xconn.flush_requests().map_err(|err| os_error!(err))?;
+        Ok(())
+    }
+
+    fn request_drag_data(&self, mime_type: &str) -> Result<AsyncRequestSerial, RequestError> {
+        let mut dnd = self.dnd.borrow_mut();
+        let Some(window) = dnd.window.filter(|_| dnd.dragging) else {
+            return Err(RequestError::Ignored);
+        };
+
+        // Types that aren't offered are refused by the source.
+        let serial = dnd.data.read(mime_type)?;
+        dnd.requests.push((serial, mkwid(window)));
+        Ok(serial)
     }
 
     fn listen_device_events(&self, allowed: DeviceEvents) {
         self.device_events.set(allowed);
     }
 
+    fn available_devices(&self) -> Box<dyn Iterator<Item = (DeviceId, CoreDeviceInfo)>> {
+        let Some(infos) = DeviceInfo::get(&self.xconn, ffi::XIAllDevices) else {
+            return Box::new(std::iter::empty());
+        };
+
+        let devices: Vec<_> = infos
+            .iter()
+            .filter_map(|info| {
+                let device = Device::new(info, &self.xconn);
+                Some((mkdid(info.deviceid as xinput::DeviceId), device.info?))
+            })
+            .collect();
+        Box::new(devices.into_iter())
+    }
+
     fn set_control_flow(&self, control_flow: ControlFlow) {
         self.control_flow.set(control_flow)
     }
@@ -1012,8 +1344,11 @@ fn mkdid(w: xinput::DeviceId) -> DeviceId {
 
 #[derive(Debug)]
 pub struct Device {
-    _name: String,
+    // The descriptor reported to the user, only physical devices have one.
+    info: Option<CoreDeviceInfo>,
     scroll_axes: Vec<(i32, ScrollAxis)>,
+    // The tool of the device, when it's a drawing tablet.
+    tablet: Option<TabletDevice>,
     // For master devices, this is the paired device (pointer <-> keyboard).
     // For slave devices, this is the master.
     attachment: c_int,
@@ -1032,10 +1367,106 @@ enum ScrollOrientation {
     Horizontal,
 }
 
+#[derive(Debug, Clone)]
+struct TabletDevice {
+    tool: TabletTool,
+    axes: Vec<(i32, TabletAxis)>,
+    state: TabletToolState,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct TabletAxis {
+    kind: TabletAxisKind,
+    min: f64,
+    max: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum TabletAxisKind {
+    Pressure,
+    Distance,
+    TiltX,
+    TiltY,
+    Twist,
+}
+
+impl TabletDevice {
+    fn new(name: &str, axes: Vec<(i32, TabletAxis)>) -> Option<Self> {
+        // Only pressure and tilt tell a tablet apart from an ordinary pointer.
+        if !axes
+            .iter()
+            .any(|(_, axis)| matches!(axis.kind, TabletAxisKind::Pressure | TabletAxisKind::TiltX))
+        {
+            return None;
+        }
+
+        // The drivers create a device per tool, X11 doesn't report its type otherwise.
+        let name = name.to_lowercase();
+        let kind = if name.contains("eraser") {
+            TabletToolKind::Eraser
+        } else if name.contains("cursor") {
+            TabletToolKind::Lens
+        } else {
+            TabletToolKind::Pen
+        };
+
+        let mut state = TabletToolState::default();
+        for (_, axis) in &axes {
+            match axis.kind {
+                TabletAxisKind::Pressure => state.force = Some(Force::Normalized(0.)),
+                TabletAxisKind::Distance => state.distance = Some(0.),
+                TabletAxisKind::TiltX | TabletAxisKind::TiltY => {
+                    state.tilt = Some(TabletToolTilt::default())
+                },
+                TabletAxisKind::Twist => state.twist = Some(0.),
+            }
+        }
+
+        Some(Self { tool: TabletTool { kind, serial: None }, axes, state })
+    }
+
+    /// The button of the tool pressed as the core button.
+    ///
+    /// The drivers map the stylus buttons to the middle and right buttons, then to the ones after
+    /// the scroll buttons, which are numbered after the known ones like on Wayland.
+    fn button(button: u32) -> TabletButton {
+        match button {
+            ffi::Button1 => TabletButton::Tip,
+            ffi::Button2 => TabletButton::Barrel(0),
+            ffi::Button3 => TabletButton::Barrel(1),
+            button => TabletButton::Barrel(button.saturating_sub(6) as u16),
+        }
+    }
+
+    /// Update the state from a valuator, returning whether it's one of the tablet axes.
+    fn update(&mut self, number: i32, value: f64) -> bool {
+        let axis = match self.axes.iter().find(|&&(axis, _)| axis == number) {
+            Some(&(_, axis)) => axis,
+            None => return false,
+        };
+
+        let normalized =
+            if axis.max > axis.min { (value - axis.min) / (axis.max - axis.min) } else { 0. };
+        match axis.kind {
+            TabletAxisKind::Pressure => self.state.force = Some(Force::Normalized(normalized)),
+            TabletAxisKind::Distance => self.state.distance = Some(normalized),
+            // The drivers report the tilt in degrees.
+            TabletAxisKind::TiltX => self.state.tilt.get_or_insert_with(Default::default).x = value,
+            TabletAxisKind::TiltY => self.state.tilt.get_or_insert_with(Default::default).y = value,
+            TabletAxisKind::Twist => self.state.twist = Some(normalized * 360.),
+        }
+
+        true
+    }
+}
+
 impl Device {
-    fn new(info: &ffi::XIDeviceInfo) -> Self {
+    fn new(info: &ffi::XIDeviceInfo, xconn: &XConnection) -> Self {
+        let atoms = xconn.atoms();
         let name = unsafe { CStr::from_ptr(info.name).to_string_lossy() };
         let mut scroll_axes = Vec::new();
+        let mut tablet_axes = Vec::new();
+        let mut direct_touch = false;
 
         if Device::physical_device(info) {
             // Identify scroll axes
@@ -1052,16 +1483,82 @@ impl Device {
                         },
                         position: 0.0,
                     }));
+                } else if ty == ffi::XIValuatorClass {
+                    let info = unsafe { &*(class_ptr as *const ffi::XIValuatorClassInfo) };
+                    let label = info.label as xproto::Atom;
+                    let kind = if label == atoms[AbsPressure] {
+                        TabletAxisKind::Pressure
+                    } else if label == atoms[AbsDistance] {
+                        TabletAxisKind::Distance
+                    } else if label == atoms[AbsTiltX] {
+                        TabletAxisKind::TiltX
+                    } else if label == atoms[AbsTiltY] {
+                        TabletAxisKind::TiltY
+                    } else if label == atoms[AbsWheel] {
+                        TabletAxisKind::Twist
+                    } else {
+                        continue;
+                    };
+                    tablet_axes.push((info.number, TabletAxis {
+                        kind,
+                        min: info.min,
+                        max: info.max,
+                    }));
+                } else if ty == ffi::XITouchClass {
+                    let info = unsafe { &*(class_ptr as *const ffi::XITouchClassInfo) };
+                    direct_touch |= info.mode == ffi::XIDirectTouch;
                 }
             }
         }
 
+        let tablet = TabletDevice::new(&name, tablet_axes);
+        let descriptor = Device::physical_device(info).then(|| {
+            let kind = if tablet.is_some() {
+                DeviceKind::Tablet
+            } else if direct_touch {
+                DeviceKind::Touch
+            } else {
+                match info._use {
+                    ffi::XISlaveKeyboard => DeviceKind::Keyboard,
+                    ffi::XISlavePointer => DeviceKind::Pointer,
+                    _ => DeviceKind::Unknown,
+                }
+            };
+            let (vendor_id, product_id) = match Device::product_id(xconn, info.deviceid) {
+                Some((vendor_id, product_id)) => (Some(vendor_id), Some(product_id)),
+                None => (None, None),
+            };
+            CoreDeviceInfo { name: name.into_owned(), kind, vendor_id, product_id }
+        });
         let mut device =
-            Device { _name: name.into_owned(), scroll_axes, attachment: info.attachment };
+            Device { info: descriptor, scroll_axes, tablet, attachment: info.attachment };
         device.reset_scroll_position(info);
         device
     }
 
+    /// Query the USB vendor and product IDs, which the input drivers expose as a property.
+    fn product_id(xconn: &XConnection, device: c_int) -> Option<(u16, u16)> {
+        let reply = xconn
+            .xcb_connection()
+            .xinput_xi_get_property(
+                device as xinput::DeviceId,
+                false,
+                xconn.atoms()[DeviceProductId],
+                xproto::AtomEnum::INTEGER.into(),
+                0,
+                2,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+        match reply.items {
+            xinput::XIGetPropertyItems::Data32(ids) if ids.len() == 2 => {
+                Some((u16::try_from(ids[0]).ok()?, u16::try_from(ids[1]).ok()?))
+            },
+            _ => None,
+        }
+    }
+
     fn reset_scroll_position(&mut self, info: &ffi::XIDeviceInfo) {
         if Device::physical_device(info) {
             for &class_ptr in Device::classes(info) {
@@ -1101,3 +1598,54 @@ impl Device {
 fn xinput_fp1616_to_float(fp: xinput::Fp1616) -> f64 {
     (fp as f64) / ((1 << 16) as f64)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn axis(kind: TabletAxisKind, min: f64, max: f64) -> TabletAxis {
+        TabletAxis { kind, min, max }
+    }
+
+    #[test]
+    fn tablet_axes() {
+        assert!(TabletDevice::new("Mouse", vec![]).is_none());
+
+        let axes = vec![
+            (2, axis(TabletAxisKind::Pressure, 0., 65535.)),
+            (3, axis(TabletAxisKind::TiltX, -64., 63.)),
+            (4, axis(TabletAxisKind::TiltY, -64., 63.)),
+            (5, axis(TabletAxisKind::Twist, -900., 899.)),
+            (6, axis(TabletAxisKind::Distance, 0., 0.)),
+        ];
+        let mut tablet = TabletDevice::new("Wacom Intuos Pro M Pen eraser", axes).unwrap();
+        assert_eq!(tablet.tool.kind, TabletToolKind::Eraser);
+        assert_eq!(tablet.state.force, Some(Force::Normalized(0.)));
+        assert_eq!(tablet.state.tilt, Some(TabletToolTilt::default()));
+
+        assert!(!tablet.update(0, 100.));
+        assert!(tablet.update(2, 65535. / 4.));
+        assert_eq!(tablet.state.force, Some(Force::Normalized(0.25)));
+        assert!(tablet.update(3, -20.) && tablet.update(4, 30.));
+        assert_eq!(tablet.state.tilt, Some(TabletToolTilt { x: -20., y: 30. }));
+        assert!(tablet.update(5, -900.));
+        assert_eq!(tablet.state.twist, Some(0.));
+        // An empty range can't be normalized.
+        assert!(tablet.update(6, 10.));
+        assert_eq!(tablet.state.distance, Some(0.));
+
+        let axes = vec![(2, axis(TabletAxisKind::Pressure, 0., 1.))];
+        let tablet = TabletDevice::new("HUION Tablet cursor", axes).unwrap();
+        assert_eq!(tablet.tool.kind, TabletToolKind::Lens);
+        assert_eq!(tablet.state.tilt, None);
+    }
+
+    #[test]
+    fn tablet_buttons() {
+        assert_eq!(TabletDevice::button(1), TabletButton::Tip);
+        assert_eq!(TabletDevice::button(2), TabletButton::Barrel(0));
+        assert_eq!(TabletDevice::button(3), TabletButton::Barrel(1));
+        assert_eq!(TabletDevice::button(8), TabletButton::Barrel(2));
+        assert_eq!(TabletDevice::button(9), TabletButton::Barrel(3));
+    }
+}
@@ -134,6 +134,15 @@ impl MonitorHandle {
         Some(MonitorHandle { id, name, scale_factor, position, primary, rect, video_modes })
     }
 
+    /// Whether the monitor is configured the same way as the other one.
+    pub(crate) fn same_configuration(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.primary == other.primary
+            && self.scale_factor == other.scale_factor
+            && self.rect == other.rect
+            && self.video_modes == other.video_modes
+    }
+
     pub fn dummy() -> Self {
         MonitorHandle {
             id: 0,
@@ -9,7 +9,6 @@ use x11rb::connection::Connection;
 use x11rb::protocol::render::{self, ConnectionExt as _};
 use x11rb::protocol::xproto;
 
-use super::super::ActiveEventLoop;
 use super::*;
 
 impl XConnection {
@@ -18,16 +17,21 @@ impl XConnection {
         window: xproto::Window,
         cursor: Option<CursorIcon>,
     ) -> Result<(), X11Error> {
-        let cursor = {
-            let mut cache = self.cursor_cache.lock().unwrap_or_else(|e| e.into_inner());
+        let cursor = self.cached_cursor(cursor)?;
+        self.update_cursor(window, cursor)
+    }
 
-            match cache.entry(cursor) {
-                Entry::Occupied(o) => *o.get(),
-                Entry::Vacant(v) => *v.insert(self.get_cursor(cursor)?),
-            }
-        };
+    /// The cursor for the icon, loaded once.
+    pub(crate) fn cached_cursor(
+        &self,
+        cursor: Option<CursorIcon>,
+    ) -> Result<xproto::Cursor, X11Error> {
+        let mut cache = self.cursor_cache.lock().unwrap_or_else(|e| e.into_inner());
 
-        self.update_cursor(window, cursor)
+        match cache.entry(cursor) {
+            Entry::Occupied(o) => Ok(*o.get()),
+            Entry::Vacant(v) => Ok(*v.insert(self.get_cursor(cursor)?)),
+        }
     }
 
     pub(crate) fn set_custom_cursor(
@@ -196,7 +200,7 @@ impl Eq for CustomCursor {}
 
 impl CustomCursor {
     pub(crate) fn new(
-        event_loop: &ActiveEventLoop,
+        xconn: &Arc<XConnection>,
         cursor: CustomCursorSource,
     ) -> Result<CustomCursor, RequestError> {
         let mut cursor = match cursor {
@@ -212,14 +216,13 @@ impl CustomCursor {
             chunk[0..3].reverse();
 
             // Byteswap if we need to.
-            if event_loop.xconn.needs_endian_swap() {
+            if xconn.needs_endian_swap() {
                 let value = u32::from_ne_bytes(*chunk).swap_bytes();
                 *chunk = value.to_ne_bytes();
             }
         });
 
-        let cursor = event_loop
-            .xconn
+        let cursor = xconn
             .create_cursor_from_image(
                 cursor.width(),
                 cursor.height(),
@@ -229,7 +232,7 @@ impl CustomCursor {
             )
             .map_err(|err| os_error!(err))?;
 
-        Ok(Self { xconn: event_loop.xconn.clone(), cursor })
+        Ok(Self { xconn: xconn.clone(), cursor })
     }
 }
 
@@ -239,6 +242,13 @@ impl Drop for CustomCursor {
     }
 }
 
+impl CustomCursor {
+    /// The server-side cursor.
+    pub(crate) fn xcursor(&self) -> xproto::Cursor {
+        self.cursor
+    }
+}
+
 impl CustomCursorProvider for CustomCursor {
     fn is_animated(&self) -> bool {
         false
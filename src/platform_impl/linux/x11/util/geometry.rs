@@ -1,5 +1,7 @@
 use std::cmp;
 
+use dpi::{PhysicalPosition, PhysicalSize};
+
 use super::*;
 
 // Friendly neighborhood axis-aligned rectangle
@@ -18,6 +20,14 @@ impl AaRect {
         AaRect { x, y, width, height }
     }
 
+    pub fn position(&self) -> PhysicalPosition<i32> {
+        PhysicalPosition::new(self.x as i32, self.y as i32)
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.width as u32, self.height as u32)
+    }
+
     pub fn contains_point(&self, x: i64, y: i64) -> bool {
         x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
     }
@@ -86,10 +86,22 @@ impl MotifHints {
     }
 
     pub fn set_maximizable(&mut self, maximizable: bool) {
-        if maximizable {
-            self.add_func(mwm::MWM_FUNC_MAXIMIZE);
+        self.set_func(mwm::MWM_FUNC_MAXIMIZE, maximizable);
+    }
+
+    pub fn set_minimizable(&mut self, minimizable: bool) {
+        self.set_func(mwm::MWM_FUNC_MINIMIZE, minimizable);
+    }
+
+    pub fn set_closable(&mut self, closable: bool) {
+        self.set_func(mwm::MWM_FUNC_CLOSE, closable);
+    }
+
+    fn set_func(&mut self, func: u32, enabled: bool) {
+        if enabled {
+            self.add_func(func);
         } else {
-            self.remove_func(mwm::MWM_FUNC_MAXIMIZE);
+            self.remove_func(func);
         }
     }
 
@@ -167,3 +179,27 @@ impl XConnection {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{mwm, MotifHints};
+
+    #[test]
+    fn motif_functions() {
+        let mut hints = MotifHints::new();
+        hints.set_minimizable(true);
+        assert_eq!(hints.hints.flags, 0);
+
+        // Functions are listed as the exceptions to `MWM_FUNC_ALL`.
+        hints.set_minimizable(false);
+        hints.set_closable(false);
+        assert_eq!(hints.hints.flags, mwm::MWM_HINTS_FUNCTIONS);
+        assert_eq!(
+            hints.hints.functions,
+            mwm::MWM_FUNC_ALL | mwm::MWM_FUNC_MINIMIZE | mwm::MWM_FUNC_CLOSE
+        );
+
+        hints.set_minimizable(true);
+        assert_eq!(hints.hints.functions, mwm::MWM_FUNC_ALL | mwm::MWM_FUNC_CLOSE);
+    }
+}
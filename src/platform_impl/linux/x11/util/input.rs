@@ -27,6 +27,35 @@ impl XConnection {
             .map_err(Into::into)
     }
 
+    /// Like [`Self::select_xinput_events`], also selecting the XInput 2.4 gesture events.
+    ///
+    /// The gesture events don't fit in a single mask word.
+    pub fn select_xinput_gesture_events(
+        &self,
+        window: xproto::Window,
+        device_id: u16,
+        mask: xinput::XIEventMask,
+    ) -> Result<VoidCookie<'_>, X11Error> {
+        let mut masks = [0u32; 2];
+        for event in [
+            ffi::XI_GesturePinchBegin,
+            ffi::XI_GesturePinchUpdate,
+            ffi::XI_GesturePinchEnd,
+            ffi::XI_GestureSwipeBegin,
+            ffi::XI_GestureSwipeUpdate,
+            ffi::XI_GestureSwipeEnd,
+        ] {
+            masks[event as usize / 32] |= 1 << (event % 32);
+        }
+
+        self.xcb_connection()
+            .xinput_xi_select_events(window, &[xinput::EventMask {
+                deviceid: device_id,
+                mask: vec![mask | masks[0], masks[1].into()],
+            }])
+            .map_err(Into::into)
+    }
+
     pub fn select_xkb_events(
         &self,
         device_id: xkb::DeviceSpec,
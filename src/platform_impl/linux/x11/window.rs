@@ -11,17 +11,19 @@ use std::{cmp, env};
 use dpi::{PhysicalInsets, PhysicalPosition, PhysicalSize, Position, Size};
 use tracing::{debug, info, warn};
 use winit_core::application::ApplicationHandler;
-use winit_core::cursor::Cursor;
+use winit_core::clipboard::ClipboardContents;
+use winit_core::cursor::{Cursor, CustomCursorSource};
 use winit_core::error::{NotSupportedError, RequestError};
-use winit_core::event::{SurfaceSizeWriter, WindowEvent};
+use winit_core::event::{DragAction, SurfaceSizeWriter, WindowEvent};
 use winit_core::event_loop::AsyncRequestSerial;
-use winit_core::icon::RgbaIcon;
+use winit_core::icon::{Icon, RgbaIcon};
 use winit_core::monitor::{
     Fullscreen, MonitorHandle as CoreMonitorHandle, MonitorHandleProvider, VideoMode,
 };
 use winit_core::window::{
-    CursorGrabMode, ImePurpose, ResizeDirection, Theme, UserAttentionType, Window as CoreWindow,
-    WindowAttributes, WindowButtons, WindowId, WindowLevel,
+    CursorGrabMode, ImeHint, ImePurpose, ResizeDirection, Theme, UserAttentionType,
+    Window as CoreWindow, WindowAttributes, WindowButtons, WindowCapabilities, WindowId,
+    WindowLevel,
 };
 use x11rb::connection::{Connection, RequestConnection};
 use x11rb::properties::{WmHints, WmSizeHints, WmSizeHintsSpecification};
@@ -31,6 +33,7 @@ use x11rb::protocol::xfixes::{ConnectionExt, RegionWrapper};
 use x11rb::protocol::xproto::{self, ConnectionExt as _, Rectangle};
 use x11rb::protocol::{randr, xinput};
 
+use super::dnd::DragRequest;
 use super::util::{self, SelectedCursor};
 use super::{
     ffi, ActiveEventLoop, CookieResultExt, CustomCursor, ImeRequest, ImeSender, VoidCookie,
@@ -168,6 +171,10 @@ impl CoreWindow for Window {
         self.0.enabled_buttons()
     }
 
+    fn window_capabilities(&self) -> WindowCapabilities {
+        self.0.window_capabilities()
+    }
+
     fn set_minimized(&self, minimized: bool) {
         self.0.set_minimized(minimized)
     }
@@ -205,11 +212,7 @@ impl CoreWindow for Window {
     }
 
     fn set_window_icon(&self, window_icon: Option<winit_core::icon::Icon>) {
-        let icon = match window_icon.as_ref() {
-            Some(icon) => icon.cast_ref::<RgbaIcon>(),
-            None => None,
-        };
-        self.0.set_window_icon(icon)
+        self.0.set_window_icon(window_icon.as_ref())
     }
 
     fn set_ime_cursor_area(&self, position: Position, size: Size) {
@@ -224,6 +227,10 @@ impl CoreWindow for Window {
         self.0.set_ime_purpose(purpose);
     }
 
+    fn set_ime_surrounding_text(&self, _text: &str, _cursor: usize, _anchor: usize) {}
+
+    fn set_ime_hints(&self, _hints: ImeHint) {}
+
     fn focus_window(&self) {
         self.0.focus_window();
     }
@@ -276,6 +283,15 @@ impl CoreWindow for Window {
         self.0.drag_resize_window(direction)
     }
 
+    fn start_drag(
+        &self,
+        contents: ClipboardContents,
+        actions: &[DragAction],
+        icon: Option<CustomCursorSource>,
+    ) -> Result<AsyncRequestSerial, RequestError> {
+        self.0.start_drag(contents, actions, icon)
+    }
+
     fn show_window_menu(&self, position: Position) {
         self.0.show_window_menu(position);
     }
@@ -369,6 +385,13 @@ pub struct SharedState {
     pub has_focus: bool,
     // Use `Option` to not apply hittest logic when it was never requested.
     pub cursor_hittest: Option<bool>,
+    // The theme override requested by the application.
+    pub theme: Option<Theme>,
+    // The window the popup belongs to, popups are dismissed when it loses focus.
+    pub popup_parent: Option<xproto::Window>,
+    pub enabled_buttons: WindowButtons,
+    // The capabilities last reported to the application.
+    pub window_capabilities: WindowCapabilities,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -407,6 +430,10 @@ impl SharedState {
             base_size: None,
             has_focus: false,
             cursor_hittest: None,
+            theme: window_attributes.preferred_theme,
+            popup_parent: None,
+            enabled_buttons: window_attributes.enabled_buttons,
+            window_capabilities: WindowCapabilities::all(),
         })
     }
 }
@@ -432,6 +459,7 @@ pub struct UnownedWindow {
     pub shared_state: Mutex<SharedState>,
     redraw_sender: WakeSender<WindowId>,
     activation_sender: WakeSender<super::ActivationToken>,
+    drag_sender: WakeSender<DragRequest>,
 }
 macro_rules! leap {
     ($e:expr) => {
@@ -503,10 +531,10 @@ impl UnownedWindow {
         let min_surface_size: Option<(u32, u32)> =
             window_attrs.min_surface_size.map(|size| size.to_physical::<u32>(scale_factor).into());
 
-        let position =
+        let mut position =
             window_attrs.position.map(|position| position.to_physical::<i32>(scale_factor));
 
-        let dimensions = {
+        let mut dimensions = {
             // x11 only applies constraints when the window is actively resized
             // by the user, so we have to manually apply the initial constraints
             let mut dimensions: (u32, u32) = window_attrs
@@ -527,6 +555,22 @@ impl UnownedWindow {
             dimensions
         };
 
+        // Popups are placed by us, relative to their parent.
+        let popup_parent = window_attrs.popup.map(|popup| {
+            let parent = popup.parent.into_raw() as xproto::Window;
+            let parent_position = xconn
+                .translate_coords_root(parent, root)
+                .map(|coords| PhysicalPosition::new(coords.dst_x.into(), coords.dst_y.into()))
+                .unwrap_or_default();
+            let bounds = (!guessed_monitor.is_dummy())
+                .then(|| (guessed_monitor.rect.position(), guessed_monitor.rect.size()));
+            let (popup_position, popup_size) =
+                popup.place(parent_position, scale_factor, dimensions.into(), bounds);
+            position = Some(popup_position);
+            dimensions = popup_size.into();
+            parent
+        });
+
         // An iterator over the visuals matching screen id combined with their depths.
         let mut all_visuals = screen
             .allowed_depths
@@ -579,7 +623,7 @@ impl UnownedWindow {
 
             aux = aux.event_mask(event_mask).border_pixel(0);
 
-            if x11_attributes.override_redirect {
+            if x11_attributes.override_redirect || popup_parent.is_some() {
                 aux = aux.override_redirect(true as u32);
             }
 
@@ -657,6 +701,7 @@ impl UnownedWindow {
             shared_state: SharedState::new(guessed_monitor, &window_attrs),
             redraw_sender: event_loop.redraw_sender.clone(),
             activation_sender: event_loop.activation_sender.clone(),
+            drag_sender: event_loop.drag_sender.clone(),
         };
 
         // Title must be set before mapping. Some tiling window managers (i.e. i3) use the window
@@ -664,6 +709,7 @@ impl UnownedWindow {
         // act on the wrong title state.
         leap!(window.set_title_inner(&window_attrs.title)).ignore_error();
         leap!(window.set_decorations_inner(window_attrs.decorations)).ignore_error();
+        leap!(window.set_enabled_buttons_inner(window_attrs.enabled_buttons)).ignore_error();
 
         if let Some(theme) = window_attrs.preferred_theme {
             leap!(window.set_theme_inner(Some(theme))).ignore_error();
@@ -722,7 +768,26 @@ impl UnownedWindow {
                 flusher.ignore_error()
             }
 
-            leap!(window.set_window_types(x11_attributes.x11_window_types)).ignore_error();
+            // Popups get a popup type, unless another one was picked.
+            let mut window_types = x11_attributes.x11_window_types;
+            if let Some(popup) = window_attrs.popup.filter(|_| window_types == [WindowType::Normal])
+            {
+                let window_type =
+                    if popup.grab { WindowType::PopupMenu } else { WindowType::Tooltip };
+                window_types = vec![window_type];
+            }
+            leap!(window.set_window_types(window_types)).ignore_error();
+
+            if let Some(parent) = popup_parent {
+                leap!(xconn.change_property(
+                    window.xwindow,
+                    xproto::Atom::from(xproto::AtomEnum::WM_TRANSIENT_FOR),
+                    xproto::Atom::from(xproto::AtomEnum::WINDOW),
+                    xproto::PropMode::REPLACE,
+                    &[parent],
+                ))
+                .ignore_error();
+            }
 
             // Set size hints.
             let mut min_surface_size =
@@ -744,6 +809,7 @@ impl UnownedWindow {
             shared_state.max_surface_size = max_surface_size.map(Into::into);
             shared_state.surface_resize_increments = window_attrs.surface_resize_increments;
             shared_state.base_size = x11_attributes.base_size;
+            shared_state.popup_parent = popup_parent;
 
             let normal_hints = WmSizeHints {
                 position: position.map(|PhysicalPosition { x, y }| {
@@ -773,10 +839,10 @@ impl UnownedWindow {
             .check());
 
             // Set window icons
-            if let Some(icon) =
-                window_attrs.window_icon.as_ref().and_then(|icon| icon.cast_ref::<RgbaIcon>())
-            {
-                leap!(window.set_icon_inner(icon)).ignore_error();
+            if let Some(icon) = window_attrs.window_icon.as_ref() {
+                if !icon.rgba_images().is_empty() {
+                    leap!(window.set_icon_inner(icon.rgba_images())).ignore_error();
+                }
             }
 
             // Opt into handling window close and resize synchronization
@@ -828,8 +894,12 @@ impl UnownedWindow {
                 | xinput::XIEventMask::TOUCH_BEGIN
                 | xinput::XIEventMask::TOUCH_UPDATE
                 | xinput::XIEventMask::TOUCH_END;
-            leap!(xconn.select_xinput_events(window.xwindow, super::ALL_MASTER_DEVICES, mask))
-                .ignore_error();
+            let cookie = if event_loop.xinput2_gestures {
+                xconn.select_xinput_gesture_events(window.xwindow, super::ALL_MASTER_DEVICES, mask)
+            } else {
+                xconn.select_xinput_events(window.xwindow, super::ALL_MASTER_DEVICES, mask)
+            };
+            leap!(cookie).ignore_error();
 
             // Set visibility (map window)
             if window_attrs.visible {
@@ -978,6 +1048,7 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_theme(&self, theme: Option<Theme>) {
+        self.shared_state_lock().theme = theme;
         self.set_theme_inner(theme).expect("Failed to change window theme").ignore_error();
 
         self.xconn.flush_requests().expect("Failed to change window theme");
@@ -1414,10 +1485,11 @@ impl UnownedWindow {
         self.xconn.flush_requests().expect("Failed to set window-level state");
     }
 
-    fn set_icon_inner(&self, icon: &RgbaIcon) -> Result<VoidCookie<'_>, X11Error> {
+    /// Set all the sizes of the icon, so that the window manager can pick the best one.
+    fn set_icon_inner(&self, images: &[RgbaIcon]) -> Result<VoidCookie<'_>, X11Error> {
         let atoms = self.xconn.atoms();
         let icon_atom = atoms[_NET_WM_ICON];
-        let data = rgba_to_cardinals(icon);
+        let data: Vec<_> = images.iter().flat_map(rgba_to_cardinals).collect();
         self.xconn.change_property(
             self.xwindow,
             icon_atom,
@@ -1441,10 +1513,10 @@ impl UnownedWindow {
     }
 
     #[inline]
-    pub(crate) fn set_window_icon(&self, icon: Option<&RgbaIcon>) {
-        match icon {
-            Some(icon) => self.set_icon_inner(icon),
-            None => self.unset_icon_inner(),
+    pub(crate) fn set_window_icon(&self, icon: Option<&Icon>) {
+        match icon.map(Icon::rgba_images) {
+            Some(images) if !images.is_empty() => self.set_icon_inner(images),
+            _ => self.unset_icon_inner(),
         }
         .expect_then_ignore_error("Failed to set icons");
 
@@ -1762,9 +1834,13 @@ impl UnownedWindow {
             let window_size = Some(Size::from(self.surface_size()));
             (window_size, window_size)
         };
-        self.shared_state_lock().is_resizable = resizable;
+        let maximizable = {
+            let mut shared_state_lock = self.shared_state_lock();
+            shared_state_lock.is_resizable = resizable;
+            resizable && shared_state_lock.enabled_buttons.contains(WindowButtons::MAXIMIZE)
+        };
 
-        self.set_maximizable_inner(resizable)
+        self.set_maximizable_inner(maximizable)
             .expect_then_ignore_error("Failed to call `XSetWMNormalHints`");
 
         let scale_factor = self.scale_factor();
@@ -1782,12 +1858,81 @@ impl UnownedWindow {
         self.shared_state_lock().is_resizable
     }
 
+    fn set_enabled_buttons_inner(
+        &self,
+        buttons: WindowButtons,
+    ) -> Result<VoidCookie<'_>, X11Error> {
+        let is_resizable = {
+            let mut shared_state_lock = self.shared_state_lock();
+            shared_state_lock.enabled_buttons = buttons;
+            shared_state_lock.is_resizable
+        };
+
+        let mut hints = self.xconn.get_motif_hints(self.xwindow);
+        hints.set_minimizable(buttons.contains(WindowButtons::MINIMIZE));
+        // An unresizable window can't be maximized either.
+        hints.set_maximizable(is_resizable && buttons.contains(WindowButtons::MAXIMIZE));
+        hints.set_closable(buttons.contains(WindowButtons::CLOSE));
+        self.xconn.set_motif_hints(self.xwindow, &hints)
+    }
+
     #[inline]
-    pub fn set_enabled_buttons(&self, _buttons: WindowButtons) {}
+    pub fn set_enabled_buttons(&self, buttons: WindowButtons) {
+        self.set_enabled_buttons_inner(buttons)
+            .expect_then_ignore_error("Failed to set the enabled buttons");
+        self.xconn.flush_requests().expect("Failed to set the enabled buttons");
+    }
 
     #[inline]
     pub fn enabled_buttons(&self) -> WindowButtons {
-        WindowButtons::all()
+        self.shared_state_lock().enabled_buttons
+    }
+
+    /// The actions allowed by the window manager in `_NET_WM_ALLOWED_ACTIONS`.
+    pub fn window_capabilities(&self) -> WindowCapabilities {
+        let atoms = self.xconn.atoms();
+        let allowed_actions = self
+            .xconn
+            .get_property::<xproto::Atom>(
+                self.xwindow,
+                atoms[_NET_WM_ALLOWED_ACTIONS],
+                xproto::Atom::from(xproto::AtomEnum::ATOM),
+            )
+            .unwrap_or_default();
+
+        let mut capabilities = if allowed_actions.is_empty() {
+            // The window manager doesn't restrict the actions, or doesn't manage the window yet.
+            WindowCapabilities::MAXIMIZE
+                | WindowCapabilities::FULLSCREEN
+                | WindowCapabilities::MINIMIZE
+        } else {
+            let allows = |action| allowed_actions.contains(&atoms[action]);
+            let mut capabilities = WindowCapabilities::empty();
+            capabilities.set(
+                WindowCapabilities::MAXIMIZE,
+                allows(_NET_WM_ACTION_MAXIMIZE_HORZ) || allows(_NET_WM_ACTION_MAXIMIZE_VERT),
+            );
+            capabilities.set(WindowCapabilities::FULLSCREEN, allows(_NET_WM_ACTION_FULLSCREEN));
+            capabilities.set(WindowCapabilities::MINIMIZE, allows(_NET_WM_ACTION_MINIMIZE));
+            capabilities
+        };
+
+        capabilities.set(
+            WindowCapabilities::WINDOW_MENU,
+            util::hint_is_supported(atoms[_GTK_SHOW_WINDOW_MENU]),
+        );
+        capabilities
+    }
+
+    /// Re-read the capabilities, returning them when they changed since they were last reported.
+    pub(super) fn update_window_capabilities(&self) -> Option<WindowCapabilities> {
+        let capabilities = self.window_capabilities();
+        let mut shared_state_lock = self.shared_state_lock();
+        if replace(&mut shared_state_lock.window_capabilities, capabilities) == capabilities {
+            None
+        } else {
+            Some(capabilities)
+        }
     }
 
     #[allow(dead_code)]
@@ -1995,8 +2140,66 @@ impl UnownedWindow {
         self.drag_initiate(util::MOVERESIZE_MOVE)
     }
 
-    #[inline]
-    pub fn show_window_menu(&self, _position: Position) {}
+    /// Ask the window manager to show the window menu, like GTK does for its client-side
+    /// decorations.
+    pub fn show_window_menu(&self, position: Position) {
+        let atoms = self.xconn.atoms();
+        if !util::hint_is_supported(atoms[_GTK_SHOW_WINDOW_MENU]) {
+            warn!("`show_window_menu` isn't supported by the window manager");
+            return;
+        }
+
+        let window_position = self.inner_position_physical();
+        let position = position.to_physical::<i32>(self.scale_factor());
+
+        // The window manager grabs the pointer for the menu, so release the implicit grab of the
+        // button press first.
+        let mut grabbed_lock = self.cursor_grabbed_mode.lock().unwrap();
+        self.xconn
+            .xcb_connection()
+            .ungrab_pointer(x11rb::CURRENT_TIME)
+            .expect_then_ignore_error("Failed to ungrab the pointer");
+        *grabbed_lock = CursorGrabMode::None;
+
+        self.xconn
+            .send_client_msg(
+                self.xwindow,
+                self.root,
+                atoms[_GTK_SHOW_WINDOW_MENU],
+                Some(
+                    xproto::EventMask::SUBSTRUCTURE_REDIRECT
+                        | xproto::EventMask::SUBSTRUCTURE_NOTIFY,
+                ),
+                [
+                    util::VIRTUAL_CORE_POINTER as u32,
+                    (window_position.0 + position.x) as u32,
+                    (window_position.1 + position.y) as u32,
+                    0,
+                    0,
+                ],
+            )
+            .expect_then_ignore_error("Failed to show the window menu");
+        self.xconn.flush_requests().expect("Failed to show the window menu");
+    }
+
+    /// Start dragging the contents, the drag itself is driven by the event loop.
+    pub fn start_drag(
+        &self,
+        contents: ClipboardContents,
+        actions: &[DragAction],
+        icon: Option<CustomCursorSource>,
+    ) -> Result<AsyncRequestSerial, RequestError> {
+        let icon = icon.map(|icon| CustomCursor::new(&self.xconn, icon)).transpose()?;
+        let serial = AsyncRequestSerial::get();
+        self.drag_sender.send(DragRequest {
+            window_id: self.id(),
+            serial,
+            contents,
+            actions: actions.to_vec(),
+            icon,
+        });
+        Ok(serial)
+    }
 
     /// Resizes the window while it is being dragged.
     pub fn drag_resize_window(&self, direction: ResizeDirection) -> Result<(), RequestError> {
@@ -2165,6 +2368,18 @@ impl UnownedWindow {
         Ok(serial)
     }
 
+    /// Complete the startup notification of the token, and raise the window.
+    pub fn activate_with_token(
+        &self,
+        token: winit_core::window::ActivationToken,
+    ) -> Result<(), RequestError> {
+        self.xconn
+            .remove_activation_token(self.xwindow, token.as_raw())
+            .map_err(|err| os_error!(err))?;
+        self.focus_window();
+        Ok(())
+    }
+
     #[inline]
     pub fn id(&self) -> WindowId {
         WindowId::from_raw(self.xwindow as _)
@@ -2207,7 +2422,7 @@ impl UnownedWindow {
 
     #[inline]
     pub fn theme(&self) -> Option<Theme> {
-        None
+        self.shared_state_lock().theme
     }
 
     pub fn set_content_protected(&self, _protected: bool) {}
@@ -26,6 +26,10 @@ pub struct XConnection {
     // for some reason.
     pub xinput2: ffi::XInput2,
 
+    /// Xlib only delivers the XFixes events once `libXfixes` registered their conversion, so
+    /// the library has to stay loaded as long as the display is open.
+    pub xfixes: Option<ffi::XFixes>,
+
     pub display: *mut ffi::Display,
 
     /// The manager for the XCB connection.
@@ -83,6 +87,7 @@ impl XConnection {
         let xlib = ffi::Xlib::open()?;
         let xlib_xcb = ffi::Xlib_xcb::open()?;
         let xinput2 = ffi::XInput2::open()?;
+        let xfixes = ffi::XFixes::open().ok();
 
         unsafe { (xlib.XInitThreads)() };
         unsafe { (xlib.XSetErrorHandler)(error_handler) };
@@ -146,6 +151,7 @@ impl XConnection {
         Ok(XConnection {
             xlib,
             xinput2,
+            xfixes,
             display,
             xcb: Some(xcb),
             atoms: Box::new(atoms),
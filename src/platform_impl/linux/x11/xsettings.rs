@@ -7,15 +7,18 @@
 use std::iter;
 use std::num::NonZeroUsize;
 
+use winit_core::window::Theme;
 use x11rb::protocol::xproto::{self, ConnectionExt};
 
 use super::atoms::*;
 use super::XConnection;
+use crate::platform::x11::{XSettingValue, XSettings};
 
 type Result<T> = core::result::Result<T, ParserError>;
 
 const DPI_NAME: &[u8] = b"Xft/DPI";
 const DPI_MULTIPLIER: f64 = 1024.0;
+const THEME_NAME: &[u8] = b"Net/ThemeName";
 const LITTLE_ENDIAN: u8 = b'l';
 const BIG_ENDIAN: u8 = b'B';
 
@@ -25,14 +28,7 @@ impl XConnection {
         &self,
         xsettings_screen: xproto::Atom,
     ) -> core::result::Result<Option<f64>, super::X11Error> {
-        let atoms = self.atoms();
-
-        // Get the current owner of the screen's settings.
-        let owner = self.xcb_connection().get_selection_owner(xsettings_screen)?.reply()?;
-
-        // Read the _XSETTINGS_SETTINGS property.
-        let data: Vec<u8> =
-            self.get_property(owner.owner, atoms[_XSETTINGS_SETTINGS], atoms[_XSETTINGS_SETTINGS])?;
+        let data = self.xsettings_data(xsettings_screen)?;
 
         // Parse the property.
         let dpi_setting = read_settings(&data)?
@@ -54,6 +50,55 @@ impl XConnection {
             Ok(None)
         }
     }
+
+    /// Get the theme from XSettings, guessed from the name of the GTK theme.
+    pub(crate) fn xsettings_theme(
+        &self,
+        xsettings_screen: xproto::Atom,
+    ) -> core::result::Result<Option<Theme>, super::X11Error> {
+        let data = self.xsettings_data(xsettings_screen)?;
+
+        let theme_setting = read_settings(&data)?
+            .find(|res| res.as_ref().map_or(true, |s| s.name == THEME_NAME))
+            .transpose()?;
+        let Some(theme_setting) = theme_setting else { return Ok(None) };
+        match theme_setting.data {
+            SettingData::String(name) => {
+                // The dark variants are conventionally suffixed, like `Adwaita-dark`.
+                let dark = String::from_utf8_lossy(name).to_lowercase().contains("dark");
+                Ok(Some(if dark { Theme::Dark } else { Theme::Light }))
+            },
+            SettingData::Integer(_) => Err(ParserError::BadType(SettingType::Integer).into()),
+            SettingData::Color(_) => Err(ParserError::BadType(SettingType::Color).into()),
+        }
+    }
+
+    /// Get the whole settings table from XSettings.
+    pub(crate) fn xsettings(
+        &self,
+        xsettings_screen: xproto::Atom,
+    ) -> core::result::Result<XSettings, super::X11Error> {
+        let data = self.xsettings_data(xsettings_screen)?;
+        Ok(settings_table(&data)?)
+    }
+
+    /// Read the `_XSETTINGS_SETTINGS` property of the screen's settings owner.
+    fn xsettings_data(
+        &self,
+        xsettings_screen: xproto::Atom,
+    ) -> core::result::Result<Vec<u8>, super::X11Error> {
+        let atoms = self.atoms();
+
+        // Get the current owner of the screen's settings.
+        let owner = self.xcb_connection().get_selection_owner(xsettings_screen)?.reply()?;
+
+        // Read the _XSETTINGS_SETTINGS property.
+        Ok(self.get_property(
+            owner.owner,
+            atoms[_XSETTINGS_SETTINGS],
+            atoms[_XSETTINGS_SETTINGS],
+        )?)
+    }
 }
 
 /// Read over the settings in the block of data.
@@ -69,6 +114,29 @@ fn read_settings(data: &[u8]) -> Result<impl Iterator<Item = Result<Setting<'_>>
     Ok(iter)
 }
 
+/// Collect all the settings in the block of data.
+fn settings_table(data: &[u8]) -> Result<XSettings> {
+    let mut settings = XSettings::default();
+    for setting in read_settings(data)? {
+        let setting = setting?;
+        let value = match setting.data {
+            SettingData::Integer(value) => XSettingValue::Integer(value),
+            SettingData::String(value) => {
+                XSettingValue::String(String::from_utf8_lossy(value).into_owned())
+            },
+            SettingData::Color([red, green, blue, alpha]) => XSettingValue::Color {
+                red: red as u16,
+                green: green as u16,
+                blue: blue as u16,
+                alpha: alpha as u16,
+            },
+        };
+        settings.settings.insert(String::from_utf8_lossy(setting.name).into_owned(), value);
+    }
+
+    Ok(settings)
+}
+
 /// A setting in the settings list.
 struct Setting<'a> {
     /// The name of the setting.
@@ -81,8 +149,8 @@ struct Setting<'a> {
 /// The data contained in a setting.
 enum SettingData<'a> {
     Integer(i32),
-    String(#[allow(dead_code)] &'a [u8]),
-    Color(#[allow(dead_code)] [i16; 4]),
+    String(&'a [u8]),
+    Color([i16; 4]),
 }
 
 impl<'a> Setting<'a> {
@@ -119,10 +187,10 @@ impl<'a> Setting<'a> {
 
             SettingType::Color => {
                 // Read i16's of color.
-                let (red, blue, green, alpha) =
+                let (red, green, blue, alpha) =
                     (parser.i16()?, parser.i16()?, parser.i16()?, parser.i16()?);
 
-                SettingData::Color([red, blue, green, alpha])
+                SettingData::Color([red, green, blue, alpha])
             },
         };
 
@@ -265,7 +333,10 @@ impl ParserError {
 #[cfg(test)]
 /// Tests for the XSETTINGS parser.
 mod tests {
+    use std::time::Duration;
+
     use super::*;
+    use crate::platform::x11::{FontHintStyle, SubpixelOrder};
 
     const XSETTINGS: &str = include_str!("tests/xsettings.dat");
 
@@ -309,6 +380,32 @@ mod tests {
         assert_string(&lcd.data, "lcddefault");
     }
 
+    #[test]
+    fn typed_xsettings() {
+        let data = XSETTINGS
+            .trim()
+            .split(',')
+            .map(|tok| {
+                let val = tok.strip_prefix("0x").unwrap();
+                u8::from_str_radix(val, 16).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let settings = settings_table(&data).unwrap();
+
+        assert_eq!(settings.get("Xft/DPI"), Some(&XSettingValue::Integer(96 * 1024)));
+        assert_eq!(settings.font_hinting(), Some(true));
+        assert_eq!(settings.font_rgba(), Some(SubpixelOrder::Rgb));
+        assert_eq!(settings.double_click_time(), Some(Duration::from_millis(400)));
+        assert_eq!(settings.double_click_distance(), Some(5));
+        assert_eq!(settings.drag_threshold(), Some(8));
+        assert_eq!(settings.cursor_blink(), Some(true));
+        assert_eq!(settings.cursor_theme_name(), Some("DMZ-White"));
+        assert_eq!(settings.cursor_theme_size(), Some(24));
+        assert_eq!(settings.font_hint_style(), Some(FontHintStyle::Slight));
+        assert_eq!(settings.iter().count(), settings.settings.len());
+    }
+
     fn assert_string(dat: &SettingData<'_>, s: &str) {
         match dat {
             SettingData::String(left) => assert_eq!(*left, s.as_bytes()),
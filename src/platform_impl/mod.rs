@@ -2,7 +2,7 @@
 mod android;
 #[cfg(target_vendor = "apple")]
 mod apple;
-#[cfg(any(x11_platform, wayland_platform))]
+#[cfg(any(x11_platform, wayland_platform, headless_platform))]
 mod linux;
 #[cfg(orbital_platform)]
 pub(crate) use winit_orbital as platform;
@@ -15,7 +15,7 @@ mod windows;
 use self::android as platform;
 #[cfg(target_vendor = "apple")]
 use self::apple as platform;
-#[cfg(any(x11_platform, wayland_platform))]
+#[cfg(any(x11_platform, wayland_platform, headless_platform))]
 use self::linux as platform;
 #[allow(unused_imports)]
 pub use self::platform::*;
@@ -31,6 +31,7 @@ use self::windows as platform;
     not(android_platform),
     not(x11_platform),
     not(wayland_platform),
+    not(headless_platform),
     not(web_platform),
     not(orbital_platform),
 ))]
@@ -8,10 +8,11 @@ use web_sys::Element;
 use winit_core::application::ApplicationHandler;
 use winit_core::cursor::{CustomCursor as CoreCustomCursor, CustomCursorSource};
 use winit_core::error::{NotSupportedError, RequestError};
-use winit_core::event::{ElementState, KeyEvent, TouchPhase, WindowEvent};
+use winit_core::event::{DeviceId, DeviceInfo, ElementState, KeyEvent, TouchPhase, WindowEvent};
 use winit_core::event_loop::{
     ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents,
-    EventLoopProxy as RootEventLoopProxy, OwnedDisplayHandle as CoreOwnedDisplayHandle,
+    EventLoopProxy as RootEventLoopProxy, InputPreferences,
+    OwnedDisplayHandle as CoreOwnedDisplayHandle,
 };
 use winit_core::keyboard::ModifiersState;
 use winit_core::monitor::MonitorHandle as CoremMonitorHandle;
@@ -521,6 +522,10 @@ impl RootActiveEventLoop for ActiveEventLoop {
         self.runner.listen_device_events(allowed)
     }
 
+    fn available_devices(&self) -> Box<dyn Iterator<Item = (DeviceId, DeviceInfo)>> {
+        Box::new(iter::empty())
+    }
+
     fn system_theme(&self) -> Option<Theme> {
         backend::is_dark_mode(self.runner.window()).map(|is_dark_mode| {
             if is_dark_mode {
@@ -531,6 +536,32 @@ impl RootActiveEventLoop for ActiveEventLoop {
         })
     }
 
+    fn input_preferences(&self) -> InputPreferences {
+        InputPreferences::default()
+    }
+
+    fn clipboard(&self) -> Option<&dyn winit_core::clipboard::Clipboard> {
+        None
+    }
+
+    fn primary_selection(&self) -> Option<&dyn winit_core::clipboard::Clipboard> {
+        None
+    }
+
+    fn set_drag_action(
+        &self,
+        _: Option<winit_core::event::DragAction>,
+    ) -> Result<(), RequestError> {
+        Err(NotSupportedError::new("set_drag_action is not supported").into())
+    }
+
+    fn request_drag_data(
+        &self,
+        _: &str,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(NotSupportedError::new("request_drag_data is not supported").into())
+    }
+
     fn set_control_flow(&self, control_flow: ControlFlow) {
         self.runner.set_control_flow(control_flow)
     }
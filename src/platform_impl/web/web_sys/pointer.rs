@@ -90,7 +90,9 @@ impl PointerHandler {
                         finger_id,
                         force: Some(Force::Normalized(event.pressure().into())),
                     },
-                    PointerKind::Unknown => ButtonSource::Unknown(mouse_button_to_id(button)),
+                    PointerKind::Tablet(_) | PointerKind::Unknown => {
+                        ButtonSource::Unknown(mouse_button_to_id(button))
+                    },
                 };
 
                 handler(
@@ -143,7 +145,9 @@ impl PointerHandler {
                         finger_id,
                         force: Some(Force::Normalized(event.pressure().into())),
                     },
-                    PointerKind::Unknown => ButtonSource::Unknown(mouse_button_to_id(button)),
+                    PointerKind::Tablet(_) | PointerKind::Unknown => {
+                        ButtonSource::Unknown(mouse_button_to_id(button))
+                    },
                 };
 
                 handler(
@@ -218,7 +222,7 @@ impl PointerHandler {
                                 force: Some(Force::Normalized(event.pressure().into())),
                             }
                         },
-                        PointerKind::Unknown => todo!(),
+                        PointerKind::Tablet(_) | PointerKind::Unknown => todo!(),
                     };
 
                     button_handler(
@@ -249,7 +253,9 @@ impl PointerHandler {
                                     finger_id,
                                     force: Some(Force::Normalized(event.pressure().into())),
                                 },
-                                PointerKind::Unknown => PointerSource::Unknown,
+                                PointerKind::Tablet(_) | PointerKind::Unknown => {
+                                    PointerSource::Unknown
+                                },
                             },
                         )
                     }),
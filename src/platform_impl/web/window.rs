@@ -12,8 +12,9 @@ use winit_core::error::{NotSupportedError, RequestError};
 use winit_core::icon::Icon;
 use winit_core::monitor::{Fullscreen, MonitorHandle as CoremMonitorHandle};
 use winit_core::window::{
-    CursorGrabMode, ImePurpose, ResizeDirection, Theme, UserAttentionType, Window as RootWindow,
-    WindowAttributes, WindowButtons, WindowId, WindowLevel,
+    CursorGrabMode, ImeHint, ImePurpose, ResizeDirection, Theme, UserAttentionType,
+    Window as RootWindow, WindowAttributes, WindowButtons, WindowCapabilities, WindowId,
+    WindowLevel,
 };
 
 use super::main_thread::MainThreadMarker;
@@ -253,6 +254,10 @@ impl RootWindow for Window {
         WindowButtons::all()
     }
 
+    fn window_capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities::empty()
+    }
+
     fn set_minimized(&self, _: bool) {
         // Intentionally a no-op, as canvases cannot be 'minimized'
     }
@@ -319,6 +324,10 @@ impl RootWindow for Window {
         // Currently not implemented
     }
 
+    fn set_ime_surrounding_text(&self, _text: &str, _cursor: usize, _anchor: usize) {}
+
+    fn set_ime_hints(&self, _hints: ImeHint) {}
+
     fn focus_window(&self) {
         self.inner.dispatch(|inner| {
             let _ = inner.canvas.raw().focus();
@@ -391,6 +400,15 @@ impl RootWindow for Window {
         Err(NotSupportedError::new("drag_resize_window is not supported").into())
     }
 
+    fn start_drag(
+        &self,
+        _: winit_core::clipboard::ClipboardContents,
+        _: &[winit_core::event::DragAction],
+        _: Option<winit_core::cursor::CustomCursorSource>,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(NotSupportedError::new("start_drag is not supported").into())
+    }
+
     fn show_window_menu(&self, _: Position) {}
 
     fn set_cursor_hittest(&self, _: bool) -> Result<(), RequestError> {
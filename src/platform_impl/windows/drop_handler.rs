@@ -91,7 +91,11 @@ impl FileDropHandler {
         let hdrop = unsafe { Self::iterate_filenames(pDataObj, |path| paths.push(path)) };
         drop_handler.valid = hdrop.is_some();
         if drop_handler.valid {
-            (drop_handler.send_event)(WindowEvent::DragEntered { paths, position });
+            (drop_handler.send_event)(WindowEvent::DragEntered {
+                paths,
+                mime_types: Vec::new(),
+                position,
+            });
         }
         drop_handler.cursor_effect =
             if drop_handler.valid { DROPEFFECT_COPY } else { DROPEFFECT_NONE };
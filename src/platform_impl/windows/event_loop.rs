@@ -63,13 +63,13 @@ use winit_core::application::ApplicationHandler;
 use winit_core::cursor::{CustomCursor, CustomCursorSource};
 use winit_core::error::{EventLoopError, NotSupportedError, RequestError};
 use winit_core::event::{
-    DeviceEvent, DeviceId, FingerId, Force, Ime, RawKeyEvent, SurfaceSizeWriter, TouchPhase,
-    WindowEvent,
+    DeviceEvent, DeviceId, DeviceInfo, FingerId, Force, Ime, RawKeyEvent, SurfaceSizeWriter,
+    TouchPhase, WindowEvent,
 };
 use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::{
     ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents,
-    EventLoopProxy as RootEventLoopProxy, EventLoopProxyProvider,
+    EventLoopProxy as RootEventLoopProxy, EventLoopProxyProvider, InputPreferences,
     OwnedDisplayHandle as CoreOwnedDisplayHandle,
 };
 use winit_core::keyboard::ModifiersState;
@@ -449,10 +449,40 @@ impl RootActiveEventLoop for ActiveEventLoop {
         Some(if super::dark_mode::should_use_dark_mode() { Theme::Dark } else { Theme::Light })
     }
 
+    fn input_preferences(&self) -> InputPreferences {
+        InputPreferences::default()
+    }
+
+    fn clipboard(&self) -> Option<&dyn winit_core::clipboard::Clipboard> {
+        None
+    }
+
+    fn primary_selection(&self) -> Option<&dyn winit_core::clipboard::Clipboard> {
+        None
+    }
+
+    fn set_drag_action(
+        &self,
+        _: Option<winit_core::event::DragAction>,
+    ) -> Result<(), RequestError> {
+        Err(NotSupportedError::new("set_drag_action is not supported").into())
+    }
+
+    fn request_drag_data(
+        &self,
+        _: &str,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(NotSupportedError::new("request_drag_data is not supported").into())
+    }
+
     fn listen_device_events(&self, allowed: DeviceEvents) {
         raw_input::register_all_mice_and_keyboards_for_raw_input(self.0.thread_msg_target, allowed);
     }
 
+    fn available_devices(&self) -> Box<dyn Iterator<Item = (DeviceId, DeviceInfo)>> {
+        Box::new(std::iter::empty())
+    }
+
     fn set_control_flow(&self, control_flow: ControlFlow) {
         self.0.set_control_flow(control_flow)
     }
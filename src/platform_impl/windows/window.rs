@@ -44,17 +44,18 @@ use windows_sys::Win32::UI::WindowsAndMessaging::{
     FLASHW_TRAY, GWLP_HINSTANCE, HTBOTTOM, HTBOTTOMLEFT, HTBOTTOMRIGHT, HTCAPTION, HTLEFT, HTRIGHT,
     HTTOP, HTTOPLEFT, HTTOPRIGHT, MENU_ITEM_STATE, MFS_DISABLED, MFS_ENABLED, MF_BYCOMMAND,
     NID_READY, PM_NOREMOVE, SC_CLOSE, SC_MAXIMIZE, SC_MINIMIZE, SC_MOVE, SC_RESTORE, SC_SIZE,
-    SM_DIGITIZER, SWP_ASYNCWINDOWPOS, SWP_NOACTIVATE, SWP_NOSIZE, SWP_NOZORDER, TPM_LEFTALIGN,
-    TPM_RETURNCMD, WDA_EXCLUDEFROMCAPTURE, WDA_NONE, WM_NCLBUTTONDOWN, WM_SETICON, WM_SYSCOMMAND,
-    WNDCLASSEXW,
+    SM_CXICON, SM_CXSMICON, SM_DIGITIZER, SWP_ASYNCWINDOWPOS, SWP_NOACTIVATE, SWP_NOSIZE,
+    SWP_NOZORDER, TPM_LEFTALIGN, TPM_RETURNCMD, WDA_EXCLUDEFROMCAPTURE, WDA_NONE, WM_NCLBUTTONDOWN,
+    WM_SETICON, WM_SYSCOMMAND, WNDCLASSEXW,
 };
 use winit_core::cursor::Cursor;
 use winit_core::error::RequestError;
-use winit_core::icon::{Icon, RgbaIcon};
+use winit_core::icon::Icon;
 use winit_core::monitor::{Fullscreen, MonitorHandle as CoreMonitorHandle, MonitorHandleProvider};
 use winit_core::window::{
-    CursorGrabMode, ImePurpose, ResizeDirection, Theme, UserAttentionType, Window as CoreWindow,
-    WindowAttributes, WindowButtons, WindowId, WindowLevel,
+    CursorGrabMode, ImeHint, ImePurpose, ResizeDirection, Theme, UserAttentionType,
+    Window as CoreWindow, WindowAttributes, WindowButtons, WindowCapabilities, WindowId,
+    WindowLevel,
 };
 
 use super::icon::WinCursor;
@@ -353,7 +354,12 @@ impl Window {
     }
 
     fn set_icon(&self, mut new_icon: Icon, icon_type: IconType) {
-        if let Some(icon) = new_icon.cast_ref::<RgbaIcon>() {
+        // Pick the image of the set that matches the size Windows shows the icon at.
+        let size = match icon_type {
+            IconType::Small => unsafe { GetSystemMetrics(SM_CXSMICON) },
+            IconType::Big => unsafe { GetSystemMetrics(SM_CXICON) },
+        };
+        if let Some(icon) = new_icon.rgba_image_for_size(size as u32) {
             let icon = match WinIcon::from_rgba(icon) {
                 Ok(icon) => icon,
                 Err(err) => {
@@ -629,6 +635,10 @@ impl CoreWindow for Window {
         buttons
     }
 
+    fn window_capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities::all()
+    }
+
     fn set_cursor(&self, cursor: Cursor) {
         match cursor {
             Cursor::Icon(icon) => {
@@ -738,6 +748,15 @@ impl CoreWindow for Window {
         Ok(())
     }
 
+    fn start_drag(
+        &self,
+        _: winit_core::clipboard::ClipboardContents,
+        _: &[winit_core::event::DragAction],
+        _: Option<winit_core::cursor::CustomCursorSource>,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(winit_core::error::NotSupportedError::new("start_drag is not supported").into())
+    }
+
     fn show_window_menu(&self, position: Position) {
         unsafe {
             self.handle_showing_window_menu(position);
@@ -1037,6 +1056,10 @@ impl CoreWindow for Window {
 
     fn set_ime_purpose(&self, _purpose: ImePurpose) {}
 
+    fn set_ime_surrounding_text(&self, _text: &str, _cursor: usize, _anchor: usize) {}
+
+    fn set_ime_hints(&self, _hints: ImeHint) {}
+
     fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
         let window = self.window;
         let active_window_handle = unsafe { GetActiveWindow() };
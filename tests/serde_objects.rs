@@ -3,7 +3,10 @@
 use serde::{Deserialize, Serialize};
 use winit::cursor::CursorIcon;
 use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize};
-use winit::event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase};
+use winit::event::{
+    DeviceInfo, DeviceKind, DragAction, ElementState, MouseButton, MouseScrollDelta, TabletButton,
+    TabletToolKind, TabletToolState, TouchPhase,
+};
 use winit::keyboard::{Key, KeyCode, KeyLocation, ModifiersState, NamedKey, PhysicalKey};
 
 #[allow(dead_code)]
@@ -26,6 +29,12 @@ fn events_serde() {
     needs_serde::<PhysicalKey>();
     needs_serde::<KeyLocation>();
     needs_serde::<ModifiersState>();
+    needs_serde::<DragAction>();
+    needs_serde::<TabletToolKind>();
+    needs_serde::<TabletButton>();
+    needs_serde::<TabletToolState>();
+    needs_serde::<DeviceInfo>();
+    needs_serde::<DeviceKind>();
 }
 
 #[test]
@@ -1,10 +1,14 @@
 //! End user application handling.
 
+use crate::clipboard::ClipboardEvent;
 use crate::event::{DeviceEvent, DeviceId, StartCause, WindowEvent};
 use crate::event_loop::ActiveEventLoop;
+use crate::monitor::MonitorEvent;
 use crate::window::WindowId;
 
 pub mod macos;
+pub mod wayland;
+pub mod x11;
 
 /// The handler of application-level events.
 pub trait ApplicationHandler {
@@ -211,6 +215,33 @@ pub trait ApplicationHandler {
         let _ = (event_loop, device_id, event);
     }
 
+    /// Emitted when an asynchronous [`Clipboard`] request completes.
+    ///
+    /// [`Clipboard`]: crate::clipboard::Clipboard
+    fn clipboard_event(&mut self, event_loop: &dyn ActiveEventLoop, event: ClipboardEvent) {
+        let _ = (event_loop, event);
+    }
+
+    /// Emitted when a monitor is connected, disconnected or changes its configuration.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only emitted on **Wayland**, **X11** and the headless backend.
+    fn monitor_event(&mut self, event_loop: &dyn ActiveEventLoop, event: MonitorEvent) {
+        let _ = (event_loop, event);
+    }
+
+    /// Emitted when the input preferences of the user have changed.
+    ///
+    /// The new preferences can be read with [`ActiveEventLoop::input_preferences`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only emitted on **Wayland**, **X11** and the headless backend.
+    fn input_preferences_changed(&mut self, event_loop: &dyn ActiveEventLoop) {
+        let _ = event_loop;
+    }
+
     /// Emitted when the event loop is about to block and wait for new events.
     ///
     /// Most applications shouldn't need to hook into this event since there is no real relationship
@@ -343,6 +374,22 @@ pub trait ApplicationHandler {
     fn macos_handler(&mut self) -> Option<&mut dyn macos::ApplicationHandlerExtMacOS> {
         None
     }
+
+    /// The X11-specific handler.
+    ///
+    /// The return value from this should not change at runtime.
+    #[inline(always)]
+    fn x11_handler(&mut self) -> Option<&mut dyn x11::ApplicationHandlerExtX11> {
+        None
+    }
+
+    /// The Wayland-specific handler.
+    ///
+    /// The return value from this should not change at runtime.
+    #[inline(always)]
+    fn wayland_handler(&mut self) -> Option<&mut dyn wayland::ApplicationHandlerExtWayland> {
+        None
+    }
 }
 
 #[deny(clippy::missing_trait_methods)]
@@ -387,6 +434,21 @@ impl<A: ?Sized + ApplicationHandler> ApplicationHandler for &mut A {
         (**self).device_event(event_loop, device_id, event);
     }
 
+    #[inline]
+    fn clipboard_event(&mut self, event_loop: &dyn ActiveEventLoop, event: ClipboardEvent) {
+        (**self).clipboard_event(event_loop, event);
+    }
+
+    #[inline]
+    fn monitor_event(&mut self, event_loop: &dyn ActiveEventLoop, event: MonitorEvent) {
+        (**self).monitor_event(event_loop, event);
+    }
+
+    #[inline]
+    fn input_preferences_changed(&mut self, event_loop: &dyn ActiveEventLoop) {
+        (**self).input_preferences_changed(event_loop);
+    }
+
     #[inline]
     fn about_to_wait(&mut self, event_loop: &dyn ActiveEventLoop) {
         (**self).about_to_wait(event_loop);
@@ -411,6 +473,16 @@ impl<A: ?Sized + ApplicationHandler> ApplicationHandler for &mut A {
     fn macos_handler(&mut self) -> Option<&mut dyn macos::ApplicationHandlerExtMacOS> {
         (**self).macos_handler()
     }
+
+    #[inline]
+    fn x11_handler(&mut self) -> Option<&mut dyn x11::ApplicationHandlerExtX11> {
+        (**self).x11_handler()
+    }
+
+    #[inline]
+    fn wayland_handler(&mut self) -> Option<&mut dyn wayland::ApplicationHandlerExtWayland> {
+        (**self).wayland_handler()
+    }
 }
 
 #[deny(clippy::missing_trait_methods)]
@@ -455,6 +527,21 @@ impl<A: ?Sized + ApplicationHandler> ApplicationHandler for Box<A> {
         (**self).device_event(event_loop, device_id, event);
     }
 
+    #[inline]
+    fn clipboard_event(&mut self, event_loop: &dyn ActiveEventLoop, event: ClipboardEvent) {
+        (**self).clipboard_event(event_loop, event);
+    }
+
+    #[inline]
+    fn monitor_event(&mut self, event_loop: &dyn ActiveEventLoop, event: MonitorEvent) {
+        (**self).monitor_event(event_loop, event);
+    }
+
+    #[inline]
+    fn input_preferences_changed(&mut self, event_loop: &dyn ActiveEventLoop) {
+        (**self).input_preferences_changed(event_loop);
+    }
+
     #[inline]
     fn about_to_wait(&mut self, event_loop: &dyn ActiveEventLoop) {
         (**self).about_to_wait(event_loop);
@@ -479,4 +566,14 @@ impl<A: ?Sized + ApplicationHandler> ApplicationHandler for Box<A> {
     fn macos_handler(&mut self) -> Option<&mut dyn macos::ApplicationHandlerExtMacOS> {
         (**self).macos_handler()
     }
+
+    #[inline]
+    fn x11_handler(&mut self) -> Option<&mut dyn x11::ApplicationHandlerExtX11> {
+        (**self).x11_handler()
+    }
+
+    #[inline]
+    fn wayland_handler(&mut self) -> Option<&mut dyn wayland::ApplicationHandlerExtWayland> {
+        (**self).wayland_handler()
+    }
 }
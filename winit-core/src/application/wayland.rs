@@ -0,0 +1,86 @@
+use crate::application::ApplicationHandler;
+use crate::event_loop::ActiveEventLoop;
+use crate::window::{ImeHint, ImePurpose, WindowId};
+
+/// Additional events on [`ApplicationHandler`] that are specific to Wayland.
+///
+/// This can be registered with [`ApplicationHandler::wayland_handler`].
+pub trait ApplicationHandlerExtWayland: ApplicationHandler {
+    /// The compositor sent a new state to the input method of the application.
+    ///
+    /// This is only emitted when the input method was requested with
+    /// `EventLoopBuilderExtWayland::with_input_method`, and the application should answer with
+    /// the `input_method_*` methods on `ActiveEventLoopExtWayland`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// impl ApplicationHandlerExtWayland for App {
+    ///     fn input_method_event(&mut self, event_loop: &dyn ActiveEventLoop, event: InputMethodEvent) {
+    ///         match event {
+    ///             InputMethodEvent::Activated => self.keyboard.show(),
+    ///             InputMethodEvent::Deactivated => self.keyboard.hide(),
+    ///             _ => (),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn input_method_event(&mut self, event_loop: &dyn ActiveEventLoop, event: InputMethodEvent) {
+        let _ = (event_loop, event);
+    }
+
+    /// The compositor picked the decoration mode of a window.
+    ///
+    /// This is emitted with the first configure of the window, before it's drawn for the first
+    /// time, and every time the compositor switches the mode afterwards.
+    fn decoration_mode_changed(
+        &mut self,
+        event_loop: &dyn ActiveEventLoop,
+        window_id: WindowId,
+        mode: DecorationMode,
+    ) {
+        let _ = (event_loop, window_id, mode);
+    }
+}
+
+/// Who draws the decorations of a window, as negotiated with the compositor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecorationMode {
+    /// The decorations are drawn by the client.
+    ///
+    /// Winit draws its own frame, unless the decorations were disabled with
+    /// `Window::set_decorations`, in which case the application is expected to draw them.
+    Client,
+
+    /// The decorations are drawn by the compositor.
+    Server,
+}
+
+/// The state of the text input the input method is attached to.
+///
+/// The events are delivered once the compositor applied the whole state, so several of them can
+/// be delivered in a row. The text indices are in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputMethodEvent {
+    /// A text input gained focus, and the input method should be shown.
+    ///
+    /// The surrounding text and content type are reset, and sent again right after.
+    Activated,
+
+    /// The text input lost focus, and the input method should be hidden.
+    Deactivated,
+
+    /// The text around the cursor of the text input.
+    ///
+    /// The text is truncated by the application, so the cursor and the anchor of the selection
+    /// are relative to it. When no text is selected, `cursor` and `anchor` are equal.
+    SurroundingText { text: String, cursor: usize, anchor: usize },
+
+    /// The kind of content expected by the text input.
+    ContentType { purpose: ImePurpose, hints: ImeHint },
+
+    /// Another client already acts as the input method of the seat.
+    ///
+    /// The input method doesn't receive events anymore, and requests to it are ignored.
+    Unavailable,
+}
@@ -0,0 +1,28 @@
+use crate::application::ApplicationHandler;
+use crate::event_loop::ActiveEventLoop;
+
+/// Additional events on [`ApplicationHandler`] that are specific to X11.
+///
+/// This can be registered with [`ApplicationHandler::x11_handler`].
+pub trait ApplicationHandlerExtX11: ApplicationHandler {
+    /// The XSETTINGS of the screen have changed.
+    ///
+    /// This is emitted when the settings manager, like `xsettingsd` or the settings daemon of the
+    /// desktop environment, updates the `_XSETTINGS_SETTINGS` property. The new settings can be
+    /// read with `ActiveEventLoopExtX11::xsettings`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// impl ApplicationHandlerExtX11 for App {
+    ///     fn xsettings_changed(&mut self, event_loop: &dyn ActiveEventLoop) {
+    ///         if let Some(settings) = event_loop.xsettings() {
+    ///             self.double_click_time = settings.double_click_time();
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn xsettings_changed(&mut self, event_loop: &dyn ActiveEventLoop) {
+        let _ = event_loop;
+    }
+}
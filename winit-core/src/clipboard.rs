@@ -0,0 +1,189 @@
+//! Types related to the clipboard.
+//!
+//! The clipboard is reached through [`ActiveEventLoop::clipboard`]. Writing to it is immediate,
+//! while reading is asynchronous: the request returns an [`AsyncRequestSerial`] and the result is
+//! delivered later to [`ApplicationHandler::clipboard_event`] as a [`ClipboardEvent`] tagged with
+//! that serial.
+//!
+//! On X11 and Wayland, the primary selection, which holds the last selected text and is pasted
+//! with the middle mouse button, is reached the same way through
+//! [`ActiveEventLoop::primary_selection`].
+//!
+//! [`ActiveEventLoop::clipboard`]: crate::event_loop::ActiveEventLoop::clipboard
+//! [`ActiveEventLoop::primary_selection`]: crate::event_loop::ActiveEventLoop::primary_selection
+//! [`ApplicationHandler::clipboard_event`]: crate::application::ApplicationHandler::clipboard_event
+use std::error::Error;
+use std::fmt;
+
+use crate::as_any::AsAny;
+use crate::error::RequestError;
+use crate::event_loop::AsyncRequestSerial;
+
+/// The MIME type used for UTF-8 encoded plain text.
+pub const TEXT_MIME_TYPE: &str = "text/plain;charset=utf-8";
+
+/// Access to the system clipboard or the primary selection.
+///
+/// ## Platform-specific
+///
+/// - **Wayland:** Writing requires a recent input serial, so it only works while one of the windows
+///   has keyboard focus.
+/// - **Windows / macOS / iOS / Android / Web / Orbital:** Unsupported.
+pub trait Clipboard: AsAny + fmt::Debug {
+    /// Take ownership of the clipboard and offer the given contents to other clients.
+    ///
+    /// The contents are served until another client takes ownership of the clipboard or
+    /// [`clear`][Self::clear] is called.
+    fn write(&self, contents: ClipboardContents) -> Result<(), RequestError>;
+
+    /// Request the clipboard data in the given MIME type.
+    ///
+    /// The result is delivered as [`ClipboardEvent::Read`] with the returned serial.
+    fn read(&self, mime_type: &str) -> Result<AsyncRequestSerial, RequestError>;
+
+    /// Request the list of MIME types the clipboard data is currently offered in.
+    ///
+    /// The result is delivered as [`ClipboardEvent::MimeTypes`] with the returned serial.
+    fn request_mime_types(&self) -> Result<AsyncRequestSerial, RequestError>;
+
+    /// Give up the ownership of the clipboard, if the application holds it.
+    fn clear(&self) -> Result<(), RequestError>;
+}
+
+impl dyn Clipboard + '_ {
+    /// Put the text on the clipboard.
+    ///
+    /// This is a shorthand for [`write`][Clipboard::write] with [`ClipboardContents::with_text`].
+    pub fn write_text(&self, text: impl Into<String>) -> Result<(), RequestError> {
+        self.write(ClipboardContents::new().with_text(text))
+    }
+
+    /// Request the clipboard contents as text.
+    ///
+    /// This is a shorthand for [`read`][Clipboard::read] with [`TEXT_MIME_TYPE`], use
+    /// [`ClipboardData::as_text`] on the result.
+    pub fn read_text(&self) -> Result<AsyncRequestSerial, RequestError> {
+        self.read(TEXT_MIME_TYPE)
+    }
+}
+
+impl_dyn_casting!(Clipboard);
+
+/// The data written to the clipboard, in one or several MIME types.
+///
+/// The types are offered to other clients in the order they were added, so the preferred
+/// representation should come first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClipboardContents {
+    entries: Vec<ClipboardData>,
+}
+
+impl ClipboardContents {
+    /// Creates empty contents.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds UTF-8 text, offered as [`TEXT_MIME_TYPE`].
+    ///
+    /// Backends additionally advertise the legacy text types of the platform, such as
+    /// `UTF8_STRING` on X11.
+    pub fn with_text(self, text: impl Into<String>) -> Self {
+        self.with_data(TEXT_MIME_TYPE, text.into())
+    }
+
+    /// Adds data in the given MIME type, replacing any data previously added with the same type.
+    pub fn with_data(mut self, mime_type: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        let entry = ClipboardData { mime_type: mime_type.into(), data: data.into() };
+        match self.entries.iter_mut().find(|old| old.mime_type == entry.mime_type) {
+            Some(old) => *old = entry,
+            None => self.entries.push(entry),
+        }
+        self
+    }
+
+    /// The MIME types of the contents, in the order they were added.
+    pub fn mime_types(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.mime_type.as_str())
+    }
+
+    /// Returns the data for the given MIME type.
+    pub fn get(&self, mime_type: &str) -> Option<&[u8]> {
+        self.entries.iter().find(|entry| entry.mime_type == mime_type).map(|entry| &*entry.data)
+    }
+
+    /// Returns `true` if no data was added.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Clipboard data in a single MIME type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardData {
+    /// The MIME type of the data.
+    pub mime_type: String,
+    /// The raw data.
+    pub data: Vec<u8>,
+}
+
+impl ClipboardData {
+    /// Returns the data as text when it's valid UTF-8.
+    pub fn as_text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
+    }
+}
+
+/// Identifies one of the clipboards of the system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClipboardKind {
+    /// The clipboard, returned by [`ActiveEventLoop::clipboard`].
+    ///
+    /// [`ActiveEventLoop::clipboard`]: crate::event_loop::ActiveEventLoop::clipboard
+    Clipboard,
+    /// The primary selection, returned by [`ActiveEventLoop::primary_selection`].
+    ///
+    /// [`ActiveEventLoop::primary_selection`]: crate::event_loop::ActiveEventLoop::primary_selection
+    Primary,
+}
+
+/// Result of an asynchronous clipboard request, see [`Clipboard`], or a change notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardEvent {
+    /// The data requested with [`Clipboard::read`] was received.
+    Read { serial: AsyncRequestSerial, result: Result<ClipboardData, ClipboardError> },
+
+    /// The MIME types requested with [`Clipboard::request_mime_types`] were received.
+    ///
+    /// The list is empty when the clipboard has no owner.
+    MimeTypes { serial: AsyncRequestSerial, mime_types: Vec<String> },
+
+    /// The contents of the clipboard changed, including when the application wrote to it.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Requires the XFixes extension.
+    /// - **Wayland:** Also sent when the application gains keyboard focus.
+    Changed { kind: ClipboardKind },
+}
+
+/// The reason a clipboard read failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ClipboardError {
+    /// The clipboard is empty, or its owner doesn't offer the data in the requested MIME type.
+    Unavailable,
+    /// The owner of the clipboard failed to transfer the data.
+    TransferFailed,
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable => write!(f, "clipboard data is not available in the requested type"),
+            Self::TransferFailed => write!(f, "failed to transfer the clipboard data"),
+        }
+    }
+}
+
+impl Error for ClipboardError {}
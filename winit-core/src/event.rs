@@ -11,12 +11,13 @@ use smol_str::SmolStr;
 #[cfg(web_platform)]
 use web_time::Instant;
 
+use crate::clipboard::{ClipboardData, ClipboardError};
 use crate::error::RequestError;
 use crate::event_loop::AsyncRequestSerial;
 use crate::keyboard::{self, ModifiersKeyState, ModifiersKeys, ModifiersState};
 #[cfg(doc)]
 use crate::window::Window;
-use crate::window::{ActivationToken, Theme};
+use crate::window::{ActivationToken, Theme, WindowCapabilities};
 
 /// Describes the reason the event loop is resuming.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -75,23 +76,57 @@ pub enum WindowEvent {
     /// The window has been destroyed.
     Destroyed,
 
-    /// A file drag operation has entered the window.
+    /// The popup was dismissed by the system, and should be dropped.
+    ///
+    /// This is only emitted for windows created with [`WindowAttributes::with_popup`], the popup
+    /// is hidden by the system when e.g. the user clicks outside of it.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Emitted when the compositor dismisses the `xdg_popup`.
+    /// - **X11:** Emitted when the parent window loses focus.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    ///
+    /// [`WindowAttributes::with_popup`]: crate::window::WindowAttributes::with_popup
+    PopupDismissed,
+
+    /// A drag operation has entered the window.
+    ///
+    /// Drags of files are accepted with [`DragAction::Copy`] by default, other drags are rejected
+    /// until accepted with [`ActiveEventLoop::set_drag_action`].
+    ///
+    /// [`ActiveEventLoop::set_drag_action`]: crate::event_loop::ActiveEventLoop::set_drag_action
     DragEntered {
         /// List of paths that are being dragged onto the window.
+        ///
+        /// Empty if no files are being dragged.
         paths: Vec<PathBuf>,
+        /// The MIME types the dragged data is offered in.
+        ///
+        /// The data can be requested in one of them with
+        /// [`ActiveEventLoop::request_drag_data`].
+        ///
+        /// ## Platform-specific
+        ///
+        /// - **Windows / macOS:** Always empty.
+        ///
+        /// [`ActiveEventLoop::request_drag_data`]: crate::event_loop::ActiveEventLoop::request_drag_data
+        mime_types: Vec<String>,
         /// (x,y) coordinates in pixels relative to the top-left corner of the window. May be
         /// negative on some platforms if something is dragged over a window's decorations (title
         /// bar, frame, etc).
         position: PhysicalPosition<f64>,
     },
-    /// A file drag operation has moved over the window.
+    /// A drag operation has moved over the window.
     DragMoved {
         /// (x,y) coordinates in pixels relative to the top-left corner of the window. May be
         /// negative on some platforms if something is dragged over a window's decorations (title
         /// bar, frame, etc).
         position: PhysicalPosition<f64>,
     },
-    /// The file drag operation has dropped file(s) on the window.
+    /// The drag operation has dropped its data on the window.
+    ///
+    /// The data can still be requested while this event is handled.
     DragDropped {
         /// List of paths that are being dragged onto the window.
         paths: Vec<PathBuf>,
@@ -100,7 +135,7 @@ pub enum WindowEvent {
         /// bar, frame, etc).
         position: PhysicalPosition<f64>,
     },
-    /// The file drag operation has been cancelled or left the window.
+    /// The drag operation has been cancelled or left the window.
     DragLeft {
         /// (x,y) coordinates in pixels relative to the top-left corner of the window. May be
         /// negative on some platforms if something is dragged over a window's decorations (title
@@ -112,6 +147,24 @@ pub enum WindowEvent {
         position: Option<PhysicalPosition<f64>>,
     },
 
+    /// The dragged data requested with [`ActiveEventLoop::request_drag_data`] was read.
+    ///
+    /// [`ActiveEventLoop::request_drag_data`]: crate::event_loop::ActiveEventLoop::request_drag_data
+    DragData {
+        /// The serial returned by the request.
+        serial: AsyncRequestSerial,
+        /// The requested data.
+        result: Result<ClipboardData, ClipboardError>,
+    },
+
+    /// The drag started with [`Window::start_drag`] progressed.
+    DragSource {
+        /// The serial returned by [`Window::start_drag`].
+        serial: AsyncRequestSerial,
+        /// How the drag progressed.
+        event: DragSourceEvent,
+    },
+
     /// The window gained or lost focus.
     ///
     /// The parameter is true if the window has gained focus, and false if it has lost focus.
@@ -267,8 +320,9 @@ pub enum WindowEvent {
     ///
     /// ## Platform-specific
     ///
-    /// - Only available on **macOS** and **iOS**.
+    /// - Only available on **macOS**, **iOS**, **Wayland** and **X11**.
     /// - On iOS, not recognized by default. It must be enabled when needed.
+    /// - **X11:** Requires XInput 2.4.
     PinchGesture {
         device_id: Option<DeviceId>,
         /// Positive values indicate magnification (zooming in) and  negative
@@ -283,8 +337,12 @@ pub enum WindowEvent {
     ///
     /// ## Platform-specific
     ///
-    /// - Only available on **iOS**.
+    /// - Only available on **iOS**, **Wayland** and **X11**.
     /// - On iOS, not recognized by default. It must be enabled when needed.
+    /// - **Wayland / X11:** Emitted for swipes with three or more fingers on a touchpad, since two
+    ///   fingers are used for scrolling.
+    /// - **Wayland:** Fingers held still on a touchpad are reported as a pan without movement.
+    /// - **X11:** Requires XInput 2.4.
     PanGesture {
         device_id: Option<DeviceId>,
         /// Change in pixels of pan gesture from last update.
@@ -319,8 +377,10 @@ pub enum WindowEvent {
     ///
     /// ## Platform-specific
     ///
-    /// - Only available on **macOS** and **iOS**.
+    /// - Only available on **macOS**, **iOS**, **Wayland** and **X11**.
     /// - On iOS, not recognized by default. It must be enabled when needed.
+    /// - **Wayland / X11:** Reported along with [`WindowEvent::PinchGesture`].
+    /// - **X11:** Requires XInput 2.4.
     RotationGesture {
         device_id: Option<DeviceId>,
         /// change in rotation in degrees
@@ -377,9 +437,21 @@ pub enum WindowEvent {
     ///
     /// ## Platform-specific
     ///
-    /// - **iOS / Android / X11 / Wayland / Orbital:** Unsupported.
+    /// - **iOS / Android / Orbital:** Unsupported.
     ThemeChanged(Theme),
 
+    /// The window management actions supported for the window have changed.
+    ///
+    /// Applications drawing their own decorations should hide the controls of the actions that
+    /// are no longer supported. See [`Window::window_capabilities`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Emitted when the compositor sends new `xdg_toplevel` wm_capabilities.
+    /// - **X11:** Emitted when the window manager changes `_NET_WM_ALLOWED_ACTIONS`.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    CapabilitiesChanged(WindowCapabilities),
+
     /// The window has been occluded (completely hidden from view).
     ///
     /// This is different to window visibility as it depends on whether the window is closed,
@@ -423,6 +495,34 @@ pub enum WindowEvent {
     RedrawRequested,
 }
 
+/// The progress of a drag started with [`Window::start_drag`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DragSourceEvent {
+    /// Whether the target under the pointer would accept the data if it was dropped.
+    Accepted(bool),
+    /// The action the target would perform with the data, `None` if there's none.
+    Action(Option<DragAction>),
+    /// The data was dropped and the target is done with it.
+    ///
+    /// When the action is [`DragAction::Move`], the data is expected to be removed from the
+    /// window.
+    Finished(DragAction),
+    /// The drag was cancelled, or the data was dropped where it's not accepted.
+    Cancelled,
+}
+
+/// The action performed with the data of a drag operation when it's dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DragAction {
+    /// The data is copied, the source keeps it.
+    Copy,
+    /// The data is moved, the source removes it once the drop is done.
+    Move,
+    /// A link to the data is created.
+    Link,
+}
+
 /// Represents the kind type of a pointer event.
 ///
 /// ## Platform-specific
@@ -438,6 +538,12 @@ pub enum PointerKind {
     ///
     /// **macOS:** Unsupported.
     Touch(FingerId),
+    /// See [`PointerSource::Tablet`] for more details.
+    ///
+    /// ## Platform-specific
+    ///
+    /// **Wayland / X11:** Only supported platforms.
+    Tablet(TabletTool),
     Unknown,
 }
 
@@ -488,6 +594,23 @@ pub enum PointerSource {
         ///   force will be 0.5 when a button is pressed or 0.0 otherwise.
         force: Option<Force>,
     },
+    /// Represents a tool, such as a pen or its eraser, used on a drawing tablet.
+    ///
+    /// A [`WindowEvent::PointerEntered`] is emitted when the tool comes in proximity of the
+    /// tablet over the window, and a [`WindowEvent::PointerLeft`] when it goes out of proximity.
+    /// While in proximity, the tool doesn't have to touch the tablet to emit
+    /// [`WindowEvent::PointerMoved`] events, touching it is reported as a
+    /// [`WindowEvent::PointerButton`] with [`TabletButton::Tip`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland / X11:** Only supported platforms.
+    /// - **X11:** Proximity isn't reported, [`WindowEvent::PointerEntered`] and
+    ///   [`WindowEvent::PointerLeft`] are emitted when the tool crosses the edges of the window.
+    Tablet {
+        tool: TabletTool,
+        state: TabletToolState,
+    },
     Unknown,
 }
 
@@ -496,6 +619,7 @@ impl From<PointerSource> for PointerKind {
         match source {
             PointerSource::Mouse => Self::Mouse,
             PointerSource::Touch { finger_id, .. } => Self::Touch(finger_id),
+            PointerSource::Tablet { tool, .. } => Self::Tablet(tool),
             PointerSource::Unknown => Self::Unknown,
         }
     }
@@ -517,6 +641,16 @@ pub enum ButtonSource {
         finger_id: FingerId,
         force: Option<Force>,
     },
+    /// See [`PointerSource::Tablet`] for more details.
+    ///
+    /// ## Platform-specific
+    ///
+    /// **Wayland / X11:** Only supported platforms.
+    Tablet {
+        tool: TabletTool,
+        button: TabletButton,
+        state: TabletToolState,
+    },
     Unknown(u16),
 }
 
@@ -528,6 +662,12 @@ impl ButtonSource {
         match self {
             ButtonSource::Mouse(mouse) => mouse,
             ButtonSource::Touch { .. } => MouseButton::Left,
+            ButtonSource::Tablet { button, .. } => match button {
+                TabletButton::Tip => MouseButton::Left,
+                TabletButton::Barrel(0) => MouseButton::Middle,
+                TabletButton::Barrel(1) => MouseButton::Right,
+                TabletButton::Barrel(button) => MouseButton::Other(button),
+            },
             ButtonSource::Unknown(button) => match button {
                 0 => MouseButton::Left,
                 1 => MouseButton::Middle,
@@ -546,6 +686,79 @@ impl From<MouseButton> for ButtonSource {
     }
 }
 
+/// A tool used on a drawing tablet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TabletTool {
+    /// The type of the tool.
+    pub kind: TabletToolKind,
+    /// The hardware serial number of the tool, which tells apart tools of the same type.
+    ///
+    /// ## Platform-specific
+    ///
+    /// **X11:** Always [`None`].
+    pub serial: Option<u64>,
+}
+
+/// The type of a [`TabletTool`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TabletToolKind {
+    Pen,
+    /// The eraser end of a pen.
+    Eraser,
+    Brush,
+    Pencil,
+    Airbrush,
+    Finger,
+    /// A mouse bound to the tablet.
+    Mouse,
+    /// A mouse with a lens, also known as a puck.
+    Lens,
+    Unknown,
+}
+
+/// A button of a [`TabletTool`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TabletButton {
+    /// The tip of the tool, pressed while it touches the tablet.
+    Tip,
+    /// A button on the barrel of the tool, the one closest to the tip is `0`.
+    ///
+    /// The stylus has up to 3 of them, the other buttons, like the ones of the mouse and lens
+    /// tools, are numbered from `3`.
+    Barrel(u16),
+}
+
+/// The state of the axes of a [`TabletTool`].
+///
+/// An axis is [`None`] when the tool doesn't have it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TabletToolState {
+    /// The pressure applied with the tool, normalized.
+    pub force: Option<Force>,
+    /// The distance between the tool and the tablet, normalized to the range between 0.0 and 1.0
+    /// inclusive.
+    pub distance: Option<f64>,
+    /// The tilt of the tool, in degrees.
+    pub tilt: Option<TabletToolTilt>,
+    /// The rotation of the tool around its own axis, in clockwise degrees.
+    pub twist: Option<f64>,
+}
+
+/// The tilt of a [`TabletTool`] relative to the normal of the tablet, in degrees.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TabletToolTilt {
+    /// The tilt towards the positive X axis.
+    pub x: f64,
+    /// The tilt towards the positive Y axis.
+    pub y: f64,
+}
+
 /// Identifier of an input device.
 ///
 /// Whenever you receive an event arising from a particular input device, this event contains a
@@ -604,8 +817,32 @@ impl FingerId {
 /// Note that these events are delivered regardless of input focus.
 ///
 /// [window events]: WindowEvent
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum DeviceEvent {
+    /// A device was connected.
+    ///
+    /// Unlike the other device events, this is delivered regardless of
+    /// [`ActiveEventLoop::listen_device_events`]. Devices that were already connected are not
+    /// reported, use [`ActiveEventLoop::available_devices`] to enumerate them.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Only the capabilities of the seats and the tablets are exposed as devices,
+    ///   the devices present when the event loop starts are reported as well.
+    /// - **Windows / macOS / iOS / Android / Web / Orbital:** Unsupported.
+    ///
+    /// [`ActiveEventLoop::listen_device_events`]: crate::event_loop::ActiveEventLoop::listen_device_events
+    /// [`ActiveEventLoop::available_devices`]: crate::event_loop::ActiveEventLoop::available_devices
+    Added(DeviceInfo),
+
+    /// A device was disconnected.
+    ///
+    /// Like [`DeviceEvent::Added`], this is delivered regardless of
+    /// [`ActiveEventLoop::listen_device_events`].
+    ///
+    /// [`ActiveEventLoop::listen_device_events`]: crate::event_loop::ActiveEventLoop::listen_device_events
+    Removed,
+
     /// Change in physical position of a pointing device.
     ///
     /// This represents raw, unfiltered physical motion. Not to be confused with
@@ -637,6 +874,34 @@ pub enum DeviceEvent {
     Key(RawKeyEvent),
 }
 
+/// Describes an input device, see [`DeviceEvent::Added`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeviceInfo {
+    /// The name of the device, as reported by the system.
+    pub name: String,
+    pub kind: DeviceKind,
+    /// The USB vendor ID of the device, if known.
+    pub vendor_id: Option<u16>,
+    /// The USB product ID of the device, if known.
+    pub product_id: Option<u16>,
+}
+
+/// The kind of an input device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum DeviceKind {
+    Keyboard,
+    /// A mouse, touchpad or any other device moving the cursor.
+    Pointer,
+    /// A touchscreen.
+    Touch,
+    /// A drawing tablet.
+    Tablet,
+    Unknown,
+}
+
 /// Describes a keyboard input as a raw device event.
 ///
 /// Note that holding down a key may produce repeated `RawKeyEvent`s. The
@@ -946,6 +1211,22 @@ pub enum Ime {
     /// Right before this event winit will send empty [`Self::Preedit`] event.
     Commit(String),
 
+    /// Notifies when the text around the cursor should be deleted.
+    ///
+    /// The lengths are byte-wise, and relative to the cursor or the selection, which isn't
+    /// deleted. They are within the text reported with [`Window::set_ime_surrounding_text`].
+    /// This is sent after the empty [`Self::Preedit`] and before the [`Self::Commit`] it comes
+    /// with.
+    ///
+    /// Input methods use this to replace the text before the cursor, like when correcting a word
+    /// or composing Korean syllables.
+    DeleteSurrounding {
+        /// The number of bytes to delete before the cursor.
+        before_bytes: usize,
+        /// The number of bytes to delete after the cursor.
+        after_bytes: usize,
+    },
+
     /// Notifies when the IME was disabled.
     ///
     /// After receiving this event you won't get any more [`Preedit`][Self::Preedit] or
@@ -1134,17 +1415,32 @@ mod tests {
 
             use crate::event::Ime::Enabled;
             use crate::event::WindowEvent::*;
+            use crate::clipboard::ClipboardError;
             use crate::event::{PointerKind, PointerSource};
+            use crate::event_loop::AsyncRequestSerial;
 
             with_window_event(CloseRequested);
             with_window_event(Destroyed);
+            with_window_event(PopupDismissed);
             with_window_event(Focused(true));
             with_window_event(Moved((0, 0).into()));
             with_window_event(SurfaceResized((0, 0).into()));
-            with_window_event(DragEntered { paths: vec!["x.txt".into()], position: (0, 0).into() });
+            with_window_event(DragEntered {
+                paths: vec!["x.txt".into()],
+                mime_types: vec!["text/uri-list".into()],
+                position: (0, 0).into(),
+            });
             with_window_event(DragMoved { position: (0, 0).into() });
             with_window_event(DragDropped { paths: vec!["x.txt".into()], position: (0, 0).into() });
             with_window_event(DragLeft { position: Some((0, 0).into()) });
+            with_window_event(DragData {
+                serial: AsyncRequestSerial::get(),
+                result: Err(ClipboardError::Unavailable),
+            });
+            with_window_event(DragSource {
+                serial: AsyncRequestSerial::get(),
+                event: event::DragSourceEvent::Cancelled,
+            });
             with_window_event(Ime(Enabled));
             with_window_event(PointerMoved {
                 device_id: None,
@@ -1205,6 +1501,7 @@ mod tests {
             });
             with_window_event(TouchpadPressure { device_id: None, pressure: 0.0, stage: 0 });
             with_window_event(ThemeChanged(crate::window::Theme::Light));
+            with_window_event(CapabilitiesChanged(crate::window::WindowCapabilities::all()));
             with_window_event(Occluded(true));
         }};
         (device: $closure:expr) => {{
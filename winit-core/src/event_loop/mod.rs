@@ -12,8 +12,10 @@ use rwh_06::{DisplayHandle, HandleError, HasDisplayHandle};
 use web_time::{Duration, Instant};
 
 use crate::as_any::AsAny;
+use crate::clipboard::Clipboard;
 use crate::cursor::{CustomCursor, CustomCursorSource};
 use crate::error::RequestError;
+use crate::event::{DeviceId, DeviceInfo, DragAction};
 use crate::monitor::MonitorHandle;
 use crate::window::{Theme, Window, WindowAttributes};
 
@@ -75,15 +77,103 @@ pub trait ActiveEventLoop: AsAny + fmt::Debug {
     /// [`DeviceEvent`]: crate::event::DeviceEvent
     fn listen_device_events(&self, allowed: DeviceEvents);
 
+    /// Returns the input devices currently connected.
+    ///
+    /// Changes are reported with [`DeviceEvent::Added`] and [`DeviceEvent::Removed`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Only the capabilities of the seats and the tablets are exposed as devices.
+    /// - **Windows / macOS / iOS / Android / Web / Orbital:** Unsupported, always empty.
+    ///
+    /// [`DeviceEvent::Added`]: crate::event::DeviceEvent::Added
+    /// [`DeviceEvent::Removed`]: crate::event::DeviceEvent::Removed
+    fn available_devices(&self) -> Box<dyn Iterator<Item = (DeviceId, DeviceInfo)>>;
+
     /// Returns the current system theme.
     ///
     /// Returns `None` if it cannot be determined on the current platform.
     ///
     /// ## Platform-specific
     ///
-    /// - **iOS / Android / Wayland / x11 / Orbital:** Unsupported.
+    /// - **Wayland / X11:** Read from the `color-scheme` setting of the XDG desktop portal. X11
+    ///   falls back to the `Net/ThemeName` XSETTINGS, considering themes with `dark` in their name
+    ///   as dark.
+    /// - **iOS / Android / Orbital:** Unsupported.
     fn system_theme(&self) -> Option<Theme>;
 
+    /// Returns the pointer and keyboard preferences of the user.
+    ///
+    /// Toolkits can use these instead of hard-coding the double-click interval or the drag
+    /// threshold. Changes are reported with [`ApplicationHandler::input_preferences_changed`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Read from XSETTINGS, and from XKB for the key repeat.
+    /// - **Wayland:** The key repeat is read from the keyboard of the seat, the other preferences
+    ///   from the settings of GNOME or KDE exposed by the XDG desktop portal.
+    /// - **Windows / macOS / iOS / Android / Web / Orbital:** Unsupported, always empty.
+    ///
+    /// [`ApplicationHandler::input_preferences_changed`]: crate::application::ApplicationHandler::input_preferences_changed
+    fn input_preferences(&self) -> InputPreferences;
+
+    /// Returns the system clipboard.
+    ///
+    /// Returns `None` if the clipboard is not available on the current platform.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows / macOS / iOS / Android / Web / Orbital:** Unsupported.
+    fn clipboard(&self) -> Option<&dyn Clipboard>;
+
+    /// Returns the primary selection, which holds the last selected text.
+    ///
+    /// Applications are expected to write the selected text to it and to paste its contents on
+    /// a middle mouse button click.
+    ///
+    /// Returns `None` if the primary selection is not available on the current platform.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Requires the `zwp_primary_selection_device_manager_v1` protocol.
+    /// - **Windows / macOS / iOS / Android / Web / Orbital:** Unsupported.
+    fn primary_selection(&self) -> Option<&dyn Clipboard>;
+
+    /// Accepts or rejects the data dragged over one of the windows.
+    ///
+    /// This is meant to be called while handling [`WindowEvent::DragEntered`] or
+    /// [`WindowEvent::DragMoved`], the choice applies until it's changed or the drag leaves the
+    /// window. `None` rejects the drag, otherwise the data is accepted with the given action when
+    /// dropped.
+    ///
+    /// Returns [`RequestError::Ignored`] if nothing is dragged over the windows.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** [`DragAction::Link`] is unsupported.
+    /// - **Windows / macOS / iOS / Android / Web / Orbital:** Unsupported.
+    ///
+    /// [`WindowEvent::DragEntered`]: crate::event::WindowEvent::DragEntered
+    /// [`WindowEvent::DragMoved`]: crate::event::WindowEvent::DragMoved
+    fn set_drag_action(&self, action: Option<DragAction>) -> Result<(), RequestError>;
+
+    /// Requests the data dragged over one of the windows in the given MIME type.
+    ///
+    /// The MIME type should be one of those listed in [`WindowEvent::DragEntered`]. The data is
+    /// delivered with [`WindowEvent::DragData`] and the returned serial; it can be requested
+    /// until [`WindowEvent::DragDropped`] was handled.
+    ///
+    /// Returns [`RequestError::Ignored`] if nothing is dragged over the windows.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows / macOS / iOS / Android / Web / Orbital:** Unsupported.
+    ///
+    /// [`WindowEvent::DragEntered`]: crate::event::WindowEvent::DragEntered
+    /// [`WindowEvent::DragData`]: crate::event::WindowEvent::DragData
+    /// [`WindowEvent::DragDropped`]: crate::event::WindowEvent::DragDropped
+    fn request_drag_data(&self, mime_type: &str) -> Result<AsyncRequestSerial, RequestError>;
+
     /// Sets the [`ControlFlow`].
     fn set_control_flow(&self, control_flow: ControlFlow);
 
@@ -266,6 +356,30 @@ pub enum DeviceEvents {
     Never,
 }
 
+/// The pointer and keyboard preferences of the user, see [`ActiveEventLoop::input_preferences`].
+///
+/// Preferences that couldn't be determined are `None`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct InputPreferences {
+    /// The maximum time between the clicks of a double click.
+    pub double_click_interval: Option<Duration>,
+    /// The maximum distance in physical pixels between the clicks of a double click.
+    pub double_click_distance: Option<u32>,
+    /// The distance in physical pixels the pointer has to move with a button pressed before a
+    /// drag starts.
+    pub drag_threshold: Option<u32>,
+    /// Whether the text caret should blink.
+    pub caret_blink: Option<bool>,
+    /// The time between two blinks of the text caret.
+    pub caret_blink_interval: Option<Duration>,
+    /// The time a key has to be held before it starts repeating.
+    pub key_repeat_delay: Option<Duration>,
+    /// The time between two repeats of a held key.
+    pub key_repeat_interval: Option<Duration>,
+}
+
 /// A unique identifier of the winit's async request.
 ///
 /// This could be used to identify the async request once it's done
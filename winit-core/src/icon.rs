@@ -1,7 +1,7 @@
 use std::error::Error;
 use std::ops::Deref;
 use std::sync::Arc;
-use std::{fmt, io, mem};
+use std::{fmt, io, mem, slice};
 
 use crate::as_any::{impl_dyn_casting, AsAny};
 
@@ -14,6 +14,40 @@ pub struct Icon(pub Arc<dyn IconProvider>);
 // TODO remove that once split.
 pub trait IconProvider: AsAny + fmt::Debug + Send + Sync {}
 
+impl Icon {
+    /// The RGBA images of the icon, for the backends that upload the pixels themselves.
+    ///
+    /// This is empty when the icon isn't backed by an [`RgbaIcon`] or an [`IconSet`].
+    pub fn rgba_images(&self) -> &[RgbaIcon] {
+        if let Some(icon) = self.cast_ref::<RgbaIcon>() {
+            slice::from_ref(icon)
+        } else if let Some(set) = self.cast_ref::<IconSet>() {
+            set.images()
+        } else {
+            &[]
+        }
+    }
+
+    /// The RGBA image that is best suited to be shown at the given size.
+    ///
+    /// This is the smallest image that covers the size in both dimensions, or the largest one
+    /// when none of them does, so that it's scaled down rather than up.
+    pub fn rgba_image_for_size(&self, size: u32) -> Option<&RgbaIcon> {
+        let images = self.rgba_images();
+        let side = |image: &&RgbaIcon| image.width.min(image.height);
+        images
+            .iter()
+            .filter(|image| side(image) >= size)
+            .min_by_key(side)
+            .or_else(|| images.iter().max_by_key(side))
+    }
+
+    /// The name of the icon in the freedesktop icon theme, if any.
+    pub fn theme_name(&self) -> Option<&str> {
+        self.cast_ref::<IconSet>().and_then(IconSet::name)
+    }
+}
+
 impl Deref for Icon {
     type Target = dyn IconProvider;
 
@@ -105,3 +139,77 @@ impl From<RgbaIcon> for Icon {
         Self(Arc::new(value))
     }
 }
+
+/// An icon made of several images of different sizes, with an optional themed name.
+///
+/// The backends pick the image that is the closest to the size they display the icon at, instead
+/// of scaling a single image. When the name is set, the backends that support themed icons look
+/// it up in the freedesktop icon theme, and fall back to the images when it can't be found.
+///
+/// ## Platform-specific
+///
+/// - **X11:** All the images are set in `_NET_WM_ICON`. The name is ignored.
+/// - **Wayland:** The name is only used with the `xdg_toplevel_icon_v1` protocol.
+/// - **Windows:** The name is ignored.
+#[derive(Debug, Clone, Default)]
+pub struct IconSet {
+    images: Vec<RgbaIcon>,
+    name: Option<String>,
+}
+
+impl IconSet {
+    /// Create an icon from images of different sizes.
+    pub fn new(images: Vec<RgbaIcon>) -> Self {
+        Self { images, name: None }
+    }
+
+    /// Set the name of the icon in the freedesktop icon theme, e.g. `org.example.App`.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// The images of the icon, in the order they were given.
+    pub fn images(&self) -> &[RgbaIcon] {
+        &self.images
+    }
+
+    /// The name of the icon in the freedesktop icon theme, if set with [`Self::with_name`].
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+impl IconProvider for IconSet {}
+
+impl From<IconSet> for Icon {
+    fn from(value: IconSet) -> Self {
+        Self(Arc::new(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(size: u32) -> RgbaIcon {
+        RgbaIcon::new(vec![0; (size * size) as usize * PIXEL_SIZE], size, size).unwrap()
+    }
+
+    #[test]
+    fn image_for_size() {
+        let icon = Icon::from(IconSet::new(vec![square(16), square(48), square(32)]));
+        assert_eq!(icon.rgba_images().len(), 3);
+        assert_eq!(icon.rgba_image_for_size(16).unwrap().width(), 16);
+        assert_eq!(icon.rgba_image_for_size(24).unwrap().width(), 32);
+        assert_eq!(icon.rgba_image_for_size(64).unwrap().width(), 48);
+
+        let icon = Icon::from(square(16));
+        assert_eq!(icon.rgba_image_for_size(32).unwrap().width(), 16);
+        assert_eq!(icon.theme_name(), None);
+
+        let icon = Icon::from(IconSet::new(Vec::new()).with_name("org.example.App"));
+        assert!(icon.rgba_image_for_size(32).is_none());
+        assert_eq!(icon.theme_name(), Some("org.example.App"));
+    }
+}
@@ -13,6 +13,7 @@ pub mod cursor;
 #[macro_use]
 pub mod error;
 pub mod application;
+pub mod clipboard;
 pub mod event;
 pub mod event_loop;
 pub mod icon;
@@ -5,6 +5,10 @@
 //! methods, which return an iterator of [`MonitorHandle`]:
 //! - [`ActiveEventLoop::available_monitors`][crate::event_loop::ActiveEventLoop::available_monitors].
 //! - [`Window::available_monitors`][crate::window::Window::available_monitors].
+//!
+//! Changes of the monitors are delivered to
+//! [`ApplicationHandler::monitor_event`][crate::application::ApplicationHandler::monitor_event] as
+//! [`MonitorEvent`]s.
 use std::borrow::Cow;
 use std::fmt;
 use std::num::{NonZeroU16, NonZeroU32};
@@ -121,6 +125,19 @@ impl Eq for dyn MonitorHandleProvider + '_ {}
 
 impl_dyn_casting!(MonitorHandleProvider);
 
+/// Describes a change of the monitors connected to the system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorEvent {
+    /// A monitor was connected.
+    Added(MonitorHandle),
+    /// A monitor was disconnected.
+    ///
+    /// The handle only holds the information the monitor had before it was disconnected.
+    Removed(MonitorHandle),
+    /// The position, scale factor, video mode or another property of a monitor changed.
+    Changed(MonitorHandle),
+}
+
 /// Describes a fullscreen video mode of a monitor.
 ///
 /// Can be acquired with [`MonitorHandleProvider::video_modes`].
@@ -2,13 +2,16 @@
 use std::fmt;
 
 use cursor_icon::CursorIcon;
-use dpi::{PhysicalInsets, PhysicalPosition, PhysicalSize, Position, Size};
+use dpi::{LogicalPosition, PhysicalInsets, PhysicalPosition, PhysicalSize, Position, Size};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::as_any::AsAny;
-use crate::cursor::Cursor;
+use crate::clipboard::ClipboardContents;
+use crate::cursor::{Cursor, CustomCursorSource};
 use crate::error::RequestError;
+use crate::event::DragAction;
+use crate::event_loop::AsyncRequestSerial;
 use crate::icon::Icon;
 use crate::monitor::{Fullscreen, MonitorHandle};
 
@@ -68,6 +71,7 @@ pub struct WindowAttributes {
     pub cursor: Cursor,
     pub(crate) parent_window: Option<SendSyncRawWindowHandle>,
     pub fullscreen: Option<Fullscreen>,
+    pub popup: Option<PopupAttributes>,
     pub platform: Option<Box<dyn PlatformWindowAttributes>>,
 }
 
@@ -367,6 +371,29 @@ impl WindowAttributes {
         self
     }
 
+    /// Build a popup window, like a menu, a tooltip or a combo-box dropdown.
+    ///
+    /// The popup is placed relative to its parent window according to the [`PopupAttributes`],
+    /// and [`WindowAttributes::position`] is ignored. The system may dismiss the popup at any
+    /// time, which is reported with [`WindowEvent::PopupDismissed`].
+    ///
+    /// The default is `None`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Created as an `xdg_popup`, the parent must be a window of the same event
+    ///   loop.
+    /// - **X11:** Created as an override-redirect window transient for the parent.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported, a regular window is
+    ///   created instead.
+    ///
+    /// [`WindowEvent::PopupDismissed`]: crate::event::WindowEvent::PopupDismissed
+    #[inline]
+    pub fn with_popup(mut self, popup: PopupAttributes) -> Self {
+        self.popup = Some(popup);
+        self
+    }
+
     /// Set the platform specific opaque attribute object.
     ///
     /// The interpretation will depend on the underlying backend that will be used.
@@ -401,6 +428,7 @@ impl Clone for WindowAttributes {
             cursor: self.cursor.clone(),
             parent_window: self.parent_window.clone(),
             fullscreen: self.fullscreen.clone(),
+            popup: self.popup,
             platform: self.platform.as_ref().map(|platform| platform.box_clone()),
         }
     }
@@ -429,6 +457,7 @@ impl Default for WindowAttributes {
             fullscreen: Default::default(),
             maximized: Default::default(),
             position: Default::default(),
+            popup: Default::default(),
             platform: Default::default(),
             cursor: Cursor::default(),
             blur: Default::default(),
@@ -436,6 +465,309 @@ impl Default for WindowAttributes {
     }
 }
 
+/// The placement of a popup window, for use in [`WindowAttributes::with_popup`].
+///
+/// The popup is placed against the anchor rectangle, which is relative to the surface of the
+/// parent window: the [`anchor`] picks a point on the rectangle, and the popup extends from that
+/// point in the direction of the [`gravity`], moved by the [`offset`]. When the popup doesn't fit
+/// on the monitor, it's adjusted according to the [`constraint_adjustment`].
+///
+/// This mirrors `xdg_positioner` on Wayland.
+///
+/// [`anchor`]: Self::anchor
+/// [`gravity`]: Self::gravity
+/// [`offset`]: Self::offset
+/// [`constraint_adjustment`]: Self::constraint_adjustment
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct PopupAttributes {
+    /// The window the popup belongs to, it can be another popup.
+    pub parent: WindowId,
+    /// The position of the anchor rectangle, relative to the surface of the parent.
+    pub anchor_position: Position,
+    /// The size of the anchor rectangle.
+    pub anchor_size: Size,
+    /// The point of the anchor rectangle the popup is attached to.
+    pub anchor: PopupAnchor,
+    /// The direction in which the popup extends from the anchor point.
+    ///
+    /// For example, [`PopupAnchor::BottomRight`] puts the top left corner of the popup on the
+    /// anchor point.
+    pub gravity: PopupAnchor,
+    /// Moves the popup away from the anchor point.
+    pub offset: Position,
+    /// How the popup is adjusted when it doesn't fit on the monitor.
+    pub constraint_adjustment: PopupConstraintAdjustment,
+    /// Whether the popup grabs the input, like menus do.
+    pub grab: bool,
+}
+
+impl PopupAttributes {
+    /// Create the attributes of a popup of the `parent` window, anchored to the rectangle at
+    /// `anchor_position` of `anchor_size`.
+    ///
+    /// The popup is placed below the rectangle, starting at its left edge.
+    pub fn new(
+        parent: WindowId,
+        anchor_position: impl Into<Position>,
+        anchor_size: impl Into<Size>,
+    ) -> Self {
+        Self {
+            parent,
+            anchor_position: anchor_position.into(),
+            anchor_size: anchor_size.into(),
+            anchor: PopupAnchor::BottomLeft,
+            gravity: PopupAnchor::BottomRight,
+            offset: LogicalPosition::new(0, 0).into(),
+            constraint_adjustment: PopupConstraintAdjustment::default(),
+            grab: true,
+        }
+    }
+
+    /// Sets the point of the anchor rectangle the popup is attached to.
+    ///
+    /// The default is [`PopupAnchor::BottomLeft`].
+    #[inline]
+    pub fn with_anchor(mut self, anchor: PopupAnchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Sets the direction in which the popup extends from the anchor point.
+    ///
+    /// The default is [`PopupAnchor::BottomRight`].
+    #[inline]
+    pub fn with_gravity(mut self, gravity: PopupAnchor) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Sets the offset of the popup from the anchor point.
+    ///
+    /// The default is no offset.
+    #[inline]
+    pub fn with_offset<P: Into<Position>>(mut self, offset: P) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    /// Sets how the popup is adjusted when it doesn't fit on the monitor.
+    ///
+    /// The default is to flip and then slide the popup on both axes.
+    #[inline]
+    pub fn with_constraint_adjustment(mut self, adjustment: PopupConstraintAdjustment) -> Self {
+        self.constraint_adjustment = adjustment;
+        self
+    }
+
+    /// Sets whether the popup grabs the input.
+    ///
+    /// A grabbing popup is dismissed when the user clicks outside of it, which suits menus and
+    /// dropdowns. Tooltips shouldn't grab the input.
+    ///
+    /// The default is `true`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** The grab is tied to the latest button press on the parent, so the popup
+    ///   should be created in response to it, otherwise the compositor dismisses it right away.
+    ///   The input isn't grabbed when no button was pressed on the parent.
+    /// - **X11:** The input isn't grabbed, popups are dismissed when their parent loses focus.
+    #[inline]
+    pub fn with_grab(mut self, grab: bool) -> Self {
+        self.grab = grab;
+        self
+    }
+
+    /// Compute the position and size of the popup, for backends placing popups themselves.
+    ///
+    /// The `parent_position` is the position of the surface of the parent, and `bounds` is the
+    /// area the popup should fit in, usually the monitor of the parent.
+    pub fn place(
+        &self,
+        parent_position: PhysicalPosition<i32>,
+        scale_factor: f64,
+        size: PhysicalSize<u32>,
+        bounds: Option<(PhysicalPosition<i32>, PhysicalSize<u32>)>,
+    ) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+        let anchor_position = self.anchor_position.to_physical::<i32>(scale_factor);
+        let anchor_size = self.anchor_size.to_physical::<u32>(scale_factor);
+        let offset = self.offset.to_physical::<i32>(scale_factor);
+        let adjustment = self.constraint_adjustment;
+        let (anchor_x, anchor_y) = self.anchor.sides();
+        let (gravity_x, gravity_y) = self.gravity.sides();
+
+        let x = PopupAxis {
+            anchor_start: parent_position.x + anchor_position.x,
+            anchor_len: anchor_size.width as i32,
+            anchor: anchor_x,
+            gravity: gravity_x,
+            offset: offset.x,
+        };
+        let y = PopupAxis {
+            anchor_start: parent_position.y + anchor_position.y,
+            anchor_len: anchor_size.height as i32,
+            anchor: anchor_y,
+            gravity: gravity_y,
+            offset: offset.y,
+        };
+
+        let Some((bounds_position, bounds_size)) = bounds else {
+            return (
+                PhysicalPosition::new(x.start(size.width as i32), y.start(size.height as i32)),
+                size,
+            );
+        };
+
+        let (x, width) = x.constrain(
+            size.width as i32,
+            (bounds_position.x, bounds_size.width as i32),
+            adjustment.contains(PopupConstraintAdjustment::FLIP_X),
+            adjustment.contains(PopupConstraintAdjustment::SLIDE_X),
+            adjustment.contains(PopupConstraintAdjustment::RESIZE_X),
+        );
+        let (y, height) = y.constrain(
+            size.height as i32,
+            (bounds_position.y, bounds_size.height as i32),
+            adjustment.contains(PopupConstraintAdjustment::FLIP_Y),
+            adjustment.contains(PopupConstraintAdjustment::SLIDE_Y),
+            adjustment.contains(PopupConstraintAdjustment::RESIZE_Y),
+        );
+
+        (PhysicalPosition::new(x, y), PhysicalSize::new(width as u32, height as u32))
+    }
+}
+
+/// The placement of a popup along one axis.
+struct PopupAxis {
+    anchor_start: i32,
+    anchor_len: i32,
+    /// The side of the anchor rectangle, `-1` for the start, `0` for the center and `1` for the
+    /// end.
+    anchor: i32,
+    /// The direction the popup extends to, with the same values as the `anchor`.
+    gravity: i32,
+    offset: i32,
+}
+
+impl PopupAxis {
+    /// The start of the popup of the given length.
+    fn start(&self, len: i32) -> i32 {
+        let anchor_point = self.anchor_start + self.anchor_len * (self.anchor + 1) / 2;
+        anchor_point - len * (1 - self.gravity) / 2 + self.offset
+    }
+
+    /// The start and the length of the popup, adjusted to fit in the bounds.
+    fn constrain(
+        self,
+        len: i32,
+        (bounds_start, bounds_len): (i32, i32),
+        flip: bool,
+        slide: bool,
+        resize: bool,
+    ) -> (i32, i32) {
+        let fits = |start: i32, len: i32| {
+            start >= bounds_start && start + len <= bounds_start + bounds_len
+        };
+
+        let mut start = self.start(len);
+        if fits(start, len) {
+            return (start, len);
+        }
+
+        if flip {
+            let flipped = PopupAxis {
+                anchor: -self.anchor,
+                gravity: -self.gravity,
+                offset: -self.offset,
+                ..self
+            };
+            let flipped_start = flipped.start(len);
+            if fits(flipped_start, len) {
+                return (flipped_start, len);
+            }
+        }
+
+        if slide {
+            // Prefer showing the start of the popup when it's larger than the bounds.
+            start = start.min(bounds_start + bounds_len - len).max(bounds_start);
+        }
+
+        let mut len = len;
+        if resize {
+            let end = (start + len).min(bounds_start + bounds_len);
+            start = start.max(bounds_start);
+            len = (end - start).max(1);
+        }
+
+        (start, len)
+    }
+}
+
+/// A point on a rectangle, or a direction from it, for use in [`PopupAttributes`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PopupAnchor {
+    #[default]
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    BottomLeft,
+    TopRight,
+    BottomRight,
+}
+
+impl PopupAnchor {
+    /// The horizontal and vertical sides, `-1` for the start, `0` for the center and `1` for the
+    /// end.
+    fn sides(self) -> (i32, i32) {
+        match self {
+            Self::Center => (0, 0),
+            Self::Top => (0, -1),
+            Self::Bottom => (0, 1),
+            Self::Left => (-1, 0),
+            Self::Right => (1, 0),
+            Self::TopLeft => (-1, -1),
+            Self::BottomLeft => (-1, 1),
+            Self::TopRight => (1, -1),
+            Self::BottomRight => (1, 1),
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// How a popup is adjusted when it doesn't fit on the monitor, for use in
+    /// [`PopupAttributes`].
+    ///
+    /// The adjustments are tried in order: flipping, sliding and then resizing. An empty set
+    /// leaves the popup where it is.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct PopupConstraintAdjustment: u32 {
+        /// Move the popup horizontally until it fits.
+        const SLIDE_X = 1 << 0;
+        /// Move the popup vertically until it fits.
+        const SLIDE_Y = 1 << 1;
+        /// Mirror the anchor and the gravity horizontally, when the popup fits then.
+        const FLIP_X = 1 << 2;
+        /// Mirror the anchor and the gravity vertically, when the popup fits then.
+        const FLIP_Y = 1 << 3;
+        /// Shrink the popup horizontally to the part that fits.
+        const RESIZE_X = 1 << 4;
+        /// Shrink the popup vertically to the part that fits.
+        const RESIZE_Y = 1 << 5;
+    }
+}
+
+impl Default for PopupConstraintAdjustment {
+    fn default() -> Self {
+        Self::FLIP_X | Self::FLIP_Y | Self::SLIDE_X | Self::SLIDE_Y
+    }
+}
+
 /// Wrapper for [`rwh_06::RawWindowHandle`] for [`WindowAttributes::parent_window`].
 ///
 /// # Safety
@@ -930,7 +1262,11 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     ///
     /// ## Platform-specific
     ///
-    /// - **Wayland / X11 / Orbital:** Not implemented.
+    /// - **X11:** The buttons are disabled with the Motif hints, which the window manager may
+    ///   ignore.
+    /// - **Wayland:** Only affects the client-side decorations drawn by winit, where the minimize
+    ///   and maximize buttons are hidden. The close button is always shown.
+    /// - **Orbital:** Not implemented.
     /// - **Web / iOS / Android:** Unsupported.
     fn set_enabled_buttons(&self, buttons: WindowButtons);
 
@@ -938,10 +1274,29 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     ///
     /// ## Platform-specific
     ///
-    /// - **Wayland / X11 / Orbital:** Not implemented. Always returns [`WindowButtons::all`].
+    /// - **Orbital:** Not implemented. Always returns [`WindowButtons::all`].
     /// - **Web / iOS / Android:** Unsupported. Always returns [`WindowButtons::all`].
     fn enabled_buttons(&self) -> WindowButtons;
 
+    /// Gets the window management actions the system supports for the window.
+    ///
+    /// This is useful when implementing custom decorations, to only show the controls that have
+    /// an effect. Changes are reported with [`WindowEvent::CapabilitiesChanged`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Advertised by the compositor with the `xdg_toplevel` wm_capabilities, all of
+    ///   them are assumed before version 5 of `xdg_wm_base`. Always empty for layer shell surfaces
+    ///   and popups.
+    /// - **X11:** Read from `_NET_WM_ALLOWED_ACTIONS`, all of them are assumed when the window
+    ///   manager doesn't set it. [`WindowCapabilities::WINDOW_MENU`] requires the window manager to
+    ///   support `_GTK_SHOW_WINDOW_MENU`.
+    /// - **macOS:** Doesn't report [`WindowCapabilities::WINDOW_MENU`].
+    /// - **Web / iOS / Android / Orbital:** Unsupported. Always returns an empty set.
+    ///
+    /// [`WindowEvent::CapabilitiesChanged`]: crate::event::WindowEvent::CapabilitiesChanged
+    fn window_capabilities(&self) -> WindowCapabilities;
+
     /// Minimize the window, or put it back from the minimized state.
     ///
     /// ## Platform-specific
@@ -1044,15 +1399,25 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     /// On Windows and X11, this is typically the small icon in the top-left
     /// corner of the titlebar.
     ///
+    /// Use an [`IconSet`] to provide the icon at several sizes, and to name it in the icon theme.
+    ///
     /// ## Platform-specific
     ///
-    /// - **iOS / Android / Web / Wayland / macOS / Orbital:** Unsupported.
+    /// - **iOS / Android / Web / macOS / Orbital:** Unsupported.
     ///
     /// - **Windows:** Sets `ICON_SMALL`. The base size for a window icon is 16x16, but it's
-    ///   recommended to account for screen scaling and pick a multiple of that, i.e. 32x32.
+    ///   recommended to account for screen scaling and pick a multiple of that, i.e. 32x32. The
+    ///   image of an [`IconSet`] that is the closest to the system icon size is used.
     ///
     /// - **X11:** Has no universal guidelines for icon sizes, so you're at the whims of the WM.
-    ///   That said, it's usually in the same ballpark as on Windows.
+    ///   That said, it's usually in the same ballpark as on Windows. All the sizes of an
+    ///   [`IconSet`] are set, and the WM picks the one it needs.
+    ///
+    /// - **Wayland:** Requires the `xdg_toplevel_icon_v1` protocol. The icon is also scaled to the
+    ///   sizes preferred by the compositor, and the themed name of an [`IconSet`] is used when the
+    ///   compositor finds it in the icon theme.
+    ///
+    /// [`IconSet`]: crate::icon::IconSet
     fn set_window_icon(&self, window_icon: Option<Icon>);
 
     /// Set the IME cursor editing area, where the `position` is the top left corner of that area
@@ -1127,6 +1492,35 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     /// - **iOS / Android / Web / Windows / X11 / macOS / Orbital:** Unsupported.
     fn set_ime_purpose(&self, purpose: ImePurpose);
 
+    /// Sets the IME hints for the window using [`ImeHint`].
+    ///
+    /// The hints refine the [`ImePurpose`], like disabling the auto-capitalization of a field
+    /// accepting names of files, or allowing multiple lines. There are no hints by default.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / X11 / macOS / Orbital:** Unsupported.
+    fn set_ime_hints(&self, hints: ImeHint);
+
+    /// Report the text around the cursor of the text input to the IME.
+    ///
+    /// The `cursor` and `anchor` are byte-wise indices into `text`, and delimit the selection;
+    /// they're equal without one. The text should be a paragraph or a few sentences around the
+    /// cursor, without the preedit. Input methods use it to predict and correct words, and may
+    /// request to delete some of it with [`Ime::DeleteSurrounding`].
+    ///
+    /// This should be called after every change of the text or the cursor while IME is allowed,
+    /// including after [`Ime::Enabled`], since the IME state is reset when enabling it.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Long texts are truncated around the cursor, the protocol allows 4000 bytes.
+    /// - **iOS / Android / Web / Windows / X11 / macOS / Orbital:** Unsupported.
+    ///
+    /// [`Ime::DeleteSurrounding`]: crate::event::Ime::DeleteSurrounding
+    /// [`Ime::Enabled`]: crate::event::Ime::Enabled
+    fn set_ime_surrounding_text(&self, text: &str, cursor: usize, anchor: usize);
+
     /// Brings the window to the front and sets input focus. Has no effect if the window is
     /// already in focus, minimized, or not visible.
     ///
@@ -1136,7 +1530,10 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     ///
     /// ## Platform-specific
     ///
-    /// - **iOS / Android / Wayland / Orbital:** Unsupported.
+    /// - **iOS / Android / Orbital:** Unsupported.
+    /// - **Wayland:** Requires the `xdg_activation_v1` protocol. The activation is requested with
+    ///   the latest input of the user, and the compositor may only mark the window as urgent
+    ///   instead of focusing it.
     fn focus_window(&self);
 
     /// Gets whether the window has keyboard focus.
@@ -1180,8 +1577,8 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     ///
     /// ## Platform-specific
     ///
-    /// - **iOS / Android / x11 / Orbital:** Unsupported.
-    /// - **Wayland:** Only returns theme overrides.
+    /// - **iOS / Android / Orbital:** Unsupported.
+    /// - **Wayland / X11:** Only returns theme overrides.
     fn theme(&self) -> Option<Theme>;
 
     /// Prevents the window contents from being captured by other apps.
@@ -1286,13 +1683,42 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     /// - **iOS / Android / Web:** Always returns an [`RequestError::NotSupported`].
     fn drag_resize_window(&self, direction: ResizeDirection) -> Result<(), RequestError>;
 
+    /// Starts dragging data out of the window with the left mouse button until it's released.
+    ///
+    /// There's no guarantee that this will work unless the left mouse button was pressed
+    /// immediately before this function is called.
+    ///
+    /// The drop target can choose one of the given `actions`, and request the `contents` in one
+    /// of their MIME types. The optional `icon`, which must be a [`CustomCursorSource::Image`], is
+    /// moved along with the pointer, its hotspot at the pointer position.
+    ///
+    /// The progress of the drag is reported with [`WindowEvent::DragSource`] and the returned
+    /// serial.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The icon replaces the cursor during the drag. The keyboard is grabbed too, and
+    ///   pressing Escape cancels the drag.
+    /// - **Wayland:** [`DragAction::Link`] is unsupported.
+    /// - **Windows / macOS / iOS / Android / Web / Orbital:** Unsupported.
+    ///
+    /// [`WindowEvent::DragSource`]: crate::event::WindowEvent::DragSource
+    fn start_drag(
+        &self,
+        contents: ClipboardContents,
+        actions: &[DragAction],
+        icon: Option<CustomCursorSource>,
+    ) -> Result<AsyncRequestSerial, RequestError>;
+
     /// Show [window menu] at a specified position in surface coordinates.
     ///
     /// This is the context menu that is normally shown when interacting with
     /// the title bar. This is useful when implementing custom decorations.
     ///
     /// ## Platform-specific
-    /// **Android / iOS / macOS / Orbital / Wayland / Web / X11:** Unsupported.
+    ///
+    /// - **X11:** Requires the window manager to support `_GTK_SHOW_WINDOW_MENU`.
+    /// - **Android / iOS / macOS / Orbital / Web:** Unsupported.
     ///
     /// [window menu]: https://en.wikipedia.org/wiki/Common_menus_in_Microsoft_Windows#System_menu
     fn show_window_menu(&self, position: Position);
@@ -1476,6 +1902,23 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    /// The window management actions the system supports for a window.
+    ///
+    /// See [`Window::window_capabilities`] for details.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct WindowCapabilities: u32 {
+        /// The window menu can be shown with [`Window::show_window_menu`].
+        const WINDOW_MENU = 1 << 0;
+        /// The window can be maximized.
+        const MAXIMIZE = 1 << 1;
+        /// The window can be made fullscreen.
+        const FULLSCREEN = 1 << 2;
+        /// The window can be minimized.
+        const MINIMIZE = 1 << 3;
+    }
+}
+
 /// A window level groups windows with respect to their z-position.
 ///
 /// The relative ordering between windows in different window levels is fixed.
@@ -1520,6 +1963,28 @@ pub enum ImePurpose {
     ///
     /// For example, that could alter OSK on Wayland to show extra buttons.
     Terminal,
+    /// The IME is used for alphabetic characters only.
+    Alpha,
+    /// The IME is used for digits only.
+    Digits,
+    /// The IME is used for a number, including the decimal separator and the sign.
+    Number,
+    /// The IME is used for a phone number.
+    Phone,
+    /// The IME is used for a URL.
+    Url,
+    /// The IME is used for an email address.
+    Email,
+    /// The IME is used for the name of a person.
+    Name,
+    /// The IME is used for a PIN, a numeric password.
+    Pin,
+    /// The IME is used for a date.
+    Date,
+    /// The IME is used for a time.
+    Time,
+    /// The IME is used for a date and a time.
+    DateTime,
 }
 
 impl Default for ImePurpose {
@@ -1528,6 +1993,40 @@ impl Default for ImePurpose {
     }
 }
 
+bitflags::bitflags! {
+    /// Hints on the behavior of the IME, for use in [`Window::set_ime_hints`].
+    ///
+    /// They complement the [`ImePurpose`], an empty set asks for no special behavior.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / X11 / macOS / Orbital:** Unsupported.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct ImeHint: u32 {
+        /// Suggest completions of the words.
+        const COMPLETION = 1 << 0;
+        /// Suggest corrections of the misspelled words.
+        const SPELLCHECK = 1 << 1;
+        /// Capitalize the first letter of the sentences.
+        const AUTO_CAPITALIZATION = 1 << 2;
+        /// Prefer lowercase letters.
+        const LOWERCASE = 1 << 3;
+        /// Prefer uppercase letters.
+        const UPPERCASE = 1 << 4;
+        /// Prefer capitalizing the first letter of the words.
+        const TITLECASE = 1 << 5;
+        /// The characters are hidden, like in a password.
+        const HIDDEN_TEXT = 1 << 6;
+        /// The text shouldn't be learned or remembered by the IME.
+        const SENSITIVE_DATA = 1 << 7;
+        /// Prefer latin characters, even with an IME for another script.
+        const LATIN = 1 << 8;
+        /// The text can span multiple lines.
+        const MULTILINE = 1 << 9;
+    }
+}
+
 /// An opaque token used to activate the [`Window`].
 ///
 /// [`Window`]: crate::window::Window
@@ -1561,3 +2060,81 @@ impl ActivationToken {
         &self.token
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize};
+
+    use super::{PopupAnchor, PopupAttributes, PopupConstraintAdjustment, WindowId};
+
+    const BOUNDS: (PhysicalPosition<i32>, PhysicalSize<u32>) =
+        (PhysicalPosition::new(0, 0), PhysicalSize::new(1000, 800));
+
+    fn popup() -> PopupAttributes {
+        PopupAttributes::new(
+            WindowId::from_raw(1),
+            PhysicalPosition::new(100, 50),
+            PhysicalSize::new(80, 20),
+        )
+    }
+
+    #[test]
+    fn popup_below_anchor() {
+        let (position, size) =
+            popup().place(PhysicalPosition::new(10, 10), 1.0, PhysicalSize::new(200, 300), None);
+        assert_eq!(position, PhysicalPosition::new(110, 80));
+        assert_eq!(size, PhysicalSize::new(200, 300));
+
+        let (position, _) = PopupAttributes::new(
+            WindowId::from_raw(1),
+            LogicalPosition::new(100, 50),
+            LogicalSize::new(80, 20),
+        )
+        .with_anchor(PopupAnchor::Center)
+        .with_gravity(PopupAnchor::Center)
+        .with_offset(LogicalPosition::new(5, -5))
+        .place(PhysicalPosition::new(0, 0), 2.0, PhysicalSize::new(20, 10), None);
+        assert_eq!(position, PhysicalPosition::new(280, 105));
+    }
+
+    #[test]
+    fn popup_flips_then_slides() {
+        // Doesn't fit below the anchor, but fits above it.
+        let (position, _) = popup().place(
+            PhysicalPosition::new(850, 600),
+            1.0,
+            PhysicalSize::new(200, 300),
+            Some(BOUNDS),
+        );
+        assert_eq!(position, PhysicalPosition::new(800, 350));
+
+        // Neither fits, so it stays below and slides up.
+        let (position, size) = popup().place(
+            PhysicalPosition::new(0, 400),
+            1.0,
+            PhysicalSize::new(200, 700),
+            Some(BOUNDS),
+        );
+        assert_eq!(position, PhysicalPosition::new(100, 100));
+        assert_eq!(size, PhysicalSize::new(200, 700));
+    }
+
+    #[test]
+    fn popup_resizes() {
+        let adjustment = PopupConstraintAdjustment::RESIZE_Y;
+        let (position, size) = popup().with_constraint_adjustment(adjustment).place(
+            PhysicalPosition::new(0, 600),
+            1.0,
+            PhysicalSize::new(200, 300),
+            Some(BOUNDS),
+        );
+        assert_eq!(position, PhysicalPosition::new(100, 670));
+        assert_eq!(size, PhysicalSize::new(200, 130));
+
+        let (position, size) = popup()
+            .with_constraint_adjustment(PopupConstraintAdjustment::empty())
+            .place(PhysicalPosition::new(0, 600), 1.0, PhysicalSize::new(200, 300), Some(BOUNDS));
+        assert_eq!(position, PhysicalPosition::new(100, 670));
+        assert_eq!(size, PhysicalSize::new(200, 300));
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use winit_core::clipboard::{
+    Clipboard as CoreClipboard, ClipboardContents, ClipboardData, ClipboardError, ClipboardEvent,
+    ClipboardKind,
+};
+use winit_core::error::RequestError;
+use winit_core::event_loop::AsyncRequestSerial;
+
+use crate::shared::{Action, Shared};
+
+/// An in-memory clipboard or primary selection, shared with [`HeadlessHandle`] which plays the
+/// other applications.
+///
+/// [`HeadlessHandle`]: crate::HeadlessHandle
+#[derive(Debug)]
+pub struct Clipboard {
+    pub(crate) shared: Arc<Shared>,
+    pub(crate) kind: ClipboardKind,
+}
+
+impl CoreClipboard for Clipboard {
+    fn write(&self, contents: ClipboardContents) -> Result<(), RequestError> {
+        self.shared.update(|state| state.set_clipboard(self.kind, Some(contents)));
+        Ok(())
+    }
+
+    fn read(&self, mime_type: &str) -> Result<AsyncRequestSerial, RequestError> {
+        let serial = AsyncRequestSerial::get();
+        self.shared.update(|state| {
+            let data = state.clipboard(self.kind).and_then(|contents| contents.get(mime_type));
+            let result = data
+                .map(|data| ClipboardData { mime_type: mime_type.to_owned(), data: data.to_vec() })
+                .ok_or(ClipboardError::Unavailable);
+            state.events.push_back(Action::Clipboard(ClipboardEvent::Read { serial, result }));
+        });
+        Ok(serial)
+    }
+
+    fn request_mime_types(&self) -> Result<AsyncRequestSerial, RequestError> {
+        let serial = AsyncRequestSerial::get();
+        self.shared.update(|state| {
+            let mime_types = state
+                .clipboard(self.kind)
+                .into_iter()
+                .flat_map(|contents| contents.mime_types().map(str::to_owned))
+                .collect();
+            state
+                .events
+                .push_back(Action::Clipboard(ClipboardEvent::MimeTypes { serial, mime_types }));
+        });
+        Ok(serial)
+    }
+
+    fn clear(&self) -> Result<(), RequestError> {
+        self.shared.update(|state| {
+            if state.clipboard(self.kind).is_some() {
+                state.set_clipboard(self.kind, None);
+            }
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,971 @@
+use std::cell::Cell;
+#[cfg(unix)]
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{fmt, mem};
+
+use dpi::PhysicalSize;
+use winit_core::application::ApplicationHandler;
+use winit_core::clipboard::{
+    Clipboard as CoreClipboard, ClipboardData, ClipboardError, ClipboardKind,
+};
+use winit_core::cursor::{
+    CustomCursor as CoreCustomCursor, CustomCursorProvider, CustomCursorSource,
+};
+use winit_core::error::{EventLoopError, RequestError};
+use winit_core::event::{
+    DeviceEvent, DeviceId, DeviceInfo, DragAction, StartCause, SurfaceSizeWriter, WindowEvent,
+};
+use winit_core::event_loop::pump_events::PumpStatus;
+use winit_core::event_loop::{
+    ActiveEventLoop as RootActiveEventLoop, AsyncRequestSerial, ControlFlow, DeviceEvents,
+    EventLoopProxy as CoreEventLoopProxy, EventLoopProxyProvider, InputPreferences,
+    OwnedDisplayHandle as CoreOwnedDisplayHandle,
+};
+use winit_core::monitor::MonitorHandle as CoreMonitorHandle;
+use winit_core::window::{Theme, Window as CoreWindow, WindowId};
+
+use crate::clipboard::Clipboard;
+use crate::handle::HeadlessHandle;
+use crate::shared::{Action, Shared};
+use crate::window::Window;
+
+#[derive(Debug)]
+pub struct EventLoop {
+    loop_running: bool,
+    window_target: ActiveEventLoop,
+}
+
+impl EventLoop {
+    pub fn new(_: &PlatformSpecificEventLoopAttributes) -> Result<Self, EventLoopError> {
+        let shared = Arc::new(Shared::new().map_err(|error| os_error!(error))?);
+
+        Ok(Self {
+            loop_running: false,
+            window_target: ActiveEventLoop {
+                control_flow: Cell::new(ControlFlow::default()),
+                exit: Cell::new(None),
+                device_events: Cell::new(DeviceEvents::default()),
+                event_loop_proxy: Arc::new(EventLoopProxy { shared: shared.clone() }),
+                clipboard: Clipboard { shared: shared.clone(), kind: ClipboardKind::Clipboard },
+                primary_selection: Clipboard {
+                    shared: shared.clone(),
+                    kind: ClipboardKind::Primary,
+                },
+                shared,
+            },
+        })
+    }
+
+    /// Create a handle to script the windowing system this event loop talks to.
+    pub fn handle(&self) -> HeadlessHandle {
+        self.window_target.handle()
+    }
+
+    pub fn window_target(&self) -> &dyn RootActiveEventLoop {
+        &self.window_target
+    }
+
+    pub fn run_app<A: ApplicationHandler>(mut self, app: A) -> Result<(), EventLoopError> {
+        self.run_app_on_demand(app)
+    }
+
+    pub fn run_app_on_demand<A: ApplicationHandler>(
+        &mut self,
+        mut app: A,
+    ) -> Result<(), EventLoopError> {
+        self.window_target.clear_exit();
+        loop {
+            match self.pump_app_events(None, &mut app) {
+                PumpStatus::Exit(0) => break Ok(()),
+                PumpStatus::Exit(code) => break Err(EventLoopError::ExitFailure(code)),
+                PumpStatus::Continue => continue,
+            }
+        }
+    }
+
+    pub fn pump_app_events<A: ApplicationHandler>(
+        &mut self,
+        timeout: Option<Duration>,
+        mut app: A,
+    ) -> PumpStatus {
+        if !self.loop_running {
+            self.loop_running = true;
+
+            // Run the initial loop iteration.
+            self.single_iteration(&mut app, StartCause::Init);
+        }
+
+        // Consider the possibility that the `StartCause::Init` iteration could
+        // request to Exit.
+        if !self.window_target.exiting() {
+            self.poll_events_with_timeout(timeout, &mut app);
+        }
+        if let Some(code) = self.window_target.exit_code() {
+            self.loop_running = false;
+
+            PumpStatus::Exit(code)
+        } else {
+            PumpStatus::Continue
+        }
+    }
+
+    fn has_pending(&self) -> bool {
+        self.window_target.shared.lock().has_pending()
+    }
+
+    fn poll_events_with_timeout<A: ApplicationHandler>(
+        &mut self,
+        mut timeout: Option<Duration>,
+        app: &mut A,
+    ) {
+        let start = Instant::now();
+
+        timeout = if self.has_pending() {
+            // If we already have work to do then we don't want to block on the next poll.
+            Some(Duration::ZERO)
+        } else {
+            let control_flow_timeout = match self.window_target.control_flow() {
+                ControlFlow::Wait => None,
+                ControlFlow::Poll => Some(Duration::ZERO),
+                ControlFlow::WaitUntil(wait_deadline) => {
+                    Some(wait_deadline.saturating_duration_since(start))
+                },
+            };
+
+            min_timeout(control_flow_timeout, timeout)
+        };
+
+        self.window_target.shared.wait(timeout);
+
+        // NB: `StartCause::Init` is handled as a special case and doesn't need
+        // to be considered here.
+        let cause = match self.window_target.control_flow() {
+            ControlFlow::Poll => StartCause::Poll,
+            ControlFlow::Wait => StartCause::WaitCancelled { start, requested_resume: None },
+            ControlFlow::WaitUntil(deadline) => {
+                if Instant::now() < deadline {
+                    StartCause::WaitCancelled { start, requested_resume: Some(deadline) }
+                } else {
+                    StartCause::ResumeTimeReached { start, requested_resume: deadline }
+                }
+            },
+        };
+
+        // Don't run an iteration when the wait timed out with nothing to dispatch, unless the
+        // control flow asked for one.
+        if !self.has_pending()
+            && !matches!(&cause, StartCause::ResumeTimeReached { .. } | StartCause::Poll)
+        {
+            return;
+        }
+
+        self.single_iteration(app, cause);
+    }
+
+    fn single_iteration<A: ApplicationHandler>(&mut self, app: &mut A, cause: StartCause) {
+        let target = &self.window_target;
+
+        app.new_events(target, cause);
+
+        // NB: For consistency all platforms must call `can_create_surfaces` even though headless
+        // windows don't have a surface lifecycle.
+        if cause == StartCause::Init {
+            app.can_create_surfaces(target);
+        }
+
+        // Process all pending events, including the ones queued while handling them.
+        loop {
+            let Some(action) = target.shared.lock().events.pop_front() else { break };
+            target.process_action(action, app);
+        }
+
+        // Notify the application about the dropped windows.
+        loop {
+            let Some(window_id) = target.shared.lock().destroys.pop_front() else { break };
+            app.window_event(target, window_id, WindowEvent::Destroyed);
+        }
+
+        if mem::take(&mut target.shared.lock().proxy_wake_up) {
+            app.proxy_wake_up(target);
+        }
+
+        // Empty the redraw requests.
+        let redraws = {
+            let mut state = target.shared.lock();
+            let mut redraws: Vec<WindowId> = Vec::new();
+            for window_id in mem::take(&mut state.redraws) {
+                if state.windows.contains_key(&window_id) && !redraws.contains(&window_id) {
+                    redraws.push(window_id);
+                }
+            }
+            redraws
+        };
+        for window_id in redraws {
+            app.window_event(target, window_id, WindowEvent::RedrawRequested);
+        }
+
+        // This is always the last event we dispatch before waiting again.
+        app.about_to_wait(target);
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for EventLoop {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.window_target.shared.waker_fd().as_fd()
+    }
+}
+
+#[cfg(unix)]
+impl AsRawFd for EventLoop {
+    fn as_raw_fd(&self) -> RawFd {
+        self.window_target.shared.waker_fd().as_raw_fd()
+    }
+}
+
+pub struct EventLoopProxy {
+    shared: Arc<Shared>,
+}
+
+impl fmt::Debug for EventLoopProxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventLoopProxy").finish_non_exhaustive()
+    }
+}
+
+impl EventLoopProxyProvider for EventLoopProxy {
+    fn wake_up(&self) {
+        self.shared.update(|state| state.proxy_wake_up = true);
+    }
+}
+
+pub struct ActiveEventLoop {
+    control_flow: Cell<ControlFlow>,
+    exit: Cell<Option<i32>>,
+    device_events: Cell<DeviceEvents>,
+    pub(crate) shared: Arc<Shared>,
+    event_loop_proxy: Arc<EventLoopProxy>,
+    clipboard: Clipboard,
+    primary_selection: Clipboard,
+}
+
+impl fmt::Debug for ActiveEventLoop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ActiveEventLoop")
+            .field("control_flow", &self.control_flow)
+            .field("exit", &self.exit)
+            .field("device_events", &self.device_events)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ActiveEventLoop {
+    /// Create a handle to script the windowing system this event loop talks to.
+    pub fn handle(&self) -> HeadlessHandle {
+        HeadlessHandle { shared: self.shared.clone() }
+    }
+
+    fn clear_exit(&self) {
+        self.exit.set(None);
+    }
+
+    fn set_exit_code(&self, code: i32) {
+        self.exit.set(Some(code));
+    }
+
+    fn exit_code(&self) -> Option<i32> {
+        self.exit.get()
+    }
+
+    fn process_action<A: ApplicationHandler>(&self, action: Action, app: &mut A) {
+        match action {
+            Action::Window(window_id, event) => {
+                {
+                    let mut state = self.shared.lock();
+                    let state = &mut *state;
+                    let Some(window) = state.windows.get_mut(&window_id) else { return };
+                    match event {
+                        WindowEvent::SurfaceResized(size) if window.surface_size != size => {
+                            window.surface_size = size;
+                            state.redraws.push_back(window_id);
+                        },
+                        WindowEvent::Moved(position) => window.outer_position = position,
+                        WindowEvent::Focused(focused) => window.has_focus = focused,
+                        WindowEvent::Occluded(occluded) => window.occluded = occluded,
+                        WindowEvent::PointerMoved { position, .. } => {
+                            window.cursor_position = Some(position)
+                        },
+                        WindowEvent::PointerLeft { .. } => window.cursor_position = None,
+                        _ => (),
+                    }
+                }
+
+                app.window_event(self, window_id, event);
+            },
+            Action::Device(device_id, event) => self.process_device_event(device_id, event, app),
+            Action::ScaleFactorChanged(window_id, scale_factor) => {
+                let old_size = {
+                    let mut state = self.shared.lock();
+                    let Some(window) = state.windows.get_mut(&window_id) else { return };
+                    let old_size = window.surface_size;
+                    let old_scale_factor = mem::replace(&mut window.scale_factor, scale_factor);
+                    if old_scale_factor == scale_factor {
+                        return;
+                    }
+                    old_size.to_logical::<f64>(old_scale_factor).to_physical::<u32>(scale_factor)
+                };
+
+                let new_surface_size = Arc::new(Mutex::new(old_size));
+                let event = WindowEvent::ScaleFactorChanged {
+                    scale_factor,
+                    surface_size_writer: SurfaceSizeWriter::new(Arc::downgrade(&new_surface_size)),
+                };
+                app.window_event(self, window_id, event);
+
+                let new_size: PhysicalSize<u32> = *new_surface_size.lock().unwrap();
+                let resized = {
+                    let mut state = self.shared.lock();
+                    let state = &mut *state;
+                    let Some(window) = state.windows.get_mut(&window_id) else { return };
+                    let resized = window.surface_size != new_size;
+                    if resized {
+                        window.surface_size = new_size;
+                        state.redraws.push_back(window_id);
+                    }
+                    resized
+                };
+
+                if resized {
+                    app.window_event(self, window_id, WindowEvent::SurfaceResized(new_size));
+                }
+            },
+            Action::Clipboard(event) => app.clipboard_event(self, event),
+            Action::Monitor(event) => app.monitor_event(self, event),
+            Action::InputPreferencesChanged => app.input_preferences_changed(self),
+            Action::DragEnded => {
+                let mut state = self.shared.lock();
+                if state.drag.as_ref().is_some_and(|drag| drag.ended) {
+                    state.drag = None;
+                }
+            },
+        }
+    }
+
+    fn process_device_event<A: ApplicationHandler>(
+        &self,
+        device_id: Option<DeviceId>,
+        event: DeviceEvent,
+        app: &mut A,
+    ) {
+        let deliver = match self.device_events.get() {
+            // Devices being plugged in or out are always reported.
+            _ if matches!(event, DeviceEvent::Added(_) | DeviceEvent::Removed) => true,
+            DeviceEvents::Always => true,
+            DeviceEvents::WhenFocused => {
+                self.shared.lock().windows.values().any(|window| window.has_focus)
+            },
+            DeviceEvents::Never => false,
+        };
+
+        if deliver {
+            app.device_event(self, device_id, event);
+        }
+    }
+}
+
+impl RootActiveEventLoop for ActiveEventLoop {
+    fn create_proxy(&self) -> CoreEventLoopProxy {
+        CoreEventLoopProxy::new(self.event_loop_proxy.clone())
+    }
+
+    fn create_window(
+        &self,
+        window_attributes: winit_core::window::WindowAttributes,
+    ) -> Result<Box<dyn CoreWindow>, RequestError> {
+        Ok(Box::new(Window::new(self, window_attributes)?))
+    }
+
+    fn create_custom_cursor(
+        &self,
+        source: CustomCursorSource,
+    ) -> Result<CoreCustomCursor, RequestError> {
+        Ok(CoreCustomCursor(Arc::new(CustomCursor { source })))
+    }
+
+    fn available_monitors(&self) -> Box<dyn Iterator<Item = CoreMonitorHandle>> {
+        let state = self.shared.lock();
+        let monitors: Vec<_> =
+            state.monitors.iter().map(|monitor| CoreMonitorHandle(monitor.clone())).collect();
+        Box::new(monitors.into_iter())
+    }
+
+    fn primary_monitor(&self) -> Option<CoreMonitorHandle> {
+        self.shared.lock().primary_monitor().map(|monitor| CoreMonitorHandle(monitor.clone()))
+    }
+
+    fn listen_device_events(&self, allowed: DeviceEvents) {
+        self.device_events.set(allowed);
+    }
+
+    fn available_devices(&self) -> Box<dyn Iterator<Item = (DeviceId, DeviceInfo)>> {
+        Box::new(self.shared.lock().devices.clone().into_iter())
+    }
+
+    fn system_theme(&self) -> Option<Theme> {
+        self.shared.lock().system_theme
+    }
+
+    fn input_preferences(&self) -> InputPreferences {
+        self.shared.lock().input_preferences
+    }
+
+    fn clipboard(&self) -> Option<&dyn CoreClipboard> {
+        Some(&self.clipboard)
+    }
+
+    fn primary_selection(&self) -> Option<&dyn CoreClipboard> {
+        Some(&self.primary_selection)
+    }
+
+    fn set_drag_action(&self, action: Option<DragAction>) -> Result<(), RequestError> {
+        let mut state = self.shared.lock();
+        let drag = state.drag.as_mut().filter(|drag| !drag.ended).ok_or(RequestError::Ignored)?;
+        drag.action = action;
+        Ok(())
+    }
+
+    fn request_drag_data(&self, mime_type: &str) -> Result<AsyncRequestSerial, RequestError> {
+        let serial = AsyncRequestSerial::get();
+        self.shared.update(|state| {
+            let drag = state.drag.as_ref().ok_or(RequestError::Ignored)?;
+            let result = drag
+                .contents
+                .get(mime_type)
+                .map(|data| ClipboardData { mime_type: mime_type.to_owned(), data: data.to_vec() })
+                .ok_or(ClipboardError::Unavailable);
+            let event = WindowEvent::DragData { serial, result };
+            state.events.push_back(Action::Window(drag.window_id, event));
+            Ok(serial)
+        })
+    }
+
+    fn set_control_flow(&self, control_flow: ControlFlow) {
+        self.control_flow.set(control_flow)
+    }
+
+    fn control_flow(&self) -> ControlFlow {
+        self.control_flow.get()
+    }
+
+    fn exit(&self) {
+        self.set_exit_code(0);
+    }
+
+    fn exiting(&self) -> bool {
+        self.exit.get().is_some()
+    }
+
+    fn owned_display_handle(&self) -> CoreOwnedDisplayHandle {
+        CoreOwnedDisplayHandle::new(Arc::new(OwnedDisplayHandle))
+    }
+
+    fn rwh_06_handle(&self) -> &dyn rwh_06::HasDisplayHandle {
+        self
+    }
+}
+
+impl rwh_06::HasDisplayHandle for ActiveEventLoop {
+    fn display_handle(&self) -> Result<rwh_06::DisplayHandle<'_>, rwh_06::HandleError> {
+        Err(rwh_06::HandleError::NotSupported)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct OwnedDisplayHandle;
+
+impl rwh_06::HasDisplayHandle for OwnedDisplayHandle {
+    fn display_handle(&self) -> Result<rwh_06::DisplayHandle<'_>, rwh_06::HandleError> {
+        Err(rwh_06::HandleError::NotSupported)
+    }
+}
+
+/// A custom cursor which only remembers what it was created from.
+#[derive(Debug)]
+pub struct CustomCursor {
+    pub source: CustomCursorSource,
+}
+
+impl CustomCursorProvider for CustomCursor {
+    fn is_animated(&self) -> bool {
+        matches!(self.source, CustomCursorSource::Animation(_))
+    }
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PlatformSpecificEventLoopAttributes {}
+
+/// Returns the minimum `Option<Duration>`, taking into account that `None`
+/// equates to an infinite timeout, not a zero timeout (so can't just use
+/// `Option::min`)
+fn min_timeout(a: Option<Duration>, b: Option<Duration>) -> Option<Duration> {
+    a.map_or(b, |a_timeout| b.map_or(Some(a_timeout), |b_timeout| Some(a_timeout.min(b_timeout))))
+}
+
+#[cfg(test)]
+mod tests {
+    use dpi::{LogicalSize, PhysicalPosition};
+    use winit_core::clipboard::{
+        ClipboardContents, ClipboardData, ClipboardError, ClipboardEvent, ClipboardKind,
+        TEXT_MIME_TYPE,
+    };
+    use winit_core::event::{DeviceEvent, DeviceKind, DragSourceEvent, Ime};
+    use winit_core::monitor::{MonitorEvent, VideoMode};
+    use winit_core::window::{ImeHint, ImePurpose, PopupAttributes, WindowAttributes};
+
+    use super::*;
+    use crate::MonitorAttributes;
+
+    #[derive(Default)]
+    struct App {
+        attributes: Option<WindowAttributes>,
+        windows: Vec<Box<dyn CoreWindow>>,
+        window_events: Vec<(WindowId, WindowEvent)>,
+        device_events: Vec<DeviceEvent>,
+        clipboard_events: Vec<ClipboardEvent>,
+        monitor_events: Vec<MonitorEvent>,
+        input_preferences: Vec<InputPreferences>,
+        wake_ups: usize,
+    }
+
+    impl ApplicationHandler for App {
+        fn can_create_surfaces(&mut self, event_loop: &dyn RootActiveEventLoop) {
+            let attributes = self.attributes.take().unwrap_or_default();
+            self.windows.push(event_loop.create_window(attributes).unwrap());
+        }
+
+        fn proxy_wake_up(&mut self, _: &dyn RootActiveEventLoop) {
+            self.wake_ups += 1;
+        }
+
+        fn window_event(
+            &mut self,
+            _: &dyn RootActiveEventLoop,
+            window_id: WindowId,
+            event: WindowEvent,
+        ) {
+            if let WindowEvent::ScaleFactorChanged { mut surface_size_writer, .. } = event.clone() {
+                surface_size_writer.request_surface_size(PhysicalSize::new(100, 100)).unwrap();
+            }
+            self.window_events.push((window_id, event));
+        }
+
+        fn device_event(
+            &mut self,
+            _: &dyn RootActiveEventLoop,
+            _: Option<DeviceId>,
+            event: DeviceEvent,
+        ) {
+            self.device_events.push(event);
+        }
+
+        fn clipboard_event(&mut self, _: &dyn RootActiveEventLoop, event: ClipboardEvent) {
+            self.clipboard_events.push(event);
+        }
+
+        fn monitor_event(&mut self, _: &dyn RootActiveEventLoop, event: MonitorEvent) {
+            self.monitor_events.push(event);
+        }
+
+        fn input_preferences_changed(&mut self, event_loop: &dyn RootActiveEventLoop) {
+            self.input_preferences.push(event_loop.input_preferences());
+        }
+    }
+
+    fn pump(event_loop: &mut EventLoop, app: &mut App) -> Vec<(WindowId, WindowEvent)> {
+        assert_eq!(
+            event_loop.pump_app_events(Some(Duration::ZERO), &mut *app),
+            PumpStatus::Continue
+        );
+        mem::take(&mut app.window_events)
+    }
+
+    fn event_loop() -> EventLoop {
+        EventLoop::new(&PlatformSpecificEventLoopAttributes::default()).unwrap()
+    }
+
+    #[test]
+    fn window_state_follows_requests_and_events() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let mut app = App {
+            attributes: Some(
+                WindowAttributes::default()
+                    .with_title("Headless")
+                    .with_surface_size(LogicalSize::new(200, 100)),
+            ),
+            ..Default::default()
+        };
+
+        let events = pump(&mut event_loop, &mut app);
+        let window_id = app.windows[0].id();
+        assert_eq!(events, [(window_id, WindowEvent::RedrawRequested)]);
+
+        let state = handle.window_state(window_id).unwrap();
+        assert_eq!(state.title, "Headless");
+        assert_eq!(state.surface_size, PhysicalSize::new(200, 100));
+
+        app.windows[0].set_ime_allowed(true);
+        handle.send_window_event(window_id, WindowEvent::SurfaceResized(PhysicalSize::new(30, 40)));
+        let events = pump(&mut event_loop, &mut app);
+        assert_eq!(events, [
+            (window_id, WindowEvent::Ime(Ime::Enabled)),
+            (window_id, WindowEvent::SurfaceResized(PhysicalSize::new(30, 40))),
+            (window_id, WindowEvent::RedrawRequested),
+        ]);
+        assert_eq!(app.windows[0].surface_size(), PhysicalSize::new(30, 40));
+        assert!(handle.window_state(window_id).unwrap().ime_allowed);
+
+        app.windows[0].set_ime_surrounding_text("hello", 5, 0);
+        let surrounding_text = handle.window_state(window_id).unwrap().ime_surrounding_text;
+        assert_eq!(surrounding_text, Some(("hello".into(), 5, 0)));
+
+        app.windows[0].set_ime_purpose(ImePurpose::Email);
+        app.windows[0].set_ime_hints(ImeHint::COMPLETION | ImeHint::LOWERCASE);
+        let state = handle.window_state(window_id).unwrap();
+        assert_eq!(state.ime_purpose, ImePurpose::Email);
+        assert_eq!(state.ime_hints, ImeHint::COMPLETION | ImeHint::LOWERCASE);
+
+        app.windows.clear();
+        let events = pump(&mut event_loop, &mut app);
+        assert_eq!(events, [(window_id, WindowEvent::Destroyed)]);
+        assert!(handle.windows().is_empty());
+    }
+
+    #[test]
+    fn scale_factor_change() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let mut app = App::default();
+        pump(&mut event_loop, &mut app);
+        let window_id = app.windows[0].id();
+
+        handle.change_scale_factor(window_id, 2.0);
+        let events = pump(&mut event_loop, &mut app);
+        assert!(matches!(
+            events[0],
+            (_, WindowEvent::ScaleFactorChanged { scale_factor, .. }) if scale_factor == 2.0
+        ));
+        assert_eq!(events[1..], [
+            (window_id, WindowEvent::SurfaceResized(PhysicalSize::new(100, 100))),
+            (window_id, WindowEvent::RedrawRequested),
+        ]);
+        assert_eq!(app.windows[0].scale_factor(), 2.0);
+    }
+
+    #[test]
+    fn monitors() {
+        let event_loop = event_loop();
+        let handle = event_loop.handle();
+        let first = handle.add_monitor(MonitorAttributes::default().with_name("first"));
+        let second = handle.add_monitor(
+            MonitorAttributes::default()
+                .with_position(PhysicalPosition::new(1920, 0))
+                .with_scale_factor(1.5),
+        );
+
+        let target = event_loop.window_target();
+        assert_eq!(target.available_monitors().count(), 2);
+        assert_eq!(target.primary_monitor(), Some(first.clone()));
+        assert_eq!(first.name().as_deref(), Some("first"));
+
+        handle.set_primary_monitor(Some(&second));
+        assert_eq!(target.primary_monitor().unwrap().scale_factor(), 1.5);
+
+        handle.remove_monitor(&second);
+        assert_eq!(target.primary_monitor(), None);
+        assert_eq!(target.available_monitors().collect::<Vec<_>>(), [first]);
+    }
+
+    #[test]
+    fn monitor_events() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let mut app = App::default();
+        pump(&mut event_loop, &mut app);
+
+        let monitor = handle.add_monitor(MonitorAttributes::default());
+        let updated =
+            handle.update_monitor(&monitor, MonitorAttributes::default().with_scale_factor(2.0));
+        handle.remove_monitor(&monitor);
+        pump(&mut event_loop, &mut app);
+
+        let updated = updated.unwrap();
+        assert_eq!(updated, monitor);
+        assert_eq!(updated.scale_factor(), 2.0);
+        assert_eq!(app.monitor_events, [
+            MonitorEvent::Added(monitor.clone()),
+            MonitorEvent::Changed(updated),
+            MonitorEvent::Removed(monitor.clone()),
+        ]);
+        assert_eq!(event_loop.window_target().available_monitors().count(), 0);
+        assert!(handle.update_monitor(&monitor, MonitorAttributes::default()).is_none());
+    }
+
+    #[test]
+    fn input_preferences_changed() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let mut app = App::default();
+        pump(&mut event_loop, &mut app);
+        assert_eq!(event_loop.window_target().input_preferences(), InputPreferences::default());
+
+        let mut preferences = InputPreferences::default();
+        preferences.double_click_interval = Some(Duration::from_millis(400));
+        preferences.drag_threshold = Some(8);
+        handle.set_input_preferences(preferences);
+        // Setting the same preferences again isn't reported.
+        handle.set_input_preferences(preferences);
+        pump(&mut event_loop, &mut app);
+
+        assert_eq!(app.input_preferences, [preferences]);
+        assert_eq!(event_loop.window_target().input_preferences(), preferences);
+    }
+
+    #[test]
+    fn device_events_follow_focus() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let mut app = App::default();
+        pump(&mut event_loop, &mut app);
+
+        let event = DeviceEvent::PointerMotion { delta: (1.0, 1.0) };
+        handle.send_device_event(None, event.clone());
+        pump(&mut event_loop, &mut app);
+        assert!(app.device_events.is_empty());
+
+        app.windows[0].focus_window();
+        handle.send_device_event(None, event.clone());
+        pump(&mut event_loop, &mut app);
+        assert_eq!(app.device_events, [event]);
+    }
+
+    #[test]
+    fn device_hotplug() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let mut app = App::default();
+        pump(&mut event_loop, &mut app);
+
+        let info = DeviceInfo {
+            name: "Tablet".into(),
+            kind: DeviceKind::Tablet,
+            vendor_id: Some(0x056a),
+            product_id: Some(0x0357),
+        };
+        let device_id = handle.add_device(info.clone());
+        let devices: Vec<_> = event_loop.window_target().available_devices().collect();
+        assert_eq!(devices, [(device_id, info.clone())]);
+
+        handle.remove_device(device_id);
+        pump(&mut event_loop, &mut app);
+        // Delivered even though no window has focus.
+        assert_eq!(app.device_events, [DeviceEvent::Added(info), DeviceEvent::Removed]);
+        assert_eq!(event_loop.window_target().available_devices().count(), 0);
+    }
+
+    #[test]
+    fn proxy_wakes_up_event_loop() {
+        let mut event_loop = event_loop();
+        let mut app = App::default();
+        pump(&mut event_loop, &mut app);
+
+        let proxy = event_loop.window_target().create_proxy();
+        std::thread::spawn(move || proxy.wake_up());
+        event_loop.window_target().set_control_flow(ControlFlow::Wait);
+        while app.wake_ups == 0 {
+            event_loop.pump_app_events(None, &mut app);
+        }
+        assert_eq!(app.wake_ups, 1);
+    }
+
+    #[test]
+    fn clipboard_round_trip() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let mut app = App::default();
+        pump(&mut event_loop, &mut app);
+
+        let clipboard = event_loop.window_target().clipboard().unwrap();
+        clipboard.write_text("copied").unwrap();
+        assert_eq!(handle.clipboard(), Some(ClipboardContents::new().with_text("copied")));
+
+        handle.set_clipboard(Some(ClipboardContents::new().with_data("image/png", [1, 2, 3])));
+        let clipboard = event_loop.window_target().clipboard().unwrap();
+        let types = clipboard.request_mime_types().unwrap();
+        let text = clipboard.read_text().unwrap();
+        let png = clipboard.read("image/png").unwrap();
+        pump(&mut event_loop, &mut app);
+        assert_eq!(app.clipboard_events, [
+            ClipboardEvent::Changed { kind: ClipboardKind::Clipboard },
+            ClipboardEvent::Changed { kind: ClipboardKind::Clipboard },
+            ClipboardEvent::MimeTypes { serial: types, mime_types: vec!["image/png".into()] },
+            ClipboardEvent::Read { serial: text, result: Err(ClipboardError::Unavailable) },
+            ClipboardEvent::Read {
+                serial: png,
+                result: Ok(ClipboardData { mime_type: "image/png".into(), data: vec![1, 2, 3] }),
+            },
+        ]);
+    }
+
+    #[test]
+    fn primary_selection_is_separate() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let mut app = App::default();
+        pump(&mut event_loop, &mut app);
+
+        handle.set_primary_selection(Some(ClipboardContents::new().with_text("selected")));
+        let primary = event_loop.window_target().primary_selection().unwrap();
+        let text = primary.read_text().unwrap();
+        pump(&mut event_loop, &mut app);
+        assert_eq!(handle.clipboard(), None);
+        assert_eq!(app.clipboard_events, [
+            ClipboardEvent::Changed { kind: ClipboardKind::Primary },
+            ClipboardEvent::Read {
+                serial: text,
+                result: Ok(ClipboardData {
+                    mime_type: TEXT_MIME_TYPE.into(),
+                    data: b"selected".into()
+                }),
+            },
+        ]);
+
+        app.clipboard_events.clear();
+        event_loop.window_target().primary_selection().unwrap().clear().unwrap();
+        pump(&mut event_loop, &mut app);
+        assert_eq!(handle.primary_selection(), None);
+        assert_eq!(app.clipboard_events, [ClipboardEvent::Changed {
+            kind: ClipboardKind::Primary
+        }]);
+    }
+
+    #[test]
+    fn drag_and_drop() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let mut app = App::default();
+        pump(&mut event_loop, &mut app);
+        let window_id = app.windows[0].id();
+        let position = PhysicalPosition::new(1.0, 2.0);
+
+        let contents = ClipboardContents::new().with_text("dragged");
+        handle.drag_enter(window_id, Vec::new(), contents, position);
+        let events = pump(&mut event_loop, &mut app);
+        assert_eq!(events, [(window_id, WindowEvent::DragEntered {
+            paths: Vec::new(),
+            mime_types: vec![TEXT_MIME_TYPE.into()],
+            position,
+        })]);
+        assert_eq!(handle.drag_action(), None);
+
+        let target = event_loop.window_target();
+        target.set_drag_action(Some(DragAction::Move)).unwrap();
+        assert_eq!(handle.drag_action(), Some(DragAction::Move));
+        assert_eq!(handle.drag_drop(), Some(DragAction::Move));
+        let text = target.request_drag_data(TEXT_MIME_TYPE).unwrap();
+        let events = pump(&mut event_loop, &mut app);
+        assert_eq!(events, [
+            (window_id, WindowEvent::DragDropped { paths: Vec::new(), position }),
+            (window_id, WindowEvent::DragData {
+                serial: text,
+                result: Ok(ClipboardData {
+                    mime_type: TEXT_MIME_TYPE.into(),
+                    data: b"dragged".to_vec(),
+                }),
+            }),
+        ]);
+
+        let target = event_loop.window_target();
+        assert!(matches!(target.request_drag_data(TEXT_MIME_TYPE), Err(RequestError::Ignored)));
+        assert!(matches!(target.set_drag_action(None), Err(RequestError::Ignored)));
+    }
+
+    #[test]
+    fn outgoing_drag() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let mut app = App::default();
+        pump(&mut event_loop, &mut app);
+        let window_id = app.windows[0].id();
+
+        let contents = ClipboardContents::new().with_text("dragged");
+        let actions = [DragAction::Copy, DragAction::Move];
+        let serial = app.windows[0].start_drag(contents.clone(), &actions, None).unwrap();
+        let drag = handle.outgoing_drag().unwrap();
+        assert_eq!(
+            (drag.window_id, drag.contents, drag.actions),
+            (window_id, contents, actions.into())
+        );
+
+        handle.accept_outgoing_drag(Some(DragAction::Link));
+        handle.accept_outgoing_drag(Some(DragAction::Move));
+        handle.drop_outgoing_drag();
+        let events = pump(&mut event_loop, &mut app);
+        let source_event = |event| (window_id, WindowEvent::DragSource { serial, event });
+        assert_eq!(events, [
+            source_event(DragSourceEvent::Accepted(false)),
+            source_event(DragSourceEvent::Action(None)),
+            source_event(DragSourceEvent::Accepted(true)),
+            source_event(DragSourceEvent::Action(Some(DragAction::Move))),
+            source_event(DragSourceEvent::Finished(DragAction::Move)),
+        ]);
+        assert_eq!(handle.outgoing_drag(), None);
+    }
+
+    #[test]
+    fn popup_placement() {
+        let mut event_loop = event_loop();
+        let handle = event_loop.handle();
+        let size = PhysicalSize::new(1000, 800);
+        handle.add_monitor(
+            MonitorAttributes::default().with_video_modes(vec![VideoMode::new(size, None, None)]),
+        );
+        let mut app = App {
+            attributes: Some(
+                WindowAttributes::default().with_position(PhysicalPosition::new(700, 500)),
+            ),
+            ..Default::default()
+        };
+        pump(&mut event_loop, &mut app);
+        let parent_id = app.windows[0].id();
+
+        // There's no room below the anchor, so the popup flips above it.
+        let popup = PopupAttributes::new(
+            parent_id,
+            PhysicalPosition::new(100, 50),
+            PhysicalSize::new(80, 20),
+        );
+        let attributes = WindowAttributes::default()
+            .with_surface_size(PhysicalSize::new(200, 300))
+            .with_popup(popup);
+        let popup = event_loop.window_target().create_window(attributes).unwrap();
+        let state = handle.window_state(popup.id()).unwrap();
+        assert_eq!(state.popup_parent, Some(parent_id));
+        assert_eq!(state.outer_position, PhysicalPosition::new(800, 250));
+        assert_eq!(state.surface_size, PhysicalSize::new(200, 300));
+
+        handle.send_window_event(popup.id(), WindowEvent::PopupDismissed);
+        let events = pump(&mut event_loop, &mut app);
+        assert_eq!(events[0], (popup.id(), WindowEvent::PopupDismissed));
+
+        let orphan = PopupAttributes::new(
+            WindowId::from_raw(0),
+            PhysicalPosition::new(0, 0),
+            PhysicalSize::new(1, 1),
+        );
+        let attributes = WindowAttributes::default().with_popup(orphan);
+        assert!(event_loop.window_target().create_window(attributes).is_err());
+    }
+}
@@ -0,0 +1,373 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use dpi::PhysicalPosition;
+use winit_core::clipboard::{ClipboardContents, ClipboardKind};
+use winit_core::cursor::CustomCursorSource;
+use winit_core::event::{
+    DeviceEvent, DeviceId, DeviceInfo, DragAction, DragSourceEvent, WindowEvent,
+};
+use winit_core::event_loop::{AsyncRequestSerial, InputPreferences};
+use winit_core::monitor::{
+    MonitorEvent, MonitorHandle as CoreMonitorHandle, MonitorHandleProvider,
+};
+use winit_core::window::{Theme, WindowId};
+
+use crate::monitor::{MonitorAttributes, MonitorHandle};
+use crate::shared::{Action, Drag, Shared};
+use crate::window::WindowState;
+
+static DEVICE_ID: AtomicI64 = AtomicI64::new(1);
+
+/// An event to deliver through [`HeadlessHandle::send_events`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptedEvent {
+    Window { window_id: WindowId, event: WindowEvent },
+    Device { device_id: Option<DeviceId>, event: DeviceEvent },
+}
+
+/// Data dragged out of a window with [`Window::start_drag`], see
+/// [`HeadlessHandle::outgoing_drag`].
+///
+/// [`Window::start_drag`]: winit_core::window::Window::start_drag
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct OutgoingDrag {
+    pub window_id: WindowId,
+    pub contents: ClipboardContents,
+    pub actions: Vec<DragAction>,
+    pub icon: Option<CustomCursorSource>,
+    /// The action chosen with [`HeadlessHandle::accept_outgoing_drag`].
+    pub action: Option<DragAction>,
+    pub(crate) serial: AsyncRequestSerial,
+}
+
+/// Remote control of a headless [`EventLoop`], playing the part of the windowing system.
+///
+/// The handle can be cloned and sent to other threads. Events sent through it are queued and
+/// delivered to the application in order on the next iteration of the event loop, waking it up
+/// when needed.
+///
+/// [`EventLoop`]: crate::EventLoop
+#[derive(Clone)]
+pub struct HeadlessHandle {
+    pub(crate) shared: Arc<Shared>,
+}
+
+impl fmt::Debug for HeadlessHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeadlessHandle").finish_non_exhaustive()
+    }
+}
+
+impl HeadlessHandle {
+    /// Deliver a [`WindowEvent`] to the window.
+    ///
+    /// Events affecting the window state, like [`WindowEvent::SurfaceResized`],
+    /// [`WindowEvent::Moved`] or [`WindowEvent::Focused`], are reflected in the
+    /// [`WindowState`] before they reach the application. Events for windows that were dropped
+    /// in the meantime are discarded.
+    ///
+    /// To change the scale factor, use [`HeadlessHandle::change_scale_factor`] instead.
+    pub fn send_window_event(&self, window_id: WindowId, event: WindowEvent) {
+        self.shared.update(|state| state.events.push_back(Action::Window(window_id, event)));
+    }
+
+    /// Deliver a [`DeviceEvent`], subject to [`ActiveEventLoop::listen_device_events`].
+    ///
+    /// [`ActiveEventLoop::listen_device_events`]: winit_core::event_loop::ActiveEventLoop::listen_device_events
+    pub fn send_device_event(&self, device_id: Option<DeviceId>, event: DeviceEvent) {
+        self.shared.update(|state| state.events.push_back(Action::Device(device_id, event)));
+    }
+
+    /// Deliver a sequence of events in one go, so they are all seen in the same iteration.
+    pub fn send_events(&self, events: impl IntoIterator<Item = ScriptedEvent>) {
+        self.shared.update(|state| {
+            state.events.extend(events.into_iter().map(|event| match event {
+                ScriptedEvent::Window { window_id, event } => Action::Window(window_id, event),
+                ScriptedEvent::Device { device_id, event } => Action::Device(device_id, event),
+            }))
+        });
+    }
+
+    /// Change the scale factor of the window.
+    ///
+    /// This delivers [`WindowEvent::ScaleFactorChanged`] suggesting a surface size that keeps the
+    /// logical size, followed by [`WindowEvent::SurfaceResized`] when the surface size changes.
+    pub fn change_scale_factor(&self, window_id: WindowId, scale_factor: f64) {
+        self.shared.update(|state| {
+            state.events.push_back(Action::ScaleFactorChanged(window_id, scale_factor))
+        });
+    }
+
+    /// Plug in a new monitor.
+    ///
+    /// The first monitor that is added becomes the primary one. The application receives
+    /// [`MonitorEvent::Added`].
+    pub fn add_monitor(&self, attributes: MonitorAttributes) -> CoreMonitorHandle {
+        let monitor = Arc::new(MonitorHandle::new(attributes));
+        self.shared.update(|state| {
+            if state.monitors.is_empty() {
+                state.primary_monitor = Some(monitor.id());
+            }
+            state.monitors.push(monitor.clone());
+            let event = MonitorEvent::Added(CoreMonitorHandle(monitor.clone()));
+            state.events.push_back(Action::Monitor(event));
+        });
+        CoreMonitorHandle(monitor)
+    }
+
+    /// Change the attributes of the monitor, like its video mode or scale factor.
+    ///
+    /// The application receives [`MonitorEvent::Changed`] with the returned monitor.
+    pub fn update_monitor(
+        &self,
+        monitor: &CoreMonitorHandle,
+        attributes: MonitorAttributes,
+    ) -> Option<CoreMonitorHandle> {
+        let id = monitor.id();
+        self.shared.update(|state| {
+            let monitor = state.monitors.iter_mut().find(|monitor| monitor.id() == id)?;
+            *monitor = Arc::new(monitor.with_attributes(attributes));
+            let monitor = CoreMonitorHandle(monitor.clone());
+            state.events.push_back(Action::Monitor(MonitorEvent::Changed(monitor.clone())));
+            Some(monitor)
+        })
+    }
+
+    /// Unplug the monitor.
+    ///
+    /// The application receives [`MonitorEvent::Removed`].
+    pub fn remove_monitor(&self, monitor: &CoreMonitorHandle) {
+        let id = monitor.id();
+        self.shared.update(|state| {
+            let Some(pos) = state.monitors.iter().position(|monitor| monitor.id() == id) else {
+                return;
+            };
+            let monitor = CoreMonitorHandle(state.monitors.remove(pos));
+            if state.primary_monitor == Some(id) {
+                state.primary_monitor = None;
+            }
+            state.events.push_back(Action::Monitor(MonitorEvent::Removed(monitor)));
+        });
+    }
+
+    /// Plug in a new input device.
+    ///
+    /// The application receives [`DeviceEvent::Added`] with the returned ID.
+    pub fn add_device(&self, info: DeviceInfo) -> DeviceId {
+        let device_id = DeviceId::from_raw(DEVICE_ID.fetch_add(1, Ordering::Relaxed));
+        self.shared.update(|state| {
+            state.devices.push((device_id, info.clone()));
+            state.events.push_back(Action::Device(Some(device_id), DeviceEvent::Added(info)));
+        });
+        device_id
+    }
+
+    /// Unplug the input device.
+    ///
+    /// The application receives [`DeviceEvent::Removed`].
+    pub fn remove_device(&self, device_id: DeviceId) {
+        self.shared.update(|state| {
+            let Some(pos) = state.devices.iter().position(|&(id, _)| id == device_id) else {
+                return;
+            };
+            state.devices.remove(pos);
+            state.events.push_back(Action::Device(Some(device_id), DeviceEvent::Removed));
+        });
+    }
+
+    /// Set the monitor reported by [`ActiveEventLoop::primary_monitor`].
+    ///
+    /// [`ActiveEventLoop::primary_monitor`]: winit_core::event_loop::ActiveEventLoop::primary_monitor
+    pub fn set_primary_monitor(&self, monitor: Option<&CoreMonitorHandle>) {
+        let id = monitor.map(|monitor| monitor.id());
+        self.shared.update(|state| state.primary_monitor = id);
+    }
+
+    /// Change the system theme.
+    ///
+    /// Windows which don't override the theme receive [`WindowEvent::ThemeChanged`].
+    pub fn set_system_theme(&self, theme: Option<Theme>) {
+        self.shared.update(|state| {
+            if state.system_theme == theme {
+                return;
+            }
+
+            state.system_theme = theme;
+            let Some(theme) = theme else { return };
+            let mut window_ids: Vec<_> = state
+                .windows
+                .iter()
+                .filter(|(_, window)| window.theme.is_none())
+                .map(|(&window_id, _)| window_id)
+                .collect();
+            window_ids.sort();
+            for window_id in window_ids {
+                state.events.push_back(Action::Window(window_id, WindowEvent::ThemeChanged(theme)));
+            }
+        });
+    }
+
+    /// Change the input preferences of the user.
+    ///
+    /// The application is notified with [`ApplicationHandler::input_preferences_changed`].
+    ///
+    /// [`ApplicationHandler::input_preferences_changed`]: winit_core::application::ApplicationHandler::input_preferences_changed
+    pub fn set_input_preferences(&self, preferences: InputPreferences) {
+        self.shared.update(|state| {
+            if state.input_preferences != preferences {
+                state.input_preferences = preferences;
+                state.events.push_back(Action::InputPreferencesChanged);
+            }
+        });
+    }
+
+    /// Put data on the clipboard, as if another application took its ownership.
+    ///
+    /// `None` empties the clipboard.
+    pub fn set_clipboard(&self, contents: Option<ClipboardContents>) {
+        self.shared.update(|state| state.set_clipboard(ClipboardKind::Clipboard, contents));
+    }
+
+    /// The current contents of the clipboard.
+    pub fn clipboard(&self) -> Option<ClipboardContents> {
+        self.shared.lock().clipboard.clone()
+    }
+
+    /// Put data on the primary selection, as if text was selected in another application.
+    ///
+    /// `None` empties the primary selection.
+    pub fn set_primary_selection(&self, contents: Option<ClipboardContents>) {
+        self.shared.update(|state| state.set_clipboard(ClipboardKind::Primary, contents));
+    }
+
+    /// The current contents of the primary selection.
+    pub fn primary_selection(&self) -> Option<ClipboardContents> {
+        self.shared.lock().primary_selection.clone()
+    }
+
+    /// Start dragging data over the window, as if another application did.
+    ///
+    /// This delivers [`WindowEvent::DragEntered`] with the `paths` and the MIME types of the
+    /// `contents`, which the application can request. Drags with paths are accepted with
+    /// [`DragAction::Copy`] until the application decides otherwise. A previous drag is left.
+    pub fn drag_enter(
+        &self,
+        window_id: WindowId,
+        paths: Vec<PathBuf>,
+        contents: ClipboardContents,
+        position: PhysicalPosition<f64>,
+    ) {
+        self.drag_leave();
+        self.shared.update(|state| {
+            let mime_types = contents.mime_types().map(str::to_owned).collect();
+            let event = WindowEvent::DragEntered { paths: paths.clone(), mime_types, position };
+            state.events.push_back(Action::Window(window_id, event));
+            let action = (!paths.is_empty()).then_some(DragAction::Copy);
+            state.drag = Some(Drag { window_id, paths, contents, position, action, ended: false });
+        });
+    }
+
+    /// Move the dragged data over the window, delivering [`WindowEvent::DragMoved`].
+    pub fn drag_move(&self, position: PhysicalPosition<f64>) {
+        self.shared.update(|state| {
+            let Some(drag) = state.drag.as_mut().filter(|drag| !drag.ended) else { return };
+            drag.position = position;
+            state
+                .events
+                .push_back(Action::Window(drag.window_id, WindowEvent::DragMoved { position }));
+        });
+    }
+
+    /// Drop the dragged data on the window, delivering [`WindowEvent::DragDropped`].
+    ///
+    /// Returns the action the application chose, or `None` if it rejected the drag, in which
+    /// case [`WindowEvent::DragLeft`] is delivered instead.
+    pub fn drag_drop(&self) -> Option<DragAction> {
+        self.shared.update(|state| {
+            let drag = state.drag.as_mut().filter(|drag| !drag.ended)?;
+            drag.ended = true;
+            let event = match drag.action {
+                Some(_) => {
+                    WindowEvent::DragDropped { paths: drag.paths.clone(), position: drag.position }
+                },
+                None => WindowEvent::DragLeft { position: Some(drag.position) },
+            };
+            state.events.push_back(Action::Window(drag.window_id, event));
+            state.events.push_back(Action::DragEnded);
+            drag.action
+        })
+    }
+
+    /// Cancel the drag, delivering [`WindowEvent::DragLeft`].
+    pub fn drag_leave(&self) {
+        self.shared.update(|state| {
+            let Some(drag) = state.drag.as_mut().filter(|drag| !drag.ended) else { return };
+            drag.ended = true;
+            let event = WindowEvent::DragLeft { position: Some(drag.position) };
+            state.events.push_back(Action::Window(drag.window_id, event));
+            state.events.push_back(Action::DragEnded);
+        });
+    }
+
+    /// The action the application currently chose for the drag, `None` if it's rejected.
+    pub fn drag_action(&self) -> Option<DragAction> {
+        self.shared.lock().drag.as_ref().and_then(|drag| drag.action)
+    }
+
+    /// The data currently dragged out of one of the windows.
+    pub fn outgoing_drag(&self) -> Option<OutgoingDrag> {
+        self.shared.lock().outgoing_drag.clone()
+    }
+
+    /// Accept the data dragged out of the window with the given action, or reject it, as the
+    /// target under the pointer would.
+    ///
+    /// Actions the drag doesn't allow are rejected.
+    pub fn accept_outgoing_drag(&self, action: Option<DragAction>) {
+        self.shared.update(|state| {
+            let Some(drag) = state.outgoing_drag.as_mut() else { return };
+            drag.action = action.filter(|action| drag.actions.contains(action));
+            for event in [
+                DragSourceEvent::Accepted(drag.action.is_some()),
+                DragSourceEvent::Action(drag.action),
+            ] {
+                let event = WindowEvent::DragSource { serial: drag.serial, event };
+                state.events.push_back(Action::Window(drag.window_id, event));
+            }
+        });
+    }
+
+    /// Drop the data dragged out of the window, finishing the drag when it was accepted and
+    /// cancelling it otherwise.
+    pub fn drop_outgoing_drag(&self) {
+        self.shared.update(|state| {
+            let Some(drag) = state.outgoing_drag.as_ref() else { return };
+            let event = match drag.action {
+                Some(action) => DragSourceEvent::Finished(action),
+                None => DragSourceEvent::Cancelled,
+            };
+            state.end_outgoing_drag(event);
+        });
+    }
+
+    /// Cancel the drag out of the window.
+    pub fn cancel_outgoing_drag(&self) {
+        self.shared.update(|state| state.end_outgoing_drag(DragSourceEvent::Cancelled));
+    }
+
+    /// The windows that are currently alive, in creation order.
+    pub fn windows(&self) -> Vec<WindowId> {
+        let mut window_ids: Vec<_> = self.shared.lock().windows.keys().copied().collect();
+        window_ids.sort();
+        window_ids
+    }
+
+    /// The state of the window, or `None` if the window has been dropped.
+    pub fn window_state(&self, window_id: WindowId) -> Option<WindowState> {
+        self.shared.lock().windows.get(&window_id).cloned()
+    }
+}
@@ -0,0 +1,80 @@
+//! # Headless
+//!
+//! An in-memory backend implementing the `winit-core` traits without connecting to any display
+//! server. It's meant for running integration tests of [`ApplicationHandler`] implementations in
+//! environments where neither X11 nor Wayland is available, like CI.
+//!
+//! Nothing is ever presented on screen. Instead, the [`HeadlessHandle`] is used to script the
+//! events the "windowing system" delivers, to set up fake monitors, and to inspect the state the
+//! application requested on its windows.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use winit_core::application::ApplicationHandler;
+//! use winit_core::event::WindowEvent;
+//! use winit_core::event_loop::pump_events::PumpStatus;
+//! use winit_core::event_loop::ActiveEventLoop;
+//! use winit_core::window::{Window, WindowAttributes, WindowId};
+//! use winit_headless::{EventLoop, PlatformSpecificEventLoopAttributes};
+//!
+//! #[derive(Default)]
+//! struct App {
+//!     window: Option<Box<dyn Window>>,
+//! }
+//!
+//! impl ApplicationHandler for App {
+//!     fn can_create_surfaces(&mut self, event_loop: &dyn ActiveEventLoop) {
+//!         let attributes = WindowAttributes::default().with_title("Test");
+//!         self.window = Some(event_loop.create_window(attributes).unwrap());
+//!     }
+//!
+//!     fn window_event(
+//!         &mut self,
+//!         event_loop: &dyn ActiveEventLoop,
+//!         _: WindowId,
+//!         event: WindowEvent,
+//!     ) {
+//!         if event == WindowEvent::CloseRequested {
+//!             event_loop.exit();
+//!         }
+//!     }
+//! }
+//!
+//! let mut event_loop = EventLoop::new(&PlatformSpecificEventLoopAttributes::default()).unwrap();
+//! let handle = event_loop.handle();
+//! let mut app = App::default();
+//!
+//! // Run the first iteration, which creates the window.
+//! event_loop.pump_app_events(Some(Duration::ZERO), &mut app);
+//! let window_id = handle.windows()[0];
+//! assert_eq!(handle.window_state(window_id).unwrap().title, "Test");
+//!
+//! // Pretend that the user clicked the close button.
+//! handle.send_window_event(window_id, WindowEvent::CloseRequested);
+//! let status = event_loop.pump_app_events(Some(Duration::ZERO), &mut app);
+//! assert_eq!(status, PumpStatus::Exit(0));
+//! ```
+//!
+//! [`ApplicationHandler`]: winit_core::application::ApplicationHandler
+
+pub use self::clipboard::Clipboard;
+pub use self::event_loop::{
+    ActiveEventLoop, EventLoop, EventLoopProxy, PlatformSpecificEventLoopAttributes,
+};
+pub use self::handle::{HeadlessHandle, OutgoingDrag, ScriptedEvent};
+pub use self::monitor::{MonitorAttributes, MonitorHandle};
+pub use self::window::{Window, WindowState};
+
+macro_rules! os_error {
+    ($error:expr) => {{
+        winit_core::error::OsError::new(line!(), file!(), $error)
+    }};
+}
+
+mod clipboard;
+pub mod event_loop;
+mod handle;
+pub mod monitor;
+mod shared;
+pub mod window;
@@ -0,0 +1,132 @@
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dpi::PhysicalPosition;
+use winit_core::monitor::{MonitorHandleProvider, VideoMode};
+
+static MONITOR_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Description of a fake monitor, see [`HeadlessHandle::add_monitor`].
+///
+/// [`HeadlessHandle::add_monitor`]: crate::HeadlessHandle::add_monitor
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorAttributes {
+    pub(crate) name: Option<String>,
+    pub(crate) position: PhysicalPosition<i32>,
+    pub(crate) scale_factor: f64,
+    pub(crate) video_modes: Vec<VideoMode>,
+    pub(crate) current_video_mode: Option<VideoMode>,
+}
+
+impl Default for MonitorAttributes {
+    fn default() -> Self {
+        Self {
+            name: None,
+            position: PhysicalPosition::new(0, 0),
+            scale_factor: 1.0,
+            video_modes: Vec::new(),
+            current_video_mode: None,
+        }
+    }
+}
+
+impl MonitorAttributes {
+    /// Sets the human-readable name of the monitor.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the position of the top-left corner of the monitor in the desktop coordinates.
+    ///
+    /// The default is `(0, 0)`.
+    pub fn with_position(mut self, position: PhysicalPosition<i32>) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Sets the scale factor reported for the monitor and the windows on it.
+    ///
+    /// The default is `1.0`.
+    pub fn with_scale_factor(mut self, scale_factor: f64) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// Sets the video modes the monitor supports.
+    pub fn with_video_modes(mut self, video_modes: Vec<VideoMode>) -> Self {
+        self.video_modes = video_modes;
+        self
+    }
+
+    /// Sets the video mode the monitor is currently in.
+    ///
+    /// When unset, the first of the [video modes] is used.
+    ///
+    /// [video modes]: Self::with_video_modes
+    pub fn with_current_video_mode(mut self, video_mode: VideoMode) -> Self {
+        self.current_video_mode = Some(video_mode);
+        self
+    }
+}
+
+/// A fake monitor registered with [`HeadlessHandle::add_monitor`].
+///
+/// [`HeadlessHandle::add_monitor`]: crate::HeadlessHandle::add_monitor
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorHandle {
+    id: u64,
+    attributes: MonitorAttributes,
+}
+
+impl MonitorHandle {
+    pub(crate) fn new(attributes: MonitorAttributes) -> Self {
+        Self { id: MONITOR_ID.fetch_add(1, Ordering::Relaxed), attributes }
+    }
+
+    /// The same monitor with other attributes.
+    pub(crate) fn with_attributes(&self, attributes: MonitorAttributes) -> Self {
+        Self { id: self.id, attributes }
+    }
+
+    /// Whether the point lies within the current video mode of the monitor.
+    pub(crate) fn contains(&self, point: PhysicalPosition<i32>) -> bool {
+        let Some(size) = self.current_video_mode().map(|mode| mode.size()) else {
+            return false;
+        };
+        let origin = self.attributes.position;
+        let x = i64::from(point.x) - i64::from(origin.x);
+        let y = i64::from(point.y) - i64::from(origin.y);
+        (0..i64::from(size.width)).contains(&x) && (0..i64::from(size.height)).contains(&y)
+    }
+}
+
+impl MonitorHandleProvider for MonitorHandle {
+    fn id(&self) -> u128 {
+        self.id as u128
+    }
+
+    fn native_id(&self) -> u64 {
+        self.id
+    }
+
+    fn name(&self) -> Option<Cow<'_, str>> {
+        self.attributes.name.as_deref().map(Cow::Borrowed)
+    }
+
+    fn position(&self) -> Option<PhysicalPosition<i32>> {
+        Some(self.attributes.position)
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.attributes.scale_factor
+    }
+
+    fn current_video_mode(&self) -> Option<VideoMode> {
+        self.attributes.current_video_mode.or_else(|| self.attributes.video_modes.first().copied())
+    }
+
+    fn video_modes(&self) -> Box<dyn Iterator<Item = VideoMode>> {
+        Box::new(self.attributes.video_modes.clone().into_iter())
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::{HashMap, VecDeque};
+use std::io;
+#[cfg(unix)]
+use std::io::{Read, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use dpi::PhysicalPosition;
+use winit_core::clipboard::{ClipboardContents, ClipboardEvent, ClipboardKind};
+use winit_core::event::{
+    DeviceEvent, DeviceId, DeviceInfo, DragAction, DragSourceEvent, WindowEvent,
+};
+use winit_core::event_loop::InputPreferences;
+use winit_core::monitor::{MonitorEvent, MonitorHandleProvider};
+use winit_core::window::{Theme, WindowId};
+
+use crate::handle::OutgoingDrag;
+use crate::monitor::MonitorHandle;
+use crate::window::WindowState;
+
+/// Something the "windowing system" wants to tell the application.
+#[derive(Debug)]
+pub(crate) enum Action {
+    Window(WindowId, WindowEvent),
+    Device(Option<DeviceId>, DeviceEvent),
+    /// Needs the event loop to construct the `SurfaceSizeWriter` and to follow up with a resize.
+    ScaleFactorChanged(WindowId, f64),
+    Clipboard(ClipboardEvent),
+    Monitor(MonitorEvent),
+    InputPreferencesChanged,
+    /// The drag was dropped or left, its data stays available until then.
+    DragEnded,
+}
+
+/// Data dragged over one of the windows by [`HeadlessHandle`].
+///
+/// [`HeadlessHandle`]: crate::HeadlessHandle
+#[derive(Debug)]
+pub(crate) struct Drag {
+    pub window_id: WindowId,
+    pub paths: Vec<PathBuf>,
+    pub contents: ClipboardContents,
+    pub position: PhysicalPosition<f64>,
+    pub action: Option<DragAction>,
+    /// Whether the drag was dropped or left, and only waits for [`Action::DragEnded`].
+    pub ended: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct State {
+    pub events: VecDeque<Action>,
+    pub redraws: VecDeque<WindowId>,
+    pub destroys: VecDeque<WindowId>,
+    pub proxy_wake_up: bool,
+    pub windows: HashMap<WindowId, WindowState>,
+    pub monitors: Vec<Arc<MonitorHandle>>,
+    pub primary_monitor: Option<u128>,
+    pub devices: Vec<(DeviceId, DeviceInfo)>,
+    pub system_theme: Option<Theme>,
+    pub input_preferences: InputPreferences,
+    pub clipboard: Option<ClipboardContents>,
+    pub primary_selection: Option<ClipboardContents>,
+    pub drag: Option<Drag>,
+    pub outgoing_drag: Option<OutgoingDrag>,
+}
+
+impl State {
+    pub fn has_pending(&self) -> bool {
+        !self.events.is_empty()
+            || !self.redraws.is_empty()
+            || !self.destroys.is_empty()
+            || self.proxy_wake_up
+    }
+
+    pub fn clipboard(&self, kind: ClipboardKind) -> Option<&ClipboardContents> {
+        match kind {
+            ClipboardKind::Clipboard => self.clipboard.as_ref(),
+            ClipboardKind::Primary => self.primary_selection.as_ref(),
+        }
+    }
+
+    /// Replace the contents of the clipboard and notify the application.
+    pub fn set_clipboard(&mut self, kind: ClipboardKind, contents: Option<ClipboardContents>) {
+        match kind {
+            ClipboardKind::Clipboard => self.clipboard = contents,
+            ClipboardKind::Primary => self.primary_selection = contents,
+        }
+        self.events.push_back(Action::Clipboard(ClipboardEvent::Changed { kind }));
+    }
+
+    /// End the drag started by one of the windows, reporting how it ended.
+    pub fn end_outgoing_drag(&mut self, event: DragSourceEvent) {
+        let Some(drag) = self.outgoing_drag.take() else { return };
+        let event = WindowEvent::DragSource { serial: drag.serial, event };
+        self.events.push_back(Action::Window(drag.window_id, event));
+    }
+
+    pub fn primary_monitor(&self) -> Option<&Arc<MonitorHandle>> {
+        let id = self.primary_monitor?;
+        self.monitors.iter().find(|monitor| monitor.id() == id)
+    }
+}
+
+/// State shared between the event loop, its windows, proxies and [`HeadlessHandle`]s.
+///
+/// [`HeadlessHandle`]: crate::HeadlessHandle
+#[derive(Debug)]
+pub(crate) struct Shared {
+    state: Mutex<State>,
+    condvar: Condvar,
+    /// Socket pair mirroring the wake ups, so the event loop can be integrated into other
+    /// event loops through its file descriptor.
+    #[cfg(unix)]
+    waker: (UnixStream, UnixStream),
+}
+
+impl Shared {
+    pub fn new() -> io::Result<Self> {
+        #[cfg(unix)]
+        let waker = {
+            let (reader, writer) = UnixStream::pair()?;
+            reader.set_nonblocking(true)?;
+            writer.set_nonblocking(true)?;
+            (reader, writer)
+        };
+
+        Ok(Self {
+            state: Mutex::new(State::default()),
+            condvar: Condvar::new(),
+            #[cfg(unix)]
+            waker,
+        })
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poison| poison.into_inner())
+    }
+
+    /// Modify the state and wake up the event loop.
+    pub fn update<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+        let result = f(&mut self.lock());
+        self.condvar.notify_all();
+        #[cfg(unix)]
+        {
+            // When the buffer is full the event loop is already due to wake up.
+            let _ = (&self.waker.1).write(&[0]);
+        }
+        result
+    }
+
+    /// Block until there's something to dispatch or the timeout has elapsed.
+    pub fn wait(&self, timeout: Option<Duration>) {
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let mut state = self.lock();
+        while !state.has_pending() {
+            state = match deadline {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    if timeout.is_zero() {
+                        break;
+                    }
+                    self.condvar
+                        .wait_timeout(state, timeout)
+                        .unwrap_or_else(|poison| poison.into_inner())
+                        .0
+                },
+                // `Instant` overflow is as good as waiting forever.
+                None => self.condvar.wait(state).unwrap_or_else(|poison| poison.into_inner()),
+            };
+        }
+        drop(state);
+
+        #[cfg(unix)]
+        {
+            let mut buf = [0u8; 64];
+            while matches!((&self.waker.0).read(&mut buf), Ok(n) if n > 0) {}
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn waker_fd(&self) -> &UnixStream {
+        &self.waker.0
+    }
+}
@@ -0,0 +1,550 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use dpi::{PhysicalInsets, PhysicalPosition, PhysicalSize, Position, Size};
+use winit_core::clipboard::ClipboardContents;
+use winit_core::cursor::{Cursor, CustomCursorSource};
+use winit_core::error::{NotSupportedError, RequestError};
+use winit_core::event::{DragAction, DragSourceEvent, Ime, WindowEvent};
+use winit_core::event_loop::AsyncRequestSerial;
+use winit_core::icon::Icon;
+use winit_core::monitor::{Fullscreen, MonitorHandle as CoreMonitorHandle, MonitorHandleProvider};
+use winit_core::window::{
+    self, CursorGrabMode, ImeHint, ImePurpose, ResizeDirection, Theme, UserAttentionType,
+    Window as CoreWindow, WindowButtons, WindowCapabilities, WindowId, WindowLevel,
+};
+
+use crate::event_loop::ActiveEventLoop;
+use crate::handle::OutgoingDrag;
+use crate::monitor::MonitorHandle;
+use crate::shared::{Action, Shared, State};
+
+static WINDOW_ID: AtomicUsize = AtomicUsize::new(1);
+
+const DEFAULT_SURFACE_SIZE: PhysicalSize<u32> = PhysicalSize::new(800, 600);
+
+/// Snapshot of everything the application requested on a headless window.
+///
+/// Obtained with [`HeadlessHandle::window_state`].
+///
+/// [`HeadlessHandle::window_state`]: crate::HeadlessHandle::window_state
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WindowState {
+    pub title: String,
+    pub surface_size: PhysicalSize<u32>,
+    pub min_surface_size: Option<PhysicalSize<u32>>,
+    pub max_surface_size: Option<PhysicalSize<u32>>,
+    pub surface_resize_increments: Option<PhysicalSize<u32>>,
+    pub outer_position: PhysicalPosition<i32>,
+    pub scale_factor: f64,
+    pub visible: bool,
+    pub resizable: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub fullscreen: Option<Fullscreen>,
+    pub decorations: bool,
+    pub window_level: WindowLevel,
+    pub cursor: Cursor,
+    pub cursor_visible: bool,
+    pub cursor_grab: CursorGrabMode,
+    pub cursor_position: Option<PhysicalPosition<f64>>,
+    pub cursor_hittest: bool,
+    pub ime_allowed: bool,
+    pub ime_cursor_area: Option<(Position, Size)>,
+    pub ime_purpose: ImePurpose,
+    pub ime_hints: ImeHint,
+    /// The surrounding text, with the cursor and the anchor.
+    pub ime_surrounding_text: Option<(String, usize, usize)>,
+    pub theme: Option<Theme>,
+    pub blur: bool,
+    pub transparent: bool,
+    pub content_protected: bool,
+    pub enabled_buttons: WindowButtons,
+    pub window_icon: Option<Icon>,
+    pub has_focus: bool,
+    pub occluded: bool,
+    pub user_attention: Option<UserAttentionType>,
+    /// The window the popup belongs to, when created with [`WindowAttributes::with_popup`].
+    ///
+    /// [`WindowAttributes::with_popup`]: winit_core::window::WindowAttributes::with_popup
+    pub popup_parent: Option<WindowId>,
+}
+
+impl WindowState {
+    fn clamp_surface_size(&self, size: PhysicalSize<u32>) -> PhysicalSize<u32> {
+        let mut size = size;
+        if let Some(min) = self.min_surface_size {
+            size.width = size.width.max(min.width);
+            size.height = size.height.max(min.height);
+        }
+        if let Some(max) = self.max_surface_size {
+            size.width = size.width.min(max.width);
+            size.height = size.height.min(max.height);
+        }
+        size
+    }
+}
+
+#[derive(Debug)]
+pub struct Window {
+    id: WindowId,
+    shared: Arc<Shared>,
+}
+
+impl Window {
+    pub(crate) fn new(
+        el: &ActiveEventLoop,
+        attrs: window::WindowAttributes,
+    ) -> Result<Self, RequestError> {
+        let id = WindowId::from_raw(WINDOW_ID.fetch_add(1, Ordering::Relaxed));
+        let shared = el.shared.clone();
+
+        shared.update(|state| {
+            let monitor = attrs
+                .position
+                .and_then(|position| monitor_at(state, position.to_physical(1.0)))
+                .or_else(|| default_monitor(state));
+            let scale_factor = monitor.map(|monitor| monitor.scale_factor()).unwrap_or(1.0);
+            let position = match attrs.position {
+                Some(position) => position.to_physical(scale_factor),
+                None => monitor.and_then(|monitor| monitor.position()).unwrap_or_default(),
+            };
+
+            let mut window_state = WindowState {
+                title: attrs.title,
+                surface_size: DEFAULT_SURFACE_SIZE,
+                min_surface_size: attrs.min_surface_size.map(|size| size.to_physical(scale_factor)),
+                max_surface_size: attrs.max_surface_size.map(|size| size.to_physical(scale_factor)),
+                surface_resize_increments: attrs
+                    .surface_resize_increments
+                    .map(|size| size.to_physical(scale_factor)),
+                outer_position: position,
+                scale_factor,
+                visible: attrs.visible,
+                resizable: attrs.resizable,
+                minimized: false,
+                maximized: attrs.maximized,
+                fullscreen: attrs.fullscreen,
+                decorations: attrs.decorations,
+                window_level: attrs.window_level,
+                cursor: attrs.cursor,
+                cursor_visible: true,
+                cursor_grab: CursorGrabMode::None,
+                cursor_position: None,
+                cursor_hittest: true,
+                ime_allowed: false,
+                ime_cursor_area: None,
+                ime_purpose: ImePurpose::Normal,
+                ime_hints: ImeHint::empty(),
+                ime_surrounding_text: None,
+                theme: attrs.preferred_theme,
+                blur: attrs.blur,
+                transparent: attrs.transparent,
+                content_protected: attrs.content_protected,
+                enabled_buttons: attrs.enabled_buttons,
+                window_icon: attrs.window_icon,
+                has_focus: false,
+                occluded: false,
+                user_attention: None,
+                popup_parent: None,
+            };
+            let surface_size = attrs
+                .surface_size
+                .map_or(DEFAULT_SURFACE_SIZE, |size| size.to_physical(scale_factor));
+            window_state.surface_size = window_state.clamp_surface_size(surface_size);
+
+            // Place popups relative to their parent, on the monitor of the parent.
+            if let Some(popup) = attrs.popup {
+                let parent = state.windows.get(&popup.parent).ok_or_else(|| {
+                    NotSupportedError::new("the parent of the popup doesn't exist")
+                })?;
+                let bounds = monitor_at(state, parent.outer_position).and_then(|monitor| {
+                    Some((monitor.position()?, monitor.current_video_mode()?.size()))
+                });
+                let (position, size) = popup.place(
+                    parent.outer_position,
+                    parent.scale_factor,
+                    window_state.surface_size,
+                    bounds,
+                );
+                window_state.outer_position = position;
+                window_state.surface_size = size;
+                window_state.scale_factor = parent.scale_factor;
+                window_state.popup_parent = Some(popup.parent);
+            }
+
+            let visible = window_state.visible;
+            state.windows.insert(id, window_state);
+
+            if visible {
+                state.redraws.push_back(id);
+            }
+
+            Ok::<_, RequestError>(())
+        })?;
+
+        Ok(Self { id, shared })
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut WindowState) -> R) -> Option<R> {
+        self.shared.lock().windows.get_mut(&self.id).map(f)
+    }
+
+    fn update_state(&self, f: impl FnOnce(&mut WindowState)) {
+        let _ = self.with_state(f);
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        self.shared.update(|state| {
+            state.windows.remove(&self.id);
+            state.destroys.push_back(self.id);
+        });
+    }
+}
+
+/// The monitor the given point is on.
+fn monitor_at(state: &State, position: PhysicalPosition<i32>) -> Option<&Arc<MonitorHandle>> {
+    state.monitors.iter().find(|monitor| monitor.contains(position))
+}
+
+/// The monitor windows are put on when there's nothing better.
+fn default_monitor(state: &State) -> Option<&Arc<MonitorHandle>> {
+    state.primary_monitor().or_else(|| state.monitors.first())
+}
+
+impl CoreWindow for Window {
+    fn id(&self) -> WindowId {
+        self.id
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.with_state(|state| state.scale_factor).unwrap_or(1.0)
+    }
+
+    fn request_redraw(&self) {
+        self.shared.update(|state| state.redraws.push_back(self.id));
+    }
+
+    fn pre_present_notify(&self) {}
+
+    fn reset_dead_keys(&self) {}
+
+    fn surface_position(&self) -> PhysicalPosition<i32> {
+        PhysicalPosition::new(0, 0)
+    }
+
+    fn outer_position(&self) -> Result<PhysicalPosition<i32>, RequestError> {
+        self.with_state(|state| state.outer_position).ok_or(RequestError::Ignored)
+    }
+
+    fn set_outer_position(&self, position: Position) {
+        self.update_state(|state| {
+            state.outer_position = position.to_physical(state.scale_factor);
+        });
+    }
+
+    fn surface_size(&self) -> PhysicalSize<u32> {
+        self.with_state(|state| state.surface_size).unwrap_or_default()
+    }
+
+    fn request_surface_size(&self, size: Size) -> Option<PhysicalSize<u32>> {
+        let id = self.id;
+        self.shared.update(|state| {
+            let window = state.windows.get_mut(&id)?;
+            let size = window.clamp_surface_size(size.to_physical(window.scale_factor));
+            if window.surface_size != size {
+                window.surface_size = size;
+                state.redraws.push_back(id);
+            }
+            Some(size)
+        })
+    }
+
+    fn outer_size(&self) -> PhysicalSize<u32> {
+        self.surface_size()
+    }
+
+    fn safe_area(&self) -> PhysicalInsets<u32> {
+        PhysicalInsets::new(0, 0, 0, 0)
+    }
+
+    fn set_min_surface_size(&self, min_size: Option<Size>) {
+        self.update_state(|state| {
+            state.min_surface_size = min_size.map(|size| size.to_physical(state.scale_factor));
+            state.surface_size = state.clamp_surface_size(state.surface_size);
+        });
+    }
+
+    fn set_max_surface_size(&self, max_size: Option<Size>) {
+        self.update_state(|state| {
+            state.max_surface_size = max_size.map(|size| size.to_physical(state.scale_factor));
+            state.surface_size = state.clamp_surface_size(state.surface_size);
+        });
+    }
+
+    fn surface_resize_increments(&self) -> Option<PhysicalSize<u32>> {
+        self.with_state(|state| state.surface_resize_increments).flatten()
+    }
+
+    fn set_surface_resize_increments(&self, increments: Option<Size>) {
+        self.update_state(|state| {
+            state.surface_resize_increments =
+                increments.map(|size| size.to_physical(state.scale_factor));
+        });
+    }
+
+    fn set_title(&self, title: &str) {
+        self.update_state(|state| state.title = title.to_owned());
+    }
+
+    fn set_transparent(&self, transparent: bool) {
+        self.update_state(|state| state.transparent = transparent);
+    }
+
+    fn set_blur(&self, blur: bool) {
+        self.update_state(|state| state.blur = blur);
+    }
+
+    fn set_visible(&self, visible: bool) {
+        self.update_state(|state| state.visible = visible);
+    }
+
+    fn is_visible(&self) -> Option<bool> {
+        self.with_state(|state| state.visible)
+    }
+
+    fn set_resizable(&self, resizable: bool) {
+        self.update_state(|state| state.resizable = resizable);
+    }
+
+    fn is_resizable(&self) -> bool {
+        self.with_state(|state| state.resizable).unwrap_or_default()
+    }
+
+    fn set_enabled_buttons(&self, buttons: WindowButtons) {
+        self.update_state(|state| state.enabled_buttons = buttons);
+    }
+
+    fn enabled_buttons(&self) -> WindowButtons {
+        self.with_state(|state| state.enabled_buttons).unwrap_or(WindowButtons::all())
+    }
+
+    fn window_capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities::all()
+    }
+
+    fn set_minimized(&self, minimized: bool) {
+        self.update_state(|state| state.minimized = minimized);
+    }
+
+    fn is_minimized(&self) -> Option<bool> {
+        self.with_state(|state| state.minimized)
+    }
+
+    fn set_maximized(&self, maximized: bool) {
+        self.update_state(|state| state.maximized = maximized);
+    }
+
+    fn is_maximized(&self) -> bool {
+        self.with_state(|state| state.maximized).unwrap_or_default()
+    }
+
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        self.update_state(|state| state.fullscreen = fullscreen);
+    }
+
+    fn fullscreen(&self) -> Option<Fullscreen> {
+        self.with_state(|state| state.fullscreen.clone()).flatten()
+    }
+
+    fn set_decorations(&self, decorations: bool) {
+        self.update_state(|state| state.decorations = decorations);
+    }
+
+    fn is_decorated(&self) -> bool {
+        self.with_state(|state| state.decorations).unwrap_or_default()
+    }
+
+    fn set_window_level(&self, level: WindowLevel) {
+        self.update_state(|state| state.window_level = level);
+    }
+
+    fn set_window_icon(&self, window_icon: Option<Icon>) {
+        self.update_state(|state| state.window_icon = window_icon);
+    }
+
+    fn set_ime_cursor_area(&self, position: Position, size: Size) {
+        self.update_state(|state| state.ime_cursor_area = Some((position, size)));
+    }
+
+    fn set_ime_allowed(&self, allowed: bool) {
+        let id = self.id;
+        self.shared.update(|state| {
+            let Some(window) = state.windows.get_mut(&id) else { return };
+            if window.ime_allowed == allowed {
+                return;
+            }
+
+            window.ime_allowed = allowed;
+            let event = if allowed { Ime::Enabled } else { Ime::Disabled };
+            state.events.push_back(Action::Window(id, WindowEvent::Ime(event)));
+        });
+    }
+
+    fn set_ime_purpose(&self, purpose: ImePurpose) {
+        self.update_state(|state| state.ime_purpose = purpose);
+    }
+
+    fn set_ime_hints(&self, hints: ImeHint) {
+        self.update_state(|state| state.ime_hints = hints);
+    }
+
+    fn set_ime_surrounding_text(&self, text: &str, cursor: usize, anchor: usize) {
+        let text = text.to_owned();
+        self.update_state(|state| state.ime_surrounding_text = Some((text, cursor, anchor)));
+    }
+
+    fn focus_window(&self) {
+        let id = self.id;
+        self.shared.update(|state| {
+            if !state.windows.get(&id).is_some_and(|window| !window.has_focus) {
+                return;
+            }
+
+            for (&window_id, window) in state.windows.iter_mut() {
+                let focused = window_id == id;
+                if window.has_focus != focused {
+                    window.has_focus = focused;
+                    state
+                        .events
+                        .push_back(Action::Window(window_id, WindowEvent::Focused(focused)));
+                }
+            }
+        });
+    }
+
+    fn has_focus(&self) -> bool {
+        self.with_state(|state| state.has_focus).unwrap_or_default()
+    }
+
+    fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+        self.update_state(|state| state.user_attention = request_type);
+    }
+
+    fn set_theme(&self, theme: Option<Theme>) {
+        self.update_state(|state| state.theme = theme);
+    }
+
+    fn theme(&self) -> Option<Theme> {
+        let state = self.shared.lock();
+        state.windows.get(&self.id).and_then(|window| window.theme).or(state.system_theme)
+    }
+
+    fn set_content_protected(&self, protected: bool) {
+        self.update_state(|state| state.content_protected = protected);
+    }
+
+    fn title(&self) -> String {
+        self.with_state(|state| state.title.clone()).unwrap_or_default()
+    }
+
+    fn set_cursor(&self, cursor: Cursor) {
+        self.update_state(|state| state.cursor = cursor);
+    }
+
+    fn set_cursor_position(&self, position: Position) -> Result<(), RequestError> {
+        self.with_state(|state| {
+            state.cursor_position = Some(position.to_physical(state.scale_factor));
+        })
+        .ok_or(RequestError::Ignored)
+    }
+
+    fn set_cursor_grab(&self, mode: CursorGrabMode) -> Result<(), RequestError> {
+        self.with_state(|state| state.cursor_grab = mode).ok_or(RequestError::Ignored)
+    }
+
+    fn set_cursor_visible(&self, visible: bool) {
+        self.update_state(|state| state.cursor_visible = visible);
+    }
+
+    fn drag_window(&self) -> Result<(), RequestError> {
+        Err(NotSupportedError::new("drag_window is not supported").into())
+    }
+
+    fn drag_resize_window(&self, _direction: ResizeDirection) -> Result<(), RequestError> {
+        Err(NotSupportedError::new("drag_resize_window is not supported").into())
+    }
+
+    fn start_drag(
+        &self,
+        contents: ClipboardContents,
+        actions: &[DragAction],
+        icon: Option<CustomCursorSource>,
+    ) -> Result<AsyncRequestSerial, RequestError> {
+        if icon.as_ref().is_some_and(|icon| !matches!(icon, CustomCursorSource::Image(_))) {
+            return Err(NotSupportedError::new("only image drag icons are supported").into());
+        }
+
+        let serial = AsyncRequestSerial::get();
+        self.shared.update(|state| {
+            state.end_outgoing_drag(DragSourceEvent::Cancelled);
+            state.outgoing_drag = Some(OutgoingDrag {
+                window_id: self.id,
+                contents,
+                actions: actions.to_vec(),
+                icon,
+                action: None,
+                serial,
+            });
+        });
+        Ok(serial)
+    }
+
+    fn show_window_menu(&self, _position: Position) {}
+
+    fn set_cursor_hittest(&self, hittest: bool) -> Result<(), RequestError> {
+        self.with_state(|state| state.cursor_hittest = hittest).ok_or(RequestError::Ignored)
+    }
+
+    fn current_monitor(&self) -> Option<CoreMonitorHandle> {
+        let state = self.shared.lock();
+        let position = state.windows.get(&self.id)?.outer_position;
+        monitor_at(&state, position)
+            .or_else(|| default_monitor(&state))
+            .map(|monitor| CoreMonitorHandle(monitor.clone()))
+    }
+
+    fn available_monitors(&self) -> Box<dyn Iterator<Item = CoreMonitorHandle>> {
+        let state = self.shared.lock();
+        let monitors: Vec<_> =
+            state.monitors.iter().map(|monitor| CoreMonitorHandle(monitor.clone())).collect();
+        Box::new(monitors.into_iter())
+    }
+
+    fn primary_monitor(&self) -> Option<CoreMonitorHandle> {
+        self.shared.lock().primary_monitor().map(|monitor| CoreMonitorHandle(monitor.clone()))
+    }
+
+    fn rwh_06_display_handle(&self) -> &dyn rwh_06::HasDisplayHandle {
+        self
+    }
+
+    fn rwh_06_window_handle(&self) -> &dyn rwh_06::HasWindowHandle {
+        self
+    }
+}
+
+impl rwh_06::HasWindowHandle for Window {
+    fn window_handle(&self) -> Result<rwh_06::WindowHandle<'_>, rwh_06::HandleError> {
+        Err(rwh_06::HandleError::NotSupported)
+    }
+}
+
+impl rwh_06::HasDisplayHandle for Window {
+    fn display_handle(&self) -> Result<rwh_06::DisplayHandle<'_>, rwh_06::HandleError> {
+        Err(rwh_06::HandleError::NotSupported)
+    }
+}
@@ -6,7 +6,7 @@ use dpi::{PhysicalInsets, PhysicalPosition, PhysicalSize, Position, Size};
 use winit_core::cursor::Cursor;
 use winit_core::error::{NotSupportedError, RequestError};
 use winit_core::monitor::{Fullscreen, MonitorHandle as CoreMonitorHandle};
-use winit_core::window::{self, ImePurpose, Window as CoreWindow, WindowId};
+use winit_core::window::{self, ImeHint, ImePurpose, Window as CoreWindow, WindowId};
 
 use crate::event_loop::{ActiveEventLoop, EventLoopProxy};
 use crate::{RedoxSocket, WindowProperties};
@@ -363,6 +363,10 @@ impl CoreWindow for Window {
     #[inline]
     fn set_ime_purpose(&self, _purpose: ImePurpose) {}
 
+    fn set_ime_surrounding_text(&self, _text: &str, _cursor: usize, _anchor: usize) {}
+
+    fn set_ime_hints(&self, _hints: ImeHint) {}
+
     #[inline]
     fn focus_window(&self) {}
 
@@ -422,6 +426,15 @@ impl CoreWindow for Window {
         Ok(())
     }
 
+    fn start_drag(
+        &self,
+        _: winit_core::clipboard::ClipboardContents,
+        _: &[winit_core::event::DragAction],
+        _: Option<winit_core::cursor::CustomCursorSource>,
+    ) -> Result<winit_core::event_loop::AsyncRequestSerial, RequestError> {
+        Err(NotSupportedError::new("start_drag is not supported").into())
+    }
+
     #[inline]
     fn show_window_menu(&self, _position: Position) {}
 
@@ -438,6 +451,11 @@ impl CoreWindow for Window {
         window::WindowButtons::all()
     }
 
+    #[inline]
+    fn window_capabilities(&self) -> window::WindowCapabilities {
+        window::WindowCapabilities::empty()
+    }
+
     #[inline]
     fn theme(&self) -> Option<window::Theme> {
         None